@@ -81,7 +81,7 @@ fn main() {
         // config.set_icache_size(4096);
         // config.set_decode_cache_size(4096);
         config.set_mmu_type("sv39"); // sv39 sv48 sv57
-        config.set_isa("rv64imac");
+        config.set_isa("rv64imac").unwrap();
         config.set_s_mode();
         config.set_disable_check_tohost(true);
         info!("{:?}", config);
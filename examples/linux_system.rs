@@ -79,8 +79,9 @@ fn main() {
     config.set_tlb_size(256);
     config.set_icache_size(4096);
     config.set_decode_cache_size(4096);
+    config.set_block_cache_size(4096);
     config.set_mmu_type("sv39"); // sv39 sv48 sv57
-    config.set_isa("rv64imac");
+    config.set_isa("rv64imac_zicsr_zifencei_sstc").unwrap();
     config.set_s_mode();
     let config = Rc::new(config);
 
@@ -26,7 +26,7 @@ fn main() {
 
     let mut config = Config::new();
     config.set_mmu_type("bare");
-    config.set_isa("rv64im");
+    config.set_isa("rv64im").unwrap();
 
     // create system bus, which functions are as follows
     // 1. manage all devices,including plic,clint,and sram
@@ -0,0 +1,126 @@
+// See LICENSE for license details.
+
+// The virtual memory environment: the test runs in U-mode with Sv39 paging at
+// va = pa - DRAM_BASE, a small S-mode kernel reports the result on ecall and
+// fails the test on any other trap. Unlike upstream's vm.c the user pages are
+// mapped up front by one megapage, so there is no demand paging.
+
+#ifndef _ENV_VIRTUAL_SINGLE_CORE_H
+#define _ENV_VIRTUAL_SINGLE_CORE_H
+
+#include "../p/riscv_test.h"
+
+#define DRAM_BASE 0x80000000
+
+#undef RVTEST_FP_ENABLE
+#define RVTEST_FP_ENABLE fscsr x0
+
+#define KERNEL_PTE ((DRAM_BASE >> 12 << PTE_PPN_SHIFT) | PTE_V | PTE_R | PTE_W | PTE_X | PTE_A | PTE_D)
+
+#undef RVTEST_CODE_BEGIN
+#define RVTEST_CODE_BEGIN                                               \
+        .section .text.init;                                            \
+        .align  6;                                                      \
+        .globl _start;                                                  \
+_start:                                                                 \
+        j handle_reset;                                                 \
+        .align 2;                                                       \
+        /* nothing is expected to trap to M-mode */                     \
+trap_vector:                                                            \
+        li a0, (1337 << 1) | 1;                                         \
+        j terminate;                                                    \
+handle_reset:                                                           \
+        INIT_XREG;                                                      \
+        RISCV_MULTICORE_DISABLE;                                        \
+        INIT_PMP;                                                       \
+        la t0, trap_vector;                                             \
+        csrw mtvec, t0;                                                 \
+        /* the kernel sees the memory at va = pa through a gigapage */  \
+        la t1, pt_root;                                                 \
+        li t0, KERNEL_PTE;                                              \
+        sd t0, (DRAM_BASE >> 30) * 8(t1);                               \
+        /* and the user image at va = pa - DRAM_BASE through a megapage */ \
+        ori t0, t0, PTE_U;                                              \
+        la t2, pt_user;                                                 \
+        sd t0, 0(t2);                                                   \
+        srli t2, t2, 12;                                                \
+        slli t2, t2, PTE_PPN_SHIFT;                                     \
+        ori t2, t2, PTE_V;                                              \
+        sd t2, 0(t1);                                                   \
+        srli t1, t1, 12;                                                \
+        li t0, SATP_MODE_SV39 << 60;                                    \
+        or t1, t1, t0;                                                  \
+        csrw satp, t1;                                                  \
+        sfence.vma;                                                     \
+        la t0, trap_entry;                                              \
+        csrw stvec, t0;                                                 \
+        li t0, (1 << CAUSE_MISALIGNED_FETCH) |                          \
+               (1 << CAUSE_FETCH_ACCESS) |                              \
+               (1 << CAUSE_ILLEGAL_INSTRUCTION) |                       \
+               (1 << CAUSE_BREAKPOINT) |                                \
+               (1 << CAUSE_MISALIGNED_LOAD) |                           \
+               (1 << CAUSE_LOAD_ACCESS) |                               \
+               (1 << CAUSE_MISALIGNED_STORE) |                          \
+               (1 << CAUSE_STORE_ACCESS) |                              \
+               (1 << CAUSE_USER_ECALL) |                                \
+               (1 << CAUSE_FETCH_PAGE_FAULT) |                          \
+               (1 << CAUSE_LOAD_PAGE_FAULT) |                           \
+               (1 << CAUSE_STORE_PAGE_FAULT);                           \
+        csrw medeleg, t0;                                               \
+        csrwi mideleg, 0;                                               \
+        csrwi mie, 0;                                                   \
+        /* enter S-mode with the FPU on */                              \
+        li t0, (MSTATUS_MPP & (MSTATUS_MPP >> 1)) | MSTATUS_FS;         \
+        csrw mstatus, t0;                                               \
+        csrwi fcsr, 0;                                                  \
+        la t0, supervisor_start;                                        \
+        csrw mepc, t0;                                                  \
+        mret;                                                           \
+supervisor_start:                                                       \
+        la t0, userstart;                                               \
+        li t1, DRAM_BASE;                                               \
+        sub t0, t0, t1;                                                 \
+        csrw sepc, t0;                                                  \
+        li t0, SSTATUS_SPP;                                             \
+        csrc sstatus, t0;                                               \
+        sret;                                                           \
+        .align 2;                                                       \
+        /* the user ecall passes the result in a0, as tohost takes it */ \
+trap_entry:                                                             \
+        csrr t5, scause;                                                \
+        li t6, CAUSE_USER_ECALL;                                        \
+        beq t5, t6, terminate;                                          \
+        ori TESTNUM, TESTNUM, 1337;                                     \
+        sll a0, TESTNUM, 1;                                             \
+        or a0, a0, 1;                                                   \
+terminate:                                                              \
+        la t5, tohost;                                                  \
+1:      sd a0, 0(t5);                                                   \
+        j 1b;                                                           \
+        .pushsection .bss;                                              \
+        .align 12; pt_root: .skip 4096;                                 \
+        .align 12; pt_user: .skip 4096;                                 \
+        .popsection;                                                    \
+        .text;                                                          \
+        .global userstart;                                              \
+userstart:                                                              \
+        init
+
+//-----------------------------------------------------------------------
+// Pass/Fail Macro
+//-----------------------------------------------------------------------
+
+#undef RVTEST_PASS
+#define RVTEST_PASS li a0, 1; ecall
+
+#undef RVTEST_FAIL
+#define RVTEST_FAIL sll a0, TESTNUM, 1; 1:beqz a0, 1b; or a0, a0, 1; ecall;
+
+//-----------------------------------------------------------------------
+// Data Section Macro
+//-----------------------------------------------------------------------
+
+#undef RVTEST_DATA_END
+#define RVTEST_DATA_END
+
+#endif
@@ -0,0 +1,180 @@
+// See LICENSE for license details.
+//
+// Reference results for gen_fp_tests.py, computed by the host FPU under the
+// requested rounding mode. Reads one operation per line on stdin:
+//
+//   <s|d> <op> <rne|rtz|rdn|rup> <a> <b> <c>
+//
+// with the operands as hex bit patterns (integers as 64-bit two's complement)
+// and prints the result bits and the RISC-V fflags in hex. `<s|d> dec <text>`
+// prints the bits of a decimal instead.
+//
+// Build with: cc -O0 -frounding-math -ffp-contract=off fp_ref.c -lm
+
+#include <fenv.h>
+#include <math.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#pragma STDC FENV_ACCESS ON
+
+static float to_s(uint64_t b)
+{
+  uint32_t w = (uint32_t)b;
+  float f;
+  memcpy(&f, &w, 4);
+  return f;
+}
+
+static double to_d(uint64_t b)
+{
+  double d;
+  memcpy(&d, &b, 8);
+  return d;
+}
+
+static uint64_t from_s(float f)
+{
+  uint32_t w;
+  memcpy(&w, &f, 4);
+  return w;
+}
+
+static uint64_t from_d(double d)
+{
+  uint64_t b;
+  memcpy(&b, &d, 8);
+  return b;
+}
+
+// fflags: NV 0x10, DZ 0x08, OF 0x04, UF 0x02, NX 0x01
+static unsigned fflags(void)
+{
+  unsigned f = 0;
+  if (fetestexcept(FE_INVALID)) f |= 0x10;
+  if (fetestexcept(FE_DIVBYZERO)) f |= 0x08;
+  if (fetestexcept(FE_OVERFLOW)) f |= 0x04;
+  if (fetestexcept(FE_UNDERFLOW)) f |= 0x02;
+  if (fetestexcept(FE_INEXACT)) f |= 0x01;
+  return f;
+}
+
+static int rounding(const char *rm)
+{
+  if (!strcmp(rm, "rne")) return FE_TONEAREST;
+  if (!strcmp(rm, "rtz")) return FE_TOWARDZERO;
+  if (!strcmp(rm, "rdn")) return FE_DOWNWARD;
+  if (!strcmp(rm, "rup")) return FE_UPWARD;
+  fprintf(stderr, "unknown rounding mode %s\n", rm);
+  exit(1);
+}
+
+// fcvt.{w|wu|l|lu}: the host rounds, out of range inputs saturate as the
+// spec says, a 32-bit result is sign-extended like on RV64
+static uint64_t to_int(volatile double x, const char *t, unsigned *flags)
+{
+  volatile double r = nearbyint(x);
+  int is_signed = t[1] != 'u';
+  int width = t[0] == 'w' ? 32 : 64;
+  double lo = is_signed ? -ldexp(1, width - 1) : 0;
+  double hi = is_signed ? ldexp(1, width - 1) : ldexp(1, width);
+  uint64_t v;
+
+  if (isnan(x) || r < lo || r >= hi) {
+    *flags = 0x10;
+    if (!isnan(x) && r < lo)
+      v = is_signed ? (uint64_t)1 << (width - 1) : 0;
+    else
+      v = is_signed ? ((uint64_t)1 << (width - 1)) - 1 : ~(uint64_t)0;
+  } else {
+    *flags = r != x ? 0x01 : 0;
+    v = is_signed ? (uint64_t)(int64_t)r : (uint64_t)r;
+  }
+  if (width == 32)
+    v = (uint64_t)(int64_t)(int32_t)(uint32_t)v;
+  return v;
+}
+
+static uint64_t from_int(int single, uint64_t a, const char *t)
+{
+  int is_signed = t[1] != 'u';
+  if (t[0] == 'w')
+    a = is_signed ? (uint64_t)(int64_t)(int32_t)a : (uint32_t)a;
+  if (single)
+    return from_s(is_signed ? (float)(int64_t)a : (float)a);
+  return from_d(is_signed ? (double)(int64_t)a : (double)a);
+}
+
+int main(void)
+{
+  char line[256];
+
+  while (fgets(line, sizeof(line), stdin)) {
+    char fmt[4], op[16], rm[16], text[128];
+    unsigned long long a = 0, b = 0, c = 0;
+    uint64_t r;
+    unsigned flags;
+    int single;
+
+    if (sscanf(line, "%3s %15s %127s", fmt, op, text) == 3 && !strcmp(op, "dec")) {
+      if (fmt[0] == 's')
+        printf("%08llx\n", (unsigned long long)from_s(strtof(text, NULL)));
+      else
+        printf("%016llx\n", (unsigned long long)from_d(strtod(text, NULL)));
+      continue;
+    }
+    if (sscanf(line, "%3s %15s %15s %llx %llx %llx", fmt, op, rm, &a, &b, &c) < 4) {
+      fprintf(stderr, "bad line: %s", line);
+      return 1;
+    }
+    single = fmt[0] == 's';
+
+    fesetround(rounding(rm));
+    feclearexcept(FE_ALL_EXCEPT);
+    flags = 0;
+    if (!strncmp(op, "to_", 3)) {
+      r = to_int(single ? to_s(a) : to_d(a), op + 3, &flags);
+    } else if (!strncmp(op, "from_", 5)) {
+      r = from_int(single, a, op + 5);
+      flags = fflags();
+    } else if (!strcmp(op, "narrow")) {
+      volatile double x = to_d(a);
+      r = from_s((float)x);
+      flags = fflags();
+    } else if (single) {
+      volatile float x = to_s(a), y = to_s(b), z = to_s(c), v;
+      if (!strcmp(op, "fadd")) v = x + y;
+      else if (!strcmp(op, "fsub")) v = x - y;
+      else if (!strcmp(op, "fmul")) v = x * y;
+      else if (!strcmp(op, "fdiv")) v = x / y;
+      else if (!strcmp(op, "fsqrt")) v = sqrtf(x);
+      else if (!strcmp(op, "fmadd")) v = fmaf(x, y, z);
+      else {
+        fprintf(stderr, "unknown op %s\n", op);
+        return 1;
+      }
+      r = from_s(v);
+      flags = fflags();
+    } else {
+      volatile double x = to_d(a), y = to_d(b), z = to_d(c), v;
+      if (!strcmp(op, "fadd")) v = x + y;
+      else if (!strcmp(op, "fsub")) v = x - y;
+      else if (!strcmp(op, "fmul")) v = x * y;
+      else if (!strcmp(op, "fdiv")) v = x / y;
+      else if (!strcmp(op, "fsqrt")) v = sqrt(x);
+      else if (!strcmp(op, "fmadd")) v = fma(x, y, z);
+      else {
+        fprintf(stderr, "unknown op %s\n", op);
+        return 1;
+      }
+      r = from_d(v);
+      flags = fflags();
+    }
+    fesetround(FE_TONEAREST);
+    printf("%0*llx %02x\n", single && strncmp(op, "to_", 3) ? 8 : 16,
+           (unsigned long long)r, flags);
+  }
+  return 0;
+}
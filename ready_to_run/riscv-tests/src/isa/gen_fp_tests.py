@@ -0,0 +1,314 @@
+#!/usr/bin/env python3
+# See LICENSE for license details.
+#
+# Generates frm_arith.S and frm_fcvt.S in rv64uf and rv64ud, the static
+# rounding mode tests that the upstream suites don't have:
+#
+#   ./gen_fp_tests.py rv64uf rv64ud
+#
+# The tests are split in two files since llvm-mc doesn't relax the branches
+# to fail, which have to stay within 4KiB.
+#
+# The expected results and flags come from the host FPU through fp_ref.c.
+# The host has no round-to-max-magnitude mode, so rmm is taken from the rtz
+# and round-away results, with exact rational arithmetic deciding the ties.
+
+import os
+import struct
+import subprocess
+import sys
+import tempfile
+from fractions import Fraction
+
+RNE, RTZ, RDN, RUP, RMM = "rne", "rtz", "rdn", "rup", "rmm"
+
+HEADER = """\
+# See LICENSE for license details.
+
+#*****************************************************************************
+# {name}.S
+#-----------------------------------------------------------------------------
+#
+# Test the static rounding modes of the {p} {what}.
+# Generated by gen_fp_tests.py, the decimal values are in the comments.
+#
+
+#include "riscv_test.h"
+#include "test_macros.h"
+
+RVTEST_RV64UF
+RVTEST_CODE_BEGIN
+"""
+
+FOOTER = """
+  TEST_PASSFAIL
+
+RVTEST_CODE_END
+
+  .data
+RVTEST_DATA_BEGIN
+
+  TEST_DATA
+
+RVTEST_DATA_END
+"""
+
+
+class Ref:
+    def __init__(self, here):
+        self.dir = tempfile.TemporaryDirectory()
+        exe = os.path.join(self.dir.name, "fp_ref")
+        subprocess.run(
+            ["cc", "-O0", "-frounding-math", "-ffp-contract=off", "-o", exe, os.path.join(here, "fp_ref.c"), "-lm"],
+            check=True,
+        )
+        self.exe = exe
+
+    def run(self, lines):
+        out = subprocess.run([self.exe], input="\n".join(lines) + "\n", capture_output=True, text=True, check=True)
+        return out.stdout.split("\n")[:-1]
+
+    def dec(self, p, text):
+        return int(self.run(["%s dec %s" % (p, text)])[0], 16)
+
+    # (result, flags) of one operation with a host rounding mode
+    def op(self, p, op, rm, a=0, b=0, c=0):
+        r, flags = self.run(["%s %s %s %x %x %x" % (p, op, rm, a, b, c)])[0].split()
+        return int(r, 16), int(flags, 16)
+
+
+class Suite:
+    def __init__(self, ref, p):
+        self.ref = ref
+        self.p = p
+        self.big = p.upper()
+        self.word = "word" if p == "s" else "dword"
+
+    def bits(self, x):
+        return x if isinstance(x, int) else self.ref.dec(self.p, x)
+
+    # the operand as the 0f:/0d: literal of the data section
+    def lit(self, b):
+        return "0f:%08x" % b if self.p == "s" else "0d:%016x" % b
+
+    def value(self, b, p=None):
+        if (p or self.p) == "s":
+            return struct.unpack("<f", struct.pack("<I", b & 0xFFFFFFFF))[0]
+        return struct.unpack("<d", struct.pack("<Q", b))[0]
+
+    def dec(self, b, p=None):
+        v = self.value(b, p)
+        return "%.9g" % v if (p or self.p) == "s" else repr(v)
+
+    def exact(self, b, p=None):
+        return Fraction(self.value(b, p))
+
+    # rmm is rne except on a tie, which goes to the rtz result's neighbour
+    # away from zero
+    def calc(self, op, rm, a=0, b=0, c=0, exact=None):
+        if rm != RMM:
+            return self.ref.op(self.p, op, rm, a, b, c)
+        r, flags = self.ref.op(self.p, op, RNE, a, b, c)
+        if exact is None or exact == 0 or flags & 0x10:
+            return r, flags
+        z, _ = self.ref.op(self.p, op, RTZ, a, b, c)
+        away, _ = self.ref.op(self.p, op, RUP if exact > 0 else RDN, a, b, c)
+        if op.startswith("to_"):
+            mid = Fraction(sext(z) + sext(away), 2)
+        else:
+            rp = "s" if op == "narrow" else None
+            if z == away or any(v != v or abs(v) == float("inf") for v in (self.value(z, rp), self.value(away, rp))):
+                return r, flags
+            mid = (self.exact(z, rp) + self.exact(away, rp)) / 2
+        return (away if exact == mid else r), flags
+
+
+def sext(x, width=64):
+    return x - (1 << width) if x >> (width - 1) else x
+
+
+def flags_str(flags):
+    return "0x%02x" % flags if flags else "0"
+
+
+class Writer:
+    def __init__(self, s, name, what):
+        self.s = s
+        self.name = name
+        self.what = what
+        self.lines = []
+        self.num = 2
+
+    def section(self, title):
+        self.lines += [
+            "",
+            "  #-------------------------------------------------------------",
+            "  # " + title,
+            "  #-------------------------------------------------------------",
+            "",
+        ]
+
+    # a TEST_FP_OP_{S,D}_INTERNAL with the operands in f0, f1 and f2, a
+    # single precision suite loads the result with lw
+    def add(self, flags, result, ops, code, comment):
+        s = self.s
+        result &= 0xFFFFFFFF if s.p == "s" else (1 << 64) - 1
+        lits = [s.lit(x) for x in ops] + ["0.0"] * (3 - len(ops))
+        self.lines.append(
+            "  TEST_FP_OP_%s_INTERNAL(%2d, %s, %s 0x%x, %s, %s); // %s"
+            % (s.big, self.num, flags_str(flags), s.word, result, ", ".join(lits), code, comment)
+        )
+        self.num += 1
+
+    def text(self):
+        return HEADER.format(name=self.name, p="." + self.s.p, what=self.what) + "\n".join(self.lines) + "\n" + FOOTER
+
+
+EXACT = {
+    "fadd": lambda a, b, c: a + b,
+    "fsub": lambda a, b, c: a - b,
+    "fmul": lambda a, b, c: a * b,
+    "fdiv": lambda a, b, c: a / b if b else None,
+    "fmadd": lambda a, b, c: a * b + c,
+    # a square root is never halfway between two floats
+    "fsqrt": lambda a, b, c: None,
+}
+
+
+# f[rd] = op(f[rs1], ...) with a static rounding mode
+def arith(w, op, rm, *ops):
+    s = w.s
+    ops = [s.bits(x) for x in ops]
+    exact = EXACT[op](*([s.exact(x) for x in ops] + [0] * (3 - len(ops))))
+    r, flags = s.calc(op, rm, *ops, exact=exact)
+    regs = ", ".join("f%d" % i for i in range(len(ops)))
+    inst = "%s.%s" % (op, s.p)
+    w.add(
+        flags,
+        r,
+        ops,
+        "%s f3, %s, %s; fmv.x.%s a0, f3" % (inst, regs, rm, s.p),
+        "%s(%s, %s) = %s" % (inst, ", ".join(s.dec(x) for x in ops), rm, s.dec(r)),
+    )
+
+
+# fcvt.{w|wu|l|lu}.{s|d}, the result is checked as a sign-extended word for
+# single precision
+def to_int(w, t, rm, a):
+    s = w.s
+    a = s.bits(a)
+    r, flags = s.calc("to_" + t, rm, a, exact=s.exact(a))
+    if s.p == "s":
+        assert sext(r) == sext(r & 0xFFFFFFFF, 32), "%s does not fit a word" % r
+    inst = "fcvt.%s.%s" % (t, s.p)
+    w.add(
+        flags,
+        r,
+        [a],
+        "%s a0, f0, %s" % (inst, rm),
+        "%s(%s, %s) = %d" % (inst, s.dec(a), rm, sext(r)),
+    )
+
+
+# fcvt.{s|d}.{w|wu|l|lu}
+def from_int(w, t, rm, n):
+    s = w.s
+    r, flags = s.calc("from_" + t, rm, n & (1 << 64) - 1, exact=Fraction(n))
+    inst = "fcvt.%s.%s" % (s.p, t)
+    w.add(
+        flags,
+        r,
+        [],
+        "li a4, %d; %s f3, a4, %s; fmv.x.%s a0, f3" % (n, inst, rm, s.p),
+        "%s(%d, %s) = %s" % (inst, n, rm, s.dec(r)),
+    )
+
+
+# fcvt.s.d, the single result is read back with fmv.x.s
+def narrow(w, rm, a):
+    s = w.s
+    a = s.bits(a)
+    r, flags = s.calc("narrow", rm, a, exact=s.exact(a))
+    w.add(
+        flags,
+        sext(r, 32),
+        [a],
+        "fcvt.s.d f3, f0, %s; fmv.x.s a0, f3" % rm,
+        "fcvt.s.d(%s, %s) = %s" % (s.dec(a), rm, s.dec(r, "s")),
+    )
+
+
+def gen_frm(s):
+    p = s.p
+    w = Writer(s, "frm_arith", "arithmetic instructions")
+    single = p == "s"
+    tiny = "1.401298464324817e-45" if single else "5e-324"
+    huge = "3.4028234663852886e38" if single else "1.7976931348623157e308"
+    # 1 + half an ulp is a tie, rne keeps 1.0 and rmm rounds away
+    half_ulp = "5.9604644775390625e-08" if single else "1.1102230246251565e-16"
+    rms = (RNE, RTZ, RDN, RUP, RMM)
+
+    w.section("f{add|sub|mul}")
+    for rm in rms:
+        arith(w, "fadd", rm, "1.0", "0.00000001")
+        arith(w, "fsub", rm, "-1.0", "0.00000001")
+        arith(w, "fadd", rm, "1.0", half_ulp)
+        arith(w, "fsub", rm, "-1.0", half_ulp)
+    arith(w, "fsub", RDN, "1.5", "1.5")
+    arith(w, "fadd", RTZ, huge, huge)
+    arith(w, "fmul", RUP, "-" + huge, "2.0")
+    arith(w, "fmul", RUP, tiny, "0.5")
+    arith(w, "fmul", RDN, tiny, "0.5")
+
+    w.section("f{div|sqrt}")
+    for rm in rms:
+        arith(w, "fdiv", rm, "1.0", "3.0")
+        arith(w, "fdiv", rm, "-2.0", "3.0")
+        arith(w, "fsqrt", rm, "2.0")
+
+    w.section("fmadd")
+    for rm in rms:
+        arith(w, "fmadd", rm, "1.0", "3.0", "0.00000001")
+    arith(w, "fmadd", RDN, "1.0", "-1.0", "1.0")
+
+    arith_tests = w
+    w = Writer(s, "frm_fcvt", "conversions")
+    w.section("fcvt.%s.{w|wu|l|lu}" % p)
+    odd = 0x1000001 if single else 0x20000000000001
+    for rm in rms:
+        from_int(w, "l", rm, odd)
+        from_int(w, "l", rm, -odd - 2)
+        if single:
+            from_int(w, "w", rm, 0x7FFFFFFF)
+    from_int(w, "lu", RTZ, -1)
+
+    w.section("fcvt.{w|wu|l|lu}.%s" % p)
+    for a in ("2.5", "-2.5", "0.5", "-0.5", "1.5"):
+        for rm in rms:
+            to_int(w, "w", rm, a)
+    to_int(w, "wu", RUP, "-0.5")
+    to_int(w, "lu", RDN, "-0.5")
+    to_int(w, "l", RMM, "-2.5")
+
+    if not single:
+        w.section("fcvt.s.d")
+        for a in ("1.1", "-1.1", "1e40", "1e-50", "1.0000000596046448"):
+            for rm in rms:
+                narrow(w, rm, a)
+    return [arith_tests, w]
+
+
+SUITES = {"rv64uf": "s", "rv64ud": "d"}
+
+
+def main(suites):
+    here = os.path.dirname(os.path.abspath(__file__))
+    ref = Ref(here)
+    for name in suites:
+        for w in gen_frm(Suite(ref, SUITES[name])):
+            with open(os.path.join(here, name, w.name + ".S"), "w") as out:
+                out.write(w.text())
+
+
+if __name__ == "__main__":
+    main(sys.argv[1:] or list(SUITES))
@@ -1,21 +1,155 @@
+use core::fmt;
+
+use alloc::string::String;
 use log::info;
 
 use crate::rv64core::csr_regs_define::StapMode;
 
-const IMPLMENTED_ISA: [u8; 4] = [b'i', b'm', b'a', b'c'];
+const IMPLMENTED_ISA: [u8; 8] = [b'i', b'm', b'a', b'f', b'd', b'c', b'v', b'h'];
+// multi-letter extensions, the index is the bit in ext_flags
+const IMPLMENTED_EXT: [&str; 35] = [
+    "zicsr",
+    "zifencei",
+    "zba",
+    "zbb",
+    "zbc",
+    "zbs",
+    "sstc",
+    "zicbom",
+    "zicboz",
+    "zicbop",
+    "svnapot",
+    "svpbmt",
+    "svinval",
+    "svadu",
+    "sscofpmf",
+    "smaia",
+    "ssaia",
+    "zacas",
+    "zabha",
+    "zbkb",
+    "zbkc",
+    "zbkx",
+    "zknd",
+    "zkne",
+    "zknh",
+    "zksed",
+    "zksh",
+    "zkr",
+    "zcb",
+    "zcmp",
+    "zcmt",
+    "zicond",
+    "zihintpause",
+    "zawrs",
+    "zimop",
+];
+// the extensions an RV32 hart may have, the others are only implemented for RV64
+const RV32_ISA: [u8; 7] = [b'i', b'g', b'm', b'a', b'f', b'd', b'c'];
+const RV32_EXT: [&str; 11] = [
+    "zicsr",
+    "zifencei",
+    "zicbom",
+    "zicboz",
+    "zicbop",
+    "svinval",
+    "zabha",
+    "zicond",
+    "zihintpause",
+    "zawrs",
+    "zimop",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IsaErr {
+    // only rv32 and rv64 are supported
+    UnsupportedXlen(String),
+    // the extension is not implemented for rv32
+    Rv64Only(String),
+    // the first single-letter extension must be i or g
+    InvalidBase(String),
+    UnknownExtension(String),
+    DuplicateExtension(String),
+    // the extension requires another one
+    MissingDependency(String, &'static str),
+    // the extension reuses the encodings of another one
+    Conflict(String, &'static str),
+}
+
+impl fmt::Display for IsaErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IsaErr::UnsupportedXlen(isa) => write!(f, "unsupported xlen: {isa}"),
+            IsaErr::Rv64Only(ext) => write!(f, "{ext} is not supported on rv32"),
+            IsaErr::InvalidBase(base) => write!(f, "invalid base isa: {base:?}"),
+            IsaErr::UnknownExtension(ext) => write!(f, "unknown extension: {ext}"),
+            IsaErr::DuplicateExtension(ext) => write!(f, "duplicate extension: {ext}"),
+            IsaErr::MissingDependency(ext, dep) => write!(f, "{ext} requires {dep}"),
+            IsaErr::Conflict(ext, other) => write!(f, "{ext} conflicts with {other}"),
+        }
+    }
+}
+
+const fn isa_mask(isa: u8) -> u32 {
+    1 << (isa - b'a')
+}
+
+fn ext_mask(ext: &str) -> u64 {
+    IMPLMENTED_EXT
+        .iter()
+        .position(|x| *x == ext)
+        .map_or(0, |idx| 1 << idx)
+}
+
+// skip the version number "<major>[p<minor>]" at the beginning of s
+fn skip_version(s: &str) -> &str {
+    let s = s.trim_start_matches(|c: char| c.is_ascii_digit());
+    match s.strip_prefix('p') {
+        Some(minor) if minor.starts_with(|c: char| c.is_ascii_digit()) => {
+            minor.trim_start_matches(|c: char| c.is_ascii_digit())
+        }
+        _ => s,
+    }
+}
 
+// strip the version number "<major>[p<minor>]" at the end of a multi-letter extension
+fn strip_version(ext: &str) -> &str {
+    let name = ext.trim_end_matches(|c: char| c.is_ascii_digit());
+    match name.strip_suffix('p') {
+        Some(major) if major.ends_with(|c: char| c.is_ascii_digit()) && name.len() < ext.len() => {
+            major.trim_end_matches(|c: char| c.is_ascii_digit())
+        }
+        _ => name,
+    }
+}
 
 #[derive(Debug)]
 pub struct Config {
     icache_size: Option<usize>,
     dcache_size: Option<usize>,
     decode_cache_size: Option<usize>,
+    block_cache_size: Option<usize>,
     tlb_size: Option<usize>,
     mmu_type: StapMode,
     s_mode: bool,
     u_mode: bool,
     isa_falgs: u32,
+    ext_flags: u64,
+    // MXLEN, 32 or 64
+    xlen: usize,
+    pmp_num: usize,
+    // vector register length and maximum element width in bits
+    vlen: usize,
+    elen: usize,
     disable_check_tohost: bool,
+    // split misaligned loads and stores instead of raising an address-misaligned exception
+    misaligned_access: bool,
+    // the block size in bytes zeroed by cbo.zero
+    cboz_block_size: usize,
+    // the cycles a WFI that has to trap, e.g. with mstatus.TW=1, may wait before trapping
+    wfi_timeout: usize,
+    // the seed of the Zkr entropy source, None takes one from the host
+    entropy_seed: Option<u64>,
 }
 
 impl Default for Config {
@@ -24,12 +158,22 @@ impl Default for Config {
             icache_size: Default::default(),
             dcache_size: Default::default(),
             decode_cache_size: Default::default(),
+            block_cache_size: Default::default(),
             tlb_size: Default::default(),
             mmu_type: StapMode::Bare,
             isa_falgs: 0,
+            ext_flags: 0,
+            xlen: 64,
+            pmp_num: 16,
+            vlen: 128,
+            elen: 64,
             s_mode: false,
             u_mode: false,
             disable_check_tohost: false,
+            misaligned_access: false,
+            cboz_block_size: 64,
+            wfi_timeout: 0,
+            entropy_seed: None,
         }
     }
 }
@@ -55,35 +199,141 @@ impl Config {
     pub fn set_decode_cache_size(&mut self, size: usize) {
         self.decode_cache_size = Some(size);
     }
+    pub fn set_block_cache_size(&mut self, size: usize) {
+        self.block_cache_size = Some(size);
+    }
     pub fn set_tlb_size(&mut self, size: usize) {
         self.tlb_size = Some(size);
     }
-    // sv39 sv48
+    // sv32 for rv32, sv39 sv48 sv57 for rv64
     pub fn set_mmu_type(&mut self, mmu_type: &str) {
         let mmu_type = mmu_type.to_lowercase();
         match mmu_type.as_str() {
             "bare" => self.mmu_type = StapMode::Bare,
+            "sv32" => self.mmu_type = StapMode::Sv32,
             "sv39" => self.mmu_type = StapMode::Sv39,
             "sv48" => self.mmu_type = StapMode::Sv48,
             "sv57" => self.mmu_type = StapMode::Sv57,
             err => panic!("mmu type err:{err}"),
         }
     }
-    // TODO: parse isa string
-    pub fn set_isa(&mut self, isa_str: &str) {
+    // 0, 16 or 64 pmp entries
+    pub fn set_pmp_num(&mut self, num: usize) {
+        match num {
+            0 | 16 | 64 => self.pmp_num = num,
+            err => panic!("pmp num err:{err}"),
+        }
+    }
+    // VLEN is a power of two between 128 and 65536
+    pub fn set_vlen(&mut self, vlen: usize) {
+        match vlen {
+            128..=65536 if vlen.is_power_of_two() => self.vlen = vlen,
+            err => panic!("vlen err:{err}"),
+        }
+    }
+    // ELEN is 32 or 64, vector instructions with SEW > ELEN are illegal
+    pub fn set_elen(&mut self, elen: usize) {
+        match elen {
+            32 | 64 => self.elen = elen,
+            err => panic!("elen err:{err}"),
+        }
+    }
+    // Parse an isa string such as "rv64imafdc_zicsr_zifencei_zba_zbb_sstc".
+    // Single-letter extensions come first, multi-letter extensions are separated
+    // by '_' and any extension may carry a version number like "2p1".
+    pub fn set_isa(&mut self, isa_str: &str) -> Result<(), IsaErr> {
         let isa_str = isa_str.to_ascii_lowercase();
         info!("isa_str:{:?}", isa_str);
-        isa_str.strip_prefix("rv64").map_or_else(
-            || panic!("isa err:{isa_str}"),
-            |f| {
-                for i in f.bytes() {
-                    if IMPLMENTED_ISA.contains(&i) {
-                        let idx = i - b'a';
-                        self.isa_falgs |= 1 << idx;
-                    }
-                }
-            },
-        )
+
+        let (xlen, f) = match isa_str.split_at_checked(4) {
+            Some(("rv64", f)) => (64, f),
+            Some(("rv32", f)) => (32, f),
+            _ => return Err(IsaErr::UnsupportedXlen(isa_str.clone())),
+        };
+
+        let mut isa_flags = 0_u32;
+        let mut ext_flags = 0_u64;
+        // zicsr and zifencei are always implemented
+        let implied_ext = ext_mask("zicsr") | ext_mask("zifencei");
+
+        // the first multi-letter extension may directly follow the single-letter ones
+        let mut parts = f.split('_');
+        let base = parts.next().unwrap_or_default();
+        let split = base.find(['z', 's', 'x']).unwrap_or(base.len());
+        let (single, first_ext) = base.split_at(split);
+
+        let mut letters = single;
+        while let Some(c) = letters.chars().next() {
+            // c as u8 below would fold a non-ASCII letter onto an unrelated one
+            if !c.is_ascii_lowercase() {
+                return Err(IsaErr::UnknownExtension(c.into()));
+            }
+            letters = skip_version(&letters[c.len_utf8()..]);
+            let bits = match c {
+                'g' => b"imafd".iter().fold(0, |acc, x| acc | isa_mask(*x)),
+                c if IMPLMENTED_ISA.contains(&(c as u8)) => isa_mask(c as u8),
+                c => return Err(IsaErr::UnknownExtension(c.into())),
+            };
+            if isa_flags == 0 && !matches!(c, 'i' | 'g') {
+                return Err(IsaErr::InvalidBase(c.into()));
+            }
+            if isa_flags & bits != 0 {
+                return Err(IsaErr::DuplicateExtension(c.into()));
+            }
+            if xlen == 32 && !RV32_ISA.contains(&(c as u8)) {
+                return Err(IsaErr::Rv64Only(c.into()));
+            }
+            isa_flags |= bits;
+        }
+        if isa_flags == 0 {
+            return Err(IsaErr::InvalidBase(String::new()));
+        }
+
+        for ext in core::iter::once(first_ext).chain(parts) {
+            if ext.is_empty() {
+                continue;
+            }
+            let name = strip_version(ext);
+            let bit = match IMPLMENTED_EXT.iter().position(|x| *x == name) {
+                Some(idx) => 1 << idx,
+                None => return Err(IsaErr::UnknownExtension(name.into())),
+            };
+            if ext_flags & bit != 0 {
+                return Err(IsaErr::DuplicateExtension(name.into()));
+            }
+            if xlen == 32 && !RV32_EXT.contains(&name) {
+                return Err(IsaErr::Rv64Only(name.into()));
+            }
+            ext_flags |= bit;
+        }
+
+        // D depends on F
+        if isa_flags & isa_mask(b'd') != 0 && isa_flags & isa_mask(b'f') == 0 {
+            return Err(IsaErr::MissingDependency("d".into(), "f"));
+        }
+        // Zacas and Zabha extend the AMOs of A, Zawrs waits on the reservation of LR
+        for ext in ["zacas", "zabha", "zawrs"] {
+            if ext_flags & ext_mask(ext) != 0 && isa_flags & isa_mask(b'a') == 0 {
+                return Err(IsaErr::MissingDependency(ext.into(), "a"));
+            }
+        }
+        // Zcb, Zcmp and Zcmt are built on top of C
+        for ext in ["zcb", "zcmp", "zcmt"] {
+            if ext_flags & ext_mask(ext) != 0 && isa_flags & isa_mask(b'c') == 0 {
+                return Err(IsaErr::MissingDependency(ext.into(), "c"));
+            }
+        }
+        // Zcmp and Zcmt take over the c.fsdsp encodings, so C with D excludes them
+        for ext in ["zcmp", "zcmt"] {
+            if ext_flags & ext_mask(ext) != 0 && isa_flags & isa_mask(b'd') != 0 {
+                return Err(IsaErr::Conflict(ext.into(), "d"));
+            }
+        }
+
+        self.isa_falgs = isa_flags;
+        self.ext_flags = ext_flags | implied_ext;
+        self.xlen = xlen;
+        Ok(())
     }
 
     pub fn set_disable_check_tohost(&mut self, disable: bool) {
@@ -93,10 +343,54 @@ impl Config {
     pub fn disable_check_tohost(&self) -> bool {
         self.disable_check_tohost
     }
+    pub fn set_misaligned_access(&mut self, enable: bool) {
+        self.misaligned_access = enable;
+    }
+    pub fn misaligned_access(&self) -> bool {
+        self.misaligned_access
+    }
+    // a power of two between 8 bytes and the page size
+    pub fn set_cboz_block_size(&mut self, size: usize) {
+        match size {
+            8..=4096 if size.is_power_of_two() => self.cboz_block_size = size,
+            err => panic!("cboz block size err:{err}"),
+        }
+    }
+    pub fn cboz_block_size(&self) -> usize {
+        self.cboz_block_size
+    }
+    // 0 makes WFI trap at once, as spike and qemu do
+    pub fn set_wfi_timeout(&mut self, cycles: usize) {
+        self.wfi_timeout = cycles;
+    }
+    pub fn wfi_timeout(&self) -> usize {
+        self.wfi_timeout
+    }
+    // a fixed seed makes the values read from the seed csr reproducible
+    pub fn set_entropy_seed(&mut self, seed: u64) {
+        self.entropy_seed = Some(seed);
+    }
+    pub fn entropy_seed(&self) -> Option<u64> {
+        self.entropy_seed
+    }
 
     pub fn is_enable_isa(&self, isa: u8) -> bool {
-        let idx = isa - b'a';
-        self.isa_falgs & (1 << idx) != 0
+        self.isa_falgs & isa_mask(isa) != 0
+    }
+
+    // query a multi-letter extension such as "zba"
+    pub fn is_enable_ext(&self, ext: &str) -> bool {
+        self.ext_flags & ext_mask(ext) != 0
+    }
+
+    // MXLEN, the XLEN of M-mode
+    pub fn xlen(&self) -> usize {
+        self.xlen
+    }
+    // An RV64 hart may run S-mode and U-mode with XLEN=32 by mstatus.SXL and UXL.
+    // A hypervisor only runs 64-bit guests, so the fields are fixed with H.
+    pub fn xlen_switchable(&self) -> bool {
+        self.xlen == 64 && self.u_mode && !self.h_mode()
     }
 
     pub fn get_mmu_type(&self) -> StapMode {
@@ -112,23 +406,41 @@ impl Config {
     pub fn decode_cache_size(&self) -> Option<usize> {
         self.decode_cache_size
     }
+    pub fn block_cache_size(&self) -> Option<usize> {
+        self.block_cache_size
+    }
     pub fn tlb_size(&self) -> Option<usize> {
         self.tlb_size
     }
 
+    pub fn pmp_num(&self) -> usize {
+        self.pmp_num
+    }
+
+    pub fn vlen(&self) -> usize {
+        self.vlen
+    }
+    pub fn elen(&self) -> usize {
+        self.elen
+    }
+
     pub fn s_mode(&self) -> bool {
         self.s_mode
     }
     pub fn u_mode(&self) -> bool {
         self.u_mode
     }
+    // the hypervisor extension needs S-mode
+    pub fn h_mode(&self) -> bool {
+        self.s_mode && self.is_enable_isa(b'h')
+    }
 }
 
 #[test]
 fn config_isa_test() {
     simple_logger::SimpleLogger::new().init().unwrap();
     let mut config = Config::new();
-    config.set_isa("RV64IMAC_zicsr");
+    config.set_isa("RV64IMAC_zicsr").unwrap();
 
     assert!(config.is_enable_isa(b'i'));
     assert!(config.is_enable_isa(b'm'));
@@ -137,4 +449,134 @@ fn config_isa_test() {
 
     assert!(!config.is_enable_isa(b'f'));
     assert!(!config.is_enable_isa(b'd'));
+
+    let mut config = Config::new();
+    config.set_isa("rv64imc_zba_zbb_zbs").unwrap();
+
+    assert!(config.is_enable_ext("zba"));
+    assert!(config.is_enable_ext("zbb"));
+    assert!(config.is_enable_ext("zbs"));
+    assert!(!config.is_enable_ext("zbc"));
+    // the letters of multi-letter extensions are not single-letter extensions
+    assert!(!config.is_enable_isa(b'a'));
+    assert!(!config.is_enable_isa(b'b'));
+
+    let mut config = Config::new();
+    config.set_isa("rv64gc_zba1p0_zbb_sstc").unwrap();
+    assert!(config.is_enable_isa(b'f'));
+    assert!(config.is_enable_isa(b'd'));
+    assert!(config.is_enable_ext("zifencei"));
+    assert!(config.is_enable_ext("zba"));
+    assert!(config.is_enable_ext("sstc"));
+
+    let mut config = Config::new();
+    config.set_isa("rv64i2p1m2p0a_zicsr2p0").unwrap();
+    assert!(config.is_enable_isa(b'm'));
+    assert!(config.is_enable_isa(b'a'));
+
+    let mut config = Config::new();
+    config.set_isa("rv64imacv").unwrap();
+    assert!(config.is_enable_isa(b'v'));
+    assert!(!config.is_enable_isa(b'f'));
+
+    let mut config = Config::new();
+    config.set_isa("rv64imac_zacas_zabha").unwrap();
+    assert!(config.is_enable_ext("zacas"));
+    assert!(config.is_enable_ext("zabha"));
+
+    let mut config = Config::new();
+    config
+        .set_isa("rv64imac_zicond_zihintpause_zawrs_zimop")
+        .unwrap();
+    assert!(config.is_enable_ext("zicond"));
+    assert!(config.is_enable_ext("zawrs"));
+    assert!(!config.is_enable_ext("zacas"));
+
+    let mut config = Config::new();
+    config.set_isa("rv64imach").unwrap();
+    assert!(config.is_enable_isa(b'h'));
+    assert!(!config.h_mode());
+    config.set_s_mode();
+    assert!(config.h_mode());
+    assert!(!config.xlen_switchable());
+
+    let mut config = Config::new();
+    config.set_isa("rv32gc_zicond_zawrs").unwrap();
+    assert_eq!(config.xlen(), 32);
+    assert!(config.is_enable_isa(b'd'));
+    assert!(config.is_enable_ext("zawrs"));
+    config.set_s_mode();
+    assert!(!config.xlen_switchable());
+    config.set_isa("rv64gc").unwrap();
+    assert_eq!(config.xlen(), 64);
+    assert!(config.xlen_switchable());
+}
+
+#[test]
+fn config_isa_err_test() {
+    let mut config = Config::new();
+
+    assert_eq!(
+        config.set_isa("rv128imac"),
+        Err(IsaErr::UnsupportedXlen("rv128imac".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv32imacv"),
+        Err(IsaErr::Rv64Only("v".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv32imac_zba"),
+        Err(IsaErr::Rv64Only("zba".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv64mac"),
+        Err(IsaErr::InvalidBase("m".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv64imacq"),
+        Err(IsaErr::UnknownExtension("q".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv64imac_zfoo"),
+        Err(IsaErr::UnknownExtension("zfoo".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv64imm"),
+        Err(IsaErr::DuplicateExtension("m".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv64i_zba_zba"),
+        Err(IsaErr::DuplicateExtension("zba".into()))
+    );
+    assert_eq!(
+        config.set_isa("rv64imadc"),
+        Err(IsaErr::MissingDependency("d".into(), "f"))
+    );
+    assert_eq!(
+        config.set_isa("rv64imc_zacas"),
+        Err(IsaErr::MissingDependency("zacas".into(), "a"))
+    );
+    assert_eq!(
+        config.set_isa("rv64ima_zcmp"),
+        Err(IsaErr::MissingDependency("zcmp".into(), "c"))
+    );
+    assert_eq!(
+        config.set_isa("rv64imafdc_zcmp"),
+        Err(IsaErr::Conflict("zcmp".into(), "d"))
+    );
+    assert_eq!(
+        config.set_isa("rv64gc_zcb_zcmt"),
+        Err(IsaErr::Conflict("zcmt".into(), "d"))
+    );
+    assert_eq!(
+        config.set_isa("rv64imü"),
+        Err(IsaErr::UnknownExtension("ü".into()))
+    );
+    // U+0163 truncates to b'c'
+    assert_eq!(
+        config.set_isa("rv64ima\u{163}"),
+        Err(IsaErr::UnknownExtension("\u{163}".into()))
+    );
+    // a failed parse leaves the config untouched
+    assert!(!config.is_enable_isa(b'i'));
 }
@@ -0,0 +1,316 @@
+use alloc::vec::Vec;
+use log::warn;
+
+use crate::{rv64core::csr_regs_define::XipIn, tools::RcCell};
+
+use super::device_trait::DeviceBase;
+
+/*
+ * RISC-V ACLINT, the CLINT split into three devices that can be placed
+ * anywhere and serve a group of harts:
+ *
+ * MSWI:   base + 4 × i: MSIP of hart first_hart + i
+ * SSWI:   base + 4 × i: SETSSIP of hart first_hart + i
+ * MTIMER: base + 8 × i: MTIMECMP of hart first_hart + i
+ *         base + 0x7FF8: MTIME, shared by all the MTIMERs of the bus
+ */
+const SWI_PER_HART: u64 = 0x4;
+const SWI_SIZE: u64 = 0x4000;
+const MTIMECMP_PER_HART: u64 = 0x8;
+const MTIME_OFFSET: u64 = 0x7ff8;
+const MTIMER_SIZE: u64 = 0x8000;
+
+// the hart index is 12 bits wide
+const ACLINT_MAX_HARTS: usize = 4095;
+
+pub struct DeviceAclint {
+    pub start: u64,
+    pub len: u64,
+    pub instance: Aclint,
+    pub name: &'static str,
+}
+
+// the harts served by a device, indexed by hart_id - first
+struct HartGroup<T> {
+    first: usize,
+    slots: Vec<Option<T>>,
+}
+
+impl<T> HartGroup<T> {
+    fn new(first: usize, num: usize) -> Self {
+        assert!(
+            num <= ACLINT_MAX_HARTS,
+            "aclint num_harts:{} is too large",
+            num
+        );
+        HartGroup {
+            first,
+            slots: (0..num).map(|_| None).collect(),
+        }
+    }
+    fn get(&self, idx: usize) -> Option<&T> {
+        self.slots.get(idx)?.as_ref()
+    }
+    fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        self.slots.get_mut(idx)?.as_mut()
+    }
+    // the slot of hart_id, if it belongs to the group
+    fn slot(&mut self, hart_id: usize) -> Option<&mut Option<T>> {
+        self.slots.get_mut(hart_id.checked_sub(self.first)?)
+    }
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().flatten()
+    }
+}
+
+// MSWI drives msip, SSWI sets ssip and reads as zero
+pub struct AclintSwi {
+    harts: HartGroup<RcCell<XipIn>>,
+    supervisor: bool,
+}
+
+impl AclintSwi {
+    fn read(&self, idx: usize) -> u64 {
+        match (self.supervisor, self.harts.get(idx)) {
+            (false, Some(xip)) => xip.get().msip() as u64,
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, idx: usize, data: u64) {
+        let Some(xip) = self.harts.get(idx) else {
+            return;
+        };
+        let mut val = xip.get();
+        match self.supervisor {
+            true if data & 1 != 0 => val.set_ssip(true),
+            true => {}
+            false => val.set_msip(data & 1 != 0),
+        }
+        xip.set(val);
+    }
+}
+
+struct MtimerHart {
+    mtimecmp: u64,
+    xip: RcCell<XipIn>,
+}
+
+pub struct AclintMtimer {
+    harts: HartGroup<MtimerHart>,
+    mtime: RcCell<u64>,
+}
+
+impl AclintMtimer {
+    fn tick(&mut self) {
+        let mtime = self.mtime.get();
+        for hart in self.harts.iter() {
+            let mut xip = hart.xip.get();
+            xip.set_mtip(mtime >= hart.mtimecmp);
+            hart.xip.set(xip);
+        }
+    }
+}
+
+pub enum Aclint {
+    Mswi(AclintSwi),
+    Sswi(AclintSwi),
+    Mtimer(AclintMtimer),
+}
+
+impl Aclint {
+    // num_harts harts, starting from first_hart
+    pub fn new_mswi(first_hart: usize, num_harts: usize) -> Self {
+        Aclint::Mswi(AclintSwi {
+            harts: HartGroup::new(first_hart, num_harts),
+            supervisor: false,
+        })
+    }
+
+    pub fn new_sswi(first_hart: usize, num_harts: usize) -> Self {
+        Aclint::Sswi(AclintSwi {
+            harts: HartGroup::new(first_hart, num_harts),
+            supervisor: true,
+        })
+    }
+
+    // the mtimers of a platform share the same mtime
+    pub fn new_mtimer(first_hart: usize, num_harts: usize, mtime: RcCell<u64>) -> Self {
+        Aclint::Mtimer(AclintMtimer {
+            harts: HartGroup::new(first_hart, num_harts),
+            mtime,
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        match self {
+            Aclint::Mswi(_) | Aclint::Sswi(_) => SWI_SIZE,
+            Aclint::Mtimer(_) => MTIMER_SIZE,
+        }
+    }
+
+    // Connect the hart if it belongs to the group, the mtimer returns its mtime.
+    pub fn add_hart(&mut self, hart_id: usize, xip_share: RcCell<XipIn>) -> Option<RcCell<u64>> {
+        match self {
+            Aclint::Mswi(swi) | Aclint::Sswi(swi) => {
+                let slot = swi.harts.slot(hart_id)?;
+                *slot = Some(xip_share);
+                None
+            }
+            Aclint::Mtimer(mtimer) => {
+                let slot = mtimer.harts.slot(hart_id)?;
+                *slot = Some(MtimerHart {
+                    mtimecmp: u64::MAX,
+                    xip: xip_share,
+                });
+                Some(mtimer.mtime.clone())
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if let Aclint::Mtimer(mtimer) = self {
+            mtimer.tick();
+        }
+    }
+
+    // the mtime ticks until the next timer interrupt of an mtimer
+    pub fn timer_delta(&self) -> Option<u64> {
+        let Aclint::Mtimer(mtimer) = self else {
+            return None;
+        };
+        let mtime = mtimer.mtime.get();
+        mtimer
+            .harts
+            .iter()
+            .filter(|hart| hart.mtimecmp > mtime && hart.mtimecmp != u64::MAX)
+            .map(|hart| hart.mtimecmp - mtime)
+            .min()
+    }
+}
+
+// a 4 or 8 bytes access to a 64-bit register
+fn read_u64(reg: u64, offset: u64, len: usize) -> u64 {
+    match (offset, len) {
+        (0, 8) => reg,
+        (0, 4) => reg & 0xffff_ffff,
+        (4, 4) => reg >> 32,
+        _ => 0,
+    }
+}
+
+fn write_u64(reg: u64, offset: u64, data: u64, len: usize) -> u64 {
+    match (offset, len) {
+        (0, 8) => data,
+        (0, 4) => (reg & !0xffff_ffff) | (data & 0xffff_ffff),
+        (4, 4) => (reg & 0xffff_ffff) | (data << 32),
+        _ => reg,
+    }
+}
+
+impl DeviceBase for Aclint {
+    fn do_read(&mut self, addr: u64, len: usize) -> u64 {
+        match self {
+            Aclint::Mswi(swi) | Aclint::Sswi(swi) => swi_index(addr).map_or(0, |idx| swi.read(idx)),
+            Aclint::Mtimer(mtimer) if addr >= MTIME_OFFSET => {
+                read_u64(mtimer.mtime.get(), addr - MTIME_OFFSET, len)
+            }
+            Aclint::Mtimer(mtimer) => {
+                let idx = (addr / MTIMECMP_PER_HART) as usize;
+                match mtimer.harts.get(idx) {
+                    Some(hart) => read_u64(hart.mtimecmp, addr % MTIMECMP_PER_HART, len),
+                    _ => 0,
+                }
+            }
+        }
+    }
+
+    fn do_write(&mut self, addr: u64, data: u64, len: usize) -> u64 {
+        match self {
+            Aclint::Mswi(swi) | Aclint::Sswi(swi) => match swi_index(addr) {
+                Some(idx) if len == 4 => swi.write(idx, data),
+                _ => warn!("aclint swi write addr:0x{:x} len:{}", addr, len),
+            },
+            Aclint::Mtimer(mtimer) if addr >= MTIME_OFFSET => {
+                let mtime = write_u64(mtimer.mtime.get(), addr - MTIME_OFFSET, data, len);
+                mtimer.mtime.set(mtime);
+                mtimer.tick();
+            }
+            Aclint::Mtimer(mtimer) => {
+                let idx = (addr / MTIMECMP_PER_HART) as usize;
+                if let Some(hart) = mtimer.harts.get_mut(idx) {
+                    hart.mtimecmp = write_u64(hart.mtimecmp, addr % MTIMECMP_PER_HART, data, len);
+                }
+                mtimer.tick();
+            }
+        }
+        0
+    }
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            Aclint::Mswi(_) => "ACLINT MSWI",
+            Aclint::Sswi(_) => "ACLINT SSWI",
+            Aclint::Mtimer(_) => "ACLINT MTIMER",
+        }
+    }
+
+    fn do_update(&mut self) {
+        self.tick();
+    }
+}
+
+// the register index of an aligned access
+fn swi_index(addr: u64) -> Option<usize> {
+    (addr & (SWI_PER_HART - 1) == 0).then_some((addr / SWI_PER_HART) as usize)
+}
+
+#[test]
+fn aclint_test() {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    let mtime = Rc::new(Cell::new(0));
+    let xip0 = Rc::new(Cell::new(XipIn::new()));
+    let xip1 = Rc::new(Cell::new(XipIn::new()));
+
+    // a group of harts 1 ~ 2, hart 0 is not served
+    let mut mswi = Aclint::new_mswi(1, 2);
+    let mut sswi = Aclint::new_sswi(1, 2);
+    let mut mtimer = Aclint::new_mtimer(1, 2, mtime.clone());
+    assert!(mtimer.add_hart(0, xip0.clone()).is_none());
+    assert!(mtimer.add_hart(3, xip1.clone()).is_none());
+    mswi.add_hart(1, xip1.clone());
+    sswi.add_hart(1, xip1.clone());
+    let mtimer_mtime = mtimer.add_hart(1, xip1.clone()).unwrap();
+    assert!(Rc::ptr_eq(&mtime, &mtimer_mtime));
+
+    // hart 1 is the first of the group
+    mswi.do_write(0, 1, 4);
+    assert!(xip1.get().msip());
+    assert_eq!(mswi.do_read(0, 4), 1);
+    mswi.do_write(0, 0, 4);
+    assert!(!xip1.get().msip());
+
+    // SETSSIP only sets SSIP, and reads as zero
+    sswi.do_write(0, 1, 4);
+    assert!(xip1.get().ssip());
+    assert_eq!(sswi.do_read(0, 4), 0);
+    sswi.do_write(0, 0, 4);
+    assert!(xip1.get().ssip());
+
+    mtimer.do_write(0, 100, 8);
+    mtimer.do_write(MTIME_OFFSET, 99, 8);
+    assert!(!xip1.get().mtip());
+    mtime.set(100);
+    mtimer.tick();
+    assert!(xip1.get().mtip());
+    assert_eq!(mtimer.do_read(MTIME_OFFSET + 4, 4), 0);
+    mtimer.do_write(4, 1, 4);
+    assert!(!xip1.get().mtip());
+    assert_eq!(mtimer.do_read(0, 8), 1 << 32 | 100);
+    assert!(!xip0.get().mtip());
+    // the ticks left until the mtimecmp of hart 1
+    assert_eq!(mtimer.timer_delta(), Some(1 << 32));
+    assert_eq!(mswi.timer_delta(), None);
+}
@@ -0,0 +1,732 @@
+use core::cell::Cell;
+
+use alloc::{rc::Rc, vec::Vec};
+use log::warn;
+
+use crate::{rv64core::csr_regs_define::XipIn, tools::RcCell};
+
+use super::{device_imsic::ImsicFile, device_trait::DeviceBase};
+
+/*
+ * AIA advanced platform-level interrupt controller, with the root M-level
+ * domain at base and its S-level child domain at base + S_DOMAIN_BASE.
+ * Each domain has the memory map:
+ *
+ * base + 0x0000: domaincfg
+ * base + 0x0004: sourcecfg[1] ~ sourcecfg[1023]
+ * base + 0x1BC0: mmsiaddrcfg, mmsiaddrcfgh, smsiaddrcfg, smsiaddrcfgh
+ * base + 0x1C00: setip[0] ~ setip[31]
+ * base + 0x1CDC: setipnum
+ * base + 0x1D00: in_clrip[0] ~ in_clrip[31]
+ * base + 0x1DDC: clripnum
+ * base + 0x1E00: setie[0] ~ setie[31]
+ * base + 0x1EDC: setienum
+ * base + 0x1F00: clrie[0] ~ clrie[31]
+ * base + 0x1FDC: clrienum
+ * base + 0x2000: setipnum_le
+ * base + 0x2004: setipnum_be
+ * base + 0x3000: genmsi
+ * base + 0x3004: target[1] ~ target[1023]
+ * base + 0x4000: interrupt delivery control, 32 bytes per hart
+ */
+const DOMAINCFG: u64 = 0x0000;
+const SOURCECFG_BASE: u64 = 0x0004;
+const SOURCECFG_END: u64 = 0x0ffc;
+const MSIADDRCFG_BASE: u64 = 0x1bc0;
+const MSIADDRCFG_END: u64 = 0x1bcc;
+const SETIP_BASE: u64 = 0x1c00;
+const SETIP_END: u64 = 0x1c7c;
+const SETIPNUM: u64 = 0x1cdc;
+const IN_CLRIP_BASE: u64 = 0x1d00;
+const IN_CLRIP_END: u64 = 0x1d7c;
+const CLRIPNUM: u64 = 0x1ddc;
+const SETIE_BASE: u64 = 0x1e00;
+const SETIE_END: u64 = 0x1e7c;
+const SETIENUM: u64 = 0x1edc;
+const CLRIE_BASE: u64 = 0x1f00;
+const CLRIE_END: u64 = 0x1f7c;
+const CLRIENUM: u64 = 0x1fdc;
+const SETIPNUM_LE: u64 = 0x2000;
+const SETIPNUM_BE: u64 = 0x2004;
+const GENMSI: u64 = 0x3000;
+const TARGET_BASE: u64 = 0x3004;
+const TARGET_END: u64 = 0x3ffc;
+const IDC_BASE: u64 = 0x4000;
+const IDC_PER_HART: u64 = 0x20;
+
+const IDC_IDELIVERY: u64 = 0x00;
+const IDC_IFORCE: u64 = 0x04;
+const IDC_ITHRESHOLD: u64 = 0x08;
+const IDC_TOPI: u64 = 0x18;
+const IDC_CLAIMI: u64 = 0x1c;
+
+pub const S_DOMAIN_BASE: u64 = 0x0100_0000;
+
+// sources 1 ~ 63, the others are not implemented
+pub const APLIC_NUM_SOURCES: u32 = 63;
+
+// domaincfg: bit 31 reads as one, IE is bit 8 and DM (MSI delivery) is bit 2
+const DOMAINCFG_RO: u32 = 0x8000_0000;
+const DOMAINCFG_IE: u32 = 1 << 8;
+const DOMAINCFG_DM: u32 = 1 << 2;
+
+// sourcecfg: D delegates the source to the child domain, otherwise SM is the source mode
+const SOURCECFG_D: u32 = 1 << 10;
+const SOURCECFG_SM: u32 = 0x7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceMode {
+    Inactive = 0,
+    Detached = 1,
+    Edge1 = 4,
+    Edge0 = 5,
+    Level1 = 6,
+    Level0 = 7,
+}
+
+impl SourceMode {
+    fn from_sourcecfg(cfg: u32) -> Self {
+        match cfg & SOURCECFG_SM {
+            1 => SourceMode::Detached,
+            4 => SourceMode::Edge1,
+            5 => SourceMode::Edge0,
+            6 => SourceMode::Level1,
+            7 => SourceMode::Level0,
+            _ => SourceMode::Inactive,
+        }
+    }
+    fn is_level(&self) -> bool {
+        matches!(self, SourceMode::Level1 | SourceMode::Level0)
+    }
+    // the input value after inverting the low and falling-edge modes
+    fn rectify(&self, input: bool) -> bool {
+        match self {
+            SourceMode::Edge1 | SourceMode::Level1 => input,
+            SourceMode::Edge0 | SourceMode::Level0 => !input,
+            SourceMode::Inactive | SourceMode::Detached => false,
+        }
+    }
+}
+
+pub struct DeviceAplic {
+    pub start: u64,
+    pub len: u64,
+    pub instance: Aplic,
+    pub name: &'static str,
+}
+
+struct IrqSource {
+    id: u32,
+    input: Rc<Cell<bool>>,
+}
+
+// interrupt delivery control of a hart, used by the direct delivery mode
+#[derive(Clone, Copy, Default)]
+struct Idc {
+    idelivery: bool,
+    iforce: bool,
+    ithreshold: u32,
+}
+
+#[derive(Clone, Copy)]
+struct AplicDomain {
+    domaincfg: u32,
+    sourcecfg: [u32; 64],
+    target: [u32; 64],
+    pending: u64,
+    enable: u64,
+    // the rectified inputs, to detect the edges
+    rectified: u64,
+}
+
+impl AplicDomain {
+    fn new() -> Self {
+        AplicDomain {
+            domaincfg: 0,
+            sourcecfg: [0; 64],
+            target: [0; 64],
+            pending: 0,
+            enable: 0,
+            rectified: 0,
+        }
+    }
+    fn ie(&self) -> bool {
+        self.domaincfg & DOMAINCFG_IE != 0
+    }
+    fn msi_mode(&self) -> bool {
+        self.domaincfg & DOMAINCFG_DM != 0
+    }
+    fn mode(&self, id: u32) -> SourceMode {
+        let cfg = self.sourcecfg[id as usize];
+        match cfg & SOURCECFG_D != 0 {
+            true => SourceMode::Inactive,
+            false => SourceMode::from_sourcecfg(cfg),
+        }
+    }
+    // the sources that are not inactive in this domain
+    fn active(&self) -> u64 {
+        (1..=APLIC_NUM_SOURCES)
+            .filter(|id| self.mode(*id) != SourceMode::Inactive)
+            .fold(0, |acc, id| acc | 1 << id)
+    }
+    fn target_hart(&self, id: u32) -> usize {
+        (self.target[id as usize] >> 18) as usize
+    }
+    // direct mode priority, a priority of zero is read as one
+    fn target_iprio(&self, id: u32) -> u32 {
+        (self.target[id as usize] & 0xff).max(1)
+    }
+    fn target_eiid(&self, id: u32) -> u64 {
+        (self.target[id as usize] & 0x7ff) as u64
+    }
+
+    // the highest priority pending and enabled source for the hart: (id, iprio)
+    fn top(&self, hart: usize, idc: &Idc) -> Option<(u32, u32)> {
+        let ready = self.pending & self.enable & self.active();
+        (1..=APLIC_NUM_SOURCES)
+            .filter(|id| ready >> id & 1 != 0 && self.target_hart(*id) == hart)
+            .map(|id| (id, self.target_iprio(id)))
+            .filter(|(_, iprio)| idc.ithreshold == 0 || *iprio < idc.ithreshold)
+            .min_by_key(|(id, iprio)| (*iprio, *id))
+    }
+
+    // setip and setipnum only set the pending bit of active sources,
+    // and of level sources only when the rectified input is high
+    fn set_pending(&mut self, id: u32) {
+        let mode = self.mode(id);
+        let ok = match mode {
+            SourceMode::Inactive => false,
+            SourceMode::Level1 | SourceMode::Level0 => {
+                self.msi_mode() && self.rectified >> id & 1 != 0
+            }
+            _ => true,
+        };
+        if ok {
+            self.pending |= 1 << id;
+        }
+    }
+    fn clear_pending(&mut self, id: u32) {
+        let mode = self.mode(id);
+        if !mode.is_level() || self.msi_mode() {
+            self.pending &= !(1 << id);
+        }
+    }
+}
+
+struct AplicHart {
+    xip: RcCell<XipIn>,
+    // the MSI targets of the M and S domains
+    files: [Option<Rc<ImsicFile>>; 2],
+    idc: [Idc; 2],
+}
+
+pub struct Aplic {
+    irq_sources: Vec<IrqSource>,
+    // 0: the root M-level domain, 1: the S-level child domain
+    domains: [AplicDomain; 2],
+    msiaddrcfg: [u32; 4],
+    harts: Vec<AplicHart>,
+}
+
+impl Aplic {
+    pub fn new() -> Self {
+        Aplic {
+            irq_sources: Vec::new(),
+            domains: [AplicDomain::new(); 2],
+            msiaddrcfg: [0; 4],
+            harts: Vec::new(),
+        }
+    }
+
+    // The source is a wire, its level is sampled at every tick.
+    pub fn register_irq_source(&mut self, irq_id: u32, irq_input: Rc<Cell<bool>>) {
+        assert!(
+            (1..=APLIC_NUM_SOURCES).contains(&irq_id),
+            "irq_id:{} is out of range",
+            irq_id
+        );
+        if self.irq_sources.iter().any(|item| item.id == irq_id) {
+            panic!("irq_id:{} is already registered", irq_id);
+        };
+        self.irq_sources.push(IrqSource {
+            id: irq_id,
+            input: irq_input,
+        });
+    }
+
+    // The xip is driven in the direct delivery mode, the interrupt files
+    // receive the MSIs of the M and S domains.
+    pub fn add_hart(
+        &mut self,
+        xip_share: RcCell<XipIn>,
+        m_file: Option<Rc<ImsicFile>>,
+        s_file: Option<Rc<ImsicFile>>,
+    ) {
+        self.harts.push(AplicHart {
+            xip: xip_share,
+            files: [m_file, s_file],
+            idc: [Idc::default(); 2],
+        });
+    }
+
+    pub fn tick(&mut self) {
+        let inputs = self
+            .irq_sources
+            .iter()
+            .filter(|item| item.input.get())
+            .fold(0_u64, |acc, item| acc | 1 << item.id);
+
+        for (level, domain) in self.domains.iter_mut().enumerate() {
+            let mut rectified = 0;
+            for id in 1..=APLIC_NUM_SOURCES {
+                let mode = domain.mode(id);
+                let high = mode.rectify(inputs >> id & 1 != 0);
+                rectified |= (high as u64) << id;
+                let rising = high && domain.rectified >> id & 1 == 0;
+                // a level source tracks its input in the direct mode, while
+                // in the MSI mode it is only set again by a new assertion
+                match mode {
+                    SourceMode::Level1 | SourceMode::Level0 if !domain.msi_mode() => {
+                        domain.pending = (domain.pending & !(1 << id)) | ((high as u64) << id);
+                    }
+                    SourceMode::Edge1
+                    | SourceMode::Edge0
+                    | SourceMode::Level1
+                    | SourceMode::Level0
+                        if rising =>
+                    {
+                        domain.pending |= 1 << id;
+                    }
+                    _ => {}
+                }
+            }
+            domain.rectified = rectified;
+
+            match domain.msi_mode() {
+                true => Self::forward_msis(domain, level, &self.harts),
+                false => Self::update_harts(domain, level, &self.harts),
+            }
+        }
+    }
+
+    // send the pending and enabled sources to the interrupt files
+    fn forward_msis(domain: &mut AplicDomain, level: usize, harts: &[AplicHart]) {
+        if !domain.ie() {
+            return;
+        }
+        let mut ready = domain.pending & domain.enable & domain.active();
+        while ready != 0 {
+            let id = ready.trailing_zeros();
+            ready &= ready - 1;
+            domain.pending &= !(1 << id);
+            let file = harts
+                .get(domain.target_hart(id))
+                .and_then(|hart| hart.files[level].as_ref());
+            match file {
+                Some(file) => file.set_pending(domain.target_eiid(id)),
+                None => warn!("aplic msi to a missing interrupt file, source:{}", id),
+            }
+        }
+    }
+
+    // A hart with an interrupt file at this level takes meip/seip from the file,
+    // so a domain left in the direct mode must not overwrite it.
+    fn update_harts(domain: &AplicDomain, level: usize, harts: &[AplicHart]) {
+        for (i, hart) in harts.iter().enumerate() {
+            if hart.files[level].is_some() {
+                continue;
+            }
+            let idc = &hart.idc[level];
+            let level_eip =
+                domain.ie() && idc.idelivery && (idc.iforce || domain.top(i, idc).is_some());
+            let mut xip = hart.xip.get();
+            match level {
+                0 => xip.set_meip(level_eip),
+                _ => xip.set_seip(level_eip),
+            };
+            hart.xip.set(xip);
+        }
+    }
+
+    fn topi(&self, level: usize, hart: usize) -> u32 {
+        let idc = &self.harts[hart].idc[level];
+        self.domains[level]
+            .top(hart, idc)
+            .map_or(0, |(id, iprio)| id << 16 | iprio)
+    }
+
+    // reading claimi returns topi and clears the pending bit, or iforce if nothing is pending
+    fn claimi(&mut self, level: usize, hart: usize) -> u32 {
+        let topi = self.topi(level, hart);
+        match topi >> 16 {
+            0 => self.harts[hart].idc[level].iforce = false,
+            id => self.domains[level].clear_pending(id),
+        }
+        topi
+    }
+
+    fn idc_read(&mut self, level: usize, offset: u64) -> u32 {
+        let hart = (offset / IDC_PER_HART) as usize;
+        if hart >= self.harts.len() {
+            return 0;
+        }
+        let idc = &self.harts[hart].idc[level];
+        match offset % IDC_PER_HART {
+            IDC_IDELIVERY => idc.idelivery as u32,
+            IDC_IFORCE => idc.iforce as u32,
+            IDC_ITHRESHOLD => idc.ithreshold,
+            IDC_TOPI => self.topi(level, hart),
+            IDC_CLAIMI => self.claimi(level, hart),
+            _ => 0,
+        }
+    }
+
+    fn idc_write(&mut self, level: usize, offset: u64, val: u32) {
+        let hart = (offset / IDC_PER_HART) as usize;
+        let Some(hart) = self.harts.get_mut(hart) else {
+            return;
+        };
+        let idc = &mut hart.idc[level];
+        match offset % IDC_PER_HART {
+            IDC_IDELIVERY => idc.idelivery = val & 1 != 0,
+            IDC_IFORCE => idc.iforce = val & 1 != 0,
+            IDC_ITHRESHOLD => idc.ithreshold = val & 0xff,
+            _ => {}
+        }
+    }
+
+    fn domain_read(&mut self, level: usize, offset: u64) -> u32 {
+        let domain = self.domains[level];
+        match offset {
+            DOMAINCFG => DOMAINCFG_RO | domain.domaincfg,
+            SOURCECFG_BASE..=SOURCECFG_END => {
+                let id = (offset / 4) as usize;
+                domain.sourcecfg.get(id).copied().unwrap_or(0)
+            }
+            MSIADDRCFG_BASE..=MSIADDRCFG_END if level == 0 => {
+                self.msiaddrcfg[((offset - MSIADDRCFG_BASE) / 4) as usize]
+            }
+            SETIP_BASE..=SETIP_END => word_read(domain.pending, (offset - SETIP_BASE) / 4),
+            IN_CLRIP_BASE..=IN_CLRIP_END => {
+                word_read(domain.rectified, (offset - IN_CLRIP_BASE) / 4)
+            }
+            SETIE_BASE..=SETIE_END => word_read(domain.enable, (offset - SETIE_BASE) / 4),
+            TARGET_BASE..=TARGET_END => {
+                let id = ((offset - GENMSI) / 4) as usize;
+                domain.target.get(id).copied().unwrap_or(0)
+            }
+            IDC_BASE.. if !domain.msi_mode() => self.idc_read(level, offset - IDC_BASE),
+            _ => 0,
+        }
+    }
+
+    fn domain_write(&mut self, level: usize, offset: u64, val: u32) {
+        let id_of = |val: u32| (1..=APLIC_NUM_SOURCES).contains(&val).then_some(val);
+        let msi_mode = self.domains[level].msi_mode();
+        match offset {
+            SOURCECFG_BASE..=SOURCECFG_END => {
+                if let Some(id) = id_of((offset / 4) as u32) {
+                    self.write_sourcecfg(level, id, val);
+                }
+                return;
+            }
+            MSIADDRCFG_BASE..=MSIADDRCFG_END if level == 0 => {
+                self.msiaddrcfg[((offset - MSIADDRCFG_BASE) / 4) as usize] = val;
+                return;
+            }
+            // the MSI is sent at once, so genmsi.Busy always reads as zero
+            GENMSI if msi_mode => {
+                let hart = (val >> 18) as usize;
+                let file = self
+                    .harts
+                    .get(hart)
+                    .and_then(|hart| hart.files[level].as_ref());
+                if let Some(file) = file {
+                    file.set_pending((val & 0x7ff) as u64);
+                }
+                return;
+            }
+            IDC_BASE.. if !msi_mode => {
+                self.idc_write(level, offset - IDC_BASE, val);
+                return;
+            }
+            _ => {}
+        }
+
+        let harts = self.harts.len();
+        let domain = &mut self.domains[level];
+        match offset {
+            DOMAINCFG => domain.domaincfg = val & (DOMAINCFG_IE | DOMAINCFG_DM),
+            SETIP_BASE..=SETIP_END => {
+                let mut bits = word_bits((offset - SETIP_BASE) / 4, val);
+                while bits != 0 {
+                    domain.set_pending(bits.trailing_zeros());
+                    bits &= bits - 1;
+                }
+            }
+            SETIPNUM | SETIPNUM_LE => {
+                if let Some(id) = id_of(val) {
+                    domain.set_pending(id);
+                }
+            }
+            SETIPNUM_BE => {
+                if let Some(id) = id_of(val.swap_bytes()) {
+                    domain.set_pending(id);
+                }
+            }
+            IN_CLRIP_BASE..=IN_CLRIP_END => {
+                let mut bits = word_bits((offset - IN_CLRIP_BASE) / 4, val);
+                while bits != 0 {
+                    domain.clear_pending(bits.trailing_zeros());
+                    bits &= bits - 1;
+                }
+            }
+            CLRIPNUM => {
+                if let Some(id) = id_of(val) {
+                    domain.clear_pending(id);
+                }
+            }
+            // only the active sources can be enabled
+            SETIE_BASE..=SETIE_END => {
+                domain.enable |= word_bits((offset - SETIE_BASE) / 4, val) & domain.active();
+            }
+            SETIENUM => {
+                if let Some(id) = id_of(val) {
+                    domain.enable |= 1 << id & domain.active();
+                }
+            }
+            CLRIE_BASE..=CLRIE_END => {
+                domain.enable &= !word_bits((offset - CLRIE_BASE) / 4, val);
+            }
+            CLRIENUM => {
+                if let Some(id) = id_of(val) {
+                    domain.enable &= !(1 << id);
+                }
+            }
+            TARGET_BASE..=TARGET_END => {
+                if let Some(id) = id_of(((offset - GENMSI) / 4) as u32) {
+                    // the hart index is limited to the existing harts
+                    let hart = ((val >> 18) as usize).min(harts.saturating_sub(1)) as u32;
+                    let low = match msi_mode {
+                        true => val & 0x3f7ff,
+                        false => (val & 0xff).max(1),
+                    };
+                    domain.target[id as usize] = hart << 18 | low;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Only the root domain can delegate a source to its child. A source that is
+    // not delegated to the child domain is read-only zero there.
+    fn write_sourcecfg(&mut self, level: usize, id: u32, val: u32) {
+        let idx = id as usize;
+        let cfg = match (level, val & SOURCECFG_D != 0) {
+            (0, true) => SOURCECFG_D,
+            (0, false) => val & SOURCECFG_SM,
+            (_, _) if self.domains[0].sourcecfg[idx] & SOURCECFG_D == 0 => 0,
+            (_, true) => 0,
+            (_, false) => val & SOURCECFG_SM,
+        };
+        // the reserved modes 2 and 3 are treated as inactive
+        let cfg = match cfg & SOURCECFG_D == 0 && matches!(cfg, 2 | 3) {
+            true => 0,
+            false => cfg,
+        };
+        self.domains[level].sourcecfg[idx] = cfg;
+        if level == 0 && cfg & SOURCECFG_D == 0 {
+            self.domains[1].sourcecfg[idx] = 0;
+        }
+        // a source changing its mode loses its pending and enable bits
+        for domain in self.domains.iter_mut() {
+            if domain.mode(id) == SourceMode::Inactive {
+                domain.pending &= !(1 << id);
+                domain.enable &= !(1 << id);
+            }
+        }
+    }
+}
+
+// the bits of word idx in a 32-bit register array, limited to the implemented sources
+fn word_read(bits: u64, idx: u64) -> u32 {
+    match idx {
+        0 => bits as u32,
+        1 => (bits >> 32) as u32,
+        _ => 0,
+    }
+}
+
+fn word_bits(idx: u64, val: u32) -> u64 {
+    let bits = match idx {
+        0 => val as u64 & !1,
+        1 => (val as u64) << 32,
+        _ => 0,
+    };
+    bits & source_mask()
+}
+
+fn source_mask() -> u64 {
+    u64::MAX >> (63 - APLIC_NUM_SOURCES) & !1
+}
+
+impl Default for Aplic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceBase for Aplic {
+    fn do_read(&mut self, addr: u64, len: usize) -> u64 {
+        if len != 4 {
+            warn!("aplic read addr:0x{:x} len:{}", addr, len);
+            return 0;
+        }
+        let level = (addr / S_DOMAIN_BASE) as usize;
+        self.domain_read(level, addr % S_DOMAIN_BASE) as u64
+    }
+
+    fn do_write(&mut self, addr: u64, data: u64, len: usize) -> u64 {
+        if len != 4 {
+            warn!("aplic write addr:0x{:x} len:{}", addr, len);
+            return 0;
+        }
+        let level = (addr / S_DOMAIN_BASE) as usize;
+        self.domain_write(level, addr % S_DOMAIN_BASE, data as u32);
+        0
+    }
+
+    fn get_name(&self) -> &'static str {
+        "APLIC"
+    }
+
+    fn do_update(&mut self) {
+        self.tick();
+    }
+
+    fn reset(&mut self) {
+        self.domains = [AplicDomain::new(); 2];
+        self.msiaddrcfg = [0; 4];
+        self.harts
+            .iter_mut()
+            .for_each(|hart| hart.idc = [Idc::default(); 2]);
+    }
+}
+
+#[cfg(test)]
+fn aplic_for_test() -> (Aplic, Rc<Cell<bool>>, RcCell<XipIn>, Rc<ImsicFile>) {
+    let input = Rc::new(Cell::new(false));
+    let xip = Rc::new(Cell::new(XipIn::new()));
+    let s_file = Rc::new(ImsicFile::new(xip.clone(), false));
+    let mut aplic = Aplic::new();
+    aplic.register_irq_source(10, input.clone());
+    aplic.add_hart(xip.clone(), None, Some(s_file.clone()));
+    (aplic, input, xip, s_file)
+}
+
+#[test]
+fn aplic_direct_test() {
+    let (mut aplic, input, xip, _) = aplic_for_test();
+    aplic.do_write(SOURCECFG_BASE + 9 * 4, SourceMode::Level1 as u64, 4);
+    aplic.do_write(TARGET_BASE + 9 * 4, 3, 4);
+    aplic.do_write(SETIENUM, 10, 4);
+    aplic.do_write(IDC_BASE + IDC_IDELIVERY, 1, 4);
+    aplic.do_write(DOMAINCFG, DOMAINCFG_IE as u64, 4);
+    assert_eq!(aplic.do_read(DOMAINCFG, 4), 0x8000_0100);
+
+    aplic.tick();
+    assert!(!xip.get().meip());
+    input.set(true);
+    aplic.tick();
+    assert!(xip.get().meip());
+    assert_eq!(aplic.do_read(IDC_BASE + IDC_TOPI, 4), 10 << 16 | 3);
+
+    // the threshold masks priority 3 and above
+    aplic.do_write(IDC_BASE + IDC_ITHRESHOLD, 3, 4);
+    aplic.tick();
+    assert!(!xip.get().meip());
+    aplic.do_write(IDC_BASE + IDC_ITHRESHOLD, 0, 4);
+
+    // a level source stays pending until its input is low
+    assert_eq!(aplic.do_read(IDC_BASE + IDC_CLAIMI, 4), 10 << 16 | 3);
+    aplic.tick();
+    assert!(xip.get().meip());
+    input.set(false);
+    aplic.tick();
+    assert!(!xip.get().meip());
+    assert_eq!(aplic.do_read(IDC_BASE + IDC_CLAIMI, 4), 0);
+}
+
+#[test]
+fn aplic_msi_test() {
+    use super::device_imsic::{ISELECT_EIDELIVERY, ISELECT_EIE0};
+
+    let (mut aplic, input, xip, s_file) = aplic_for_test();
+    s_file.write_ireg(ISELECT_EIDELIVERY, 1);
+    s_file.write_ireg(ISELECT_EIE0, u64::MAX);
+
+    // the root domain delegates source 10 to the S domain
+    aplic.do_write(SOURCECFG_BASE + 9 * 4, SOURCECFG_D as u64, 4);
+    let s_domain = S_DOMAIN_BASE;
+    // the M domain can not use it anymore
+    aplic.do_write(SETIENUM, 10, 4);
+    assert_eq!(aplic.do_read(SETIE_BASE, 4), 0);
+
+    aplic.do_write(
+        s_domain + SOURCECFG_BASE + 9 * 4,
+        SourceMode::Edge1 as u64,
+        4,
+    );
+    aplic.do_write(s_domain + TARGET_BASE + 9 * 4, 42, 4);
+    aplic.do_write(s_domain + SETIENUM, 10, 4);
+    aplic.do_write(
+        s_domain + DOMAINCFG,
+        (DOMAINCFG_IE | DOMAINCFG_DM) as u64,
+        4,
+    );
+
+    input.set(true);
+    aplic.tick();
+    assert!(xip.get().seip());
+    assert_eq!(s_file.read_topei(), 42 << 16 | 42);
+    s_file.claim();
+    assert!(!xip.get().seip());
+
+    // no new MSI until the next edge
+    aplic.tick();
+    assert!(!xip.get().seip());
+    input.set(false);
+    aplic.tick();
+    input.set(true);
+    aplic.tick();
+    assert!(xip.get().seip());
+
+    // genmsi sends an MSI directly
+    s_file.claim();
+    aplic.do_write(s_domain + GENMSI, 7, 4);
+    assert_eq!(s_file.read_topei(), 7 << 16 | 7);
+}
+
+#[test]
+fn aplic_direct_with_imsic_test() {
+    use super::device_imsic::{ISELECT_EIDELIVERY, ISELECT_EIE0};
+
+    let xip = Rc::new(Cell::new(XipIn::new()));
+    let m_file = Rc::new(ImsicFile::new(xip.clone(), true));
+    let mut aplic = Aplic::new();
+    aplic.add_hart(xip.clone(), Some(m_file.clone()), None);
+    m_file.write_ireg(ISELECT_EIDELIVERY, 1);
+    m_file.write_ireg(ISELECT_EIE0, u64::MAX);
+
+    // the M domain is still in the direct mode after reset
+    aplic.do_write(DOMAINCFG, DOMAINCFG_IE as u64, 4);
+    aplic.do_write(IDC_BASE + IDC_IDELIVERY, 1, 4);
+    m_file.set_pending(9);
+    assert!(xip.get().meip());
+    aplic.tick();
+    aplic.tick();
+    assert!(xip.get().meip());
+
+    m_file.claim();
+    assert!(!xip.get().meip());
+}
@@ -0,0 +1,281 @@
+use core::cell::Cell;
+
+use alloc::{rc::Rc, vec::Vec};
+use log::warn;
+
+use crate::{rv64core::csr_regs_define::XipIn, tools::RcCell};
+
+use super::device_trait::DeviceBase;
+
+/*
+ * AIA incoming MSI controller, one 4KiB interrupt file per hart:
+ *
+ * base + 0x0000: seteipnum_le
+ * base + 0x0004: seteipnum_be
+ *
+ * The M-level files start at base, the S-level files at base + S_FILE_BASE.
+ */
+const FILE_SIZE: u64 = 0x1000;
+const SETEIPNUM_LE: u64 = 0x0;
+const SETEIPNUM_BE: u64 = 0x4;
+pub const S_FILE_BASE: u64 = 0x0400_0000;
+
+// identities 1 ~ 255, identity 0 does not exist
+pub const IMSIC_NUM_IDS: u64 = 255;
+const EIX_WORDS: usize = 4;
+
+// the registers selected by miselect/siselect, iprio0 ~ iprio15 hold the major
+// interrupt priorities and the others belong to the interrupt file
+pub const ISELECT_IPRIO0: u64 = 0x30;
+pub const ISELECT_IPRIO15: u64 = 0x3f;
+pub const ISELECT_EIDELIVERY: u64 = 0x70;
+pub const ISELECT_EITHRESHOLD: u64 = 0x72;
+pub const ISELECT_EIP0: u64 = 0x80;
+pub const ISELECT_EIP63: u64 = 0xbf;
+pub const ISELECT_EIE0: u64 = 0xc0;
+pub const ISELECT_EIE63: u64 = 0xff;
+
+pub struct DeviceImsic {
+    pub start: u64,
+    pub len: u64,
+    pub instance: Imsic,
+    pub name: &'static str,
+}
+
+// An interrupt file, shared between the MSI writes on the bus and the
+// indirect csrs of its hart. A pending and enabled identity raises meip/seip.
+pub struct ImsicFile {
+    eidelivery: Cell<bool>,
+    eithreshold: Cell<u64>,
+    eip: [Cell<u64>; EIX_WORDS],
+    eie: [Cell<u64>; EIX_WORDS],
+    xip: RcCell<XipIn>,
+    mmode: bool,
+}
+
+impl ImsicFile {
+    pub fn new(xip_share: RcCell<XipIn>, mmode: bool) -> Self {
+        ImsicFile {
+            eidelivery: Cell::new(false),
+            eithreshold: Cell::new(0),
+            eip: Default::default(),
+            eie: Default::default(),
+            xip: xip_share,
+            mmode,
+        }
+    }
+
+    pub fn reset(&self) {
+        self.eidelivery.set(false);
+        self.eithreshold.set(0);
+        self.eip.iter().for_each(|word| word.set(0));
+        self.eie.iter().for_each(|word| word.set(0));
+        self.update_xip();
+    }
+
+    // an MSI, out of range identities are ignored
+    pub fn set_pending(&self, id: u64) {
+        if id == 0 || id > IMSIC_NUM_IDS {
+            return;
+        }
+        let word = &self.eip[id as usize / 64];
+        word.set(word.get() | 1 << (id % 64));
+        self.update_xip();
+    }
+
+    // the lowest pending and enabled identity below eithreshold, 0 if none
+    fn top_id(&self) -> u64 {
+        let threshold = self.eithreshold.get();
+        let top = self
+            .eip
+            .iter()
+            .zip(self.eie.iter())
+            .enumerate()
+            .find_map(|(i, (eip, eie))| {
+                let bits = eip.get() & eie.get();
+                (bits != 0).then(|| i as u64 * 64 + bits.trailing_zeros() as u64)
+            })
+            .unwrap_or(0);
+        match threshold != 0 && top >= threshold {
+            true => 0,
+            false => top,
+        }
+    }
+
+    // xtopei: the identity in 26:16 and the priority, the same number, in 10:0
+    pub fn read_topei(&self) -> u64 {
+        let id = self.top_id();
+        id << 16 | id
+    }
+
+    // a write to xtopei claims the interrupt it reports
+    pub fn claim(&self) {
+        let id = self.top_id();
+        if id != 0 {
+            let word = &self.eip[id as usize / 64];
+            word.set(word.get() & !(1 << (id % 64)));
+            self.update_xip();
+        }
+    }
+
+    pub fn read_ireg(&self, iselect: u64) -> u64 {
+        match iselect {
+            ISELECT_EIDELIVERY => self.eidelivery.get() as u64,
+            ISELECT_EITHRESHOLD => self.eithreshold.get(),
+            ISELECT_EIP0..=ISELECT_EIP63 => {
+                eix_word(&self.eip, iselect - ISELECT_EIP0).map_or(0, |word| word.get())
+            }
+            ISELECT_EIE0..=ISELECT_EIE63 => {
+                eix_word(&self.eie, iselect - ISELECT_EIE0).map_or(0, |word| word.get())
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn write_ireg(&self, iselect: u64, data: u64) {
+        match iselect {
+            ISELECT_EIDELIVERY => self.eidelivery.set(data & 1 != 0),
+            ISELECT_EITHRESHOLD => self.eithreshold.set(data & IMSIC_NUM_IDS),
+            ISELECT_EIP0..=ISELECT_EIP63 => {
+                if let Some(word) = eix_word(&self.eip, iselect - ISELECT_EIP0) {
+                    word.set(eix_mask(iselect - ISELECT_EIP0, data));
+                }
+            }
+            ISELECT_EIE0..=ISELECT_EIE63 => {
+                if let Some(word) = eix_word(&self.eie, iselect - ISELECT_EIE0) {
+                    word.set(eix_mask(iselect - ISELECT_EIE0, data));
+                }
+            }
+            _ => {}
+        }
+        self.update_xip();
+    }
+
+    fn update_xip(&self) {
+        let level = self.eidelivery.get() && self.top_id() != 0;
+        let mut xip = self.xip.get();
+        match self.mmode {
+            true => xip.set_meip(level),
+            false => xip.set_seip(level),
+        };
+        self.xip.set(xip);
+    }
+}
+
+// On RV64 only the even eipX/eieX exist, each holding 64 identities.
+// The words beyond IMSIC_NUM_IDS are read-only zero.
+fn eix_word(eix: &[Cell<u64>; EIX_WORDS], idx: u64) -> Option<&Cell<u64>> {
+    eix.get(idx as usize / 2)
+}
+
+// identity 0 is read-only zero
+fn eix_mask(idx: u64, data: u64) -> u64 {
+    match idx {
+        0 => data & !1,
+        _ => data,
+    }
+}
+
+pub struct Imsic {
+    m_files: Vec<Rc<ImsicFile>>,
+    s_files: Vec<Rc<ImsicFile>>,
+}
+
+impl Imsic {
+    pub fn new() -> Self {
+        Imsic {
+            m_files: Vec::new(),
+            s_files: Vec::new(),
+        }
+    }
+    // the files of a hart are mapped at its hart index
+    pub fn add_hart(&mut self, m_file: Rc<ImsicFile>, s_file: Option<Rc<ImsicFile>>) {
+        self.m_files.push(m_file);
+        if let Some(s_file) = s_file {
+            self.s_files.push(s_file);
+        }
+    }
+
+    fn file(&self, addr: u64) -> Option<&Rc<ImsicFile>> {
+        let (files, offset) = match addr >= S_FILE_BASE {
+            true => (&self.s_files, addr - S_FILE_BASE),
+            false => (&self.m_files, addr),
+        };
+        files.get((offset / FILE_SIZE) as usize)
+    }
+}
+
+impl Default for Imsic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceBase for Imsic {
+    // seteipnum is write-only
+    fn do_read(&mut self, _addr: u64, _len: usize) -> u64 {
+        0
+    }
+
+    fn do_write(&mut self, addr: u64, data: u64, len: usize) -> u64 {
+        let id = match (addr % FILE_SIZE, len) {
+            (SETEIPNUM_LE, 4) => data as u32,
+            (SETEIPNUM_BE, 4) => (data as u32).swap_bytes(),
+            _ => {
+                warn!("imsic write addr:0x{:x} len:{}", addr, len);
+                return 0;
+            }
+        };
+        match self.file(addr) {
+            Some(file) => file.set_pending(id as u64),
+            None => warn!("imsic write to a missing file, addr:0x{:x}", addr),
+        }
+        0
+    }
+
+    fn get_name(&self) -> &'static str {
+        "IMSIC"
+    }
+
+    fn reset(&mut self) {
+        self.m_files.iter().for_each(|file| file.reset());
+        self.s_files.iter().for_each(|file| file.reset());
+    }
+}
+
+#[test]
+fn imsic_file_test() {
+    let xip = Rc::new(Cell::new(XipIn::new()));
+    let file = Rc::new(ImsicFile::new(xip.clone(), true));
+    let mut imsic = Imsic::new();
+    imsic.add_hart(file.clone(), None);
+
+    file.write_ireg(ISELECT_EIE0, 0xffff_ffff);
+    imsic.do_write(SETEIPNUM_LE, 5, 4);
+    imsic.do_write(SETEIPNUM_LE, 3, 4);
+    // the file is not delivering yet
+    assert!(!xip.get().meip());
+    assert_eq!(file.read_ireg(ISELECT_EIP0), 0b101000);
+
+    file.write_ireg(ISELECT_EIDELIVERY, 1);
+    assert!(xip.get().meip());
+    assert_eq!(file.read_topei(), 3 << 16 | 3);
+
+    // only identities below the threshold are reported
+    file.write_ireg(ISELECT_EITHRESHOLD, 3);
+    assert_eq!(file.read_topei(), 0);
+    assert!(!xip.get().meip());
+    file.write_ireg(ISELECT_EITHRESHOLD, 0);
+
+    file.claim();
+    assert_eq!(file.read_topei(), 5 << 16 | 5);
+    file.claim();
+    assert_eq!(file.read_topei(), 0);
+    assert!(!xip.get().meip());
+
+    // a disabled identity stays pending
+    imsic.do_write(SETEIPNUM_BE, 100_u32.swap_bytes() as u64, 4);
+    assert!(!xip.get().meip());
+    assert_eq!(file.read_ireg(ISELECT_EIP0 + 2), 1 << 36);
+    assert_eq!(file.read_ireg(ISELECT_EIP0), 0);
+}
@@ -84,6 +84,16 @@ impl Clint {
         self.mitme.set(mitme);
     }
 
+    // the mtime ticks until the next timer interrupt, None if no timer is armed
+    pub fn timer_delta(&self) -> Option<u64> {
+        let mtime = self.mitme.get();
+        self.harts
+            .iter()
+            .filter(|hart| hart.mtimecmp > mtime && hart.mtimecmp != u64::MAX)
+            .map(|hart| hart.mtimecmp - mtime)
+            .min()
+    }
+
     pub fn tick(&mut self, inc: usize) {
         self.mtime_inc(inc);
         for hart in self.harts.iter_mut() {
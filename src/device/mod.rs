@@ -1,5 +1,8 @@
 pub mod device_16550a;
+pub mod device_aclint;
 pub mod device_am_uart;
+pub mod device_aplic;
+pub mod device_imsic;
 pub mod device_memory;
 pub mod device_sifive_clint;
 pub mod device_sifive_plic;
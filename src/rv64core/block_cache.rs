@@ -0,0 +1,278 @@
+use alloc::{rc::Rc, vec::Vec};
+use hashbrown::HashMap;
+use hashlink::LruCache;
+use log::info;
+
+use crate::rv64core::inst::inst_base::{
+    is_branch, is_compressed_instruction, Instruction, Operands, PrivilegeLevels,
+};
+
+// a block never crosses a page, so one translation serves all of its instructions
+const BLOCK_PAGE_SHIFT: u64 = 12;
+// the longest run decoded into one block
+pub const BLOCK_MAX_INSTS: usize = 64;
+
+// an instruction decoded once when its block is built, the handler gets its operands
+#[derive(Clone, Copy)]
+pub struct DecodedInst {
+    pub inst: u32,
+    pub op: Option<&'static Instruction>,
+    pub ops: Operands,
+    pub len: u8,
+    // BEQ ~ BGEU, C.BEQZ and C.BNEZ, counted by the BranchTaken event
+    pub branch: bool,
+}
+
+impl DecodedInst {
+    pub fn new(inst: u32, op: Option<&'static Instruction>, rv32: bool) -> Self {
+        DecodedInst {
+            inst,
+            op,
+            ops: Operands::decode(inst, rv32),
+            len: if is_compressed_instruction(inst) {
+                2
+            } else {
+                4
+            },
+            branch: is_branch(inst),
+        }
+    }
+}
+
+// a straight-line run of instructions in one physical page, decoded for one XLEN
+pub struct InstBlock {
+    insts: Vec<DecodedInst>,
+    rv32: bool,
+    bytes: usize,
+}
+
+impl InstBlock {
+    pub fn new(insts: Vec<DecodedInst>, rv32: bool) -> Self {
+        let bytes = insts.iter().map(|d| d.len as usize).sum();
+        InstBlock { insts, rv32, bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.insts.is_empty()
+    }
+
+    // the bytes covered by the block, checked against the pmp on every entry
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+// The last instruction of a block: jumps, SYSTEM (csr, xRET, ecall, wfi, ...) and
+// MISC-MEM (fence, fence.i, cbo). Whatever may change the XLEN, the privilege or the
+// translation ends the block, taken conditional branches leave it on their own.
+pub fn ends_block(inst: u32, rv32: bool) -> bool {
+    let funct3 = (inst >> 13) & 0b111;
+    match inst & 0b11 {
+        // c.j, and c.jal on RV32
+        0b01 => funct3 == 0b101 || (rv32 && funct3 == 0b001),
+        // c.jr, c.jalr and c.ebreak, and the Zcmp/Zcmt jumps
+        0b10 => (funct3 == 0b100 && (inst >> 2) & 0x1f == 0) || funct3 == 0b101,
+        0b00 => false,
+        _ => matches!(inst & 0x7f, 0x6f | 0x67 | 0x73 | 0x0f),
+    }
+}
+
+// Pre-decoded basic blocks keyed by the physical pc of their first instruction.
+// The running block is followed as long as the pc stays sequential, a store to a
+// page holding blocks drops them, fence.i and sfence.vma drop all of them.
+pub struct BlockCache {
+    blocks: LruCache<u64, Rc<InstBlock>>,
+    // the blocks starting in each physical page
+    pages: HashMap<u64, Vec<u64>>,
+    running: Option<Rc<InstBlock>>,
+    running_page: u64,
+    running_mode: (PrivilegeLevels, bool),
+    next: usize,
+    next_pc: u64,
+    hit: u64,
+    miss: u64,
+}
+
+impl BlockCache {
+    pub fn new(size: usize) -> Self {
+        BlockCache {
+            blocks: LruCache::new(size),
+            pages: HashMap::new(),
+            running: None,
+            running_page: 0,
+            running_mode: (PrivilegeLevels::Machine, false),
+            next: 0,
+            next_pc: 0,
+            hit: 0,
+            miss: 0,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.blocks.capacity() != 0
+    }
+
+    // the next instruction of the running block, if pc follows it in the same mode
+    pub fn next(&mut self, pc: u64, mode: (PrivilegeLevels, bool)) -> Option<DecodedInst> {
+        let block = self.running.as_ref()?;
+        match block.insts.get(self.next) {
+            Some(d) if pc == self.next_pc && mode == self.running_mode => {
+                let d = *d;
+                self.next += 1;
+                self.next_pc = pc.wrapping_add(d.len as u64);
+                Some(d)
+            }
+            _ => {
+                self.running = None;
+                None
+            }
+        }
+    }
+
+    pub fn lookup(&mut self, paddr: u64, rv32: bool) -> Option<Rc<InstBlock>> {
+        match self.blocks.get(&paddr) {
+            Some(block) if block.rv32 == rv32 => {
+                self.hit += 1;
+                Some(block.clone())
+            }
+            _ => {
+                self.miss += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, paddr: u64, block: InstBlock) -> Rc<InstBlock> {
+        let block = Rc::new(block);
+        if self.blocks.contains_key(&paddr) {
+            self.blocks.insert(paddr, block.clone());
+            return block;
+        }
+        if self.blocks.len() >= self.blocks.capacity() {
+            if let Some((old, _)) = self.blocks.remove_lru() {
+                self.unlink(old);
+            }
+        }
+        self.blocks.insert(paddr, block.clone());
+        self.pages
+            .entry(paddr >> BLOCK_PAGE_SHIFT)
+            .or_default()
+            .push(paddr);
+        block
+    }
+
+    // start running block at its first instruction, pc is its virtual address
+    pub fn enter(
+        &mut self,
+        block: Rc<InstBlock>,
+        paddr: u64,
+        pc: u64,
+        mode: (PrivilegeLevels, bool),
+    ) -> Option<DecodedInst> {
+        let first = *block.insts.first()?;
+        self.running = Some(block);
+        self.running_page = paddr >> BLOCK_PAGE_SHIFT;
+        self.running_mode = mode;
+        self.next = 1;
+        self.next_pc = pc.wrapping_add(first.len as u64);
+        Some(first)
+    }
+
+    fn unlink(&mut self, paddr: u64) {
+        let page = paddr >> BLOCK_PAGE_SHIFT;
+        if let Some(starts) = self.pages.get_mut(&page) {
+            starts.retain(|start| *start != paddr);
+            if starts.is_empty() {
+                self.pages.remove(&page);
+            }
+        }
+    }
+
+    // a store to [paddr, paddr + len) drops the blocks of the pages it touches
+    pub fn invalidate(&mut self, paddr: u64, len: usize) {
+        if self.pages.is_empty() {
+            return;
+        }
+        let first = paddr >> BLOCK_PAGE_SHIFT;
+        let last = paddr.saturating_add(len.max(1) as u64 - 1) >> BLOCK_PAGE_SHIFT;
+        for page in first..=last {
+            let Some(starts) = self.pages.remove(&page) else {
+                continue;
+            };
+            starts.iter().for_each(|start| {
+                self.blocks.remove(start);
+            });
+            if self.running_page == page {
+                self.running = None;
+            }
+        }
+    }
+
+    // stop following the running block, the next instruction looks up a block again
+    pub fn stop(&mut self) {
+        self.running = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.pages.clear();
+        self.running = None;
+    }
+
+    pub fn show_perf(&self) {
+        info!("block cache hit: {}, miss: {}", self.hit, self.miss);
+    }
+}
+
+#[test]
+fn block_cache_test() {
+    use crate::rv64core::inst::inst_rv64i::INSTRUCTIONS_I;
+    use alloc::vec;
+
+    let op = Some(&INSTRUCTIONS_I[0]);
+    let mode = (PrivilegeLevels::Supervisor, false);
+    let addi = DecodedInst::new(0x0010_0093, op, false);
+    let c_addi = DecodedInst::new(0x0085, op, false);
+    let mut cache = BlockCache::new(2);
+
+    let block = cache.insert(0x8000_0000, InstBlock::new(vec![addi, c_addi, addi], false));
+    assert_eq!(block.bytes(), 10);
+    assert!(cache.lookup(0x8000_0000, true).is_none());
+    let block = cache.lookup(0x8000_0000, false).unwrap();
+
+    // the block is followed while the pc is sequential and the mode is unchanged
+    assert!(cache.enter(block, 0x8000_0000, 0x1000, mode).is_some());
+    assert_eq!(cache.next(0x1004, mode).unwrap().inst, 0x0085);
+    assert!(cache
+        .next(0x1006, (PrivilegeLevels::Machine, false))
+        .is_none());
+    assert!(cache.next(0x1006, mode).is_none());
+
+    // a store to the page drops its blocks and the running block
+    let block = cache.lookup(0x8000_0000, false).unwrap();
+    cache.enter(block, 0x8000_0000, 0x1000, mode);
+    cache.insert(0x8000_1000, InstBlock::new(vec![addi], false));
+    cache.invalidate(0x8000_0ffc, 8);
+    assert!(cache.next(0x1004, mode).is_none());
+    assert!(cache.lookup(0x8000_0000, false).is_none());
+    assert!(cache.lookup(0x8000_1000, false).is_none());
+
+    // the least recently used block is evicted along with its page entry
+    cache.insert(0x8000_2000, InstBlock::new(vec![addi], false));
+    cache.insert(0x8000_3000, InstBlock::new(vec![addi], false));
+    cache.insert(0x8000_4000, InstBlock::new(vec![addi], false));
+    assert!(cache.lookup(0x8000_2000, false).is_none());
+    assert!(!cache.pages.contains_key(&0x8_0002));
+
+    cache.clear();
+    assert!(cache.lookup(0x8000_4000, false).is_none());
+
+    assert!(ends_block(0x0000_006f, false)); // jal
+    assert!(ends_block(0x0000_8067, false)); // ret
+    assert!(ends_block(0x3020_0073, false)); // mret
+    assert!(ends_block(0x0000_100f, false)); // fence.i
+    assert!(ends_block(0x8082, false)); // c.jr ra
+    assert!(ends_block(0x2001, true)); // c.jal
+    assert!(!ends_block(0x2001, false)); // c.addiw
+    assert!(!ends_block(0x0000_0463, false)); // beq
+}
@@ -1,17 +1,21 @@
 use core::cmp::max;
 
+use alloc::rc::Rc;
 use alloc::vec::Vec;
 use alloc::{boxed::Box, string::ToString};
 use log::warn;
 
-use crate::tools::{check_aligned, check_area};
+use crate::tools::{check_aligned, check_area, RcCell};
 use crate::{
     device::{
+        device_aclint::{Aclint, DeviceAclint},
+        device_aplic::{Aplic, DeviceAplic},
+        device_imsic::{DeviceImsic, Imsic},
         device_sifive_clint::{Clint, DeviceClint},
         device_sifive_plic::{DevicePlic, SifvePlic},
         device_trait::DeviceBase,
     },
-    rv64core::inst::inst_rv64a::LrScReservation,
+    rv64core::{csr_regs_define::XipIn, inst::inst_rv64a::LrScReservation},
 };
 
 use super::inst::inst_base::RVerr;
@@ -28,8 +32,15 @@ unsafe impl Send for DeviceType {}
 pub struct Bus {
     pub clint: DeviceClint,
     pub plic: DevicePlic,
+    // AIA interrupt controllers, the aplic takes over the plic area once enabled
+    pub aplic: Option<DeviceAplic>,
+    pub imsic: Option<DeviceImsic>,
+    // ACLINT devices, a hart served by them is not connected to the clint
+    pub aclint: Vec<DeviceAclint>,
+    // the mtime shared by the aclint mtimers
+    pub aclint_mtime: RcCell<u64>,
     pub devices: Vec<DeviceType>,
-    pub lr_sc_set: LrScReservation, // for rv64a inst
+    pub lr_sc_set: Rc<LrScReservation>, // for rv64a inst
 }
 
 unsafe impl Send for Bus {}
@@ -54,7 +65,11 @@ impl Bus {
             devices: vec![],
             clint,
             plic,
-            lr_sc_set: LrScReservation::new(),
+            aplic: None,
+            imsic: None,
+            aclint: vec![],
+            aclint_mtime: RcCell::new(0.into()),
+            lr_sc_set: Rc::new(LrScReservation::new()),
         }
     }
 
@@ -62,6 +77,48 @@ impl Bus {
         self.devices.push(device);
     }
 
+    // Harts built afterwards use the aclint devices of their group instead of the clint.
+    // The mtimers are expected to share aclint_mtime.
+    pub fn add_aclint(&mut self, start: u64, instance: Aclint) {
+        self.aclint.push(DeviceAclint {
+            start,
+            len: instance.size(),
+            name: instance.get_name(),
+            instance,
+        });
+    }
+
+    // connect a hart to the aclint devices serving it, and return the mtime of its mtimer
+    pub fn add_aclint_hart(
+        &mut self,
+        hart_id: usize,
+        xip_share: RcCell<XipIn>,
+    ) -> Option<RcCell<u64>> {
+        self.aclint.iter_mut().fold(None, |mtime, device| {
+            device
+                .instance
+                .add_hart(hart_id, xip_share.clone())
+                .or(mtime)
+        })
+    }
+
+    // Harts built afterwards deliver their external interrupts through the aplic
+    // and the interrupt files instead of the plic.
+    pub fn enable_aia(&mut self) {
+        self.aplic = Some(DeviceAplic {
+            start: 0x0C00_0000,
+            len: 0x0200_0000,
+            instance: Aplic::new(),
+            name: "APLIC",
+        });
+        self.imsic = Some(DeviceImsic {
+            start: 0x2400_0000,
+            len: 0x0500_0000,
+            instance: Imsic::new(),
+            name: "IMSIC",
+        });
+    }
+
     pub fn read(&mut self, addr: u64, len: usize) -> Result<u64, RVerr> {
         if !check_aligned(addr, len) {
             warn!("bus read:{:x},{:x}", addr, len);
@@ -71,7 +128,11 @@ impl Bus {
         // special devices
         // such as clint
         let mut special_device = || -> Result<u64, RVerr> {
-            if check_area(self.clint.start, self.clint.len, addr) {
+            if let Some((aia, offset)) = aia_device(&mut self.aplic, &mut self.imsic, addr) {
+                Ok(aia.do_read(offset, len))
+            } else if let Some((aclint, offset)) = aclint_device(&mut self.aclint, addr) {
+                Ok(aclint.do_read(offset, len))
+            } else if check_area(self.clint.start, self.clint.len, addr) {
                 Ok(self.clint.instance.do_read(addr - self.clint.start, len))
             } else if check_area(self.plic.start, self.plic.len, addr) {
                 Ok(self.plic.instance.do_read(addr - self.plic.start, len))
@@ -103,7 +164,11 @@ impl Bus {
         }
 
         let mut special_device = || -> Result<u64, RVerr> {
-            if check_area(self.clint.start, self.clint.len, addr) {
+            if let Some((aia, offset)) = aia_device(&mut self.aplic, &mut self.imsic, addr) {
+                Ok(aia.do_write(offset, data, len))
+            } else if let Some((aclint, offset)) = aclint_device(&mut self.aclint, addr) {
+                Ok(aclint.do_write(offset, data, len))
+            } else if check_area(self.clint.start, self.clint.len, addr) {
                 Ok(self
                     .clint
                     .instance
@@ -135,7 +200,13 @@ impl Bus {
 
     pub fn copy_from_slice(&mut self, addr: u64, data: &[u8]) -> Result<(), RVerr> {
         let mut special_device = || -> Result<(), RVerr> {
-            if check_area(self.clint.start, self.clint.len, addr) {
+            if let Some((aia, offset)) = aia_device(&mut self.aplic, &mut self.imsic, addr) {
+                aia.copy_from_slice(offset, data);
+                Ok(())
+            } else if let Some((aclint, offset)) = aclint_device(&mut self.aclint, addr) {
+                aclint.copy_from_slice(offset, data);
+                Ok(())
+            } else if check_area(self.clint.start, self.clint.len, addr) {
                 self.clint
                     .instance
                     .copy_from_slice(addr - self.clint.start, data);
@@ -173,7 +244,13 @@ impl Bus {
             .map(|device| device.instance.copy_to_slice(addr - device.start, data));
 
         let mut special_device = || -> Result<(), RVerr> {
-            if check_area(self.clint.start, self.clint.len, addr) {
+            if let Some((aia, offset)) = aia_device(&mut self.aplic, &mut self.imsic, addr) {
+                aia.copy_to_slice(offset, data);
+                Ok(())
+            } else if let Some((aclint, offset)) = aclint_device(&mut self.aclint, addr) {
+                aclint.copy_to_slice(offset, data);
+                Ok(())
+            } else if check_area(self.clint.start, self.clint.len, addr) {
                 self.clint
                     .instance
                     .copy_to_slice(addr - self.clint.start, data);
@@ -201,8 +278,67 @@ impl Bus {
         self.devices
             .iter_mut()
             .for_each(|device| device.instance.do_update());
-        self.clint.instance.tick(max(interval_cycle / 10, 1));
+        let mtime_inc = max(interval_cycle / 10, 1);
+        self.clint.instance.tick(mtime_inc);
+        self.aclint_mtime
+            .set(self.aclint_mtime.get() + mtime_inc as u64);
+        self.aclint
+            .iter_mut()
+            .for_each(|device| device.instance.tick());
         self.plic.instance.tick();
+        if let Some(aplic) = self.aplic.as_mut() {
+            aplic.instance.tick();
+        }
+    }
+
+    // the mtime ticks until the next clint or aclint timer interrupt
+    pub fn timer_delta(&self) -> Option<u64> {
+        self.aclint
+            .iter()
+            .filter_map(|device| device.instance.timer_delta())
+            .chain(self.clint.instance.timer_delta())
+            .min()
+    }
+
+    // move mtime forward without executing, e.g. while every hart waits in WFI
+    pub fn advance_mtime(&mut self, ticks: u64) {
+        self.clint.instance.tick(ticks as usize);
+        self.aclint_mtime.set(self.aclint_mtime.get() + ticks);
+        self.aclint
+            .iter_mut()
+            .for_each(|device| device.instance.tick());
+    }
+}
+
+// the aclint device at addr and the offset into it
+fn aclint_device(aclint: &mut [DeviceAclint], addr: u64) -> Option<(&mut dyn DeviceBase, u64)> {
+    aclint
+        .iter_mut()
+        .find(|device| check_area(device.start, device.len, addr))
+        .map(|device| {
+            (
+                &mut device.instance as &mut dyn DeviceBase,
+                addr - device.start,
+            )
+        })
+}
+
+// the aia device at addr and the offset into it
+fn aia_device<'a>(
+    aplic: &'a mut Option<DeviceAplic>,
+    imsic: &'a mut Option<DeviceImsic>,
+    addr: u64,
+) -> Option<(&'a mut dyn DeviceBase, u64)> {
+    if let Some(aplic) = aplic {
+        if check_area(aplic.start, aplic.len, addr) {
+            return Some((&mut aplic.instance, addr - aplic.start));
+        }
+    }
+    match imsic {
+        Some(imsic) if check_area(imsic.start, imsic.len, addr) => {
+            Some((&mut imsic.instance, addr - imsic.start))
+        }
+        _ => None,
     }
 }
 
@@ -244,6 +380,31 @@ impl core::fmt::Display for Bus {
         ))
         .unwrap();
 
+        let interrupt_devices = self
+            .aplic
+            .iter()
+            .map(|aplic| (aplic.name, aplic.start, aplic.len))
+            .chain(
+                self.imsic
+                    .iter()
+                    .map(|imsic| (imsic.name, imsic.start, imsic.len)),
+            )
+            .chain(
+                self.aclint
+                    .iter()
+                    .map(|aclint| (aclint.name, aclint.start, aclint.len)),
+            );
+        for (name, start, len) in interrupt_devices {
+            f.write_fmt(format_args!(
+                "name:{:15} Area:0X{:08X}-->0X{:08X},len:0X{:08X}\n",
+                name,
+                start,
+                start + len,
+                len
+            ))
+            .unwrap();
+        }
+
         x.for_each(|device_str| f.write_str(&device_str).unwrap());
         Ok(())
     }
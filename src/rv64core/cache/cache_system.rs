@@ -1,6 +1,10 @@
 use alloc::rc::Rc;
 
-use crate::{config::Config, rv64core::bus::Bus, tools::RcRefCell};
+use crate::{
+    config::Config,
+    rv64core::{bus::Bus, hpm::Hpm},
+    tools::RcRefCell,
+};
 
 use super::{cpu_dcache::CpuDcache, cpu_icache::CpuIcache};
 
@@ -12,9 +16,9 @@ pub struct CacheSystem {
 }
 
 impl CacheSystem {
-    pub fn new(bus: RcRefCell<Bus>, config: Rc<Config>) -> Self {
-        let icache = CpuIcache::new(bus.clone(), config.icache_size().unwrap_or(0));
-        let dcache = CpuDcache::new(bus.clone(),config.dcache_size().unwrap_or(0));
+    pub fn new(bus: RcRefCell<Bus>, config: Rc<Config>, hpm: Rc<Hpm>) -> Self {
+        let icache = CpuIcache::new(bus.clone(), config.icache_size().unwrap_or(0), hpm.clone());
+        let dcache = CpuDcache::new(bus.clone(),config.dcache_size().unwrap_or(0), hpm);
         CacheSystem {
             icache,
             dcache,
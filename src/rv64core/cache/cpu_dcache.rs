@@ -1,7 +1,9 @@
 use crate::rv64core::bus::Bus;
+use crate::rv64core::hpm::{Hpm, HpmEvent};
 use crate::rv64core::inst::inst_base::RVerr;
 use crate::tools::RcRefCell;
 
+use alloc::rc::Rc;
 use log::info;
 
 #[derive(Clone)]
@@ -61,10 +63,11 @@ pub struct CpuDcache {
     dcache_size: usize,
     hit: u64,
     miss: u64,
+    hpm: Rc<Hpm>,
 }
 
 impl CpuDcache {
-    pub fn new(bus: RcRefCell<Bus>, dcache_size: usize) -> Self {
+    pub fn new(bus: RcRefCell<Bus>, dcache_size: usize, hpm: Rc<Hpm>) -> Self {
         // let caches = vec![CacheLine::new(); 32];
         let caches = hashbrown::HashMap::new();
         CpuDcache {
@@ -73,6 +76,7 @@ impl CpuDcache {
             dcache_size,
             hit: 0,
             miss: 0,
+            hpm,
         }
     }
     fn tag(&self, addr: u64) -> u64 {
@@ -116,10 +120,12 @@ impl CpuDcache {
             .get_mut(&tag)
             .map(|cache_line| {
                 self.hit += 1;
+                self.hpm.record(HpmEvent::DcacheHit);
                 Ok(cache_line.read(offset, len))
             })
             .unwrap_or_else(|| {
                 self.miss += 1;
+                self.hpm.record(HpmEvent::DcacheMiss);
                 self.alloc_cache_line(addr);
                 self.read(addr, len)
             })
@@ -136,24 +142,60 @@ impl CpuDcache {
             .get_mut(&tag)
             .map(|cache_line| {
                 self.hit += 1;
+                self.hpm.record(HpmEvent::DcacheHit);
                 cache_line.write(offset, data, len);
                 Ok(0)
             })
             .unwrap_or_else(|| {
                 self.miss += 1;
+                self.hpm.record(HpmEvent::DcacheMiss);
                 self.alloc_cache_line(addr);
                 self.write(addr, data, len)
             })
     }
+    // Svpbmt NC/IO accesses go to the bus, a line cached through another mapping is
+    // flushed first so the bus sees the latest data
+    pub fn read_uncached(&mut self, addr: u64, len: usize) -> Result<u64, RVerr> {
+        self.flush(addr);
+        self.bus.borrow_mut().read(addr, len)
+    }
+    pub fn write_uncached(&mut self, addr: u64, data: u64, len: usize) -> Result<u64, RVerr> {
+        self.flush(addr);
+        self.bus.borrow_mut().write(addr, data, len)
+    }
+
+    // cbo.clean writes back a dirty cache line and keeps it
+    pub fn clean(&mut self, addr: u64) {
+        let tag = self.tag(addr);
+        if let Some(cache_line) = self.caches.get_mut(&tag) {
+            if cache_line.dirty() {
+                write_back(&mut self.bus.borrow_mut(), cache_line);
+                cache_line.dirty = false;
+            }
+        }
+    }
+
+    // cbo.flush writes back a dirty cache line and drops it
+    pub fn flush(&mut self, addr: u64) {
+        let tag = self.tag(addr);
+        if let Some(mut cache_line) = self.caches.remove(&tag) {
+            if cache_line.dirty() {
+                write_back(&mut self.bus.borrow_mut(), &mut cache_line);
+            }
+        }
+    }
+
+    // cbo.inval drops a cache line, dirty data is lost
+    pub fn inval(&mut self, addr: u64) {
+        let tag = self.tag(addr);
+        self.caches.remove(&tag);
+    }
+
     pub fn clear(&mut self) {
         let mut bus = self.bus.borrow_mut();
         self.caches.iter_mut().for_each(|(_, cache_line)| {
             if cache_line.dirty() {
-                let addr = cache_line.tag << 6;
-                for i in (0..64).step_by(8) {
-                    let data = cache_line.read(i, 8);
-                    bus.write(addr + i as u64, data, 8).unwrap();
-                }
+                write_back(&mut bus, cache_line);
             }
             // cache_line.clear()
         });
@@ -192,17 +234,20 @@ impl CpuDcache {
         if need_write_back {
             let mut cache_line_wb: CacheLine = self.remove_random().expect("remove_random err");
             let mut bus = self.bus.borrow_mut();
-
-            let addr = cache_line_wb.tag << 6;
-            for i in (0..64).step_by(8) {
-                let data: u64 = cache_line_wb.read(i, 8);
-                bus.write(addr + i as u64, data, 8).unwrap();
-            }
+            write_back(&mut bus, &mut cache_line_wb);
         };
         self.caches.insert(tag, new_line);
     }
 }
 
+fn write_back(bus: &mut Bus, cache_line: &mut CacheLine) {
+    let addr = cache_line.tag << 6;
+    for i in (0..64).step_by(8) {
+        let data = cache_line.read(i, 8);
+        bus.write(addr + i as u64, data, 8).unwrap();
+    }
+}
+
 // impl Default for CpuDcache {
 //     fn default() -> Self {
 //         Self::new()
@@ -1,8 +1,13 @@
+use alloc::rc::Rc;
 use hashbrown::HashMap;
 use log::info;
 
 use crate::{
-    rv64core::{bus::Bus, inst::inst_base::RVerr},
+    rv64core::{
+        bus::Bus,
+        hpm::{Hpm, HpmEvent},
+        inst::inst_base::RVerr,
+    },
     tools::RcRefCell,
 };
 
@@ -15,16 +20,18 @@ pub struct CpuIcache {
     icache_size: usize,
     hit: u64,
     miss: u64,
+    hpm: Rc<Hpm>,
 }
 
 impl CpuIcache {
-    pub fn new(bus: RcRefCell<Bus>, size: usize) -> Self {
+    pub fn new(bus: RcRefCell<Bus>, size: usize, hpm: Rc<Hpm>) -> Self {
         CpuIcache {
             bus,
             inst_hash: HashMap::new(),
             icache_size: size,
             hit: 0,
             miss: 0,
+            hpm,
         }
     }
 
@@ -45,12 +52,14 @@ impl CpuIcache {
 
         if let Some(inst_pack) = self.inst_hash.get(&pc) {
             self.hit += 1;
+            self.hpm.record(HpmEvent::IcacheHit);
             return Ok(inst_pack.inst as u64);
         }
         let mut bus = self.bus.borrow_mut();
         match bus.read(addr, len) {
             Ok(data) => {
                 self.miss += 1;
+                self.hpm.record(HpmEvent::IcacheMiss);
                 if self.inst_hash.len() >= self.icache_size {
                     drop(bus);
                     self.remove_random();
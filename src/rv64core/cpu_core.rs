@@ -1,6 +1,6 @@
 use core::{borrow::Borrow, cell::Cell, result};
 
-use alloc::rc::Rc;
+use alloc::{rc::Rc, vec::Vec};
 use log::{debug, info, warn};
 
 use crate::{
@@ -8,13 +8,21 @@ use crate::{
     dbg::dm_interface::DebugModuleSlave,
     difftest::difftest_trait::Difftest,
     rv64core::{
+        block_cache::{ends_block, BlockCache, DecodedInst, InstBlock, BLOCK_MAX_INSTS},
         bus::Bus,
         csr_regs::CsrRegs,
-        csr_regs_define::XipIn,
+        csr_regs_define::{XipIn, VS_INTERRUPT_MASK},
+        fpr::Fpr,
         gpr::Gpr,
-        inst::inst_base::{AccessType, PrivilegeLevels},
+        hpm::HpmEvent,
+        inst::{
+            inst_base::{AccessType, PrivilegeLevels, RVerr},
+            inst_rv64a::{LrScReservation, LR_SC_WINDOW},
+        },
         inst_decode::InstDecode,
         traptype::TrapType,
+        trigger::{TriggerAction, TriggerOp},
+        vpr::Vpr,
     },
     tools::{check_aligned, RcRefCell},
 };
@@ -23,8 +31,10 @@ use crate::{
 use crate::trace::traces::TraceType;
 
 use super::{
-    cache::cache_system::CacheSystem, inst::inst_base::is_compressed_instruction,
-    mmu::cpu_mmu::Mmu, traptype::DebugCause,
+    cache::cache_system::CacheSystem,
+    inst::inst_base::is_compressed_instruction,
+    mmu::cpu_mmu::{Mmu, MmuHypervisorCsrs},
+    traptype::DebugCause,
 };
 
 pub struct DebugState {
@@ -47,6 +57,8 @@ pub struct DebugState {
     pub havereset: bool,
     // 处理器是否处于 debug 模式
     pub debug_mode: bool,
+    // a trigger hit with the debug mode action halts instead of trapping
+    pub trigger_halt: bool,
 }
 
 impl DebugState {
@@ -59,6 +71,7 @@ impl DebugState {
             havereset: false,
             debug_mode: false,
             singlestep_flag: false,
+            trigger_halt: false,
         }
     }
 }
@@ -72,6 +85,9 @@ impl Default for DebugState {
 #[derive(PartialEq, Debug)]
 pub enum CpuState {
     Running,
+    // stalled in WFI until an interrupt is pending, or in WRS until the
+    // reservation set is broken
+    Waiting,
     Haltd,
     Stop,
     Abort,
@@ -117,46 +133,90 @@ impl CpuCoreBuild {
 
     pub fn build(&self) -> CpuCore {
         let mut csr_regs_u = CsrRegs::new(self.hart_id, self.config.clone());
-        let privi_u = Rc::new(Cell::new(PrivilegeLevels::Machine));
+        let privi_u = csr_regs_u.privilege.clone();
         // some csr regs are shared with other modules
         let xstatus = csr_regs_u.xstatus.clone();
         let satp = csr_regs_u.satp.clone();
         // let mtime = csr_regs_u.time.clone();
         let xip = csr_regs_u.xip.clone();
+        let virt = csr_regs_u.virt.clone();
+        let h_csrs = MmuHypervisorCsrs {
+            virt: virt.clone(),
+            hstatus: csr_regs_u.hstatus.clone(),
+            vsstatus: csr_regs_u.vsstatus.clone(),
+            vsatp: csr_regs_u.vsatp.clone(),
+            hgatp: csr_regs_u.hgatp.clone(),
+            henvcfg: csr_regs_u.henvcfg.clone(),
+        };
+
+        let hpm = csr_regs_u.hpm.clone();
+        let cache_system = RcRefCell::new(
+            CacheSystem::new(self.shared_bus.clone(), self.config.clone(), hpm.clone()).into(),
+        );
 
-        let cache_system =
-            RcRefCell::new(CacheSystem::new(self.shared_bus.clone(), self.config.clone()).into());
+        let lr_sc_set = self.shared_bus.borrow_mut().lr_sc_set.clone();
+        let lr_sc_slot = lr_sc_set.add_hart();
 
         let mmu_u = Mmu::new(
             cache_system.clone(),
+            lr_sc_set.clone(),
             privi_u.clone(),
             xstatus,
             satp,
+            csr_regs_u.menvcfg.clone(),
+            csr_regs_u.pmp.clone(),
+            h_csrs,
+            hpm,
             self.config.clone(),
         );
         {
             let bus_u = mmu_u.caches.borrow_mut().bus.clone();
             let mut bus_u = bus_u.borrow_mut();
 
-            let mtime = bus_u.clint.instance.add_hart(xip.clone());
+            let mtime = match bus_u.add_aclint_hart(self.hart_id, xip.clone()) {
+                Some(mtime) => mtime,
+                None => bus_u.clint.instance.add_hart(xip.clone()),
+            };
             csr_regs_u.add_mtime(mtime);
-            // add plic context for core0 m-mode and s-mode
-            bus_u.plic.instance.add_context(xip.clone(), true);
-            if self.smode {
-                bus_u.plic.instance.add_context(xip, false);
+            if let (Some(imsic), Some(m_file)) = (&mut bus_u.imsic, &csr_regs_u.imsic_m) {
+                imsic
+                    .instance
+                    .add_hart(m_file.clone(), csr_regs_u.imsic_s.clone());
+            }
+            if let Some(aplic) = &mut bus_u.aplic {
+                aplic.instance.add_hart(
+                    xip,
+                    csr_regs_u.imsic_m.clone(),
+                    csr_regs_u.imsic_s.clone(),
+                );
+            } else {
+                // add plic context for core0 m-mode and s-mode
+                bus_u.plic.instance.add_context(xip.clone(), true);
+                if self.smode {
+                    bus_u.plic.instance.add_context(xip, false);
+                }
             }
         }
 
         CpuCore {
             gpr: Gpr::new(),
+            fpr: Fpr::new(),
+            vpr: Vpr::new(self.config.vlen()),
             csr_regs: csr_regs_u,
             mmu: mmu_u,
             decode: InstDecode::new(self.config.clone()),
+            block_cache: BlockCache::new(self.config.block_cache_size().unwrap_or(0)),
             cache_system,
             pc: self.boot_pc,
             npc: self.boot_pc,
             cur_priv: privi_u,
+            cur_virt: virt,
             cpu_state: CpuState::Stop,
+            wfi_timeout: None,
+            wrs_timeout: None,
+            lr_sc_set,
+            lr_sc_slot,
+            lr_sc_window: 0,
             #[cfg(feature = "rv_debug_trace")]
             trace_sender: self.trace_sender.clone(),
             config: self.config.clone(),
@@ -167,14 +227,28 @@ impl CpuCoreBuild {
 
 pub struct CpuCore {
     pub gpr: Gpr,
+    pub fpr: Fpr,
+    pub vpr: Vpr,
     pub csr_regs: CsrRegs,
     pub mmu: Mmu,
     pub decode: InstDecode,
+    pub block_cache: BlockCache,
     pub cache_system: RcRefCell<CacheSystem>,
     pub pc: u64,
     pub npc: u64,
     pub cur_priv: Rc<Cell<PrivilegeLevels>>,
+    // virtualization mode (V), VS-mode and VU-mode are S-mode and U-mode with V=1
+    pub cur_virt: Rc<Cell<bool>>,
     pub cpu_state: CpuState,
+    // the cycles left before a WFI that has to trap raises its exception
+    wfi_timeout: Option<(usize, TrapType)>,
+    // the cycles left before a WRS completes on its own, WRS.NTO never does
+    wrs_timeout: Option<usize>,
+    lr_sc_set: Rc<LrScReservation>,
+    lr_sc_slot: usize,
+    // the instructions left in which a constrained LR/SC loop runs without
+    // being interleaved with the other harts
+    lr_sc_window: usize,
     pub debug_state: DebugState,
     pub config: Rc<Config>,
     #[cfg(feature = "rv_debug_trace")]
@@ -183,11 +257,18 @@ pub struct CpuCore {
 impl CpuCore {
     fn reset(&mut self) {
         self.gpr = Gpr::new();
+        self.fpr = Fpr::new();
+        self.vpr.reset();
         self.csr_regs.reset();
         self.npc = 0x8000_0000; //TODO: config
         self.cpu_state = CpuState::Running;
+        self.wfi_timeout = None;
+        self.wrs_timeout = None;
+        self.lr_sc_set.clear(self.lr_sc_slot);
+        self.lr_sc_window = 0;
         self.debug_state = DebugState::new();
         self.decode.reset();
+        self.block_cache.clear();
         let mut cache = self.cache_system.borrow_mut();
         cache.icache.clear();
         cache.dcache.clear();
@@ -220,22 +301,120 @@ impl CpuCore {
             Err(TrapType::LoadAddressMisaligned(addr))
         }
     }
+    // the XLEN of M-mode is MXLEN, mstatus.SXL and UXL give the one of S-mode and U-mode
+    fn update_xlen(&mut self) {
+        let rv32 = self
+            .csr_regs
+            .is_rv32(self.cur_priv.get(), self.cur_virt.get());
+        self.gpr.set_rv32(rv32);
+        self.decode.set_rv32(rv32);
+        self.mmu.rv32 = rv32;
+    }
+
     pub fn inst_fetch(&mut self) -> Result<u64, TrapType> {
-        self.pc = self.npc;
+        self.update_xlen();
+        self.pc = match self.mmu.rv32 {
+            true => self.npc & 0xffff_ffff,
+            false => self.npc,
+        };
 
         // assert!(self.pc % 2 == 0, "pc must be aligned to 2");
         self.fetch_from_mem(self.pc, 4)
     }
 
+    // Fetch the instruction at npc along with its decode. With the block cache, the
+    // instructions following the running block need no translation, fetch or decode.
+    fn fetch_decoded(&mut self) -> Result<DecodedInst, TrapType> {
+        if !self.block_cache.enabled() {
+            let inst = self.inst_fetch()? as u32;
+            let op = self.decode.fast_path(inst);
+            return Ok(DecodedInst::new(inst, op, self.mmu.rv32));
+        }
+        // the XLEN can not change while the running block continues in the same mode
+        let mode = (self.cur_priv.get(), self.cur_virt.get());
+        let pc = match self.mmu.rv32 {
+            true => self.npc & 0xffff_ffff,
+            false => self.npc,
+        };
+        if let Some(decoded) = self.block_cache.next(pc, mode) {
+            self.pc = pc;
+            return Ok(decoded);
+        }
+
+        self.update_xlen();
+        self.pc = match self.mmu.rv32 {
+            true => self.npc & 0xffff_ffff,
+            false => self.npc,
+        };
+        if let Some(decoded) = self.block_enter(mode) {
+            return Ok(decoded);
+        }
+        // faults are raised by the normal fetch
+        let inst = self.fetch_from_mem(self.pc, 4)? as u32;
+        let op = self.decode.fast_path(inst);
+        Ok(DecodedInst::new(inst, op, self.mmu.rv32))
+    }
+
+    // start running the block at pc, it is built on a miss
+    fn block_enter(&mut self, mode: (PrivilegeLevels, bool)) -> Option<DecodedInst> {
+        self.mmu.update_access_type(&AccessType::Fetch(self.pc));
+        let paddr = self.mmu.translate(self.pc, 2).ok()?;
+        let rv32 = self.mmu.rv32;
+        let block = match self.block_cache.lookup(paddr, rv32) {
+            Some(block) => block,
+            None => {
+                let block = self.block_build(rv32);
+                if block.is_empty() {
+                    return None;
+                }
+                self.block_cache.insert(paddr, block)
+            }
+        };
+        // the translation checked the page, but the pmp regions may be smaller than it
+        if !self.mmu.pmp_check(paddr, block.bytes()) {
+            return None;
+        }
+        self.block_cache.enter(block, paddr, self.pc, mode)
+    }
+
+    // decode the straight-line run at pc, up to the end of its page
+    fn block_build(&mut self, rv32: bool) -> InstBlock {
+        let mut insts = Vec::new();
+        let mut pc = self.pc;
+        let mut offset = pc & 0xfff;
+        while insts.len() < BLOCK_MAX_INSTS {
+            let Ok(inst) = self.fetch_from_mem(pc, 4) else {
+                break;
+            };
+            let op = self.decode.fast_path(inst as u32);
+            let decoded = DecodedInst::new(inst as u32, op, rv32);
+            offset += decoded.len as u64;
+            if decoded.op.is_none() || offset > 0x1000 {
+                break;
+            }
+            insts.push(decoded);
+            pc = pc.wrapping_add(decoded.len as u64);
+            if ends_block(decoded.inst, rv32) || offset == 0x1000 {
+                break;
+            }
+        }
+        InstBlock::new(insts, rv32)
+    }
+
     pub fn decode_and_excute(&mut self, inst: u32) -> Result<(), TrapType> {
         let inst_op = self.decode.fast_path(inst);
-        match inst_op {
+        self.excute_op(&DecodedInst::new(inst, inst_op, self.mmu.rv32))
+    }
+
+    fn excute_op(&mut self, decoded: &DecodedInst) -> Result<(), TrapType> {
+        let inst = decoded.inst;
+        match decoded.op {
             Some(i) => {
                 #[cfg(feature = "rv_debug_trace")]
                 if let Some(sender) = &self.trace_sender {
                     sender.send(TraceType::Itrace(self.pc, inst)).unwrap();
                 };
-                (i.operation)(self, inst, self.pc) // return
+                (i.operation)(self, inst, self.pc, &decoded.ops) // return
             }
             None => {
                 warn!("IllegalInstruction,pc:{:X},inst:{:x}", self.pc, inst);
@@ -254,6 +433,9 @@ impl CpuCore {
         info!("cycle:{},instret:{}", cycle, instret);
         // let x = self.cache_system.borrow();
         // self.decode.show_perf();
+        if self.block_cache.enabled() {
+            self.block_cache.show_perf();
+        }
         // self.mmu.show_perf();
     }
 
@@ -271,9 +453,10 @@ impl CpuCore {
         // 1. pc changes to the value stored in dpc.
         self.set_pc(self.csr_regs.dpc.get());
         // 2. The current privilege mode and virtualization mode are changed to that specified by prv and v.
-        // NOT support virtualization now
         self.cur_priv
             .set(PrivilegeLevels::from_usize(dcsr.prv().into()).unwrap());
+        self.cur_virt
+            .set(dcsr.v() && self.cur_priv.get() != PrivilegeLevels::Machine);
 
         // 3. When resuming from debug mode, clear mstatus.MPRV if the new privilege mode is less than M-mode
         if (self.cur_priv.get() as usize) < (PrivilegeLevels::Machine as usize) {
@@ -282,8 +465,12 @@ impl CpuCore {
             self.csr_regs.xstatus.set(xstatus_tmp)
         }
 
+        // the debugger may have written the csrs the running block was entered with
+        self.block_cache.stop();
+
         // 4. The debug mode is cleared.
         self.debug_state.debug_mode = false;
+        self.csr_regs.triggers.set_debug_mode(false);
         self.cpu_state = CpuState::Running;
 
         self.debug_state.resumeack = true;
@@ -312,31 +499,103 @@ impl CpuCore {
         assert_eq!(self.cpu_state, CpuState::Running, "not in running state");
 
         // Increment the cycle counter
-        let cycle = self.csr_regs.cycle.get();
-        self.csr_regs.cycle.set(cycle + 1);
+        if !self.csr_regs.hpm.cycle_inhibited() {
+            let cycle = self.csr_regs.cycle.get();
+            self.csr_regs.cycle.set(cycle + 1);
+        }
 
-        let fetch_ret = self.inst_fetch();
+        self.lr_sc_window = self.lr_sc_window.saturating_sub(1);
+
+        let privi = self.cur_priv.get();
+        let virt = self.cur_virt.get();
+
+        // an icount trigger that reached zero fires before the next instruction
+        if let Some(action) = self.csr_regs.triggers.icount_fire(privi) {
+            self.pc = self.npc;
+            let trap_type = self.trigger_trap(action, 0);
+            self.handle_trigger_or_exception(trap_type);
+            return;
+        }
+
+        let fetch_ret = self.fetch_decoded();
         let exe_ret = match fetch_ret {
             // op ret
-            Ok(inst_val) => {
-                self.advance_pc(inst_val as u32);
-                self.decode_and_excute(inst_val as u32)
+            Ok(decoded) => {
+                self.npc = self.pc.wrapping_add(decoded.len as u64);
+                let sequential_pc = self.npc;
+                let ret = self
+                    .trigger_check(TriggerOp::Execute, self.pc, decoded.inst as u64)
+                    .and_then(|_| self.excute_op(&decoded));
+                if ret.is_ok() && self.npc != sequential_pc && decoded.branch {
+                    self.csr_regs.hpm.record(HpmEvent::BranchTaken);
+                }
+                ret
             }
             // fetch fault
             Err(trap_type) => Err(trap_type),
         };
 
         if let Err(trap_type) = exe_ret {
-            self.handle_exceptions(trap_type);
+            self.handle_trigger_or_exception(trap_type);
         } else {
             // Increment the instruction counter
-            let instret = self.csr_regs.instret.get();
-            self.csr_regs.instret.set(instret + 1);
+            if !self.csr_regs.hpm.instret_inhibited() {
+                let instret = self.csr_regs.instret.get();
+                self.csr_regs.instret.set(instret + 1);
+            }
+            self.csr_regs.triggers.icount_retire(privi, virt);
+        }
+    }
+
+    // Sdtrig: a matching trigger aborts the access, the hit either raises a breakpoint
+    // exception or halts the hart once the instruction is abandoned
+    pub fn trigger_check(&mut self, op: TriggerOp, addr: u64, value: u64) -> Result<(), TrapType> {
+        self.trigger_check_select(op, addr, value, None)
+    }
+
+    // Address triggers on a load fire before the access, so they take priority over its
+    // faults and side effects. The data triggers can only be checked after it.
+    fn load_trigger_check(&mut self, addr: u64, data: Option<u64>) -> Result<(), TrapType> {
+        match data {
+            None => self.trigger_check_select(TriggerOp::Load, addr, 0, Some(false)),
+            Some(data) => self.trigger_check_select(TriggerOp::Load, addr, data, Some(true)),
+        }
+    }
+
+    fn trigger_check_select(
+        &mut self,
+        op: TriggerOp,
+        addr: u64,
+        value: u64,
+        select: Option<bool>,
+    ) -> Result<(), TrapType> {
+        let privi = self.cur_priv.get();
+        let virt = self.cur_virt.get();
+        match self
+            .csr_regs
+            .triggers
+            .match_access(op, addr, value, select, privi, virt)
+        {
+            Some(action) => Err(self.trigger_trap(action, addr)),
+            None => Ok(()),
+        }
+    }
+
+    fn trigger_trap(&mut self, action: TriggerAction, tval: u64) -> TrapType {
+        self.debug_state.trigger_halt = action == TriggerAction::DebugMode;
+        TrapType::Breakpoint(tval)
+    }
+
+    fn handle_trigger_or_exception(&mut self, trap_type: TrapType) {
+        if core::mem::take(&mut self.debug_state.trigger_halt) {
+            self.enter_debug_mode(DebugCause::Trigger, self.pc);
+        } else {
+            self.handle_exceptions(trap_type);
         }
     }
 
     pub fn execute(&mut self, num: usize) {
-        for _ in 0..num {
+        for i in 0..num {
             match self.cpu_state {
                 CpuState::Running => {
                     if self.debug_state.resetreq_signal {
@@ -351,6 +610,14 @@ impl CpuCore {
                         self.handle_interrupt();
                     }
                 }
+                CpuState::Waiting => {
+                    // the rest of the cycles are idle, a woken up hart takes
+                    // the interrupt before the next instruction
+                    if self.wfi_wait(num - i) {
+                        break;
+                    }
+                    self.handle_interrupt();
+                }
                 CpuState::Haltd => {
                     if self.debug_state.resumereq_flag {
                         self.resume_proc();
@@ -359,11 +626,94 @@ impl CpuCore {
                 _ => break,
             };
         }
+        // Forward progress: finish an LR/SC sequence started at the end of the
+        // chunk, so that the other harts can not break it every time.
+        while self.lr_sc_window != 0
+            && self.cpu_state == CpuState::Running
+            && !self.debug_state.debug_mode
+            && self.lr_sc_set.is_reserved(self.lr_sc_slot)
+        {
+            self.real_excute();
+            self.handle_interrupt();
+        }
+    }
+
+    // Stall the hart after a WFI. The hart raises timeout_trap if it is not woken up
+    // within the configured wfi_timeout, at once if that is zero.
+    pub fn wait_for_interrupt(&mut self, timeout_trap: Option<TrapType>) -> Result<(), TrapType> {
+        let timeout = self.config.wfi_timeout();
+        match timeout_trap {
+            Some(trap) if timeout == 0 => return Err(trap),
+            _ => {}
+        }
+        self.cpu_state = CpuState::Waiting;
+        self.wfi_timeout = timeout_trap.map(|trap| (timeout, trap));
+        Ok(())
+    }
+
+    pub fn holds_reservation(&self) -> bool {
+        self.lr_sc_set.is_reserved(self.lr_sc_slot)
+    }
+
+    // Stall the hart after a WRS.NTO or WRS.STO, timeout is None for WRS.NTO.
+    // timeout_trap is the same as for WFI.
+    pub fn wait_on_reservation(
+        &mut self,
+        timeout: Option<usize>,
+        timeout_trap: Option<TrapType>,
+    ) -> Result<(), TrapType> {
+        self.wait_for_interrupt(timeout_trap)?;
+        self.wrs_timeout = Some(timeout.unwrap_or(usize::MAX));
+        Ok(())
+    }
+
+    fn wake_up(&mut self) {
+        self.cpu_state = CpuState::Running;
+        self.wfi_timeout = None;
+        self.wrs_timeout = None;
+    }
+
+    // Returns true if the hart keeps waiting for the given cycles. The hart wakes up
+    // on a pending and enabled interrupt even if it is globally disabled, and on a
+    // debug request. A WRS also completes when its reservation is broken or its own
+    // timeout expires. An expired WFI timeout raises its exception at the WFI or
+    // WRS.NTO, which is still self.pc.
+    fn wfi_wait(&mut self, cycles: usize) -> bool {
+        self.csr_regs.update_stip();
+        let pending = u64::from(self.csr_regs.xie.get()) & u64::from(self.csr_regs.xip.get());
+        if pending != 0 || self.debug_state.haltreq_signal || self.debug_state.resetreq_signal {
+            self.wake_up();
+            return false;
+        }
+        if let Some(left) = self.wrs_timeout {
+            if left <= cycles || !self.lr_sc_set.is_reserved(self.lr_sc_slot) {
+                self.wake_up();
+                return false;
+            }
+            self.wrs_timeout = Some(left - cycles);
+        }
+        match self.wfi_timeout {
+            Some((left, trap)) if left <= cycles => {
+                self.wake_up();
+                self.handle_exceptions(trap);
+                false
+            }
+            Some((left, trap)) => {
+                self.wfi_timeout = Some((left - cycles, trap));
+                true
+            }
+            None => true,
+        }
+    }
+
+    // the mtime ticks until the Sstc timer of this hart fires
+    pub fn timer_delta(&self) -> Option<u64> {
+        self.csr_regs.stimecmp_delta()
     }
 
     // for difftest
     pub fn execute_as_ref(&mut self, num: usize) {
-        for _ in 0..num {
+        for i in 0..num {
             match self.cpu_state {
                 CpuState::Running => {
                     // Increment the cycle counter
@@ -390,6 +740,12 @@ impl CpuCore {
                     let instret = self.csr_regs.instret.get();
                     self.csr_regs.instret.set(instret + 1);
                 }
+                // woken up as in execute, the interrupts are left to the difftest
+                CpuState::Waiting => {
+                    if self.wfi_wait(num - i) {
+                        break;
+                    }
+                }
                 _ => break,
             };
         }
@@ -409,15 +765,22 @@ impl CpuCore {
     }
 
     pub fn handle_exceptions(&mut self, trap_type: TrapType) {
+        self.csr_regs.hpm.record(HpmEvent::Exception);
+        self.lr_sc_set.clear(self.lr_sc_slot);
         let medeleg = self.csr_regs.medeleg.get();
         let mut mstatus = self.csr_regs.xstatus.get();
 
         let has_exception = u64::from(medeleg) & (1_u64 << trap_type.get_exception_num()) != 0;
+        let has_exception_vs =
+            u64::from(self.csr_regs.hedeleg.get()) & (1_u64 << trap_type.get_exception_num()) != 0;
 
         let trap_to_s_enable = self.cur_priv.get() <= PrivilegeLevels::Supervisor;
+        let virt = self.cur_virt.get();
 
         let tval = trap_type.get_tval();
         let cause = trap_type.idx();
+        // GVA is set when tval holds a guest virtual address
+        let gva = trap_type.has_tval_addr() && (virt || self.mmu.effective_virt());
 
         log::debug!(
             "pc:{:x},trap_type:{:?},cause:{:?},tval:{:x}",
@@ -427,8 +790,23 @@ impl CpuCore {
             tval
         );
 
-        // exception to S mode
-        if has_exception & trap_to_s_enable {
+        // exception to VS mode, vsstatus/vsepc/vscause/vstval/vstvec are used
+        if has_exception & has_exception_vs & virt {
+            self.trap_to_vs(trap_type, cause, tval, self.pc);
+        }
+        // exception to HS mode
+        else if has_exception & trap_to_s_enable {
+            let mut hstatus = self.csr_regs.hstatus.get();
+            hstatus.set_spv(virt);
+            if virt {
+                hstatus.set_spvp(self.cur_priv.get() == PrivilegeLevels::Supervisor);
+            }
+            hstatus.set_gva(gva);
+            self.csr_regs.hstatus.set(hstatus);
+            self.csr_regs.htval.set(trap_type.get_tval2());
+            self.csr_regs.htinst.set(0);
+            self.cur_virt.set(false);
+
             // When a trap is taken, SPP is set to 0 if the trap originated from user mode, or 1 otherwise.
             mstatus.set_spp(!(self.cur_priv.get() == PrivilegeLevels::User));
             // When a trap is taken into supervisor mode, SPIE is set to SIE
@@ -454,9 +832,17 @@ impl CpuCore {
         }
         // exception to M mode
         else {
+            self.csr_regs.triggers.trap_to_m();
             mstatus.set_mpie(mstatus.mie());
             mstatus.set_mie(false);
             mstatus.set_mpp(self.cur_priv.get() as u8);
+            if self.config.h_mode() {
+                mstatus.set_mpv(virt);
+                mstatus.set_gva(gva);
+                self.csr_regs.mtval2.set(trap_type.get_tval2());
+                self.csr_regs.mtinst.set(0);
+            }
+            self.cur_virt.set(false);
 
             self.csr_regs.xstatus.set(mstatus);
             self.csr_regs.mepc.set(self.pc);
@@ -475,7 +861,10 @@ impl CpuCore {
         }
     }
 
+    // checked after every instruction, the interrupt is taken out of line
+    #[inline]
     pub fn handle_interrupt(&mut self) {
+        self.csr_regs.update_stip();
         // read necessary csrs
 
         let xie = self.csr_regs.xie.get();
@@ -486,6 +875,11 @@ impl CpuCore {
         if mip_mie_val == 0 {
             return;
         }
+        self.take_interrupt(mip_mie_val);
+    }
+
+    #[inline(never)]
+    fn take_interrupt(&mut self, mip_mie_val: u64) {
         // warn!("mip_mie_val:{:?}", XieIn::from(mip_mie_val));
         let mut mstatus = self.csr_regs.xstatus.get();
 
@@ -496,20 +890,39 @@ impl CpuCore {
         let int_to_m_enable = m_a1 | m_a2;
         let int_to_m_peding = mip_mie_val & !u64::from(mideleg);
 
-        let s_a1 = mstatus.sie() & (self.cur_priv.get() == PrivilegeLevels::Supervisor);
-        let s_a2 = self.cur_priv.get() < PrivilegeLevels::Supervisor;
+        let virt = self.cur_virt.get();
+        let hideleg = u64::from(self.csr_regs.hideleg.get());
+
+        // HS-mode interrupts are always enabled when running in VS/VU mode
+        let s_a1 = mstatus.sie() & (self.cur_priv.get() == PrivilegeLevels::Supervisor) & !virt;
+        let s_a2 = (self.cur_priv.get() < PrivilegeLevels::Supervisor) | virt;
         let int_to_s_enable = s_a1 | s_a2;
-        let int_to_s_peding = mip_mie_val & u64::from(mideleg);
+        let int_to_s_peding = mip_mie_val & u64::from(mideleg) & !hideleg;
+
+        // VS-mode interrupts can only be taken in VS/VU mode
+        let vsstatus = self.csr_regs.vsstatus.get();
+        let vs_a1 = vsstatus.sie() & (self.cur_priv.get() == PrivilegeLevels::Supervisor);
+        let vs_a2 = self.cur_priv.get() < PrivilegeLevels::Supervisor;
+        let int_to_vs_enable = virt & (vs_a1 | vs_a2);
+        let int_to_vs_peding = mip_mie_val & u64::from(mideleg) & hideleg;
 
         // handing interupt in M mode
         if int_to_m_enable && int_to_m_peding != 0 {
             let cause = XipIn::from(int_to_m_peding).get_priority_interupt();
 
             log::trace!("mmode int pc:{:x},cause:{:?}", self.pc, cause,);
+            self.csr_regs.hpm.record(HpmEvent::Interrupt);
+            self.lr_sc_set.clear(self.lr_sc_slot);
 
+            self.csr_regs.triggers.trap_to_m();
             mstatus.set_mpie(mstatus.mie());
             mstatus.set_mpp(self.cur_priv.get() as u8);
             mstatus.set_mie(false);
+            if self.config.h_mode() {
+                mstatus.set_mpv(virt);
+                mstatus.set_gva(false);
+            }
+            self.cur_virt.set(false);
 
             self.csr_regs.xstatus.set(mstatus);
             self.csr_regs.mepc.set(self.npc);
@@ -534,6 +947,17 @@ impl CpuCore {
             let cause = XipIn::from(int_to_s_peding).get_priority_interupt();
 
             log::trace!("smode int pc:{:x},cause:{:?}", self.pc, cause,);
+            self.csr_regs.hpm.record(HpmEvent::Interrupt);
+            self.lr_sc_set.clear(self.lr_sc_slot);
+
+            let mut hstatus = self.csr_regs.hstatus.get();
+            hstatus.set_spv(virt);
+            if virt {
+                hstatus.set_spvp(self.cur_priv.get() == PrivilegeLevels::Supervisor);
+            }
+            hstatus.set_gva(false);
+            self.csr_regs.hstatus.set(hstatus);
+            self.cur_virt.set(false);
 
             // When a trap is taken, SPP is set to 0 if the trap originated from user mode, or 1 otherwise.
             mstatus.set_spp(!(self.cur_priv.get() == PrivilegeLevels::User));
@@ -554,6 +978,43 @@ impl CpuCore {
             self.cur_priv.set(PrivilegeLevels::Supervisor);
             self.npc = stvec.get_trap_pc(cause);
         }
+        // handing interupt in VS mode
+        // VS-level interrupts are seen by the guest as the corresponding S-level interrupts
+        else if int_to_vs_enable && int_to_vs_peding != 0 {
+            let vs_pending = (int_to_vs_peding & VS_INTERRUPT_MASK) >> 1;
+            if vs_pending == 0 {
+                return;
+            }
+            let cause = XipIn::from(vs_pending).get_priority_interupt();
+
+            log::trace!("vsmode int pc:{:x},cause:{:?}", self.pc, cause,);
+            self.csr_regs.hpm.record(HpmEvent::Interrupt);
+            self.lr_sc_set.clear(self.lr_sc_slot);
+
+            self.trap_to_vs(cause, cause.idx(), 0, self.npc);
+        }
+    }
+
+    fn trap_to_vs(&mut self, trap_type: TrapType, cause: u64, tval: u64, epc: u64) {
+        let mut vsstatus = self.csr_regs.vsstatus.get();
+        vsstatus.set_spp(!(self.cur_priv.get() == PrivilegeLevels::User));
+        vsstatus.set_spie(vsstatus.sie());
+        vsstatus.set_sie(false);
+
+        self.csr_regs.vsstatus.set(vsstatus);
+        self.csr_regs.vsepc.set(epc);
+        self.csr_regs.vscause.set(cause.into());
+        self.csr_regs.vstval.set(tval);
+        #[cfg(feature = "rv_debug_trace")]
+        if let Some(sender) = &self.trace_sender {
+            sender
+                .send(TraceType::Trap(trap_type, self.pc, tval))
+                .unwrap();
+        };
+
+        let vstvec = self.csr_regs.vstvec.get();
+        self.npc = vstvec.get_trap_pc(trap_type);
+        self.cur_priv.set(PrivilegeLevels::Supervisor);
     }
 
     pub fn read(
@@ -562,11 +1023,47 @@ impl CpuCore {
         len: usize,
         access_type: AccessType,
     ) -> Result<u64, TrapType> {
-        self.mmu.update_access_type(&access_type);
-        let paddr = self.mmu.translate(addr, len)?;
-        match self.cache_system.borrow_mut().dcache.read(paddr, len) {
-            Ok(data) => Ok(data),
-            Err(_err) => Err(access_type.throw_access_exception()),
+        self.load_trigger_check(addr, None)?;
+        // an AMO is also a store, its store address triggers fire before the load
+        if let AccessType::Amo(_) = access_type {
+            self.trigger_check_select(TriggerOp::Store, addr, 0, Some(false))?;
+        }
+        let data = if self.split_misaligned(addr, len, &access_type) {
+            self.misaligned_read(addr, len, access_type)?
+        } else {
+            self.mmu.update_access_type(&access_type);
+            let paddr = self.mmu.translate(addr, len)?;
+            self.dcache_read(paddr, len, self.mmu.uncacheable())
+                .map_err(|_err| access_type.throw_access_exception())?
+        };
+        // data triggers match the loaded value, rd is left untouched on a hit
+        self.load_trigger_check(addr, Some(data))?;
+        Ok(data)
+    }
+
+    // Svpbmt NC and IO pages are accessed around the data cache
+    pub fn dcache_read(&self, paddr: u64, len: usize, uncached: bool) -> Result<u64, RVerr> {
+        let mut cache_system = self.cache_system.borrow_mut();
+        match uncached {
+            true => cache_system.dcache.read_uncached(paddr, len),
+            false => cache_system.dcache.read(paddr, len),
+        }
+    }
+
+    pub fn dcache_write(
+        &mut self,
+        paddr: u64,
+        data: u64,
+        len: usize,
+        uncached: bool,
+    ) -> Result<u64, RVerr> {
+        self.block_cache.invalidate(paddr, len);
+        // a store from any hart breaks the reservations on its cache line
+        self.lr_sc_set.invalidate(paddr, len);
+        let mut cache_system = self.cache_system.borrow_mut();
+        match uncached {
+            true => cache_system.dcache.write_uncached(paddr, data, len),
+            false => cache_system.dcache.write(paddr, data, len),
         }
     }
 
@@ -589,40 +1086,139 @@ impl CpuCore {
         len: usize,
         access_type: AccessType,
     ) -> Result<u64, TrapType> {
+        // store triggers fire before memory is changed
+        self.trigger_check(TriggerOp::Store, addr, data)?;
+        if self.split_misaligned(addr, len, &access_type) {
+            return self.misaligned_write(addr, data, len, access_type);
+        }
         self.mmu.update_access_type(&access_type);
         let paddr = self.mmu.translate(addr, len)?;
-        match self
-            .cache_system
-            .borrow_mut()
-            .dcache
-            .write(paddr, data, len)
+        self.dcache_write(paddr, data, len, self.mmu.uncacheable())
+            .map_err(|_err| access_type.throw_access_exception())
+    }
+
+    // AMOs and LR/SC always raise an address-misaligned exception
+    fn split_misaligned(&self, addr: u64, len: usize, access_type: &AccessType) -> bool {
+        self.config.misaligned_access()
+            && !check_aligned(addr, len)
+            && matches!(access_type, AccessType::Load(_) | AccessType::Store(_))
+    }
+
+    // A misaligned access covers the ends of two naturally aligned blocks of its own size,
+    // each inside one page. Both parts are translated and pmp checked before memory is
+    // touched, so an access that crosses a page faults on the failing part with the address
+    // of that part as tval. Each block keeps whether its page is uncacheable.
+    fn misaligned_translate(
+        &mut self,
+        addr: u64,
+        len: usize,
+        access_type: &AccessType,
+    ) -> Result<[(u64, bool); 2], TrapType> {
+        let block_mask = len as u64 - 1;
+        let hi = (addr | block_mask).wrapping_add(1);
+        let lo_len = hi.wrapping_sub(addr) as usize;
+        let parts = [(addr, lo_len), (hi, len - lo_len)];
+        let mut blocks = [(0_u64, false); 2];
+        for ((part, part_len), block) in parts.into_iter().zip(blocks.iter_mut()) {
+            let part_access = access_type.with_tval(part);
+            self.mmu.update_access_type(&part_access);
+            let paddr = self.mmu.translate(part, 1)?;
+            if !self.mmu.pmp_check(paddr, part_len) {
+                return Err(part_access.throw_access_exception());
+            }
+            *block = (paddr & !block_mask, self.mmu.uncacheable());
+        }
+        Ok(blocks)
+    }
+
+    fn misaligned_read(
+        &mut self,
+        addr: u64,
+        len: usize,
+        access_type: AccessType,
+    ) -> Result<u64, TrapType> {
+        let blocks = self.misaligned_translate(addr, len, &access_type)?;
+        let mut data = [0_u64; 2];
+        for (data, (paddr, uncached)) in data.iter_mut().zip(blocks) {
+            *data = self
+                .dcache_read(paddr, len, uncached)
+                .map_err(|_err| access_type.throw_access_exception())?;
+        }
+        let width = len as u32 * 8;
+        let shift = (addr & (len as u64 - 1)) as u32 * 8;
+        let mask = u64::MAX >> (64 - width);
+        Ok((data[0] >> shift | data[1] << (width - shift)) & mask)
+    }
+
+    // Both blocks are read and merged before the first one is written, so a bus error
+    // leaves memory unchanged. The bytes of a block outside the access keep their value.
+    fn misaligned_write(
+        &mut self,
+        addr: u64,
+        data: u64,
+        len: usize,
+        access_type: AccessType,
+    ) -> Result<u64, TrapType> {
+        let blocks = self.misaligned_translate(addr, len, &access_type)?;
+        let width = len as u32 * 8;
+        let shift = (addr & (len as u64 - 1)) as u32 * 8;
+        let mask = u64::MAX >> (64 - width);
+        let parts = [
+            (mask << shift & mask, data << shift),
+            (mask >> (width - shift), data >> (width - shift)),
+        ];
+        let mut merged = [0_u64; 2];
+        for ((merged, (paddr, uncached)), (part_mask, part_data)) in
+            merged.iter_mut().zip(blocks).zip(parts)
         {
-            Ok(data) => Ok(data),
-            Err(_err) => Err(access_type.throw_access_exception()),
+            let old = self
+                .dcache_read(paddr, len, uncached)
+                .map_err(|_err| access_type.throw_access_exception())?;
+            *merged = old & !part_mask | part_data & part_mask;
         }
+        for (merged, (paddr, uncached)) in merged.into_iter().zip(blocks) {
+            self.dcache_write(paddr, merged, len, uncached)
+                .map_err(|_err| access_type.throw_access_exception())?;
+        }
+        Ok(0)
     }
 
-    pub fn lr_sc_reservation_set(&mut self, addr: u64) {
-        self.mmu
-            .caches
-            .borrow_mut()
-            .bus
-            .borrow_mut()
-            .lr_sc_set
-            .set(addr);
+    // LR: load and reserve the cache line of the physical address. A misaligned LR
+    // is never split, it faults before any memory access or trigger check.
+    pub fn load_reserved(&mut self, addr: u64, len: usize) -> Result<u64, TrapType> {
+        let access_type = AccessType::Load(addr);
+        if !check_aligned(addr, len) {
+            return Err(access_type.throw_addr_misaligned_exception());
+        }
+        self.load_trigger_check(addr, None)?;
+        self.mmu.update_access_type(&access_type);
+        let paddr = self.mmu.translate(addr, len)?;
+        let data = self
+            .dcache_read(paddr, len, self.mmu.uncacheable())
+            .map_err(|_err| access_type.throw_access_exception())?;
+        self.load_trigger_check(addr, Some(data))?;
+        self.lr_sc_set.set(self.lr_sc_slot, paddr);
+        self.lr_sc_window = LR_SC_WINDOW;
+        Ok(data)
     }
-    pub fn lr_sc_reservation_check_and_clear(&mut self, addr: u64) -> bool {
-        self.mmu
-            .caches
-            .borrow_mut()
-            .bus
-            .borrow_mut()
-            .lr_sc_set
-            .check_and_clear(addr)
+
+    // SC: store only if the reservation still holds, returns whether it succeeded.
+    // The address is translated first, so a failing SC still raises its faults.
+    pub fn store_conditional(
+        &mut self,
+        addr: u64,
+        data: u64,
+        len: usize,
+    ) -> Result<bool, TrapType> {
+        let access_type = AccessType::Store(addr);
+        self.mmu.update_access_type(&access_type);
+        let paddr = self.mmu.translate(addr, len)?;
+        if !self.lr_sc_set.check_and_clear(self.lr_sc_slot, paddr) {
+            return Ok(false);
+        }
+        self.write(addr, data, len, access_type)?;
+        Ok(true)
     }
-    // pub fn lr_sc_reservation_clear(&mut self) {
-    //     self.lr_sc_set.lock().unwrap().clear();
-    // }
 
     // halt
     pub fn enter_debug_mode(&mut self, cause: DebugCause, pc: u64) {
@@ -634,7 +1230,7 @@ impl CpuCore {
         dcsr.set_cause(cause as u8);
         // 2. dcsr->prv and dcsr->v are set to reflect current privilege mode.
         dcsr.set_prv(self.cur_priv.get() as u8);
-        dcsr.set_v(false); // do not support virtualnization
+        dcsr.set_v(self.cur_virt.get());
 
         self.csr_regs.dcsr.set(dcsr);
 
@@ -643,10 +1239,12 @@ impl CpuCore {
         self.csr_regs.dpc.set(pc);
         // 4. The hart enters Debug Mode.
         self.debug_state.debug_mode = true;
+        self.csr_regs.triggers.set_debug_mode(true);
         self.cpu_state = CpuState::Haltd;
 
         // 5. debug mode is always performed in M mode
         self.cur_priv.set(PrivilegeLevels::Machine);
+        self.cur_virt.set(false);
     }
 }
 
@@ -658,6 +1256,7 @@ impl Difftest for CpuCore {
         self.csr_regs.read_raw(csr)
     }
     fn set_mem(&mut self, paddr: u64, data: u64, len: usize) {
+        self.block_cache.invalidate(paddr, len);
         let _ret = self.mmu.caches.borrow_mut().dcache.write(paddr, data, len);
     }
     fn get_mem(&self, paddr: u64, len: usize) -> u64 {
@@ -719,6 +1318,7 @@ impl DebugModuleSlave for CpuCore {
 
     fn write_memory(&mut self, address: u64, length: usize, value: u64) -> Option<u64> {
         let paddr = address;
+        self.block_cache.invalidate(paddr, length);
         let result = match self
             .cache_system
             .borrow_mut()
@@ -777,3 +1377,615 @@ impl DebugModuleSlave for CpuCore {
         self.debug_state.havereset = false;
     }
 }
+
+// A hart booting at MEM_BASE with 1MiB of memory, for the instruction tests
+#[cfg(test)]
+pub fn cpu_for_test(config: Config) -> CpuCore {
+    use crate::{
+        device::{
+            device_memory::DeviceMemory,
+            device_trait::{DeviceBase, MEM_BASE},
+        },
+        rv64core::bus::DeviceType,
+    };
+    use alloc::boxed::Box;
+
+    let bus: RcRefCell<Bus> = RcRefCell::new(Bus::new().into());
+    let mem = DeviceMemory::new(0x10_0000);
+    bus.borrow_mut().add_device(DeviceType {
+        start: MEM_BASE,
+        len: mem.size() as u64,
+        name: mem.get_name(),
+        instance: Box::new(mem),
+    });
+    let smode = config.s_mode();
+    let mut cpu = CpuCoreBuild::new(bus, Rc::new(config))
+        .with_smode(smode)
+        .build();
+    cpu.cpu_state = CpuState::Running;
+    cpu
+}
+
+#[cfg(test)]
+impl CpuCore {
+    // physical memory, around the caches
+    pub fn mem_read(&self, paddr: u64, len: usize) -> u64 {
+        let bus = self.cache_system.borrow_mut().bus.clone();
+        let data = bus.borrow_mut().read(paddr, len).unwrap();
+        data
+    }
+
+    pub fn mem_write(&self, paddr: u64, data: u64, len: usize) {
+        let bus = self.cache_system.borrow_mut().bus.clone();
+        bus.borrow_mut().write(paddr, data, len).unwrap();
+    }
+
+    // open the whole memory to S and U-mode with pmp entry 0
+    pub fn pmp_for_test(&mut self) {
+        use crate::rv64core::inst::inst_base::{CSR_PMPADDR0, CSR_PMPCFG0};
+
+        self.csr_regs.write_raw(CSR_PMPADDR0.into(), u64::MAX >> 10);
+        self.csr_regs.write_raw(CSR_PMPCFG0.into(), 0x1f);
+    }
+
+    // Sv39 with the root page table at root
+    pub fn sv39_for_test(&mut self, root: u64) {
+        use crate::rv64core::inst::inst_base::CSR_SATP;
+
+        self.pmp_for_test();
+        self.csr_regs
+            .write_raw(CSR_SATP.into(), 8 << 60 | root >> 12);
+    }
+
+    // Map a 4KiB page, the next level tables follow the root, so only the pages of
+    // one 2MiB region can be mapped. A PTE is returned with its physical address.
+    pub fn map_for_test(&self, root: u64, va: u64, pa: u64, flags: u64) -> u64 {
+        let vpn = |level: u64| (va >> (12 + 9 * level)) & 0x1ff;
+        self.mem_write(root + vpn(2) * 8, (root + 0x1000) >> 2 | 1, 8);
+        self.mem_write(root + 0x1000 + vpn(1) * 8, (root + 0x2000) >> 2 | 1, 8);
+        let pte_addr = root + 0x2000 + vpn(0) * 8;
+        self.mem_write(pte_addr, pa >> 2 | flags, 8);
+        pte_addr
+    }
+
+    // place the instruction at the physical address of npc and run it
+    pub fn exec_for_test(&mut self, inst: u32) {
+        self.mem_write(self.npc, inst as u64, 4);
+        self.execute(1);
+    }
+}
+
+#[test]
+fn load_trigger_priority_test() {
+    use crate::rv64core::trigger::McontrolIn;
+
+    let mut config = Config::new();
+    config.set_isa("rv64imac_zicboz").unwrap();
+    let mut cpu = cpu_for_test(config);
+    let cause = |cpu: &CpuCore| cpu.csr_regs.mcause.get().exception_code();
+    // tdata2 holds the address, M-mode triggers need tcontrol.MTE
+    let watch = |cpu: &mut CpuCore, op: TriggerOp, addr: u64| {
+        let triggers = &cpu.csr_regs.triggers;
+        let c = McontrolIn::new()
+            .with_m(true)
+            .with_load(op == TriggerOp::Load)
+            .with_store(op == TriggerOp::Store)
+            .with_ty(2);
+        triggers.write_tdata(1, c.into());
+        triggers.write_tdata(2, addr);
+        triggers.write_tcontrol(1 << 3);
+    };
+
+    // ld a0, 0(a1) from an address with no device behind it
+    watch(&mut cpu, TriggerOp::Load, 0x100);
+    cpu.gpr.write(11, 0x100);
+    cpu.exec_for_test(0x0005_b503);
+    assert_eq!(cause(&cpu), 3);
+    assert_eq!(cpu.csr_regs.mtval.get(), 0x100);
+
+    // without the trigger the access fault is raised
+    cpu.csr_regs.triggers.write_tdata(1, 0);
+    cpu.npc = 0x8000_0000;
+    cpu.exec_for_test(0x0005_b503);
+    assert_eq!(cause(&cpu), 5);
+
+    // cbo.zero (a1) is a store
+    watch(&mut cpu, TriggerOp::Store, 0x8000_1000);
+    cpu.gpr.write(11, 0x8000_1000);
+    cpu.mem_write(0x8000_1000, 0x55, 8);
+    cpu.npc = 0x8000_0000;
+    cpu.exec_for_test(0x0045_a00f);
+    assert_eq!(cause(&cpu), 3);
+    assert_eq!(cpu.mem_read(0x8000_1000, 8), 0x55);
+}
+
+#[test]
+fn amo_store_trigger_test() {
+    use crate::rv64core::trigger::McontrolIn;
+
+    let mut config = Config::new();
+    config.set_isa("rv64imac").unwrap();
+    let mut cpu = cpu_for_test(config);
+    // amoadd.w t1, t2, (t0)
+    let amoadd = |cpu: &mut CpuCore| {
+        cpu.gpr.write(5, 0x8000_1000);
+        cpu.gpr.write(6, 0x77);
+        cpu.gpr.write(7, 1);
+        cpu.mem_write(0x8000_1000, 0x55, 4);
+        cpu.npc = 0x8000_0000;
+        cpu.exec_for_test(0x0072_a32f);
+    };
+
+    // a store-only mcontrol6 address watchpoint fires before the AMO reads memory
+    let triggers = &cpu.csr_regs.triggers;
+    triggers.write_tdata(1, 6 << 60 | 1 << 6 | 1 << 1);
+    triggers.write_tdata(2, 0x8000_1000);
+    triggers.write_tcontrol(1 << 3);
+    amoadd(&mut cpu);
+    assert_eq!(cpu.csr_regs.mcause.get().exception_code(), 3);
+    assert_eq!(cpu.csr_regs.mtval.get(), 0x8000_1000);
+    assert_eq!(cpu.gpr.read(6), 0x77);
+    assert_eq!(cpu.mem_read(0x8000_1000, 4), 0x55);
+
+    // a store data trigger matches the value the AMO would write
+    let c = McontrolIn::new()
+        .with_m(true)
+        .with_store(true)
+        .with_select(true)
+        .with_ty(2);
+    let triggers = &cpu.csr_regs.triggers;
+    triggers.write_tdata(1, c.into());
+    triggers.write_tdata(2, 0x56);
+    // the trap cleared tcontrol.MTE
+    triggers.write_tcontrol(1 << 3);
+    amoadd(&mut cpu);
+    assert_eq!(cpu.csr_regs.mcause.get().exception_code(), 3);
+    assert_eq!(cpu.gpr.read(6), 0x77);
+    assert_eq!(cpu.mem_read(0x8000_1000, 4), 0x55);
+
+    // without a trigger the AMO completes
+    cpu.csr_regs.triggers.write_tdata(1, 0);
+    amoadd(&mut cpu);
+    assert_eq!(cpu.npc, 0x8000_0004);
+    assert_eq!(cpu.gpr.read(6), 0x55);
+    assert_eq!(cpu.mem_read(0x8000_1000, 4), 0x56);
+}
+
+#[test]
+fn store_breaks_reservation_test() {
+    let mut config = Config::new();
+    config.set_isa("rv64imac_zicboz_svadu").unwrap();
+    config.set_mmu_type("sv39");
+    config.set_s_mode();
+    let mut cpu = cpu_for_test(config);
+
+    // cbo.zero (a1) on the reserved line
+    cpu.load_reserved(0x8000_1008, 8).unwrap();
+    assert!(cpu.holds_reservation());
+    cpu.gpr.write(11, 0x8000_1000);
+    cpu.exec_for_test(0x0045_a00f);
+    assert!(!cpu.holds_reservation());
+
+    // Svadu setting A in a PTE on the reserved line
+    let root = 0x8008_0000;
+    cpu.sv39_for_test(root);
+    cpu.csr_regs.write_raw(
+        crate::rv64core::inst::inst_base::CSR_MENVCFG.into(),
+        1 << 61,
+    );
+    // V|R|W
+    let pte_addr = cpu.map_for_test(root, 0x1000, 0x8000_3000, 0b111);
+    cpu.load_reserved(pte_addr, 8).unwrap();
+    cpu.cur_priv.set(PrivilegeLevels::Supervisor);
+    cpu.read(0x1000, 8, AccessType::Load(0x1000)).unwrap();
+    assert_eq!(cpu.mem_read(pte_addr, 8) & 0xff, 0b0100_0111);
+    assert!(!cpu.holds_reservation());
+}
+
+#[test]
+fn misaligned_split_test() {
+    let mut config = Config::new();
+    config.set_isa("rv64imac").unwrap();
+    config.set_mmu_type("sv39");
+    config.set_s_mode();
+    config.set_misaligned_access(true);
+    let mut cpu = cpu_for_test(config);
+    let root = 0x8008_0000;
+    cpu.sv39_for_test(root);
+    // two pages that are not contiguous in physical memory, the page after them is unmapped
+    // V|R|W|A|D
+    cpu.map_for_test(root, 0x1000, 0x8000_3000, 0xc7);
+    cpu.map_for_test(root, 0x2000, 0x8000_5000, 0xc7);
+    cpu.mem_write(0x8000_3ff8, 0x1122_3344_5566_7788, 8);
+    cpu.mem_write(0x8000_5000, 0x99aa_bbcc_ddee_ff00, 8);
+    cpu.mem_write(0x8000_5ff8, 0x1122_3344_5566_7788, 8);
+    cpu.cur_priv.set(PrivilegeLevels::Supervisor);
+
+    // the two halves come from their own pages
+    let load = |cpu: &mut CpuCore, addr: u64, len| cpu.read(addr, len, AccessType::Load(addr));
+    assert_eq!(load(&mut cpu, 0x1ffd, 8), Ok(0xccdd_eeff_0011_2233));
+    assert_eq!(load(&mut cpu, 0x1fff, 2), Ok(0x0011));
+    assert_eq!(load(&mut cpu, 0x1ffa, 4), Ok(0x3344_5566));
+
+    // only the bytes of the access are written
+    let store = |cpu: &mut CpuCore, addr: u64, data, len| {
+        cpu.write(addr, data, len, AccessType::Store(addr))
+    };
+    assert_eq!(store(&mut cpu, 0x1ffd, 0x0102_0304_0506_0708, 8), Ok(0));
+    assert_eq!(cpu.mem_read(0x8000_3ff8, 8), 0x0607_0844_5566_7788);
+    assert_eq!(cpu.mem_read(0x8000_5000, 8), 0x99aa_bb01_0203_0405);
+    assert_eq!(store(&mut cpu, 0x1ff9, 0xaabb, 2), Ok(0));
+    assert_eq!(cpu.mem_read(0x8000_3ff8, 8), 0x0607_0844_55aa_bb88);
+    assert_eq!(load(&mut cpu, 0x1ffd, 8), Ok(0x0102_0304_0506_0708));
+
+    // a fault on the second page has its address as tval and leaves the first page untouched
+    assert_eq!(
+        load(&mut cpu, 0x2ffe, 4),
+        Err(TrapType::LoadPageFault(0x3000))
+    );
+    assert_eq!(
+        store(&mut cpu, 0x2ffe, 0xdead_beef, 4),
+        Err(TrapType::StorePageFault(0x3000))
+    );
+    assert_eq!(cpu.mem_read(0x8000_5ff8, 8), 0x1122_3344_5566_7788);
+}
+
+#[test]
+fn wfi_waiting_test() {
+    use crate::rv64core::inst::inst_base::CSR_MIE;
+
+    let wfi = 0x1050_0073;
+    let set_mtip = |cpu: &CpuCore, level: bool| {
+        let mut xip = cpu.csr_regs.xip.get();
+        xip.set_mtip(level);
+        cpu.csr_regs.xip.set(xip);
+    };
+    let mut config = Config::new();
+    config.set_isa("rv64imac").unwrap();
+    let mut cpu = cpu_for_test(config);
+    cpu.csr_regs.write_raw(CSR_MIE.into(), 1 << 7);
+
+    // the hart waits without retiring anything until an enabled interrupt is pending,
+    // even with mstatus.MIE=0
+    cpu.exec_for_test(wfi);
+    assert_eq!(cpu.cpu_state, CpuState::Waiting);
+    let instret = cpu.csr_regs.instret.get();
+    cpu.execute(100);
+    assert_eq!(cpu.cpu_state, CpuState::Waiting);
+    assert_eq!(cpu.csr_regs.instret.get(), instret);
+    set_mtip(&cpu, true);
+    cpu.execute(1);
+    assert_eq!(cpu.cpu_state, CpuState::Running);
+    assert_eq!(cpu.npc, 0x8000_0004);
+
+    // the difftest reference wakes up the same way
+    set_mtip(&cpu, false);
+    cpu.npc = 0x8000_0000;
+    cpu.execute_as_ref(1);
+    assert_eq!(cpu.cpu_state, CpuState::Waiting);
+    cpu.execute_as_ref(100);
+    assert_eq!(cpu.cpu_state, CpuState::Waiting);
+    set_mtip(&cpu, true);
+    cpu.execute_as_ref(1);
+    assert_eq!(cpu.cpu_state, CpuState::Running);
+    assert_eq!(cpu.npc, 0x8000_0004);
+}
+
+#[test]
+fn wfi_timeout_test() {
+    let wfi = 0x1050_0073;
+    // run a WFI in the mode and return the exception it raised after the timeout
+    let wfi_trap = |cpu: &mut CpuCore, privi: PrivilegeLevels, virt: bool| {
+        cpu.cur_priv.set(privi);
+        cpu.cur_virt.set(virt);
+        cpu.npc = 0x8000_0000;
+        cpu.csr_regs.mcause.set(0.into());
+        cpu.exec_for_test(wfi);
+        match cpu.config.wfi_timeout() {
+            0 => {}
+            timeout => {
+                assert_eq!(cpu.cpu_state, CpuState::Waiting);
+                cpu.execute(timeout - 1);
+                assert_eq!(cpu.cpu_state, CpuState::Waiting);
+                cpu.execute(1);
+            }
+        }
+        assert_eq!(cpu.cpu_state, CpuState::Running);
+        assert_eq!(cpu.cur_priv.get(), PrivilegeLevels::Machine);
+        assert_eq!(cpu.csr_regs.mepc.get(), 0x8000_0000);
+        cpu.csr_regs.mcause.get().exception_code()
+    };
+    let set_tw = |cpu: &CpuCore, tw: bool, vtw: bool| {
+        let mut mstatus = cpu.csr_regs.xstatus.get();
+        mstatus.set_tw(tw);
+        cpu.csr_regs.xstatus.set(mstatus);
+        let mut hstatus = cpu.csr_regs.hstatus.get();
+        hstatus.set_vtw(vtw);
+        cpu.csr_regs.hstatus.set(hstatus);
+    };
+
+    for timeout in [0, 100] {
+        let mut config = Config::new();
+        config.set_isa("rv64imach").unwrap();
+        config.set_s_mode();
+        config.set_wfi_timeout(timeout);
+        let mut cpu = cpu_for_test(config);
+        cpu.pmp_for_test();
+
+        set_tw(&cpu, true, false);
+        assert_eq!(wfi_trap(&mut cpu, PrivilegeLevels::Supervisor, false), 2);
+        // TW takes precedence over VTW
+        set_tw(&cpu, true, true);
+        assert_eq!(wfi_trap(&mut cpu, PrivilegeLevels::Supervisor, true), 2);
+        assert_eq!(wfi_trap(&mut cpu, PrivilegeLevels::User, true), 2);
+
+        // U-mode traps regardless of TW, VU-mode and VS-mode with VTW=1 raise
+        // a virtual instruction exception
+        set_tw(&cpu, false, false);
+        assert_eq!(wfi_trap(&mut cpu, PrivilegeLevels::User, false), 2);
+        assert_eq!(wfi_trap(&mut cpu, PrivilegeLevels::User, true), 22);
+        set_tw(&cpu, false, true);
+        assert_eq!(wfi_trap(&mut cpu, PrivilegeLevels::Supervisor, true), 22);
+
+        // S-mode and VS-mode with VTW=0 wait for an interrupt
+        set_tw(&cpu, false, false);
+        for virt in [false, true] {
+            cpu.cur_priv.set(PrivilegeLevels::Supervisor);
+            cpu.cur_virt.set(virt);
+            cpu.npc = 0x8000_0000;
+            cpu.exec_for_test(wfi);
+            cpu.execute(1000);
+            assert_eq!(cpu.cpu_state, CpuState::Waiting);
+            cpu.cpu_state = CpuState::Running;
+        }
+    }
+}
+
+#[test]
+fn g_stage_walk_test() {
+    use crate::rv64core::inst::inst_base::CSR_HGATP;
+
+    let mut config = Config::new();
+    config.set_isa("rv64imach").unwrap();
+    config.set_mmu_type("sv48");
+    config.set_s_mode();
+    let mut cpu = cpu_for_test(config);
+    cpu.pmp_for_test();
+    cpu.mem_write(0x8000_3008, 0x1234, 8);
+    // the guest runs in VS-mode with vsatp.MODE=Bare, so the va is the gpa
+    cpu.cur_priv.set(PrivilegeLevels::Supervisor);
+    cpu.cur_virt.set(true);
+    let load = |cpu: &mut CpuCore, gpa: u64| cpu.read(gpa, 8, AccessType::Load(gpa));
+    let fault = |gpa: u64| Err(TrapType::LoadGuestPageFault(gpa, gpa));
+    // Map the guest page at gpa to 0x8000_3000, the 16 KiB root table is indexed by
+    // 11 bits and the next level tables follow it.
+    let g_map = |cpu: &CpuCore, root: u64, levels: u64, gpa: u64, flags: u64| {
+        let mut table = root;
+        for level in (1..levels).rev() {
+            let bits = if level == levels - 1 { 11 } else { 9 };
+            let vpn = (gpa >> (12 + 9 * level)) & ((1 << bits) - 1);
+            let next = root + 0x4000 + 0x1000 * (levels - 1 - level);
+            cpu.mem_write(table + vpn * 8, next >> 2 | 1, 8);
+            table = next;
+        }
+        cpu.mem_write(
+            table + ((gpa >> 12) & 0x1ff) * 8,
+            0x8000_3000 >> 2 | flags,
+            8,
+        );
+    };
+
+    // Sv39x4 and Sv48x4
+    for (mode, levels) in [(8_u64, 3), (9, 4)] {
+        let root = 0x8000_0000 + 0x1_0000 * mode;
+        cpu.csr_regs
+            .write_raw(CSR_HGATP.into(), mode << 60 | root >> 12);
+        // the top two bits of the gpa index the root table
+        let gpa = 1 << (12 + 9 * levels + 1) | 0x1008;
+        assert_eq!(load(&mut cpu, gpa), fault(gpa));
+        // V|R|W|U|A|D
+        g_map(&cpu, root, levels, gpa, 0xd7);
+        assert_eq!(load(&mut cpu, gpa), Ok(0x1234));
+        // a gpa wider than the mode faults
+        let wide = 1 << (12 + 9 * levels + 2) | gpa;
+        assert_eq!(load(&mut cpu, wide), fault(wide));
+        // the G-stage leaf PTEs must have U=1
+        g_map(&cpu, root, levels, gpa, 0xc7);
+        assert_eq!(load(&mut cpu, gpa), fault(gpa));
+    }
+}
+
+#[test]
+fn guest_page_fault_delegation_test() {
+    use crate::rv64core::inst::inst_base::{CSR_HEDELEG, CSR_HGATP, CSR_MEDELEG, CSR_VSATP};
+
+    let mut config = Config::new();
+    config.set_isa("rv64imach").unwrap();
+    config.set_mmu_type("sv39");
+    config.set_s_mode();
+    let mut cpu = cpu_for_test(config);
+    cpu.pmp_for_test();
+    // an empty Sv39x4 root table
+    cpu.csr_regs
+        .write_raw(CSR_HGATP.into(), 8 << 60 | 0x8008_0000 >> 12);
+    // the guest loads from va in VS-mode and takes the trap
+    let trap = |cpu: &mut CpuCore, va: u64| {
+        cpu.cur_priv.set(PrivilegeLevels::Supervisor);
+        cpu.cur_virt.set(true);
+        cpu.pc = 0x8000_0000;
+        let trap = cpu.read(va, 8, AccessType::Load(va)).unwrap_err();
+        cpu.handle_exceptions(trap);
+        trap
+    };
+
+    // M-mode gets the gpa in mtval2 and mstatus.GVA=1
+    assert_eq!(
+        trap(&mut cpu, 0x1008),
+        TrapType::LoadGuestPageFault(0x1008, 0x1008)
+    );
+    assert_eq!(cpu.cur_priv.get(), PrivilegeLevels::Machine);
+    assert_eq!(cpu.csr_regs.mcause.get().exception_code(), 21);
+    assert_eq!(cpu.csr_regs.mtval.get(), 0x1008);
+    assert_eq!(cpu.csr_regs.mtval2.get(), 0x1008 >> 2);
+    let mstatus = cpu.csr_regs.xstatus.get();
+    assert!(mstatus.gva() && mstatus.mpv());
+
+    // medeleg sends it to HS-mode, the guest page faults can't be delegated by hedeleg
+    cpu.csr_regs
+        .write_raw(CSR_MEDELEG.into(), 1 << 21 | 1 << 13);
+    cpu.csr_regs
+        .write_raw(CSR_HEDELEG.into(), 1 << 21 | 1 << 13);
+    assert_eq!(cpu.csr_regs.read_raw(CSR_HEDELEG.into()), 1 << 13);
+    trap(&mut cpu, 0x1008);
+    assert_eq!(cpu.cur_priv.get(), PrivilegeLevels::Supervisor);
+    assert!(!cpu.cur_virt.get());
+    assert_eq!(cpu.csr_regs.scause.get().exception_code(), 21);
+    assert_eq!(cpu.csr_regs.stval.get(), 0x1008);
+    assert_eq!(cpu.csr_regs.htval.get(), 0x1008 >> 2);
+    let hstatus = cpu.csr_regs.hstatus.get();
+    assert!(hstatus.gva() && hstatus.spv());
+
+    // a VS-stage PTE at an unmapped gpa reports the PTE address in htval
+    // and the original va in stval
+    cpu.csr_regs
+        .write_raw(CSR_VSATP.into(), 8 << 60 | 0x2000 >> 12);
+    assert_eq!(
+        trap(&mut cpu, 0x1008),
+        TrapType::LoadGuestPageFault(0x1008, 0x2000)
+    );
+    assert_eq!(cpu.csr_regs.stval.get(), 0x1008);
+    assert_eq!(cpu.csr_regs.htval.get(), 0x2000 >> 2);
+
+    // a VS-stage page fault delegated by hedeleg goes to VS-mode, the root table is empty
+    cpu.csr_regs
+        .write_raw(CSR_VSATP.into(), 8 << 60 | 0x8008_0000 >> 12);
+    let mut hgatp = cpu.csr_regs.hgatp.get();
+    hgatp.set_mode(0);
+    cpu.csr_regs.hgatp.set(hgatp);
+    assert_eq!(trap(&mut cpu, 0x1008), TrapType::LoadPageFault(0x1008));
+    assert_eq!(cpu.cur_priv.get(), PrivilegeLevels::Supervisor);
+    assert!(cpu.cur_virt.get());
+    assert_eq!(cpu.csr_regs.vscause.get().exception_code(), 13);
+    assert_eq!(cpu.csr_regs.vstval.get(), 0x1008);
+}
+
+#[test]
+fn misa_d_test() {
+    use crate::rv64core::inst::inst_base::CSR_MISA;
+
+    // the next pc, mtvec is 0 so a trap lands on 0
+    let run = |cpu: &mut CpuCore, inst: u32| {
+        cpu.npc = 0x8000_0000;
+        cpu.exec_for_test(inst);
+        cpu.npc
+    };
+
+    for (isa, d) in [("rv64imafdc", true), ("rv64imafc", false)] {
+        let mut config = Config::new();
+        config.set_isa(isa).unwrap();
+        let mut cpu = cpu_for_test(config);
+        let misa = cpu.csr_regs.read_raw(CSR_MISA.into());
+        assert_eq!(misa & 1 << 3 != 0, d, "{isa}");
+        assert_ne!(misa & 1 << 5, 0, "{isa}");
+
+        let mut mstatus = cpu.csr_regs.xstatus.get();
+        mstatus.set_fs_dirty();
+        cpu.csr_regs.xstatus.set(mstatus);
+        // fadd.d fa0, fa1, fa2
+        cpu.fpr.write(11, 1.5_f64.to_bits());
+        cpu.fpr.write(12, 2.0_f64.to_bits());
+        let next = run(&mut cpu, 0x02c5_f553);
+        assert_eq!(next, if d { 0x8000_0004 } else { 0 }, "{isa}");
+        // c.fld fa0, 248(a1)
+        cpu.gpr.write(11, 0x8000_1000);
+        cpu.mem_write(0x8000_10f8, 2.5_f64.to_bits(), 8);
+        let next = run(&mut cpu, 0x3de8);
+        assert_eq!(next, if d { 0x8000_0002 } else { 0 }, "{isa}");
+        if d {
+            assert_eq!(cpu.fpr.read(10), 2.5_f64.to_bits());
+        } else {
+            assert_eq!(cpu.csr_regs.mcause.get().exception_code(), 2);
+        }
+        // fadd.s fa0, fa1, fa2 is there either way
+        assert_eq!(run(&mut cpu, 0x00c5_f553), 0x8000_0004, "{isa}");
+    }
+}
+
+#[test]
+fn rv32_csr_halves_test() {
+    use crate::rv64core::csr_regs_define::StapMode;
+    use crate::rv64core::inst::inst_base::{
+        CSR_CYCLE, CSR_CYCLEH, CSR_MCAUSE, CSR_MCYCLEH, CSR_MSTATUS, CSR_MSTATUSH, CSR_PMPCFG0,
+        CSR_PMPCFG1, CSR_SATP,
+    };
+    let m = PrivilegeLevels::Machine;
+
+    let mut config = Config::new();
+    config.set_isa("rv32imac").unwrap();
+    config.set_s_mode();
+    config.set_mmu_type("sv32");
+    let mut cpu = cpu_for_test(config);
+    let csr = &mut cpu.csr_regs;
+
+    // SXL and UXL are held in the upper half of mstatus but mstatush reads 0
+    assert_ne!(csr.read_raw(CSR_MSTATUS.into()) >> 32, 0);
+    assert_eq!(csr.read(CSR_MSTATUSH.into(), m), Ok(0));
+
+    // cycleh and mcycleh are the upper half of the 64-bit counter
+    csr.cycle.set(0x1_8000_0002);
+    assert_eq!(csr.read(CSR_CYCLEH.into(), m), Ok(1));
+    assert_eq!(csr.read(CSR_MCYCLEH.into(), m), Ok(1));
+    assert_eq!(csr.read(CSR_CYCLE.into(), m), Ok(0xffff_ffff_8000_0002));
+    assert_eq!(
+        csr.write(CSR_CYCLEH.into(), 0, m),
+        Err(TrapType::IllegalInstruction(0))
+    );
+
+    // pmpcfg1 holds entries 4 to 7 of pmpcfg0
+    assert_eq!(csr.write(CSR_PMPCFG0.into(), 0x0101_0101, m), Ok(()));
+    assert_eq!(csr.write(CSR_PMPCFG1.into(), 0x0300_0001, m), Ok(()));
+    assert_eq!(csr.read_raw(CSR_PMPCFG0.into()), 0x0300_0001_0101_0101);
+    assert_eq!(csr.read(CSR_PMPCFG0.into(), m), Ok(0x0101_0101));
+    assert_eq!(csr.read(CSR_PMPCFG1.into(), m), Ok(0x0300_0001));
+
+    // the interrupt bit of mcause moves between bit 31 and bit 63
+    assert_eq!(csr.write(CSR_MCAUSE.into(), 0x8000_0007, m), Ok(()));
+    assert!(csr.mcause.get().interrupt());
+    assert_eq!(csr.mcause.get().exception_code(), 7);
+    assert_eq!(csr.read(CSR_MCAUSE.into(), m), Ok(0xffff_ffff_8000_0007));
+
+    // satp is MODE[31], ASID[30:22] and PPN[21:0]
+    let satp = 1 << 31 | 0x5 << 22 | 0x3f_ffff;
+    assert_eq!(csr.write(CSR_SATP.into(), satp, m), Ok(()));
+    assert_eq!(csr.satp.get().mode(), StapMode::Sv32);
+    assert_eq!(csr.satp.get().asid(), 5);
+    assert_eq!(csr.satp.get().ppn(), 0x3f_ffff);
+    assert_eq!(csr.read(CSR_SATP.into(), m), Ok(satp as i32 as u64));
+
+    // an rv64 hart runs S-mode and U-mode with the XLEN of mstatus.SXL and UXL
+    let mut config = Config::new();
+    config.set_isa("rv64imac").unwrap();
+    config.set_s_mode();
+    let mut cpu = cpu_for_test(config);
+    let mstatus = cpu.csr_regs.read_raw(CSR_MSTATUS.into()) & !(0xf << 32);
+    let mstatus = mstatus | 1 << 34 | 2 << 32;
+    assert_eq!(cpu.csr_regs.write(CSR_MSTATUS.into(), mstatus, m), Ok(()));
+    for (privi, rv32) in [
+        (PrivilegeLevels::Machine, false),
+        (PrivilegeLevels::Supervisor, true),
+        (PrivilegeLevels::User, false),
+    ] {
+        cpu.cur_priv.set(privi);
+        cpu.update_xlen();
+        assert_eq!(cpu.mmu.rv32, rv32, "{privi:?}");
+        cpu.gpr.write(10, 0x1_8000_0000);
+        let (a0, name) = match rv32 {
+            true => (0xffff_ffff_8000_0000, "c.jal"),
+            false => (0x1_8000_0000, "c.addiw"),
+        };
+        assert_eq!(cpu.gpr.read(10), a0, "{privi:?}");
+        // c.jal in RV32, c.addiw in RV64
+        let inst = cpu.decode.fast_path(0x2001).map(|i| i.name);
+        assert_eq!(inst, Some(name), "{privi:?}");
+    }
+}
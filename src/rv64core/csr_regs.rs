@@ -5,13 +5,15 @@ use hashbrown::HashMap;
 
 use crate::{
     config::Config,
+    device::device_imsic::ImsicFile,
     rv64core::csr_regs_define::{
         CommonCSR, Counter, Csr, CsrEnum, Medeleg, MedelegIn, Mideleg, MidelegIn, Misa,
         ReadOnlyCSR, Satp, SatpIn, Xcause, XcauseIn, Xie, XieIn, Xip, XipIn, Xstatus, XstatusIn,
         Xtvec, XtvecIn,
     },
     rv64core::inst::inst_base::{
-        AccessType, PrivilegeLevels, CSR_CYCLE, CSR_INSTRET, CSR_MARCHID, CSR_MCAUSE,
+        AccessType, PrivilegeLevels, CSR_CYCLE, CSR_CYCLEH, CSR_HPMCOUNTER31H, CSR_INSTRET,
+        CSR_MARCHID, CSR_MCAUSE,
         CSR_MCOUNTEREN, CSR_MCYCLE, CSR_MEDELEG, CSR_MEPC, CSR_MHARTID, CSR_MIDELEG, CSR_MIE,
         CSR_MIMPID, CSR_MINSTRET, CSR_MIP, CSR_MISA, CSR_MSCRATCH, CSR_MSTATUS, CSR_MTVAL,
         CSR_MTVEC, CSR_MVENDORID, CSR_SATP, CSR_SCAUSE, CSR_SCOUNTEREN, CSR_SEPC, CSR_SIE, CSR_SIP,
@@ -22,14 +24,38 @@ use crate::{
 };
 
 use super::{
-    csr_regs_define::{Dcsr, DcsrIn},
-    inst::inst_base::{CSR_DCSR, CSR_DPC, CSR_DSCRATCH0, CSR_DSCRATCH1},
+    csr_regs_define::{
+        CsrAddr, Dcsr, DcsrIn, Fcsr, FcsrIn, Hgatp, HgatpIn, Hpmcounter, Hstatus, HstatusIn,
+        Ireg, Jvt, JvtIn, Mcountinhibit, Menvcfg, MenvcfgIn, Mhpmevent, Mseccfg, MseccfgIn,
+        PMPaddr, PMPcfg, Scountovf, Seed, Stimecmp,
+        Tcontrol, Tdata, Tinfo, Topei, Topi, Tselect, Vcsr, VcsrIn, VectorCsr, Vsie, Vsip, Vtype, VtypeIn, VS_INTERRUPT_MASK,
+    },
+    inst::inst_base::{
+        CSR_DCSR, CSR_DPC, CSR_DSCRATCH0, CSR_DSCRATCH1, CSR_FCSR, CSR_FFLAGS, CSR_FRM,
+        CSR_HCOUNTEREN, CSR_HEDELEG, CSR_HENVCFG, CSR_HGATP, CSR_HGEIE, CSR_HGEIP,
+        CSR_HIDELEG, CSR_HIE, CSR_HIP, CSR_HSTATUS, CSR_HTIMEDELTA, CSR_HTINST, CSR_HTVAL,
+        CSR_HPMCOUNTER3, CSR_HPMCOUNTER31, CSR_HVIP, CSR_JVT, CSR_MCOUNTINHIBIT, CSR_MENVCFG,
+        CSR_MHPMCOUNTER3, CSR_MHPMEVENT3, CSR_MIREG, CSR_MISELECT, CSR_MTINST, CSR_MTOPEI,
+        CSR_MCYCLEH, CSR_MENVCFGH, CSR_MHPMCOUNTER31H, CSR_MSECCFG, CSR_MSTATUSH, CSR_MTOPI,
+        CSR_MTVAL2, CSR_PMPADDR0, CSR_PMPCFG0, CSR_PMPCFG15,
+        CSR_SCOUNTOVF, CSR_SEED, CSR_SENVCFG, CSR_SIREG, CSR_SISELECT, CSR_STOPEI, CSR_STOPI, CSR_STIMECMP, CSR_TCONTROL, CSR_TDATA1, CSR_TDATA2, CSR_TDATA3, CSR_TINFO,
+        CSR_VCSR, CSR_VL, CSR_VLENB, CSR_VSATP, CSR_VSCAUSE,
+        CSR_VSEPC, CSR_VSIE, CSR_VSIP, CSR_VSSCRATCH, CSR_VSSTATUS, CSR_VSTART, CSR_VSTVAL, CSR_VSTVEC,
+        CSR_VTYPE, CSR_VXRM, CSR_VXSAT,
+    },
+    hpm::{Hpm, HPM_FIRST, HPM_NUM},
+    mmu::pmp::Pmp,
+    trigger::{Triggers, TRIGGER_NUM},
 };
 
 pub struct CsrRegs {
     config: Rc<Config>,
     pub csr_map: HashMap<u64, CsrEnum>,
     pub cur_priv: PrivilegeLevels,
+    // virtualization mode, shared with the cpu and the mmu
+    pub virt: RcCell<bool>,
+    // the privilege level of the hart, shared with the cpu, the mmu and the counters
+    pub privilege: RcCell<PrivilegeLevels>,
     pub xstatus: RcCell<XstatusIn>,
     pub xip: RcCell<XipIn>,
     pub xie: RcCell<XieIn>,
@@ -46,6 +72,54 @@ pub struct CsrRegs {
     pub stval: RcCell<u64>,
     pub cycle: RcCell<u64>,
     pub instret: RcCell<u64>,
+    pub mcounteren: RcCell<u64>,
+    pub scounteren: RcCell<u64>,
+    pub hcounteren: RcCell<u64>,
+    // Zihpm counters and mcountinhibit
+    pub hpm: Rc<Hpm>,
+    // Smaia/Ssaia, the interrupt files of the hart and their indirect selectors
+    pub imsic_m: Option<Rc<ImsicFile>>,
+    pub imsic_s: Option<Rc<ImsicFile>>,
+    pub miselect: RcCell<u64>,
+    pub siselect: RcCell<u64>,
+    pub menvcfg: RcCell<MenvcfgIn>,
+    pub senvcfg: RcCell<MenvcfgIn>,
+    pub henvcfg: RcCell<MenvcfgIn>,
+    // Zkr, mseccfg.USEED and SSEED open the seed csr to U-mode and S-mode
+    pub mseccfg: RcCell<MseccfgIn>,
+    // Zcmt, the base of the cm.jt/cm.jalt jump table
+    pub jvt: RcCell<JvtIn>,
+    // supervisor timer compare (Sstc)
+    pub stimecmp: RcCell<u64>,
+    mtime: Option<RcCell<u64>>,
+    // floating point
+    pub fcsr: RcCell<FcsrIn>,
+    // vector
+    pub vstart: RcCell<u64>,
+    pub vl: RcCell<u64>,
+    pub vtype: RcCell<VtypeIn>,
+    pub vcsr: RcCell<VcsrIn>,
+    // hypervisor
+    pub hstatus: RcCell<HstatusIn>,
+    pub hedeleg: RcCell<MedelegIn>,
+    pub hideleg: RcCell<MidelegIn>,
+    pub hgatp: RcCell<HgatpIn>,
+    pub htval: RcCell<u64>,
+    pub htinst: RcCell<u64>,
+    pub htimedelta: RcCell<u64>,
+    pub mtval2: RcCell<u64>,
+    pub mtinst: RcCell<u64>,
+    // the VS csrs replace their S counterparts when V=1
+    pub vsstatus: RcCell<XstatusIn>,
+    pub vstvec: RcCell<XtvecIn>,
+    pub vsepc: RcCell<u64>,
+    pub vscause: RcCell<XcauseIn>,
+    pub vstval: RcCell<u64>,
+    pub vsatp: RcCell<SatpIn>,
+    // physical memory protection
+    pub pmp: Rc<Pmp>,
+    // Sdtrig debug triggers
+    pub triggers: Rc<Triggers>,
 
     // debug mode
     pub dcsr: RcCell<DcsrIn>,
@@ -60,10 +134,10 @@ impl CsrRegs {
         mstatus_val.set_sbe(false);
         mstatus_val.set_ube(false);
         if self.config.s_mode() {
-            mstatus_val.set_sxl(2) // 64
+            mstatus_val.set_sxl(mxl(&self.config))
         }
         if self.config.u_mode() {
-            mstatus_val.set_uxl(2); // 64
+            mstatus_val.set_uxl(mxl(&self.config));
             mstatus_val.set_mprv(false);
         }
         self.xstatus.set(mstatus_val);
@@ -75,7 +149,7 @@ impl CsrRegs {
         self.mcause.set(XcauseIn::new());
         self.scause.set(XcauseIn::new());
         self.medeleg.set(MedelegIn::new());
-        self.mideleg.set(MidelegIn::new());
+        self.mideleg.set(mideleg_reset_val(&self.config));
         self.mepc.set(0);
         self.sepc.set(0);
         self.satp.set(SatpIn::new());
@@ -83,13 +157,49 @@ impl CsrRegs {
         self.stval.set(0);
         self.cycle.set(0);
         self.instret.set(0);
+        self.mcounteren.set(0);
+        self.scounteren.set(0);
+        self.hcounteren.set(0);
+        self.hpm.reset();
+        self.miselect.set(0);
+        self.siselect.set(0);
+        self.imsic_m.iter().chain(self.imsic_s.iter()).for_each(|file| file.reset());
+        self.menvcfg.set(MenvcfgIn::new());
+        self.senvcfg.set(MenvcfgIn::new());
+        self.henvcfg.set(MenvcfgIn::new());
+        self.mseccfg.set(MseccfgIn::new());
+        self.jvt.set(JvtIn::new());
+        self.stimecmp.set(u64::MAX);
+        self.fcsr.set(FcsrIn::new());
+        self.vstart.set(0);
+        self.vl.set(0);
+        self.vtype.set(VtypeIn::new().with_vill(true));
+        self.vcsr.set(VcsrIn::new());
+        self.virt.set(false);
+        self.hstatus.set(HstatusIn::new().with_vsxl(2));
+        self.hedeleg.set(MedelegIn::new());
+        self.hideleg.set(MidelegIn::new());
+        self.hgatp.set(HgatpIn::new());
+        self.htval.set(0);
+        self.htinst.set(0);
+        self.htimedelta.set(0);
+        self.mtval2.set(0);
+        self.mtinst.set(0);
+        self.vsstatus.set(XstatusIn::new().with_uxl(2));
+        self.vstvec.set(XtvecIn::new());
+        self.vsepc.set(0);
+        self.vscause.set(XcauseIn::new());
+        self.vstval.set(0);
+        self.vsatp.set(SatpIn::new());
+        self.pmp.reset();
+        self.triggers.reset();
         self.dcsr
             .set(DcsrIn::new().with_debugver(4).with_mprven(true));
         self.dpc.set(0);
     }
 
     pub fn new(hart_id: usize, config: Rc<Config>) -> Self {
-        let mut misa_val = Misa::new().with_i(true).with_mxl(2); // 64
+        let mut misa_val = Misa::new().with_i(true).with_mxl(mxl(&config));
 
         if config.is_enable_isa(b'm') {
             misa_val.set_m(true);
@@ -100,12 +210,24 @@ impl CsrRegs {
         if config.is_enable_isa(b'c') {
             misa_val.set_c(true);
         }
+        if config.is_enable_isa(b'f') {
+            misa_val.set_f(true);
+        }
+        if config.is_enable_isa(b'd') {
+            misa_val.set_d(true);
+        }
+        if config.is_enable_isa(b'v') {
+            misa_val.set_v(true);
+        }
         if config.s_mode() {
             misa_val.set_s(true);
         }
         if config.u_mode() {
             misa_val.set_u(true);
         }
+        if config.h_mode() {
+            misa_val.set_h(true);
+        }
 
         let mut mstatus_val = XstatusIn::new().with_mpp(PrivilegeLevels::Machine as u8);
 
@@ -141,10 +263,20 @@ impl CsrRegs {
         if !config.u_mode() && !config.s_mode() {
             mstatus_rmask.set_tw(true);
         }
+
+        if !config.h_mode() {
+            mstatus_rmask.set_mpv(true);
+            mstatus_rmask.set_gva(true);
+        }
+        // no custom extension state
+        mstatus_rmask.set_xs(0b11);
+        if !config.is_enable_isa(b'v') {
+            mstatus_rmask.set_vs(0b11);
+        }
         if !config.is_enable_isa(b'f') {
             mstatus_rmask.set_fs(0b11);
-            mstatus_rmask.set_vs(0b11);
-            mstatus_rmask.set_xs(0b11);
+        }
+        if !config.is_enable_isa(b'f') && !config.is_enable_isa(b'v') {
             mstatus_rmask.set_sd(true);
         }
 
@@ -174,14 +306,51 @@ impl CsrRegs {
         let mimpid = CommonCSR::new_noshare(0);
         // important csrs
         let xstatus_share = RcCell::new(mstatus_val.into());
-        let mstatus = Xstatus::new(xstatus_share.clone(), mstatus_rmask, mstatus_wmask.into());
+        let mstatus = Xstatus::new(xstatus_share.clone(), mstatus_rmask, mstatus_wmask.into())
+            .with_xl_writable(config.xlen_switchable());
         let sstatus = Xstatus::new(xstatus_share.clone(), mstatus_rmask, sstatus_wmask);
 
-        let sip_mask = XieIn::new().with_seie(true).with_ssie(true).with_stie(true);
+        let sstc = config.s_mode() && config.is_enable_ext("sstc");
+        // the cache-block operation enables are shared by menvcfg, senvcfg and henvcfg
+        let envcfg_cbo_mask = MenvcfgIn::new()
+            .with_cbie(if config.is_enable_ext("zicbom") { 0b11 } else { 0 })
+            .with_cbcfe(config.is_enable_ext("zicbom"))
+            .with_cbze(config.is_enable_ext("zicboz"));
+        // Svpbmt is enabled by menvcfg.PBMTE, and for the guest also by henvcfg.PBMTE
+        let svpbmt = config.s_mode() && config.is_enable_ext("svpbmt");
+        // Svadu hardware A/D updating is enabled by menvcfg.ADUE, and for the VS-stage by henvcfg.ADUE
+        let svadu = config.s_mode() && config.is_enable_ext("svadu");
+        let menvcfg_mask = envcfg_cbo_mask
+            .with_stce(sstc)
+            .with_pbmte(svpbmt)
+            .with_adue(svadu);
+        let menvcfg_share = Rc::new(Cell::new(MenvcfgIn::new()));
+        let menvcfg = Menvcfg::new(menvcfg_share.clone(), menvcfg_mask.into());
+        let senvcfg_share = Rc::new(Cell::new(MenvcfgIn::new()));
+        let senvcfg = Menvcfg::new(senvcfg_share.clone(), envcfg_cbo_mask.into());
+        let zkr = config.is_enable_ext("zkr");
+        let mseccfg_mask = MseccfgIn::new()
+            .with_useed(zkr && config.u_mode())
+            .with_sseed(zkr && config.s_mode());
+        let mseccfg_share = Rc::new(Cell::new(MseccfgIn::new()));
+        let mseccfg = Mseccfg::new(mseccfg_share.clone(), mseccfg_mask.into());
+        let seed = Seed::new(config.entropy_seed().unwrap_or_else(host_entropy));
+        let jvt_share = Rc::new(Cell::new(JvtIn::new()));
+        let jvt = Jvt::new(jvt_share.clone());
+
+        let sscofpmf = config.s_mode() && config.is_enable_ext("sscofpmf");
+        let sip_mask = XieIn::new()
+            .with_seie(true)
+            .with_ssie(true)
+            .with_stie(true)
+            .with_lcofie(sscofpmf);
+
+        // the VS-level interrupts and SGEI are always delegated to HS-mode
+        let h_int_mask = VS_INTERRUPT_MASK | u64::from(XipIn::new().with_sgeip(true));
 
         let xip_share = Rc::new(Cell::new(XipIn::new()));
-        let mip = Xip::new(xip_share.clone(), MASK_ALL);
-        let sip = Xip::new(xip_share.clone(), sip_mask.into());
+        let mip = Xip::new(xip_share.clone(), MASK_ALL).with_menvcfg(menvcfg_share.clone());
+        let sip = Xip::new(xip_share.clone(), sip_mask.into()).with_menvcfg(menvcfg_share.clone());
 
         let xie_share = Rc::new(Cell::new(XieIn::new()));
         let mie = Xie::new(xie_share.clone(), MASK_ALL);
@@ -198,9 +367,9 @@ impl CsrRegs {
         let stvec = Xtvec::new(stvec_share.clone());
 
         let medeleg_share = Rc::new(Cell::new(MedelegIn::new()));
-        let medeleg = Medeleg::new(medeleg_share.clone());
-        let mideleg_share = Rc::new(Cell::new(MidelegIn::new()));
-        let mideleg = Mideleg::new(mideleg_share.clone(), MASK_ALL);
+        let medeleg = Medeleg::new(medeleg_share.clone(), MASK_ALL);
+        let mideleg_share = Rc::new(Cell::new(mideleg_reset_val(&config)));
+        let mideleg = Mideleg::new(mideleg_share.clone(), MASK_ALL).with_wmask(!h_int_mask);
 
         let mepc_share = Rc::new(Cell::new(0_u64));
         let mepc = CommonCSR::new(mepc_share.clone());
@@ -230,23 +399,131 @@ impl CsrRegs {
 
         let mcounteren_share = Rc::new(Cell::new(0));
         let scounteren_share = Rc::new(Cell::new(0));
-        let mcounteren = CommonCSR::new(mcounteren_share);
-        let scounteren = CommonCSR::new(scounteren_share);
+        let mcounteren = CommonCSR::new(mcounteren_share.clone());
+        let scounteren = CommonCSR::new(scounteren_share.clone());
+
+        // floating point
+        let fcsr_share = Rc::new(Cell::new(FcsrIn::new()));
+        let fflags = Fcsr::new_fflags(fcsr_share.clone(), xstatus_share.clone());
+        let frm = Fcsr::new_frm(fcsr_share.clone(), xstatus_share.clone());
+        let fcsr = Fcsr::new_fcsr(fcsr_share.clone(), xstatus_share.clone());
+
+        // vector, the largest VLMAX is VLEN (LMUL=8, SEW=8), so vstart needs log2(VLEN) bits
+        let vstart_share = Rc::new(Cell::new(0));
+        let vl_share = Rc::new(Cell::new(0));
+        let vtype_share = Rc::new(Cell::new(VtypeIn::new().with_vill(true)));
+        let vcsr_share = Rc::new(Cell::new(VcsrIn::new()));
+        let vstart = VectorCsr::new(
+            vstart_share.clone(),
+            xstatus_share.clone(),
+            config.vlen() as u64 - 1,
+        );
+        let vl = VectorCsr::new(vl_share.clone(), xstatus_share.clone(), MASK_ALL);
+        let vlenb = VectorCsr::new(
+            Rc::new(Cell::new(config.vlen() as u64 / 8)),
+            xstatus_share.clone(),
+            MASK_ALL,
+        );
+        let vtype = Vtype::new(vtype_share.clone(), xstatus_share.clone());
+        let vxsat = Vcsr::new_vxsat(vcsr_share.clone(), xstatus_share.clone());
+        let vxrm = Vcsr::new_vxrm(vcsr_share.clone(), xstatus_share.clone());
+        let vcsr = Vcsr::new_vcsr(vcsr_share.clone(), xstatus_share.clone());
+
+        // hypervisor
+        let virt_share = Rc::new(Cell::new(false));
+        let privilege_share = Rc::new(Cell::new(PrivilegeLevels::Machine));
+        let hstatus_share = Rc::new(Cell::new(HstatusIn::new().with_vsxl(2)));
+        let hstatus = Hstatus::new(hstatus_share.clone());
+        // ecalls from HS, VS and M-mode and the guest exceptions can not be delegated to VS-mode
+        let hedeleg_share = Rc::new(Cell::new(MedelegIn::new()));
+        let hedeleg = Medeleg::new(hedeleg_share.clone(), 0xb1ff);
+        let hideleg_share = Rc::new(Cell::new(MidelegIn::new()));
+        let hideleg = Mideleg::new(hideleg_share.clone(), VS_INTERRUPT_MASK);
+        let hvip = Xip::new(xip_share.clone(), VS_INTERRUPT_MASK);
+        // hip.VSSIP aliases hvip.VSSIP, the other bits are read-only
+        let hip = Xip::new(xip_share.clone(), h_int_mask)
+            .with_wmask(u64::from(XipIn::new().with_vssip(true)));
+        let hie = Xie::new(xie_share.clone(), h_int_mask);
+        let hgatp_share = Rc::new(Cell::new(HgatpIn::new()));
+        let hgatp = Hgatp::new(
+            hgatp_share.clone(),
+            xstatus_share.clone(),
+            config.get_mmu_type(),
+        );
+        let htval_share = Rc::new(Cell::new(0_u64));
+        let htval = CommonCSR::new(htval_share.clone());
+        let htinst_share = Rc::new(Cell::new(0_u64));
+        let htinst = CommonCSR::new(htinst_share.clone());
+        let htimedelta_share = Rc::new(Cell::new(0_u64));
+        let htimedelta = CommonCSR::new(htimedelta_share.clone());
+        let hcounteren_share = Rc::new(Cell::new(0_u64));
+        let hcounteren = CommonCSR::new(hcounteren_share.clone());
+
+        // performance counters, Sscofpmf adds the overflow interrupt and the mode filters
+        let hpm_share = Rc::new(Hpm::new(
+            sscofpmf,
+            config.h_mode(),
+            privilege_share.clone(),
+            virt_share.clone(),
+            xip_share.clone(),
+        ));
+        let mcountinhibit = Mcountinhibit::new(hpm_share.clone());
+        let scountovf = Scountovf::new(
+            hpm_share.clone(),
+            mcounteren_share.clone(),
+            hcounteren_share.clone(),
+            virt_share.clone(),
+        );
+        // AIA, miselect/mireg reach the M-level interrupt file and siselect/sireg the S-level one
+        let smaia = config.is_enable_ext("smaia");
+        let ssaia = config.s_mode() && (smaia || config.is_enable_ext("ssaia"));
+        let imsic_m = smaia.then(|| Rc::new(ImsicFile::new(xip_share.clone(), true)));
+        let imsic_s = ssaia.then(|| Rc::new(ImsicFile::new(xip_share.clone(), false)));
+        let miselect_share = Rc::new(Cell::new(0_u64));
+        let siselect_share = Rc::new(Cell::new(0_u64));
+        let henvcfg_share = Rc::new(Cell::new(MenvcfgIn::new()));
+        let henvcfg_mask = envcfg_cbo_mask.with_pbmte(svpbmt).with_adue(svadu);
+        let henvcfg = Menvcfg::new(henvcfg_share.clone(), henvcfg_mask.into());
+        let mtval2_share = Rc::new(Cell::new(0_u64));
+        let mtval2 = CommonCSR::new(mtval2_share.clone());
+        let mtinst_share = Rc::new(Cell::new(0_u64));
+        let mtinst = CommonCSR::new(mtinst_share.clone());
+
+        let vsstatus_share = Rc::new(Cell::new(XstatusIn::new().with_uxl(2)));
+        let vsstatus_rmask = sstatus_wmask | u64::from(XstatusIn::new().with_uxl(0b11));
+        let vsstatus = Xstatus::new(vsstatus_share.clone(), vsstatus_rmask, sstatus_wmask);
+        let vsip = Vsip::new(xip_share.clone(), hideleg_share.clone());
+        let vsie = Vsie::new(xie_share.clone(), hideleg_share.clone());
+        let vstvec_share = Rc::new(Cell::new(XtvecIn::new()));
+        let vstvec = Xtvec::new(vstvec_share.clone());
+        let vsscratch = CommonCSR::new_noshare(0);
+        let vsepc_share = Rc::new(Cell::new(0_u64));
+        let vsepc = CommonCSR::new(vsepc_share.clone());
+        let vscause_share = Rc::new(Cell::new(XcauseIn::new()));
+        let vscause = Xcause::new(vscause_share.clone());
+        let vstval_share = Rc::new(Cell::new(0_u64));
+        let vstval = CommonCSR::new(vstval_share.clone());
+        let vsatp_share = Rc::new(Cell::new(SatpIn::new()));
+        let vsatp = Satp::new_vsatp(vsatp_share.clone(), config.get_mmu_type());
+
+        // physical memory protection
+        let pmp_share = Rc::new(Pmp::new(config.pmp_num()));
 
         // debug mode
         let dcsr_share = Rc::new(Cell::new(DcsrIn::new().with_debugver(4).with_mprven(true)));
         let dpc_share = Rc::new(Cell::new(0));
         let dpc = CommonCSR::new(dpc_share.clone());
-        let dcsr = Dcsr::new(dcsr_share.clone());
+        let dcsr = Dcsr::new(dcsr_share.clone(), config.h_mode());
         let dscratch0 = CommonCSR::new_noshare(0);
         let dscratch1 = CommonCSR::new_noshare(0);
 
-        // not support hardware trigger now
-        let tselect = ReadOnlyCSR(0);
-        let tdata1 = ReadOnlyCSR(0);
-        let tdata2 = ReadOnlyCSR(0);
-        let tdata3 = ReadOnlyCSR(0);
-        let tinfo = ReadOnlyCSR(0);
+        let triggers_share = Rc::new(Triggers::new(TRIGGER_NUM));
+        let tselect = Tselect::new(triggers_share.clone());
+        let tdata1 = Tdata::new(triggers_share.clone(), 1);
+        let tdata2 = Tdata::new(triggers_share.clone(), 2);
+        let tdata3 = Tdata::new(triggers_share.clone(), 3);
+        let tinfo = Tinfo::new(triggers_share.clone());
+        let tcontrol = Tcontrol::new(triggers_share.clone());
 
 
         let mut csr_map: HashMap<u64, CsrEnum> = HashMap::new();
@@ -283,7 +560,132 @@ impl CsrRegs {
         csr_map.insert(CSR_INSTRET.into(), instret.into());
         csr_map.insert(CSR_MCOUNTEREN.into(), mcounteren.into());
         csr_map.insert(CSR_SCOUNTEREN.into(), scounteren.into());
+        csr_map.insert(CSR_MCOUNTINHIBIT.into(), mcountinhibit.into());
+        for idx in HPM_FIRST..HPM_FIRST + HPM_NUM {
+            let offset = idx as u64 - HPM_FIRST as u64;
+            let mhpmcounter = Hpmcounter::new(hpm_share.clone(), idx);
+            let hpmcounter = Hpmcounter::new(hpm_share.clone(), idx);
+            let mhpmevent = Mhpmevent::new(hpm_share.clone(), idx);
+            csr_map.insert(CSR_MHPMCOUNTER3 as u64 + offset, mhpmcounter.into());
+            csr_map.insert(CSR_HPMCOUNTER3 as u64 + offset, hpmcounter.into());
+            csr_map.insert(CSR_MHPMEVENT3 as u64 + offset, mhpmevent.into());
+        }
+        if sscofpmf {
+            csr_map.insert(CSR_SCOUNTOVF.into(), scountovf.into());
+        }
+        if let Some(file) = &imsic_m {
+            let miselect = CommonCSR::new(miselect_share.clone());
+            let mireg = Ireg::new(miselect_share.clone(), file.clone());
+            let mtopi = Topi::new(
+                xip_share.clone(),
+                xie_share.clone(),
+                mideleg_share.clone(),
+                hideleg_share.clone(),
+                true,
+            );
+            csr_map.insert(CSR_MISELECT.into(), miselect.into());
+            csr_map.insert(CSR_MIREG.into(), mireg.into());
+            csr_map.insert(CSR_MTOPEI.into(), Topei::new(file.clone()).into());
+            csr_map.insert(CSR_MTOPI.into(), mtopi.into());
+        }
+        if let Some(file) = &imsic_s {
+            let siselect = CommonCSR::new(siselect_share.clone());
+            let sireg = Ireg::new(siselect_share.clone(), file.clone());
+            let stopi = Topi::new(
+                xip_share.clone(),
+                xie_share.clone(),
+                mideleg_share.clone(),
+                hideleg_share.clone(),
+                false,
+            );
+            csr_map.insert(CSR_SISELECT.into(), siselect.into());
+            csr_map.insert(CSR_SIREG.into(), sireg.into());
+            csr_map.insert(CSR_STOPEI.into(), Topei::new(file.clone()).into());
+            csr_map.insert(CSR_STOPI.into(), stopi.into());
+        }
+        if config.u_mode() {
+            csr_map.insert(CSR_MENVCFG.into(), menvcfg.into());
+        }
+        if config.s_mode() {
+            csr_map.insert(CSR_SENVCFG.into(), senvcfg.into());
+        }
+        if zkr {
+            csr_map.insert(CSR_MSECCFG.into(), mseccfg.into());
+            csr_map.insert(CSR_SEED.into(), seed.into());
+        }
+        if config.is_enable_ext("zcmt") {
+            csr_map.insert(CSR_JVT.into(), jvt.into());
+        }
         csr_map.insert(CSR_TSELECT.into(), tselect.into());
+        csr_map.insert(CSR_TDATA1.into(), tdata1.into());
+        csr_map.insert(CSR_TDATA2.into(), tdata2.into());
+        csr_map.insert(CSR_TDATA3.into(), tdata3.into());
+        csr_map.insert(CSR_TINFO.into(), tinfo.into());
+        csr_map.insert(CSR_TCONTROL.into(), tcontrol.into());
+
+        if config.is_enable_isa(b'f') {
+            csr_map.insert(CSR_FFLAGS.into(), fflags.into());
+            csr_map.insert(CSR_FRM.into(), frm.into());
+            csr_map.insert(CSR_FCSR.into(), fcsr.into());
+        }
+
+        if config.is_enable_isa(b'v') {
+            csr_map.insert(CSR_VSTART.into(), vstart.into());
+            csr_map.insert(CSR_VXSAT.into(), vxsat.into());
+            csr_map.insert(CSR_VXRM.into(), vxrm.into());
+            csr_map.insert(CSR_VCSR.into(), vcsr.into());
+            csr_map.insert(CSR_VL.into(), vl.into());
+            csr_map.insert(CSR_VTYPE.into(), vtype.into());
+            csr_map.insert(CSR_VLENB.into(), vlenb.into());
+        }
+
+        if config.h_mode() {
+            csr_map.insert(CSR_HSTATUS.into(), hstatus.into());
+            csr_map.insert(CSR_HEDELEG.into(), hedeleg.into());
+            csr_map.insert(CSR_HIDELEG.into(), hideleg.into());
+            csr_map.insert(CSR_HIE.into(), hie.into());
+            csr_map.insert(CSR_HIP.into(), hip.into());
+            csr_map.insert(CSR_HVIP.into(), hvip.into());
+            csr_map.insert(CSR_HGATP.into(), hgatp.into());
+            csr_map.insert(CSR_HTVAL.into(), htval.into());
+            csr_map.insert(CSR_HTINST.into(), htinst.into());
+            csr_map.insert(CSR_HTIMEDELTA.into(), htimedelta.into());
+            csr_map.insert(CSR_HCOUNTEREN.into(), hcounteren.into());
+            csr_map.insert(CSR_HENVCFG.into(), henvcfg.into());
+            // no guest external interrupts (GEILEN=0)
+            csr_map.insert(CSR_HGEIE.into(), ReadOnlyCSR(0).into());
+            csr_map.insert(CSR_HGEIP.into(), ReadOnlyCSR(0).into());
+            csr_map.insert(CSR_MTVAL2.into(), mtval2.into());
+            csr_map.insert(CSR_MTINST.into(), mtinst.into());
+
+            csr_map.insert(CSR_VSSTATUS.into(), vsstatus.into());
+            csr_map.insert(CSR_VSIE.into(), vsie.into());
+            csr_map.insert(CSR_VSIP.into(), vsip.into());
+            csr_map.insert(CSR_VSTVEC.into(), vstvec.into());
+            csr_map.insert(CSR_VSSCRATCH.into(), vsscratch.into());
+            csr_map.insert(CSR_VSEPC.into(), vsepc.into());
+            csr_map.insert(CSR_VSCAUSE.into(), vscause.into());
+            csr_map.insert(CSR_VSTVAL.into(), vstval.into());
+            csr_map.insert(CSR_VSATP.into(), vsatp.into());
+        }
+
+        // RV64 only has the even numbered pmpcfg csrs, each one covers eight entries.
+        // The csrs of unimplemented entries are read-only zero.
+        for i in (0..16).step_by(2) {
+            let base = i * 4;
+            let pmpcfg: CsrEnum = match base < pmp_share.num() {
+                true => PMPcfg::new(pmp_share.clone(), base).into(),
+                false => ReadOnlyCSR(0).into(),
+            };
+            csr_map.insert(CSR_PMPCFG0 as u64 + i as u64, pmpcfg);
+        }
+        for i in 0..64 {
+            let pmpaddr: CsrEnum = match i < pmp_share.num() {
+                true => PMPaddr::new(pmp_share.clone(), i).into(),
+                false => ReadOnlyCSR(0).into(),
+            };
+            csr_map.insert(CSR_PMPADDR0 as u64 + i as u64, pmpaddr);
+        }
 
         // debug mode
         csr_map.insert(CSR_DCSR.into(), dcsr.into());
@@ -308,6 +710,45 @@ impl CsrRegs {
             satp: satp_share,
             cycle: cycle_share,
             instret: instret_share,
+            mcounteren: mcounteren_share,
+            scounteren: scounteren_share,
+            hcounteren: hcounteren_share,
+            hpm: hpm_share,
+            imsic_m,
+            imsic_s,
+            miselect: miselect_share,
+            siselect: siselect_share,
+            menvcfg: menvcfg_share,
+            senvcfg: senvcfg_share,
+            henvcfg: henvcfg_share,
+            mseccfg: mseccfg_share,
+            jvt: jvt_share,
+            stimecmp: Rc::new(Cell::new(u64::MAX)),
+            mtime: None,
+            fcsr: fcsr_share,
+            vstart: vstart_share,
+            vl: vl_share,
+            vtype: vtype_share,
+            vcsr: vcsr_share,
+            virt: virt_share,
+            privilege: privilege_share,
+            hstatus: hstatus_share,
+            hedeleg: hedeleg_share,
+            hideleg: hideleg_share,
+            hgatp: hgatp_share,
+            htval: htval_share,
+            htinst: htinst_share,
+            htimedelta: htimedelta_share,
+            mtval2: mtval2_share,
+            mtinst: mtinst_share,
+            vsstatus: vsstatus_share,
+            vstvec: vstvec_share,
+            vsepc: vsepc_share,
+            vscause: vscause_share,
+            vstval: vstval_share,
+            vsatp: vsatp_share,
+            pmp: pmp_share,
+            triggers: triggers_share,
             cur_priv: PrivilegeLevels::Machine,
             mtvec: mtvec_share,
             stvec: stvec_share,
@@ -317,13 +758,163 @@ impl CsrRegs {
     }
 
     pub fn add_mtime(&mut self, mtime: RcCell<u64>) {
-        let time = Counter::new(mtime);
+        let time = Counter::new(mtime.clone());
         self.csr_map.insert(CSR_TIME.into(), time.into());
+
+        if self.config.s_mode() && self.config.is_enable_ext("sstc") {
+            let stimecmp = Stimecmp::new(
+                self.stimecmp.clone(),
+                mtime.clone(),
+                self.xip.clone(),
+                self.menvcfg.clone(),
+                self.mcounteren.clone(),
+            );
+            self.csr_map.insert(CSR_STIMECMP.into(), stimecmp.into());
+        }
+        self.mtime = Some(mtime);
+    }
+
+    // None if the Sstc timer is disabled or already pending
+    pub fn stimecmp_delta(&self) -> Option<u64> {
+        let mtime = self.mtime.as_ref()?.get();
+        let stimecmp = self.stimecmp.get();
+        (self.menvcfg.get().stce() && stimecmp > mtime).then(|| stimecmp - mtime)
+    }
+
+    // With menvcfg.STCE set, STIP follows the comparison of time and stimecmp
+    #[inline]
+    pub fn update_stip(&mut self) {
+        if !self.menvcfg.get().stce() {
+            return;
+        }
+        if let Some(mtime) = &self.mtime {
+            let mut xip = self.xip.get();
+            xip.set_stip(mtime.get() >= self.stimecmp.get());
+            self.xip.set(xip);
+        }
+    }
+
+    // When V=1, accesses to the S csrs are redirected to the VS csrs, and the
+    // hypervisor csrs, or the S csrs from VU-mode, raise a virtual instruction exception.
+    fn virt_csr_addr(&self, addr: u64, privi: PrivilegeLevels) -> Result<u64, TrapType> {
+        if !self.virt.get() || !self.csr_map.contains_key(&addr) {
+            return Ok(addr);
+        }
+        let addr = match CsrAddr::from(addr as u16).privilege() {
+            0b10 => return Err(TrapType::VirtualInstruction(0)),
+            0b01 if privi == PrivilegeLevels::User => {
+                return Err(TrapType::VirtualInstruction(0))
+            }
+            0b01 => match addr as u16 {
+                CSR_SATP if self.hstatus.get().vtvm() => {
+                    return Err(TrapType::VirtualInstruction(0))
+                }
+                // there are no guest interrupt files behind the VS csrs
+                CSR_SISELECT | CSR_SIREG | CSR_STOPEI | CSR_STOPI => {
+                    return Err(TrapType::VirtualInstruction(0))
+                }
+                CSR_SSTATUS | CSR_SIE | CSR_STVEC | CSR_SSCRATCH | CSR_SEPC | CSR_SCAUSE
+                | CSR_STVAL | CSR_SIP | CSR_SATP => addr + 0x100,
+                _ => addr,
+            },
+            _ => addr,
+        };
+        // the floating point and vector state is also gated by vsstatus
+        let vsstatus = self.vsstatus.get();
+        match addr as u16 {
+            CSR_FFLAGS | CSR_FRM | CSR_FCSR if vsstatus.fs_off() => {
+                Err(TrapType::IllegalInstruction(0))
+            }
+            CSR_VSTART | CSR_VXSAT | CSR_VXRM | CSR_VCSR | CSR_VL | CSR_VTYPE | CSR_VLENB
+                if vsstatus.vs_off() =>
+            {
+                Err(TrapType::IllegalInstruction(0))
+            }
+            _ => Ok(addr),
+        }
+    }
+
+    // Below M-mode seed needs mseccfg.SSEED or USEED, a guest always traps: with
+    // SSEED=1 as a virtual instruction, otherwise as an illegal instruction.
+    fn check_seed(&self, addr: u64, privi: PrivilegeLevels) -> Result<(), TrapType> {
+        if addr != CSR_SEED as u64 || privi == PrivilegeLevels::Machine {
+            return Ok(());
+        }
+        let mseccfg = self.mseccfg.get();
+        let allowed = match privi {
+            PrivilegeLevels::Supervisor => mseccfg.sseed(),
+            _ => mseccfg.useed(),
+        };
+        match (self.virt.get(), allowed) {
+            (true, _) if mseccfg.sseed() => Err(TrapType::VirtualInstruction(0)),
+            (false, true) => Ok(()),
+            _ => Err(TrapType::IllegalInstruction(0)),
+        }
+    }
+
+    // Below M-mode the counters are gated by mcounteren, then by hcounteren for a guest
+    // and by scounteren for U-mode.
+    fn check_counteren(&self, addr: u64, privi: PrivilegeLevels) -> Result<(), TrapType> {
+        let counters = CSR_CYCLE as u64..=CSR_HPMCOUNTER31 as u64;
+        if privi == PrivilegeLevels::Machine || !counters.contains(&addr) {
+            return Ok(());
+        }
+        let bit = 1_u64 << (addr - CSR_CYCLE as u64);
+        let virt = self.virt.get();
+        if self.mcounteren.get() & bit == 0 {
+            return Err(TrapType::IllegalInstruction(0));
+        }
+        if virt && self.hcounteren.get() & bit == 0 {
+            return Err(TrapType::VirtualInstruction(0));
+        }
+        if privi == PrivilegeLevels::User && self.config.s_mode() && self.scounteren.get() & bit == 0
+        {
+            return Err(match virt {
+                true => TrapType::VirtualInstruction(0),
+                false => TrapType::IllegalInstruction(0),
+            });
+        }
+        Ok(())
+    }
+
+    // xireg raises an illegal instruction exception if xiselect selects no register
+    fn check_iselect(&self, addr: u64) -> Result<(), TrapType> {
+        let iselect = match addr as u16 {
+            CSR_MIREG => &self.miselect,
+            CSR_SIREG => &self.siselect,
+            _ => return Ok(()),
+        };
+        match Ireg::is_valid(iselect.get()) {
+            true => Ok(()),
+            false => Err(TrapType::IllegalInstruction(0)),
+        }
+    }
+
+    // M-mode runs with MXLEN, S-mode and U-mode with mstatus.SXL and UXL. A guest is always 64-bit.
+    pub fn is_rv32(&self, privi: PrivilegeLevels, virt: bool) -> bool {
+        let xl = match privi {
+            PrivilegeLevels::Machine => return self.config.xlen() == 32,
+            _ if virt => return false,
+            PrivilegeLevels::Supervisor => self.xstatus.get().sxl(),
+            PrivilegeLevels::User => self.xstatus.get().uxl(),
+        };
+        xl == 1
     }
 
     pub fn read(&mut self, addr: u64, privi: PrivilegeLevels) -> Result<u64, TrapType> {
         assert!(addr < 4096); // The size of a CSR is 4KB
+        if self.is_rv32(privi, self.virt.get()) {
+            return match rv32_high_half(addr) {
+                Some(base) => Ok(rv32_view(base, self.read_csr(base, privi)? >> 32, true)),
+                None => Ok(rv32_view(addr, self.read_csr(addr, privi)?, false)),
+            };
+        }
+        self.read_csr(addr, privi)
+    }
+
+    fn read_csr(&mut self, addr: u64, privi: PrivilegeLevels) -> Result<u64, TrapType> {
         self.cur_priv = privi; // Update the current privilege level
+        let addr = self.virt_csr_addr(addr, privi)?;
 
         // Get the CSR with address addr from the CSR map. If it does not exist, return an illegal instruction trap.
         let csr = match self.csr_map.get(&addr) {
@@ -338,6 +929,14 @@ impl CsrRegs {
         {
             return Err(TrapType::IllegalInstruction(0));
         }
+        self.check_counteren(addr, privi)?;
+        self.check_iselect(addr)?;
+        self.check_seed(addr, privi)?;
+
+        // the guest sees time + htimedelta
+        if self.virt.get() && addr == CSR_TIME.into() {
+            return Ok(csr.read().wrapping_add(self.htimedelta.get()));
+        }
 
         // Return the value of the CSR.
         Ok(csr.read())
@@ -346,6 +945,13 @@ impl CsrRegs {
     pub fn write(&mut self, addr: u64, data: u64, privi: PrivilegeLevels) -> Result<(), TrapType> {
         assert!(addr < 4096); // The size of a CSR is 4KB
         self.cur_priv = privi; // Update the current privilege level
+        let (addr, data) = match self.is_rv32(privi, self.virt.get()) {
+            true => self.rv32_write_data(addr, data),
+            false => (addr, data),
+        };
+        let addr = self.virt_csr_addr(addr, privi)?;
+        self.check_iselect(addr)?;
+        self.check_seed(addr, privi)?;
 
         // Get the CSR with address addr from the CSR map. If it does not exist, return an illegal instruction trap.
         let csr = match self.csr_map.get_mut(&addr) {
@@ -363,9 +969,36 @@ impl CsrRegs {
 
         // Return the value of the CSR.
         csr.write(data);
+        // fp and vector state written by the guest also dirties vsstatus
+        if self.virt.get() {
+            let mut vsstatus = self.vsstatus.get();
+            match addr as u16 {
+                CSR_FFLAGS | CSR_FRM | CSR_FCSR => vsstatus.set_fs_dirty(),
+                CSR_VSTART | CSR_VXSAT | CSR_VXRM | CSR_VCSR => vsstatus.set_vs_dirty(),
+                _ => {}
+            }
+            self.vsstatus.set(vsstatus);
+        }
         Ok(())
     }
 
+    // An rv32 write only replaces one half of a 64-bit csr, the upper half is
+    // written through the base csr of cycleh, mstatush and the like.
+    fn rv32_write_data(&mut self, addr: u64, data: u64) -> (u64, u64) {
+        let data = data & 0xffff_ffff;
+        if let Some(base) = rv32_high_half(addr) {
+            return (base, self.read_raw(base) & 0xffff_ffff | data << 32);
+        }
+        let upper = self.read_raw(addr) & !0xffff_ffff;
+        let data = match addr as u16 {
+            CSR_MCAUSE | CSR_SCAUSE => data & 0x7fff_ffff | (data >> 31) << 63,
+            // SD is read-only
+            CSR_MSTATUS | CSR_SSTATUS => upper | data & 0x7fff_ffff,
+            _ => upper | data,
+        };
+        (addr, data)
+    }
+
     pub fn write_raw(&mut self, addr: u64, data: u64) {
         assert!(addr < 4096); // The size of a CSR is 4KB
 
@@ -389,3 +1022,59 @@ impl CsrRegs {
         csr.read()
     }
 }
+
+// misa.MXL and the reset value of mstatus.SXL and UXL, 1 is 32-bit and 2 is 64-bit
+fn mxl(config: &Config) -> u8 {
+    match config.xlen() {
+        32 => 1,
+        _ => 2,
+    }
+}
+
+// The rv32 csrs holding the upper half of a 64-bit csr, and the odd pmpcfg csrs
+// holding the upper four entries of the even one.
+fn rv32_high_half(addr: u64) -> Option<u64> {
+    match addr as u16 {
+        CSR_CYCLEH..=CSR_HPMCOUNTER31H | CSR_MCYCLEH..=CSR_MHPMCOUNTER31H => Some(addr - 0x80),
+        CSR_MSTATUSH | CSR_MENVCFGH => Some(addr - 0x10),
+        CSR_PMPCFG0..=CSR_PMPCFG15 if addr & 1 == 1 => Some(addr - 1),
+        _ => None,
+    }
+}
+
+// the rv32 layout of a csr value, sign-extended like any other 32-bit result
+fn rv32_view(addr: u64, data: u64, high: bool) -> u64 {
+    let data = match addr as u16 {
+        // SXL and UXL are not visible in mstatush
+        CSR_MSTATUS if high => data & !0xf,
+        // SD and the interrupt bit are the top bit of the csr, MXL the top two
+        CSR_MSTATUS | CSR_SSTATUS | CSR_MCAUSE | CSR_SCAUSE => {
+            data & 0x7fff_ffff | (data >> 63) << 31
+        }
+        CSR_MISA => data & 0x3ff_ffff | (data >> 62) << 30,
+        _ => data,
+    };
+    data as i32 as u64
+}
+
+// with the hypervisor extension the VS-level interrupts and SGEI are read-only one in mideleg
+fn mideleg_reset_val(config: &Config) -> MidelegIn {
+    match config.h_mode() {
+        true => MidelegIn::from(VS_INTERRUPT_MASK).with_sgeip(true),
+        false => MidelegIn::new(),
+    }
+}
+
+// a different entropy seed on every run, without std there is no host source
+#[cfg(feature = "std")]
+fn host_entropy() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
+
+#[cfg(not(feature = "std"))]
+fn host_entropy() -> u64 {
+    0x9e37_79b9_7f4a_7c15
+}
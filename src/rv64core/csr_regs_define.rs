@@ -1,4 +1,6 @@
-use alloc::boxed::Box;
+use core::cell::Cell;
+
+use alloc::rc::Rc;
 use bitfield_struct::bitfield;
 use enum_dispatch::enum_dispatch;
 
@@ -8,7 +10,11 @@ use crate::{
     tools::RcCell,
 };
 
-use super::inst::inst_base::RVerr;
+use super::{hpm::Hpm, inst::inst_base::RVerr, mmu::pmp::Pmp, trigger::Triggers};
+use crate::device::device_imsic::{
+    ImsicFile, ISELECT_EIDELIVERY, ISELECT_EIE63, ISELECT_EIP0, ISELECT_EITHRESHOLD,
+    ISELECT_IPRIO0, ISELECT_IPRIO15,
+};
 
 #[enum_dispatch]
 pub enum CsrEnum {
@@ -25,11 +31,33 @@ pub enum CsrEnum {
     Mcounteren,
     Menvcfg,
     Mseccfg,
+    Seed,
+    Jvt,
     PMPcfg,
     PMPaddr,
     Satp,
     Counter,
     Dcsr,
+    Fcsr,
+    Stimecmp,
+    Vtype,
+    VectorCsr,
+    Vcsr,
+    Hstatus,
+    Hgatp,
+    Vsip,
+    Vsie,
+    Tselect,
+    Tdata,
+    Tinfo,
+    Tcontrol,
+    Hpmcounter,
+    Mhpmevent,
+    Mcountinhibit,
+    Scountovf,
+    Ireg,
+    Topei,
+    Topi,
 }
 
 #[enum_dispatch(CsrEnum)]
@@ -94,7 +122,7 @@ pub struct CsrAddr {
     #[bits(8)]
     addr: u8,
     #[bits(2)]
-    privilege: u8,
+    pub privilege: u8,
     #[bits(2)]
     read_write: u8,
     #[bits(4)]
@@ -112,7 +140,12 @@ pub struct CsrAddr {
 // writes to the read-only bits are ignored.
 impl CsrAddr {
     pub fn check_privilege(&self, privi: PrivilegeLevels, access_type: AccessType) -> bool {
-        let has_privilege = (privi as u8) >= self.privilege();
+        // the hypervisor and VS csrs (privilege 0b10) are accessed from HS-mode
+        let require_priv = match self.privilege() {
+            0b10 => PrivilegeLevels::Supervisor as u8,
+            privilege => privilege,
+        };
+        let has_privilege = (privi as u8) >= require_priv;
         // warn!("privi:{:?},{}", privi, has_privilege);
         match access_type {
             AccessType::Store(_) => self.not_read_only() && has_privilege,
@@ -199,7 +232,9 @@ pub struct XstatusIn {
     pub sxl: u8,
     pub sbe: bool,
     pub mbe: bool,
-    #[bits(25)]
+    pub gva: bool,
+    pub mpv: bool,
+    #[bits(23)]
     _wpri4: u32,
     pub sd: bool,
 }
@@ -227,12 +262,30 @@ impl XstatusIn {
         let sd_val = self.fs() == 0b11 || self.xs() == 0b11 || self.vs() == 0b11;
         self.set_sd(sd_val);
     }
+    // FS: 0 Off, 1 Initial, 2 Clean, 3 Dirty
+    pub fn fs_off(&self) -> bool {
+        self.fs() == 0b00
+    }
+    pub fn set_fs_dirty(&mut self) {
+        self.set_fs(0b11);
+        self.set_sd(true);
+    }
+    // VS uses the same encoding as FS
+    pub fn vs_off(&self) -> bool {
+        self.vs() == 0b00
+    }
+    pub fn set_vs_dirty(&mut self) {
+        self.set_vs(0b11);
+        self.set_sd(true);
+    }
 }
 
 pub struct Xstatus {
     inner: RcCell<XstatusIn>,
     rmask: u64,
     wmask: u64,
+    // mstatus.SXL and UXL are WARL, only 1 (32-bit) and 2 (64-bit) are taken
+    xl_writable: bool,
 }
 
 impl Xstatus {
@@ -241,14 +294,29 @@ impl Xstatus {
             inner: share,
             rmask,
             wmask,
+            xl_writable: false,
         }
     }
+    pub fn with_xl_writable(mut self, writable: bool) -> Self {
+        self.xl_writable = writable;
+        self
+    }
 }
 
 impl Csr for Xstatus {
     fn write(&mut self, data: u64) {
         let new_data = write_with_mask(self.inner.get().into(), data, self.wmask);
         let mut status = XstatusIn::from(new_data);
+        // a mode that is not implemented keeps SXL or UXL at 0
+        if self.xl_writable {
+            let data = XstatusIn::from(data);
+            if matches!(data.sxl(), 1 | 2) && status.sxl() != 0 {
+                status.set_sxl(data.sxl());
+            }
+            if matches!(data.uxl(), 1 | 2) && status.uxl() != 0 {
+                status.set_uxl(data.uxl());
+            }
+        }
         status.update_sd();
         self.inner.set(status);
     }
@@ -334,17 +402,19 @@ impl Csr for Xtvec {
 pub struct XieIn {
     _pad0: bool,
     pub ssie: bool,
-    _pad1: bool,
+    pub vssie: bool,
     pub msie: bool,
     _pad2: bool,
     pub stie: bool,
-    _pad3: bool,
+    pub vstie: bool,
     pub mtie: bool,
     _pad4: bool,
     pub seie: bool,
-    _pad5: bool,
+    pub vseie: bool,
     pub meie: bool,
-    #[bits(52)]
+    pub sgeie: bool,
+    pub lcofie: bool,
+    #[bits(50)]
     _pad6: u64,
 }
 
@@ -376,20 +446,22 @@ impl Csr for Xie {
 pub struct XipIn {
     _pad0: bool,
     pub ssip: bool,
-    _pad1: bool,
+    pub vssip: bool,
     pub msip: bool,
     _pad2: bool,
     pub stip: bool,
-    _pad3: bool,
+    pub vstip: bool,
     pub mtip: bool,
     _pad4: bool,
     pub seip: bool,
-    _pad5: bool,
+    pub vseip: bool,
     pub meip: bool,
-    #[bits(52)]
+    pub sgeip: bool,
+    pub lcofip: bool,
+    #[bits(50)]
     _pad6: u64,
 }
-// standard interrupt priority is MEI, MSI, MTI, SEI, SSI, STI
+// standard interrupt priority is MEI, MSI, MTI, SEI, SSI, STI, SGEI, VSEI, VSSI, VSTI, LCOFI
 impl XipIn {
     pub fn get_priority_interupt(&self) -> TrapType {
         if self.meip() {
@@ -404,16 +476,29 @@ impl XipIn {
             return TrapType::SupervisorSoftwareInterrupt;
         } else if self.stip() {
             return TrapType::SupervisorTimerInterrupt;
+        } else if self.sgeip() {
+            return TrapType::SupervisorGuestExternalInterrupt;
+        } else if self.vseip() {
+            return TrapType::VirtualSupervisorExternalInterrupt;
+        } else if self.vssip() {
+            return TrapType::VirtualSupervisorSoftwareInterrupt;
+        } else if self.vstip() {
+            return TrapType::VirtualSupervisorTimerInterrupt;
+        } else if self.lcofip() {
+            return TrapType::LocalCounterOverflowInterrupt;
         }
         panic!("no interupt:{self:?}");
     }
     pub fn set_irq(&mut self, irq_num: usize) {
         match irq_num {
             1 => self.set_ssip(true),
+            2 => self.set_vssip(true),
             3 => self.set_msip(true),
             5 => self.set_stip(true),
+            6 => self.set_vstip(true),
             7 => self.set_mtip(true),
             9 => self.set_seip(true),
+            10 => self.set_vseip(true),
             11 => self.set_meip(true),
             _ => panic!("invalid irq num:{}", irq_num),
         }
@@ -422,12 +507,29 @@ impl XipIn {
 
 pub struct Xip {
     inner: RcCell<XipIn>,
+    menvcfg: Option<RcCell<MenvcfgIn>>,
     mask: u64,
+    wmask: u64,
 }
 
 impl Xip {
     pub fn new(share: RcCell<XipIn>, mask: u64) -> Self {
-        Self { inner: share, mask }
+        Self {
+            inner: share,
+            menvcfg: None,
+            mask,
+            wmask: mask,
+        }
+    }
+    // some visible bits are read-only, such as hip.VSTIP and the VS bits of mideleg
+    pub fn with_wmask(mut self, wmask: u64) -> Self {
+        self.wmask = wmask & self.mask;
+        self
+    }
+    // STIP becomes read-only once menvcfg.STCE is set
+    pub fn with_menvcfg(mut self, menvcfg_share: RcCell<MenvcfgIn>) -> Self {
+        self.menvcfg = Some(menvcfg_share);
+        self
     }
 }
 impl Csr for Xip {
@@ -435,7 +537,10 @@ impl Csr for Xip {
         self.inner.get().0 & self.mask
     }
     fn write(&mut self, data: u64) {
-        let mask = self.mask;
+        let mut mask = self.wmask;
+        if self.menvcfg.as_ref().is_some_and(|x| x.get().stce()) {
+            mask &= !u64::from(XipIn::new().with_stip(true));
+        }
         let mut inner = self.inner.get();
         inner.0 = write_with_mask(inner.0, data, mask);
         self.inner.set(inner);
@@ -493,19 +598,21 @@ pub struct MedelegIn {
     pub _reserved2: u64,
 }
 
+// medeleg and hedeleg
 pub struct Medeleg {
     inner: RcCell<MedelegIn>,
+    mask: u64,
 }
 
 impl Medeleg {
-    pub fn new(share: RcCell<MedelegIn>) -> Self {
-        Self { inner: share }
+    pub fn new(share: RcCell<MedelegIn>, mask: u64) -> Self {
+        Self { inner: share, mask }
     }
 }
 
 impl Csr for Medeleg {
     fn write(&mut self, data: u64) {
-        self.inner.set(MedelegIn::from(data));
+        self.inner.set(MedelegIn::from(data & self.mask));
     }
     fn read_raw(&self) -> u64 {
         self.inner.get().0
@@ -545,7 +652,7 @@ impl Mcounteren {
 }
 
 #[bitfield(u64)]
-pub struct Menvcfg {
+pub struct MenvcfgIn {
     pub fiom: bool,
     #[bits(3)]
     _wpri0: u8,
@@ -553,20 +660,41 @@ pub struct Menvcfg {
     pub cbie: u8,
     pub cbcfe: bool,
     pub cbze: bool,
-    #[bits(54)]
+    #[bits(53)]
     _wpri1: u64,
+    pub adue: bool,
     pub pbmte: bool,
     pub stce: bool,
 }
 
+pub struct Menvcfg {
+    inner: RcCell<MenvcfgIn>,
+    mask: u64,
+}
+
+impl Menvcfg {
+    pub fn new(share: RcCell<MenvcfgIn>, mask: u64) -> Self {
+        Menvcfg { inner: share, mask }
+    }
+}
+
 impl Csr for Menvcfg {
+    fn write(&mut self, data: u64) {
+        let old = self.inner.get();
+        let mut new = MenvcfgIn::from(write_with_mask(old.into(), data, self.mask));
+        // CBIE=0b10 is reserved, keep the old value
+        if new.cbie() == 0b10 {
+            new.set_cbie(old.cbie());
+        }
+        self.inner.set(new);
+    }
     fn read_raw(&self) -> u64 {
-        self.0
+        self.inner.get().into()
     }
 }
 
 #[bitfield(u64)]
-pub struct Mseccfg {
+pub struct MseccfgIn {
     pub mml: bool,
     pub mmwp: bool,
     pub rlb: bool,
@@ -577,9 +705,112 @@ pub struct Mseccfg {
     #[bits(54)]
     _wpri0: u64,
 }
+
+// Only USEED and SSEED are writable, the Smepmp bits are read-only zero.
+pub struct Mseccfg {
+    inner: RcCell<MseccfgIn>,
+    mask: u64,
+}
+
+impl Mseccfg {
+    pub fn new(share: RcCell<MseccfgIn>, mask: u64) -> Self {
+        Mseccfg { inner: share, mask }
+    }
+}
+
 impl Csr for Mseccfg {
+    fn write(&mut self, data: u64) {
+        let old = self.inner.get().into();
+        self.inner.set(write_with_mask(old, data, self.mask).into());
+    }
     fn read_raw(&self) -> u64 {
-        self.0
+        self.inner.get().into()
+    }
+}
+
+// seed: OPST in 31:30 and the entropy in 15:0
+const SEED_OPST_ES16: u64 = 0b10;
+
+// The Zkr entropy source, an xorshift64* generator that always has 16 bits ready.
+// Every read consumes them, writes are ignored.
+pub struct Seed {
+    state: Cell<u64>,
+}
+
+impl Seed {
+    pub fn new(seed: u64) -> Self {
+        // the state of xorshift must not be zero
+        Seed {
+            state: Cell::new(seed.max(1)),
+        }
+    }
+}
+
+impl Csr for Seed {
+    fn read_raw(&self) -> u64 {
+        let mut x = self.state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state.set(x);
+        let entropy = x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 48;
+        SEED_OPST_ES16 << 30 | entropy
+    }
+}
+
+#[bitfield(u64)]
+pub struct JvtIn {
+    #[bits(6)]
+    pub mode: u8,
+    #[bits(58)]
+    pub base: u64,
+}
+
+impl JvtIn {
+    pub fn table_addr(&self) -> u64 {
+        self.base() << 6
+    }
+}
+
+// Zcmt jump vector table, only the jump table mode 0 is implemented
+pub struct Jvt {
+    inner: RcCell<JvtIn>,
+}
+
+impl Jvt {
+    pub fn new(share: RcCell<JvtIn>) -> Self {
+        Jvt { inner: share }
+    }
+}
+
+impl Csr for Jvt {
+    fn write(&mut self, data: u64) {
+        self.inner.set(JvtIn::from(data).with_mode(0));
+    }
+    fn read_raw(&self) -> u64 {
+        self.inner.get().into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PmpAddrMatch {
+    Off = 0,
+    Tor = 1,
+    Na4 = 2,
+    Napot = 3,
+}
+
+impl PmpAddrMatch {
+    const fn into_bits(self) -> u8 {
+        self as u8
+    }
+    const fn from_bits(v: u8) -> Self {
+        match v {
+            0 => PmpAddrMatch::Off,
+            1 => PmpAddrMatch::Tor,
+            2 => PmpAddrMatch::Na4,
+            _ => PmpAddrMatch::Napot,
+        }
     }
 }
 
@@ -589,78 +820,327 @@ pub struct PMPcfgIn {
     pub w: bool,
     pub x: bool,
     #[bits(2)]
-    pub a: u8,
+    pub a: PmpAddrMatch,
     #[bits(2)]
     _pad: u8,
     pub l: bool,
 }
 
-impl PMPcfgIn {
-    const fn into_bits(self) -> u64 {
-        self.0 as u64
-    }
-    const fn from_bits(v: u64) -> Self {
-        let mut new = Self::new();
-        new.0 = v as u8;
-        new
-    }
+// one pmpcfg csr packs the configuration of eight pmp entries
+pub struct PMPcfg {
+    pmp: Rc<Pmp>,
+    base: usize,
 }
 
-// impl From<u64> for PMPcfgIn {
-//     fn from(value: u64) -> Self {
-//         PMPcfgIn::from(value as u8)
-//     }
-// }
-
-// impl From<PMPcfgIn> for u64 {
-//     fn from(val: PMPcfgIn) -> Self {
-//         val.0 as u64
-//     }
-// }
-pub type PMPcfgShare = RcCell<Box<PMPcfg>>;
-#[bitfield(u64)]
-pub struct PMPcfg {
-    #[bits(8)]
-    pub pmp0cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp1cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp2cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp3cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp4cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp5cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp6cfg: PMPcfgIn,
-    #[bits(8)]
-    pub pmp7cfg: PMPcfgIn,
+impl PMPcfg {
+    pub fn new(pmp: Rc<Pmp>, base: usize) -> Self {
+        PMPcfg { pmp, base }
+    }
 }
 
 impl Csr for PMPcfg {
+    fn write(&mut self, data: u64) {
+        for i in 0..8 {
+            self.pmp.write_cfg(self.base + i, (data >> (i * 8)) as u8);
+        }
+    }
     fn read_raw(&self) -> u64 {
-        self.0
+        (0..8).fold(0, |acc, i| {
+            acc | (self.pmp.read_cfg(self.base + i) as u64) << (i * 8)
+        })
     }
 }
 
-#[bitfield(u64)]
 pub struct PMPaddr {
-    #[bits(54)]
-    pub address_55_2: u64,
-    #[bits(10)]
-    _pad: u32,
+    pmp: Rc<Pmp>,
+    idx: usize,
+}
+
+impl PMPaddr {
+    pub fn new(pmp: Rc<Pmp>, idx: usize) -> Self {
+        PMPaddr { pmp, idx }
+    }
 }
 
 impl Csr for PMPaddr {
+    fn write(&mut self, data: u64) {
+        self.pmp.write_addr(self.idx, data);
+    }
     fn read_raw(&self) -> u64 {
-        self.0
+        self.pmp.read_addr(self.idx)
+    }
+}
+
+// tselect picks the trigger accessed through tdata1~3 and tinfo
+pub struct Tselect {
+    triggers: Rc<Triggers>,
+}
+
+impl Tselect {
+    pub fn new(triggers: Rc<Triggers>) -> Self {
+        Tselect { triggers }
+    }
+}
+
+impl Csr for Tselect {
+    fn write(&mut self, data: u64) {
+        self.triggers.write_tselect(data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.triggers.read_tselect()
+    }
+}
+
+pub struct Tdata {
+    triggers: Rc<Triggers>,
+    idx: usize,
+}
+
+impl Tdata {
+    pub fn new(triggers: Rc<Triggers>, idx: usize) -> Self {
+        Tdata { triggers, idx }
+    }
+}
+
+impl Csr for Tdata {
+    fn write(&mut self, data: u64) {
+        self.triggers.write_tdata(self.idx, data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.triggers.read_tdata(self.idx)
+    }
+}
+
+pub struct Tinfo {
+    triggers: Rc<Triggers>,
+}
+
+impl Tinfo {
+    pub fn new(triggers: Rc<Triggers>) -> Self {
+        Tinfo { triggers }
+    }
+}
+
+impl Csr for Tinfo {
+    fn read_raw(&self) -> u64 {
+        self.triggers.read_tinfo()
+    }
+}
+
+pub struct Tcontrol {
+    triggers: Rc<Triggers>,
+}
+
+impl Tcontrol {
+    pub fn new(triggers: Rc<Triggers>) -> Self {
+        Tcontrol { triggers }
+    }
+}
+
+impl Csr for Tcontrol {
+    fn write(&mut self, data: u64) {
+        self.triggers.write_tcontrol(data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.triggers.read_tcontrol()
+    }
+}
+
+// mhpmcounter3~31, and their read-only hpmcounter3~31 shadows
+pub struct Hpmcounter {
+    hpm: Rc<Hpm>,
+    idx: usize,
+}
+
+impl Hpmcounter {
+    pub fn new(hpm: Rc<Hpm>, idx: usize) -> Self {
+        Hpmcounter { hpm, idx }
+    }
+}
+
+impl Csr for Hpmcounter {
+    fn write(&mut self, data: u64) {
+        self.hpm.write_counter(self.idx, data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.hpm.read_counter(self.idx)
+    }
+}
+
+pub struct Mhpmevent {
+    hpm: Rc<Hpm>,
+    idx: usize,
+}
+
+impl Mhpmevent {
+    pub fn new(hpm: Rc<Hpm>, idx: usize) -> Self {
+        Mhpmevent { hpm, idx }
+    }
+}
+
+impl Csr for Mhpmevent {
+    fn write(&mut self, data: u64) {
+        self.hpm.write_event(self.idx, data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.hpm.read_event(self.idx)
+    }
+}
+
+pub struct Mcountinhibit {
+    hpm: Rc<Hpm>,
+}
+
+impl Mcountinhibit {
+    pub fn new(hpm: Rc<Hpm>) -> Self {
+        Mcountinhibit { hpm }
+    }
+}
+
+impl Csr for Mcountinhibit {
+    fn write(&mut self, data: u64) {
+        self.hpm.write_inhibit(data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.hpm.read_inhibit()
+    }
+}
+
+// Sscofpmf scountovf, the OF bits of the counters enabled by mcounteren, and also by
+// hcounteren for a guest
+pub struct Scountovf {
+    hpm: Rc<Hpm>,
+    mcounteren: RcCell<u64>,
+    hcounteren: RcCell<u64>,
+    virt: RcCell<bool>,
+}
+
+impl Scountovf {
+    pub fn new(
+        hpm: Rc<Hpm>,
+        mcounteren: RcCell<u64>,
+        hcounteren: RcCell<u64>,
+        virt: RcCell<bool>,
+    ) -> Self {
+        Scountovf {
+            hpm,
+            mcounteren,
+            hcounteren,
+            virt,
+        }
+    }
+}
+
+impl Csr for Scountovf {
+    fn read_raw(&self) -> u64 {
+        let mut mask = self.mcounteren.get();
+        if self.virt.get() {
+            mask &= self.hcounteren.get();
+        }
+        self.hpm.read_scountovf() & mask
+    }
+}
+
+// Smaia/Ssaia xireg, the register of the interrupt file selected by xiselect.
+// The major interrupt priorities (iprio) are read-only zero.
+pub struct Ireg {
+    iselect: RcCell<u64>,
+    file: Rc<ImsicFile>,
+}
+
+impl Ireg {
+    pub fn new(iselect: RcCell<u64>, file: Rc<ImsicFile>) -> Self {
+        Ireg { iselect, file }
+    }
+    // on RV64 the odd numbered iprio, eip and eie registers do not exist
+    pub fn is_valid(iselect: u64) -> bool {
+        match iselect {
+            ISELECT_IPRIO0..=ISELECT_IPRIO15 => iselect & 1 == 0,
+            ISELECT_EIDELIVERY | ISELECT_EITHRESHOLD => true,
+            ISELECT_EIP0..=ISELECT_EIE63 => iselect & 1 == 0,
+            _ => false,
+        }
+    }
+}
+
+impl Csr for Ireg {
+    fn write(&mut self, data: u64) {
+        self.file.write_ireg(self.iselect.get(), data);
+    }
+    fn read_raw(&self) -> u64 {
+        self.file.read_ireg(self.iselect.get())
+    }
+}
+
+// xtopei, a write claims the reported interrupt
+pub struct Topei {
+    file: Rc<ImsicFile>,
+}
+
+impl Topei {
+    pub fn new(file: Rc<ImsicFile>) -> Self {
+        Topei { file }
+    }
+}
+
+impl Csr for Topei {
+    fn write(&mut self, _data: u64) {
+        self.file.claim();
+    }
+    fn read_raw(&self) -> u64 {
+        self.file.read_topei()
+    }
+}
+
+// xtopi, the highest priority major interrupt pending and enabled at the level,
+// regardless of xstatus.xIE. Its IID is in 27:16, and IPRIO is one as all the
+// priority numbers are zero.
+pub struct Topi {
+    xip: RcCell<XipIn>,
+    xie: RcCell<XieIn>,
+    mideleg: RcCell<MidelegIn>,
+    hideleg: RcCell<MidelegIn>,
+    mmode: bool,
+}
+
+impl Topi {
+    pub fn new(
+        xip: RcCell<XipIn>,
+        xie: RcCell<XieIn>,
+        mideleg: RcCell<MidelegIn>,
+        hideleg: RcCell<MidelegIn>,
+        mmode: bool,
+    ) -> Self {
+        Topi {
+            xip,
+            xie,
+            mideleg,
+            hideleg,
+            mmode,
+        }
+    }
+}
+
+impl Csr for Topi {
+    fn read_raw(&self) -> u64 {
+        let pending = u64::from(self.xip.get()) & u64::from(self.xie.get());
+        let mideleg = u64::from(self.mideleg.get());
+        let pending = match self.mmode {
+            true => pending & !mideleg,
+            false => pending & mideleg & !u64::from(self.hideleg.get()),
+        };
+        match pending {
+            0 => 0,
+            _ => XipIn::from(pending).get_priority_interupt().get_irq_num() << 16 | 1,
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StapMode {
     Bare = 0,
+    // the rv32 satp has a one bit mode, Sv32 takes a reserved rv64 encoding inside the emulator
+    Sv32 = 1,
     Sv39 = 8,
     Sv48 = 9,
     Sv57 = 10,
@@ -671,6 +1151,7 @@ impl StapMode {
     pub fn get_levels(&self) -> usize {
         match self {
             StapMode::Bare => 0,
+            StapMode::Sv32 => 2,
             StapMode::Sv39 => 3,
             StapMode::Sv48 => 4,
             StapMode::Sv57 => 5,
@@ -681,6 +1162,7 @@ impl StapMode {
     pub fn get_ptesize(&self) -> usize {
         match self {
             StapMode::Bare => 0,
+            StapMode::Sv32 => 4,
             StapMode::Sv39 => 8,
             StapMode::Sv48 => 8,
             StapMode::Sv57 => 8,
@@ -694,6 +1176,7 @@ impl StapMode {
     const fn from_bits(v: u64) -> Self {
         match v {
             0 => StapMode::Bare,
+            1 => StapMode::Sv32,
             8 => StapMode::Sv39,
             9 => StapMode::Sv48,
             10 => StapMode::Sv57,
@@ -732,9 +1215,10 @@ pub struct SatpIn {
     pub mode: StapMode,
 }
 
+// satp and vsatp, only satp is trapped by mstatus.TVM
 pub struct Satp {
     inner: RcCell<SatpIn>,
-    xstatus: RcCell<XstatusIn>,
+    xstatus: Option<RcCell<XstatusIn>>,
     max_satp_mode: StapMode,
 }
 
@@ -746,21 +1230,45 @@ impl Satp {
     ) -> Self {
         Satp {
             inner: share,
-            xstatus: xstatus_share,
+            xstatus: Some(xstatus_share),
+            max_satp_mode: max_mode,
+        }
+    }
+    pub fn new_vsatp(share: RcCell<SatpIn>, max_mode: StapMode) -> Self {
+        Satp {
+            inner: share,
+            xstatus: None,
             max_satp_mode: max_mode,
         }
     }
 }
 
 impl Satp {
+    // Sv32 is only selected by the rv32 format, any other paging mode enables it
     fn unsupport_mod(&self, new_mode: StapMode) -> bool {
-        new_mode as usize > self.max_satp_mode as usize
+        match self.rv32_format() {
+            true => new_mode == StapMode::Sv32 && self.max_satp_mode == StapMode::Bare,
+            false => new_mode == StapMode::Sv32 || new_mode as usize > self.max_satp_mode as usize,
+        }
+    }
+    // with SXLEN=32 satp has the rv32 layout: MODE[31], ASID[30:22] and PPN[21:0]
+    fn rv32_format(&self) -> bool {
+        self.xstatus.as_ref().is_some_and(|x| x.get().sxl() == 1)
     }
 }
 
 impl Csr for Satp {
     fn write(&mut self, data: u64) {
-        let new_val = SatpIn::from(data);
+        let new_val = match self.rv32_format() {
+            true => SatpIn::new()
+                .with_mode(match data >> 31 & 1 {
+                    1 => StapMode::Sv32,
+                    _ => StapMode::Bare,
+                })
+                .with_asid(data >> 22 & 0x1ff)
+                .with_ppn(data & 0x3f_ffff),
+            false => SatpIn::from(data),
+        };
 
         let mut stap = self.inner.get();
         if !self.unsupport_mod(new_val.mode()) {
@@ -772,7 +1280,15 @@ impl Csr for Satp {
         self.inner.set(stap);
     }
     fn read_raw(&self) -> u64 {
-        self.inner.get().into()
+        let satp = self.inner.get();
+        match self.rv32_format() {
+            true => {
+                ((satp.mode() == StapMode::Sv32) as u64) << 31
+                    | (satp.asid() & 0x1ff) << 22
+                    | satp.ppn() & 0x3f_ffff
+            }
+            false => satp.into(),
+        }
     }
 
     fn check_permission(
@@ -781,7 +1297,7 @@ impl Csr for Satp {
         privi: PrivilegeLevels,
         _access_type: AccessType,
     ) -> Result<(), RVerr> {
-        let tvm = self.xstatus.get().tvm();
+        let tvm = self.xstatus.as_ref().is_some_and(|x| x.get().tvm());
 
         let require_priv = if tvm {
             PrivilegeLevels::Machine
@@ -812,6 +1328,68 @@ impl Csr for Counter {
     }
 }
 
+// Sstc supervisor timer compare, STIP is pending while time >= stimecmp
+pub struct Stimecmp {
+    inner: RcCell<u64>,
+    mtime: RcCell<u64>,
+    xip: RcCell<XipIn>,
+    menvcfg: RcCell<MenvcfgIn>,
+    mcounteren: RcCell<u64>,
+}
+
+impl Stimecmp {
+    pub fn new(
+        share: RcCell<u64>,
+        mtime_share: RcCell<u64>,
+        xip_share: RcCell<XipIn>,
+        menvcfg_share: RcCell<MenvcfgIn>,
+        mcounteren_share: RcCell<u64>,
+    ) -> Self {
+        Stimecmp {
+            inner: share,
+            mtime: mtime_share,
+            xip: xip_share,
+            menvcfg: menvcfg_share,
+            mcounteren: mcounteren_share,
+        }
+    }
+}
+
+impl Csr for Stimecmp {
+    fn write(&mut self, data: u64) {
+        self.inner.set(data);
+
+        if self.menvcfg.get().stce() {
+            let mut xip = self.xip.get();
+            xip.set_stip(self.mtime.get() >= data);
+            self.xip.set(xip);
+        }
+    }
+    fn read_raw(&self) -> u64 {
+        self.inner.get()
+    }
+
+    fn check_permission(
+        &self,
+        addr: u64,
+        privi: PrivilegeLevels,
+        access_type: AccessType,
+    ) -> Result<(), RVerr> {
+        // S-mode access needs both menvcfg.STCE and mcounteren.TM
+        if privi == PrivilegeLevels::Supervisor {
+            let tm = Mcounteren::from(self.mcounteren.get()).tm();
+            if !(self.menvcfg.get().stce() && tm) {
+                return Err(RVerr::CsrNotPermit);
+            }
+        }
+        let csr_addr = CsrAddr::from(addr as u16);
+        match csr_addr.check_privilege(privi, access_type) {
+            true => Ok(()),
+            false => Err(RVerr::CsrNotPermit),
+        }
+    }
+}
+
 #[bitfield(u32)]
 pub struct DcsrIn {
     #[bits(2)]
@@ -839,11 +1417,16 @@ pub struct DcsrIn {
 
 pub struct Dcsr {
     inner: RcCell<DcsrIn>,
+    // virtualization mode is supported
+    h_mode: bool,
 }
 
 impl Dcsr {
-    pub fn new(share: RcCell<DcsrIn>) -> Self {
-        Dcsr { inner: share }
+    pub fn new(share: RcCell<DcsrIn>, h_mode: bool) -> Self {
+        Dcsr {
+            inner: share,
+            h_mode,
+        }
     }
 }
 
@@ -856,10 +1439,11 @@ impl Csr for Dcsr {
         let new_in = DcsrIn::from(_data as u32);
         let mut old_val = self.inner.get();
 
-        // This bit is hardwired to 0 if the hart does not
+        // These bits are hardwired to 0 if the hart does not
         // support virtualization mode.
-        old_val.set_ebreakvs(false);
-        old_val.set_ebreakvu(false);
+        old_val.set_ebreakvs(self.h_mode && new_in.ebreakvs());
+        old_val.set_ebreakvu(self.h_mode && new_in.ebreakvu());
+        old_val.set_v(self.h_mode && new_in.v());
 
         old_val.set_ebreakm(new_in.ebreakm());
         old_val.set_ebreaks(new_in.ebreaks());
@@ -875,9 +1459,466 @@ impl Csr for Dcsr {
         old_val.set_stepie(false); // hard code to zero
         old_val.set_stopcount(false); // hard code to zero
         old_val.set_stoptime(false); // hard code to zero
-        old_val.set_v(false); // hard code to zero
         old_val.set_mprven(true); // hard code to one (1 (enabled): MPRV in mstatus takes effect in Debug Mode.)
 
         self.inner.set(old_val);
     }
 }
+
+#[bitfield(u64)]
+pub struct FcsrIn {
+    #[bits(5)]
+    pub fflags: u8,
+    #[bits(3)]
+    pub frm: u8,
+    #[bits(56)]
+    _pad: u64,
+}
+
+// fflags, frm and fcsr are different views of the same register
+pub struct Fcsr {
+    inner: RcCell<FcsrIn>,
+    xstatus: RcCell<XstatusIn>,
+    mask: u64,
+    shift: u64,
+}
+
+impl Fcsr {
+    pub fn new_fflags(share: RcCell<FcsrIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Fcsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask: 0x1f,
+            shift: 0,
+        }
+    }
+    pub fn new_frm(share: RcCell<FcsrIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Fcsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask: 0xe0,
+            shift: 5,
+        }
+    }
+    pub fn new_fcsr(share: RcCell<FcsrIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Fcsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask: 0xff,
+            shift: 0,
+        }
+    }
+}
+
+impl Csr for Fcsr {
+    fn write(&mut self, data: u64) {
+        let old = u64::from(self.inner.get());
+        let new_val = write_with_mask(old, data << self.shift, self.mask);
+        self.inner.set(FcsrIn::from(new_val));
+
+        let mut status = self.xstatus.get();
+        status.set_fs_dirty();
+        self.xstatus.set(status);
+    }
+    fn read_raw(&self) -> u64 {
+        (u64::from(self.inner.get()) & self.mask) >> self.shift
+    }
+
+    fn check_permission(
+        &self,
+        addr: u64,
+        privi: PrivilegeLevels,
+        access_type: AccessType,
+    ) -> Result<(), RVerr> {
+        // accessing the floating-point CSRs while FS is Off raises an illegal instruction
+        if self.xstatus.get().fs_off() {
+            return Err(RVerr::CsrNotPermit);
+        }
+        let csr_addr = CsrAddr::from(addr as u16);
+        match csr_addr.check_privilege(privi, access_type) {
+            true => Ok(()),
+            false => Err(RVerr::CsrNotPermit),
+        }
+    }
+}
+
+#[bitfield(u64)]
+pub struct VtypeIn {
+    #[bits(3)]
+    pub vlmul: u8,
+    #[bits(3)]
+    pub vsew: u8,
+    pub vta: bool,
+    pub vma: bool,
+    #[bits(55)]
+    _pad: u64,
+    pub vill: bool,
+}
+
+impl VtypeIn {
+    // SEW in bits
+    pub fn sew(&self) -> usize {
+        8 << self.vsew()
+    }
+    // log2(LMUL), vlmul 0b101..0b111 are the fractional 1/8..1/2
+    pub fn lmul(&self) -> i32 {
+        ((self.vlmul() as i32) << 29) >> 29
+    }
+}
+
+// vtype is only written by vsetvl{i}
+pub struct Vtype {
+    inner: RcCell<VtypeIn>,
+    xstatus: RcCell<XstatusIn>,
+}
+
+impl Vtype {
+    pub fn new(share: RcCell<VtypeIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Vtype {
+            inner: share,
+            xstatus: xstatus_share,
+        }
+    }
+}
+
+impl Csr for Vtype {
+    fn read_raw(&self) -> u64 {
+        self.inner.get().into()
+    }
+
+    fn check_permission(
+        &self,
+        addr: u64,
+        privi: PrivilegeLevels,
+        access_type: AccessType,
+    ) -> Result<(), RVerr> {
+        vector_check_permission(&self.xstatus, addr, privi, access_type)
+    }
+}
+
+// vstart, vl and vlenb
+pub struct VectorCsr {
+    inner: RcCell<u64>,
+    xstatus: RcCell<XstatusIn>,
+    mask: u64,
+}
+
+impl VectorCsr {
+    pub fn new(share: RcCell<u64>, xstatus_share: RcCell<XstatusIn>, mask: u64) -> Self {
+        VectorCsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask,
+        }
+    }
+}
+
+impl Csr for VectorCsr {
+    fn write(&mut self, data: u64) {
+        self.inner.set(data & self.mask);
+
+        let mut status = self.xstatus.get();
+        status.set_vs_dirty();
+        self.xstatus.set(status);
+    }
+    fn read_raw(&self) -> u64 {
+        self.inner.get()
+    }
+
+    fn check_permission(
+        &self,
+        addr: u64,
+        privi: PrivilegeLevels,
+        access_type: AccessType,
+    ) -> Result<(), RVerr> {
+        vector_check_permission(&self.xstatus, addr, privi, access_type)
+    }
+}
+
+#[bitfield(u64)]
+pub struct VcsrIn {
+    pub vxsat: bool,
+    #[bits(2)]
+    pub vxrm: u8,
+    #[bits(61)]
+    _pad: u64,
+}
+
+// vxsat, vxrm and vcsr are different views of the same register
+pub struct Vcsr {
+    inner: RcCell<VcsrIn>,
+    xstatus: RcCell<XstatusIn>,
+    mask: u64,
+    shift: u64,
+}
+
+impl Vcsr {
+    pub fn new_vxsat(share: RcCell<VcsrIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Vcsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask: 0x1,
+            shift: 0,
+        }
+    }
+    pub fn new_vxrm(share: RcCell<VcsrIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Vcsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask: 0x6,
+            shift: 1,
+        }
+    }
+    pub fn new_vcsr(share: RcCell<VcsrIn>, xstatus_share: RcCell<XstatusIn>) -> Self {
+        Vcsr {
+            inner: share,
+            xstatus: xstatus_share,
+            mask: 0x7,
+            shift: 0,
+        }
+    }
+}
+
+impl Csr for Vcsr {
+    fn write(&mut self, data: u64) {
+        let old = u64::from(self.inner.get());
+        let new_val = write_with_mask(old, data << self.shift, self.mask);
+        self.inner.set(VcsrIn::from(new_val));
+
+        let mut status = self.xstatus.get();
+        status.set_vs_dirty();
+        self.xstatus.set(status);
+    }
+    fn read_raw(&self) -> u64 {
+        (u64::from(self.inner.get()) & self.mask) >> self.shift
+    }
+
+    fn check_permission(
+        &self,
+        addr: u64,
+        privi: PrivilegeLevels,
+        access_type: AccessType,
+    ) -> Result<(), RVerr> {
+        vector_check_permission(&self.xstatus, addr, privi, access_type)
+    }
+}
+
+// accessing the vector CSRs while VS is Off raises an illegal instruction
+fn vector_check_permission(
+    xstatus: &RcCell<XstatusIn>,
+    addr: u64,
+    privi: PrivilegeLevels,
+    access_type: AccessType,
+) -> Result<(), RVerr> {
+    if xstatus.get().vs_off() {
+        return Err(RVerr::CsrNotPermit);
+    }
+    let csr_addr = CsrAddr::from(addr as u16);
+    match csr_addr.check_privilege(privi, access_type) {
+        true => Ok(()),
+        false => Err(RVerr::CsrNotPermit),
+    }
+}
+
+#[bitfield(u64)]
+pub struct HstatusIn {
+    #[bits(5)]
+    _wpri0: u8,
+    pub vsbe: bool,
+    pub gva: bool,
+    pub spv: bool,
+    pub spvp: bool,
+    pub hu: bool,
+    #[bits(2)]
+    _wpri1: u8,
+    #[bits(6)]
+    pub vgein: u8,
+    #[bits(2)]
+    _wpri2: u8,
+    pub vtvm: bool,
+    pub vtw: bool,
+    pub vtsr: bool,
+    #[bits(9)]
+    _wpri3: u16,
+    #[bits(2)]
+    pub vsxl: u8,
+    #[bits(30)]
+    _wpri4: u32,
+}
+
+impl HstatusIn {
+    // the privilege of the guest when the trap was taken, used by SRET and HLV/HSV
+    pub fn get_spvp_priv(&self) -> PrivilegeLevels {
+        match self.spvp() {
+            true => PrivilegeLevels::Supervisor,
+            false => PrivilegeLevels::User,
+        }
+    }
+}
+
+pub struct Hstatus {
+    inner: RcCell<HstatusIn>,
+}
+
+impl Hstatus {
+    pub fn new(share: RcCell<HstatusIn>) -> Self {
+        Hstatus { inner: share }
+    }
+}
+
+impl Csr for Hstatus {
+    fn write(&mut self, data: u64) {
+        // VSXL is fixed to 64, VSBE and VGEIN are zero (little endian only, GEILEN=0)
+        let wmask = HstatusIn::new()
+            .with_gva(true)
+            .with_spv(true)
+            .with_spvp(true)
+            .with_hu(true)
+            .with_vtvm(true)
+            .with_vtw(true)
+            .with_vtsr(true);
+        let old = u64::from(self.inner.get());
+        self.inner
+            .set(HstatusIn::from(write_with_mask(old, data, wmask.into())));
+    }
+    fn read_raw(&self) -> u64 {
+        self.inner.get().into()
+    }
+}
+
+#[bitfield(u64)]
+pub struct HgatpIn {
+    #[bits(44)]
+    pub ppn: u64,
+    #[bits(14)]
+    pub vmid: u16,
+    #[bits(2)]
+    _pad: u8,
+    #[bits(4)]
+    pub mode: u8,
+}
+
+impl HgatpIn {
+    // Sv39x4, Sv48x4 and Sv57x4 share the mode encoding of Sv39, Sv48 and Sv57
+    pub fn stage_mode(&self) -> StapMode {
+        StapMode::from_bits(self.mode().into())
+    }
+}
+
+pub struct Hgatp {
+    inner: RcCell<HgatpIn>,
+    xstatus: RcCell<XstatusIn>,
+    max_satp_mode: StapMode,
+}
+
+impl Hgatp {
+    pub fn new(
+        share: RcCell<HgatpIn>,
+        xstatus_share: RcCell<XstatusIn>,
+        max_mode: StapMode,
+    ) -> Self {
+        Hgatp {
+            inner: share,
+            xstatus: xstatus_share,
+            max_satp_mode: max_mode,
+        }
+    }
+}
+
+impl Csr for Hgatp {
+    fn write(&mut self, data: u64) {
+        let new_val = HgatpIn::from(data);
+        let mut hgatp = self.inner.get();
+        // an unsupported mode keeps the old one, Sv64x4 is not defined
+        let mode_ok = match new_val.mode() {
+            0 => true,
+            mode @ 8..=10 => mode <= self.max_satp_mode as u8,
+            _ => false,
+        };
+        if mode_ok {
+            hgatp.set_mode(new_val.mode());
+        }
+        hgatp.set_vmid(new_val.vmid());
+        // the root page table is 16 KiB aligned
+        hgatp.set_ppn(new_val.ppn() & !0b11);
+        self.inner.set(hgatp);
+    }
+    fn read_raw(&self) -> u64 {
+        self.inner.get().into()
+    }
+
+    fn check_permission(
+        &self,
+        _addr: u64,
+        privi: PrivilegeLevels,
+        _access_type: AccessType,
+    ) -> Result<(), RVerr> {
+        let require_priv = if self.xstatus.get().tvm() {
+            PrivilegeLevels::Machine
+        } else {
+            PrivilegeLevels::Supervisor
+        };
+        match require_priv.check_priv(privi) {
+            true => Ok(()),
+            false => Err(RVerr::CsrNotPermit),
+        }
+    }
+}
+
+// VSSIP, VSTIP and VSEIP sit one bit above SSIP, STIP and SEIP
+pub const VS_INTERRUPT_MASK: u64 = 0x444;
+
+// vsip is the view of the VS-level bits of hip that are delegated by hideleg
+pub struct Vsip {
+    xip: RcCell<XipIn>,
+    hideleg: RcCell<MidelegIn>,
+}
+
+impl Vsip {
+    pub fn new(xip_share: RcCell<XipIn>, hideleg_share: RcCell<MidelegIn>) -> Self {
+        Vsip {
+            xip: xip_share,
+            hideleg: hideleg_share,
+        }
+    }
+}
+
+impl Csr for Vsip {
+    fn write(&mut self, data: u64) {
+        // only SSIP is writable, it aliases hip.VSSIP
+        let mask = u64::from(XipIn::new().with_vssip(true)) & self.hideleg.get().0;
+        let mut xip = self.xip.get();
+        xip.0 = write_with_mask(xip.0, data << 1, mask);
+        self.xip.set(xip);
+    }
+    fn read_raw(&self) -> u64 {
+        (self.xip.get().0 & self.hideleg.get().0 & VS_INTERRUPT_MASK) >> 1
+    }
+}
+
+// vsie is the view of the VS-level bits of hie that are delegated by hideleg
+pub struct Vsie {
+    xie: RcCell<XieIn>,
+    hideleg: RcCell<MidelegIn>,
+}
+
+impl Vsie {
+    pub fn new(xie_share: RcCell<XieIn>, hideleg_share: RcCell<MidelegIn>) -> Self {
+        Vsie {
+            xie: xie_share,
+            hideleg: hideleg_share,
+        }
+    }
+}
+
+impl Csr for Vsie {
+    fn write(&mut self, data: u64) {
+        let mask = VS_INTERRUPT_MASK & self.hideleg.get().0;
+        let mut xie = self.xie.get();
+        xie.0 = write_with_mask(xie.0, data << 1, mask);
+        self.xie.set(xie);
+    }
+    fn read_raw(&self) -> u64 {
+        (self.xie.get().0 & self.hideleg.get().0 & VS_INTERRUPT_MASK) >> 1
+    }
+}
@@ -0,0 +1,102 @@
+use core::fmt;
+
+// Narrower values are NaN-boxed: the upper bits of the 64-bit register are all ones.
+const F32_BOX: u64 = 0xffff_ffff_0000_0000;
+const F32_CANONICAL_NAN: u32 = 0x7fc0_0000;
+
+pub struct Fpr {
+    regs: [u64; 32],
+}
+
+impl Fpr {
+    pub fn new() -> Self {
+        Fpr { regs: [0; 32] }
+    }
+
+    pub fn read(&self, idx: u64) -> u64 {
+        assert!(idx < 32);
+        self.regs.get(idx as usize).copied().unwrap_or(0)
+    }
+    pub fn write(&mut self, idx: u64, data: u64) {
+        assert!(idx < 32);
+        if let Some(x) = self.regs.get_mut(idx as usize) {
+            *x = data;
+        }
+    }
+
+    // An improperly NaN-boxed value is treated as the canonical NaN
+    pub fn read_f32(&self, idx: u64) -> u32 {
+        let data = self.read(idx);
+        if data & F32_BOX == F32_BOX {
+            data as u32
+        } else {
+            F32_CANONICAL_NAN
+        }
+    }
+    pub fn write_f32(&mut self, idx: u64, data: u32) {
+        self.write(idx, F32_BOX | data as u64);
+    }
+
+    pub fn get_register_name(num: u64) -> &'static str {
+        assert!(num < 32);
+        match num {
+            0 => "ft0",
+            1 => "ft1",
+            2 => "ft2",
+            3 => "ft3",
+            4 => "ft4",
+            5 => "ft5",
+            6 => "ft6",
+            7 => "ft7",
+            8 => "fs0",
+            9 => "fs1",
+            10 => "fa0",
+            11 => "fa1",
+            12 => "fa2",
+            13 => "fa3",
+            14 => "fa4",
+            15 => "fa5",
+            16 => "fa6",
+            17 => "fa7",
+            18 => "fs2",
+            19 => "fs3",
+            20 => "fs4",
+            21 => "fs5",
+            22 => "fs6",
+            23 => "fs7",
+            24 => "fs8",
+            25 => "fs9",
+            26 => "fs10",
+            27 => "fs11",
+            28 => "ft8",
+            29 => "ft9",
+            30 => "ft10",
+            31 => "ft11",
+            _ => panic!(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.regs = [0; 32];
+    }
+}
+
+impl Default for Fpr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Fpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ret: fmt::Result = Ok(());
+        for i in 0..32 {
+            ret = f.write_fmt(format_args!(
+                "{}:{:#x}\n",
+                Fpr::get_register_name(i),
+                self.read(i)
+            ))
+        }
+        ret
+    }
+}
@@ -37,11 +37,28 @@ pub enum GprName {
 }
 pub struct Gpr {
     regs: [u64; 32],
+    // With XLEN=32 the upper bits of a source register are ignored and the
+    // results are sign-extended, so both reads and writes sign-extend bit 31.
+    rv32: bool,
 }
 
 impl Gpr {
     pub fn new() -> Self {
-        Gpr { regs: [0; 32] }
+        Gpr {
+            regs: [0; 32],
+            rv32: false,
+        }
+    }
+
+    pub fn set_rv32(&mut self, rv32: bool) {
+        self.rv32 = rv32;
+    }
+
+    fn xlen_data(&self, data: u64) -> u64 {
+        match self.rv32 {
+            true => data as i32 as u64,
+            false => data,
+        }
     }
 
     pub fn read(&self, idx: u64) -> u64 {
@@ -49,11 +66,13 @@ impl Gpr {
         if idx == 0 {
             0
         } else {
-            self.regs.get(idx as usize).copied().unwrap_or(0)
+            let data = self.regs.get(idx as usize).copied().unwrap_or(0);
+            self.xlen_data(data)
         }
     }
     pub fn write(&mut self, idx: u64, data: u64) {
         assert!(idx < 32);
+        let data = self.xlen_data(data);
         if idx != 0 {
             if let Some(x) = self.regs.get_mut(idx as usize) {
                 *x = data;
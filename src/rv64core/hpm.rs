@@ -0,0 +1,297 @@
+use core::cell::Cell;
+
+use alloc::vec::Vec;
+use bitfield_struct::bitfield;
+
+use crate::{
+    rv64core::{csr_regs_define::XipIn, inst::inst_base::PrivilegeLevels},
+    tools::RcCell,
+};
+
+// mhpmcounter3 ~ mhpmcounter31
+pub const HPM_FIRST: usize = 3;
+pub const HPM_NUM: usize = 29;
+
+// mcountinhibit: CY, IR and HPM3~31, bit 1 (TM) is read-only zero
+const MCOUNTINHIBIT_MASK: u64 = 0xffff_fffd;
+
+// The events selected by mhpmevent, any other value counts nothing.
+// A firmware maps the generic perf events onto these numbers, e.g. through the
+// riscv,event-to-mhpmevent property of the OpenSBI pmu node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HpmEvent {
+    IcacheHit = 1,
+    IcacheMiss = 2,
+    DcacheHit = 3,
+    DcacheMiss = 4,
+    TlbHit = 5,
+    TlbMiss = 6,
+    BranchTaken = 7,
+    Exception = 8,
+    Interrupt = 9,
+}
+
+const EVENT_NUM: usize = 10;
+
+fn legal_event(event: u64) -> u64 {
+    match event {
+        1..=9 => event,
+        _ => 0,
+    }
+}
+
+// mhpmevent, the overflow and mode inhibit bits are added by Sscofpmf
+#[bitfield(u64)]
+pub struct MhpmeventIn {
+    #[bits(56)]
+    pub event: u64,
+    #[bits(2)]
+    _zero0: u8,
+    pub vuinh: bool,
+    pub vsinh: bool,
+    pub uinh: bool,
+    pub sinh: bool,
+    pub minh: bool,
+    pub of: bool,
+}
+
+// the modes filtered by the xINH bits: M, S, U, VS and VU
+const MODE_NUM: usize = 5;
+
+// Zihpm performance counters, shared between the counter csrs and the event sources
+pub struct Hpm {
+    counters: Vec<Cell<u64>>,
+    events: Vec<Cell<MhpmeventIn>>,
+    event_wmask: u64,
+    inhibit: Cell<u64>,
+    // the counters listening to each event, a bit per counter index
+    listeners: [Cell<u32>; EVENT_NUM],
+    // the counters inhibited by xINH in each mode
+    mode_inhibit: [Cell<u32>; MODE_NUM],
+    sscofpmf: bool,
+    privi: RcCell<PrivilegeLevels>,
+    virt: RcCell<bool>,
+    xip: RcCell<XipIn>,
+}
+
+impl Hpm {
+    pub fn new(
+        sscofpmf: bool,
+        h_mode: bool,
+        privi: RcCell<PrivilegeLevels>,
+        virt: RcCell<bool>,
+        xip: RcCell<XipIn>,
+    ) -> Self {
+        let flags = MhpmeventIn::new()
+            .with_of(sscofpmf)
+            .with_minh(sscofpmf)
+            .with_sinh(sscofpmf)
+            .with_uinh(sscofpmf)
+            .with_vsinh(sscofpmf && h_mode)
+            .with_vuinh(sscofpmf && h_mode);
+        Hpm {
+            counters: (0..HPM_NUM).map(|_| Cell::new(0)).collect(),
+            events: (0..HPM_NUM)
+                .map(|_| Cell::new(MhpmeventIn::new()))
+                .collect(),
+            event_wmask: u64::from(flags.with_event((1 << 56) - 1)),
+            inhibit: Cell::new(0),
+            listeners: Default::default(),
+            mode_inhibit: Default::default(),
+            sscofpmf,
+            privi,
+            virt,
+            xip,
+        }
+    }
+
+    pub fn reset(&self) {
+        self.counters.iter().for_each(|counter| counter.set(0));
+        self.events
+            .iter()
+            .for_each(|event| event.set(MhpmeventIn::new()));
+        self.inhibit.set(0);
+        self.update_listeners();
+    }
+
+    // idx is the counter number, 3 ~ 31
+    pub fn read_counter(&self, idx: usize) -> u64 {
+        self.counters[idx - HPM_FIRST].get()
+    }
+
+    pub fn write_counter(&self, idx: usize, data: u64) {
+        self.counters[idx - HPM_FIRST].set(data);
+    }
+
+    pub fn read_event(&self, idx: usize) -> u64 {
+        self.events[idx - HPM_FIRST].get().into()
+    }
+
+    pub fn write_event(&self, idx: usize, data: u64) {
+        let event = MhpmeventIn::from(data & self.event_wmask);
+        let event = event.with_event(legal_event(event.event()));
+        self.events[idx - HPM_FIRST].set(event);
+        self.update_listeners();
+    }
+
+    // the OF bits of all counters, at their counter index
+    pub fn read_scountovf(&self) -> u64 {
+        self.events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| event.get().of())
+            .fold(0, |acc, (i, _)| acc | 1 << (i + HPM_FIRST))
+    }
+
+    pub fn read_inhibit(&self) -> u64 {
+        self.inhibit.get()
+    }
+
+    pub fn write_inhibit(&self, data: u64) {
+        self.inhibit.set(data & MCOUNTINHIBIT_MASK);
+        self.update_listeners();
+    }
+
+    pub fn cycle_inhibited(&self) -> bool {
+        self.inhibit.get() & 0b001 != 0
+    }
+
+    pub fn instret_inhibited(&self) -> bool {
+        self.inhibit.get() & 0b100 != 0
+    }
+
+    fn update_listeners(&self) {
+        self.listeners.iter().for_each(|mask| mask.set(0));
+        self.mode_inhibit.iter().for_each(|mask| mask.set(0));
+        let inhibit = self.inhibit.get();
+        for (i, event) in self.events.iter().enumerate() {
+            let idx = i + HPM_FIRST;
+            let event = event.get();
+            if event.event() != 0 && (inhibit >> idx) & 1 == 0 {
+                let mask = &self.listeners[event.event() as usize];
+                mask.set(mask.get() | 1 << idx);
+            }
+            let inh = [
+                event.minh(),
+                event.sinh(),
+                event.uinh(),
+                event.vsinh(),
+                event.vuinh(),
+            ];
+            for (mask, _) in self.mode_inhibit.iter().zip(inh).filter(|(_, inh)| *inh) {
+                mask.set(mask.get() | 1 << idx);
+            }
+        }
+    }
+
+    fn mode(&self) -> usize {
+        match (self.privi.get(), self.virt.get()) {
+            (PrivilegeLevels::Machine, _) => 0,
+            (PrivilegeLevels::Supervisor, false) => 1,
+            (PrivilegeLevels::User, false) => 2,
+            (PrivilegeLevels::Supervisor, true) => 3,
+            (PrivilegeLevels::User, true) => 4,
+        }
+    }
+
+    pub fn listening(&self, event: HpmEvent) -> bool {
+        self.listeners[event as usize].get() != 0
+    }
+
+    // count one occurrence of the event in every counter selecting it
+    pub fn record(&self, event: HpmEvent) {
+        let mut mask = self.listeners[event as usize].get();
+        if mask == 0 {
+            return;
+        }
+        mask &= !self.mode_inhibit[self.mode()].get();
+        while mask != 0 {
+            let idx = mask.trailing_zeros() as usize;
+            let counter = &self.counters[idx - HPM_FIRST];
+            let val = counter.get().wrapping_add(1);
+            counter.set(val);
+            if val == 0 {
+                self.overflow(idx);
+            }
+            mask &= mask - 1;
+        }
+    }
+
+    // Sscofpmf: an overflow sets OF and raises LCOFIP, unless OF is already set
+    fn overflow(&self, idx: usize) {
+        let event = &self.events[idx - HPM_FIRST];
+        if !self.sscofpmf || event.get().of() {
+            return;
+        }
+        event.set(event.get().with_of(true));
+        self.xip.set(self.xip.get().with_lcofip(true));
+    }
+}
+
+#[cfg(test)]
+fn hpm_for_test() -> (Hpm, RcCell<PrivilegeLevels>, RcCell<XipIn>) {
+    use alloc::rc::Rc;
+
+    let privi = Rc::new(Cell::new(PrivilegeLevels::Machine));
+    let virt = Rc::new(Cell::new(false));
+    let xip = Rc::new(Cell::new(XipIn::new()));
+    let hpm = Hpm::new(true, false, privi.clone(), virt, xip.clone());
+    (hpm, privi, xip)
+}
+
+#[test]
+fn hpm_record_test() {
+    let (hpm, _, _) = hpm_for_test();
+    hpm.write_event(3, HpmEvent::DcacheMiss as u64);
+    hpm.write_event(4, HpmEvent::DcacheMiss as u64);
+    hpm.write_event(5, 0xdead);
+    assert_eq!(hpm.read_event(5), 0);
+
+    hpm.record(HpmEvent::DcacheMiss);
+    hpm.record(HpmEvent::DcacheHit);
+    assert_eq!(hpm.read_counter(3), 1);
+    assert_eq!(hpm.read_counter(4), 1);
+
+    // an inhibited counter keeps its value
+    hpm.write_inhibit(1 << 4);
+    hpm.record(HpmEvent::DcacheMiss);
+    assert_eq!(hpm.read_counter(3), 2);
+    assert_eq!(hpm.read_counter(4), 1);
+    assert!(!hpm.listening(HpmEvent::TlbMiss));
+
+    hpm.write_inhibit(u64::MAX);
+    assert_eq!(hpm.read_inhibit(), 0xffff_fffd);
+    assert!(hpm.cycle_inhibited() && hpm.instret_inhibited());
+}
+
+#[test]
+fn hpm_overflow_test() {
+    let (hpm, privi, xip) = hpm_for_test();
+    let sinh = MhpmeventIn::new().with_sinh(true);
+    hpm.write_event(3, sinh.with_event(HpmEvent::TlbMiss as u64).into());
+    hpm.write_counter(3, u64::MAX - 1);
+
+    // S-mode events are not counted
+    privi.set(PrivilegeLevels::Supervisor);
+    hpm.record(HpmEvent::TlbMiss);
+    assert_eq!(hpm.read_counter(3), u64::MAX - 1);
+
+    privi.set(PrivilegeLevels::User);
+    hpm.record(HpmEvent::TlbMiss);
+    assert!(!xip.get().lcofip());
+    hpm.record(HpmEvent::TlbMiss);
+    assert_eq!(hpm.read_counter(3), 0);
+    assert!(xip.get().lcofip());
+    assert_eq!(hpm.read_scountovf(), 1 << 3);
+    assert!(MhpmeventIn::from(hpm.read_event(3)).of());
+
+    // no interrupt while OF is still set
+    xip.set(XipIn::new());
+    hpm.write_counter(3, u64::MAX);
+    hpm.record(HpmEvent::TlbMiss);
+    assert!(!xip.get().lcofip());
+
+    // VS/VU inhibit bits are read-only zero without the hypervisor extension
+    hpm.write_event(4, u64::MAX);
+    assert_eq!(hpm.read_event(4), 0xf000_0000_0000_0000);
+}
@@ -9,7 +9,7 @@ use crate::{
 };
 
 /* Automatically generated by parse_opcodes */
-// make EXTENSIONS='rv*_i rv*_m rv*_a rv*_c  rv_zicsr rv_zifencei rv*_system'
+// make EXTENSIONS='rv*_i rv*_m rv*_a rv*_zacas rv_zabha rv*_f rv*_d rv*_c rv*_zcb rv64_zcb rv*_zcmp rv*_zcmt rv*_zba rv*_zbb rv_zbc rv*_zbs rv*_zbkb rv*_zbkc rv*_zbkx rv64_zknd rv64_zkne rv64_zknh rv*_zksed rv*_zksh rv_zicond rv_zihintpause rv_zawrs rv_zimop rv_v rv*_h rv_zicbo rv_svinval rv_zicsr rv_zifencei rv*_system'
 pub const MATCH_ADD: u32 = 0x33;
 pub const MASK_ADD: u32 = 0xfe00707f;
 pub const MATCH_ADDI: u32 = 0x13;
@@ -18,40 +18,86 @@ pub const MATCH_ADDIW: u32 = 0x1b;
 pub const MASK_ADDIW: u32 = 0x707f;
 pub const MATCH_ADDW: u32 = 0x3b;
 pub const MASK_ADDW: u32 = 0xfe00707f;
+pub const MATCH_AMOADD_B: u32 = 0x2f;
+pub const MASK_AMOADD_B: u32 = 0xf800707f;
 pub const MATCH_AMOADD_D: u32 = 0x302f;
 pub const MASK_AMOADD_D: u32 = 0xf800707f;
+pub const MATCH_AMOADD_H: u32 = 0x102f;
+pub const MASK_AMOADD_H: u32 = 0xf800707f;
 pub const MATCH_AMOADD_W: u32 = 0x202f;
 pub const MASK_AMOADD_W: u32 = 0xf800707f;
+pub const MATCH_AMOAND_B: u32 = 0x6000002f;
+pub const MASK_AMOAND_B: u32 = 0xf800707f;
 pub const MATCH_AMOAND_D: u32 = 0x6000302f;
 pub const MASK_AMOAND_D: u32 = 0xf800707f;
+pub const MATCH_AMOAND_H: u32 = 0x6000102f;
+pub const MASK_AMOAND_H: u32 = 0xf800707f;
 pub const MATCH_AMOAND_W: u32 = 0x6000202f;
 pub const MASK_AMOAND_W: u32 = 0xf800707f;
+pub const MATCH_AMOCAS_B: u32 = 0x2800002f;
+pub const MASK_AMOCAS_B: u32 = 0xf800707f;
+pub const MATCH_AMOCAS_D: u32 = 0x2800302f;
+pub const MASK_AMOCAS_D: u32 = 0xf800707f;
+pub const MATCH_AMOCAS_H: u32 = 0x2800102f;
+pub const MASK_AMOCAS_H: u32 = 0xf800707f;
+pub const MATCH_AMOCAS_Q: u32 = 0x2800402f;
+pub const MASK_AMOCAS_Q: u32 = 0xf800707f;
+pub const MATCH_AMOCAS_W: u32 = 0x2800202f;
+pub const MASK_AMOCAS_W: u32 = 0xf800707f;
+pub const MATCH_AMOMAX_B: u32 = 0xa000002f;
+pub const MASK_AMOMAX_B: u32 = 0xf800707f;
 pub const MATCH_AMOMAX_D: u32 = 0xa000302f;
 pub const MASK_AMOMAX_D: u32 = 0xf800707f;
+pub const MATCH_AMOMAX_H: u32 = 0xa000102f;
+pub const MASK_AMOMAX_H: u32 = 0xf800707f;
 pub const MATCH_AMOMAX_W: u32 = 0xa000202f;
 pub const MASK_AMOMAX_W: u32 = 0xf800707f;
+pub const MATCH_AMOMAXU_B: u32 = 0xe000002f;
+pub const MASK_AMOMAXU_B: u32 = 0xf800707f;
 pub const MATCH_AMOMAXU_D: u32 = 0xe000302f;
 pub const MASK_AMOMAXU_D: u32 = 0xf800707f;
+pub const MATCH_AMOMAXU_H: u32 = 0xe000102f;
+pub const MASK_AMOMAXU_H: u32 = 0xf800707f;
 pub const MATCH_AMOMAXU_W: u32 = 0xe000202f;
 pub const MASK_AMOMAXU_W: u32 = 0xf800707f;
+pub const MATCH_AMOMIN_B: u32 = 0x8000002f;
+pub const MASK_AMOMIN_B: u32 = 0xf800707f;
 pub const MATCH_AMOMIN_D: u32 = 0x8000302f;
 pub const MASK_AMOMIN_D: u32 = 0xf800707f;
+pub const MATCH_AMOMIN_H: u32 = 0x8000102f;
+pub const MASK_AMOMIN_H: u32 = 0xf800707f;
 pub const MATCH_AMOMIN_W: u32 = 0x8000202f;
 pub const MASK_AMOMIN_W: u32 = 0xf800707f;
+pub const MATCH_AMOMINU_B: u32 = 0xc000002f;
+pub const MASK_AMOMINU_B: u32 = 0xf800707f;
 pub const MATCH_AMOMINU_D: u32 = 0xc000302f;
 pub const MASK_AMOMINU_D: u32 = 0xf800707f;
+pub const MATCH_AMOMINU_H: u32 = 0xc000102f;
+pub const MASK_AMOMINU_H: u32 = 0xf800707f;
 pub const MATCH_AMOMINU_W: u32 = 0xc000202f;
 pub const MASK_AMOMINU_W: u32 = 0xf800707f;
+pub const MATCH_AMOOR_B: u32 = 0x4000002f;
+pub const MASK_AMOOR_B: u32 = 0xf800707f;
 pub const MATCH_AMOOR_D: u32 = 0x4000302f;
 pub const MASK_AMOOR_D: u32 = 0xf800707f;
+pub const MATCH_AMOOR_H: u32 = 0x4000102f;
+pub const MASK_AMOOR_H: u32 = 0xf800707f;
 pub const MATCH_AMOOR_W: u32 = 0x4000202f;
 pub const MASK_AMOOR_W: u32 = 0xf800707f;
+pub const MATCH_AMOSWAP_B: u32 = 0x800002f;
+pub const MASK_AMOSWAP_B: u32 = 0xf800707f;
 pub const MATCH_AMOSWAP_D: u32 = 0x800302f;
 pub const MASK_AMOSWAP_D: u32 = 0xf800707f;
+pub const MATCH_AMOSWAP_H: u32 = 0x800102f;
+pub const MASK_AMOSWAP_H: u32 = 0xf800707f;
 pub const MATCH_AMOSWAP_W: u32 = 0x800202f;
 pub const MASK_AMOSWAP_W: u32 = 0xf800707f;
+pub const MATCH_AMOXOR_B: u32 = 0x2000002f;
+pub const MASK_AMOXOR_B: u32 = 0xf800707f;
 pub const MATCH_AMOXOR_D: u32 = 0x2000302f;
 pub const MASK_AMOXOR_D: u32 = 0xf800707f;
+pub const MATCH_AMOXOR_H: u32 = 0x2000102f;
+pub const MASK_AMOXOR_H: u32 = 0xf800707f;
 pub const MATCH_AMOXOR_W: u32 = 0x2000202f;
 pub const MASK_AMOXOR_W: u32 = 0xf800707f;
 pub const MATCH_AND: u32 = 0x7033;
@@ -77,6 +123,42 @@ pub const MASK_SFENCE_VMA: u32 = 0xfe007fff;
 pub const MATCH_SRET: u32 = 0x10200073;
 pub const MASK_SRET: u32 = 0xffffffff;
 
+pub const MATCH_ADD_UW: u32 = 0x800003b;
+pub const MASK_ADD_UW: u32 = 0xfe00707f;
+pub const MATCH_AES64DS: u32 = 0x3a000033;
+pub const MASK_AES64DS: u32 = 0xfe00707f;
+pub const MATCH_AES64DSM: u32 = 0x3e000033;
+pub const MASK_AES64DSM: u32 = 0xfe00707f;
+pub const MATCH_AES64ES: u32 = 0x32000033;
+pub const MASK_AES64ES: u32 = 0xfe00707f;
+pub const MATCH_AES64ESM: u32 = 0x36000033;
+pub const MASK_AES64ESM: u32 = 0xfe00707f;
+pub const MATCH_AES64IM: u32 = 0x30001013;
+pub const MASK_AES64IM: u32 = 0xfff0707f;
+pub const MATCH_AES64KS1I: u32 = 0x31001013;
+pub const MASK_AES64KS1I: u32 = 0xff00707f;
+pub const MATCH_AES64KS2: u32 = 0x7e000033;
+pub const MASK_AES64KS2: u32 = 0xfe00707f;
+pub const MATCH_ANDN: u32 = 0x40007033;
+pub const MASK_ANDN: u32 = 0xfe00707f;
+pub const MATCH_BCLR: u32 = 0x48001033;
+pub const MASK_BCLR: u32 = 0xfe00707f;
+pub const MATCH_BCLRI: u32 = 0x48001013;
+pub const MASK_BCLRI: u32 = 0xfc00707f;
+pub const MATCH_BEXT: u32 = 0x48005033;
+pub const MASK_BEXT: u32 = 0xfe00707f;
+pub const MATCH_BEXTI: u32 = 0x48005013;
+pub const MASK_BEXTI: u32 = 0xfc00707f;
+pub const MATCH_BINV: u32 = 0x68001033;
+pub const MASK_BINV: u32 = 0xfe00707f;
+pub const MATCH_BINVI: u32 = 0x68001013;
+pub const MASK_BINVI: u32 = 0xfc00707f;
+pub const MATCH_BREV8: u32 = 0x68705013;
+pub const MASK_BREV8: u32 = 0xfff0707f;
+pub const MATCH_BSET: u32 = 0x28001033;
+pub const MASK_BSET: u32 = 0xfe00707f;
+pub const MATCH_BSETI: u32 = 0x28001013;
+pub const MASK_BSETI: u32 = 0xfc00707f;
 pub const MATCH_C_ADD: u32 = 0x9002;
 pub const MASK_C_ADD: u32 = 0xf003;
 pub const MATCH_C_ADDI: u32 = 0x1;
@@ -99,6 +181,22 @@ pub const MATCH_C_BNEZ: u32 = 0xe001;
 pub const MASK_C_BNEZ: u32 = 0xe003;
 pub const MATCH_C_EBREAK: u32 = 0x9002;
 pub const MASK_C_EBREAK: u32 = 0xffff;
+pub const MATCH_C_FLD: u32 = 0x2000;
+pub const MASK_C_FLD: u32 = 0xe003;
+pub const MATCH_C_FLDSP: u32 = 0x2002;
+pub const MASK_C_FLDSP: u32 = 0xe003;
+pub const MATCH_C_FLW: u32 = 0x6000;
+pub const MASK_C_FLW: u32 = 0xe003;
+pub const MATCH_C_FLWSP: u32 = 0x6002;
+pub const MASK_C_FLWSP: u32 = 0xe003;
+pub const MATCH_C_FSD: u32 = 0xa000;
+pub const MASK_C_FSD: u32 = 0xe003;
+pub const MATCH_C_FSDSP: u32 = 0xa002;
+pub const MASK_C_FSDSP: u32 = 0xe003;
+pub const MATCH_C_FSW: u32 = 0xe000;
+pub const MASK_C_FSW: u32 = 0xe003;
+pub const MATCH_C_FSWSP: u32 = 0xe002;
+pub const MASK_C_FSWSP: u32 = 0xe003;
 pub const MATCH_C_J: u32 = 0xa001;
 pub const MASK_C_J: u32 = 0xe003;
 pub const MATCH_C_JAL: u32 = 0x2001;
@@ -107,10 +205,16 @@ pub const MATCH_C_JALR: u32 = 0x9002;
 pub const MASK_C_JALR: u32 = 0xf07f;
 pub const MATCH_C_JR: u32 = 0x8002;
 pub const MASK_C_JR: u32 = 0xf07f;
+pub const MATCH_C_LBU: u32 = 0x8000;
+pub const MASK_C_LBU: u32 = 0xfc03;
 pub const MATCH_C_LD: u32 = 0x6000;
 pub const MASK_C_LD: u32 = 0xe003;
 pub const MATCH_C_LDSP: u32 = 0x6002;
 pub const MASK_C_LDSP: u32 = 0xe003;
+pub const MATCH_C_LH: u32 = 0x8440;
+pub const MASK_C_LH: u32 = 0xfc43;
+pub const MATCH_C_LHU: u32 = 0x8400;
+pub const MASK_C_LHU: u32 = 0xfc43;
 pub const MATCH_C_LI: u32 = 0x4001;
 pub const MASK_C_LI: u32 = 0xe003;
 pub const MATCH_C_LUI: u32 = 0x6001;
@@ -119,22 +223,40 @@ pub const MATCH_C_LW: u32 = 0x4000;
 pub const MASK_C_LW: u32 = 0xe003;
 pub const MATCH_C_LWSP: u32 = 0x4002;
 pub const MASK_C_LWSP: u32 = 0xe003;
+pub const MATCH_C_MUL: u32 = 0x9c41;
+pub const MASK_C_MUL: u32 = 0xfc63;
 pub const MATCH_C_MV: u32 = 0x8002;
 pub const MASK_C_MV: u32 = 0xf003;
 pub const MATCH_C_NOP: u32 = 0x1;
 pub const MASK_C_NOP: u32 = 0xef83;
+pub const MATCH_C_NOT: u32 = 0x9c75;
+pub const MASK_C_NOT: u32 = 0xfc7f;
 pub const MATCH_C_OR: u32 = 0x8c41;
 pub const MASK_C_OR: u32 = 0xfc63;
+pub const MATCH_C_SB: u32 = 0x8800;
+pub const MASK_C_SB: u32 = 0xfc03;
 pub const MATCH_C_SD: u32 = 0xe000;
 pub const MASK_C_SD: u32 = 0xe003;
 pub const MATCH_C_SDSP: u32 = 0xe002;
 pub const MASK_C_SDSP: u32 = 0xe003;
+pub const MATCH_C_SEXT_B: u32 = 0x9c65;
+pub const MASK_C_SEXT_B: u32 = 0xfc7f;
+pub const MATCH_C_SEXT_H: u32 = 0x9c6d;
+pub const MASK_C_SEXT_H: u32 = 0xfc7f;
+pub const MATCH_C_SH: u32 = 0x8c00;
+pub const MASK_C_SH: u32 = 0xfc43;
 pub const MATCH_C_SLLI: u32 = 0x2;
 pub const MASK_C_SLLI: u32 = 0xe003;
+pub const MATCH_C_SLLI_RV32: u32 = 0x2;
+pub const MASK_C_SLLI_RV32: u32 = 0xf003;
 pub const MATCH_C_SRAI: u32 = 0x8401;
 pub const MASK_C_SRAI: u32 = 0xec03;
+pub const MATCH_C_SRAI_RV32: u32 = 0x8401;
+pub const MASK_C_SRAI_RV32: u32 = 0xfc03;
 pub const MATCH_C_SRLI: u32 = 0x8001;
 pub const MASK_C_SRLI: u32 = 0xec03;
+pub const MATCH_C_SRLI_RV32: u32 = 0x8001;
+pub const MASK_C_SRLI_RV32: u32 = 0xfc03;
 pub const MATCH_C_SUB: u32 = 0x8c01;
 pub const MASK_C_SUB: u32 = 0xfc63;
 pub const MATCH_C_SUBW: u32 = 0x9c01;
@@ -145,6 +267,48 @@ pub const MATCH_C_SWSP: u32 = 0xc002;
 pub const MASK_C_SWSP: u32 = 0xe003;
 pub const MATCH_C_XOR: u32 = 0x8c21;
 pub const MASK_C_XOR: u32 = 0xfc63;
+pub const MATCH_C_ZEXT_B: u32 = 0x9c61;
+pub const MASK_C_ZEXT_B: u32 = 0xfc7f;
+pub const MATCH_C_ZEXT_H: u32 = 0x9c69;
+pub const MASK_C_ZEXT_H: u32 = 0xfc7f;
+pub const MATCH_C_ZEXT_W: u32 = 0x9c71;
+pub const MASK_C_ZEXT_W: u32 = 0xfc7f;
+pub const MATCH_CBO_CLEAN: u32 = 0x10200f;
+pub const MASK_CBO_CLEAN: u32 = 0xfff07fff;
+pub const MATCH_CBO_FLUSH: u32 = 0x20200f;
+pub const MASK_CBO_FLUSH: u32 = 0xfff07fff;
+pub const MATCH_CBO_INVAL: u32 = 0x200f;
+pub const MASK_CBO_INVAL: u32 = 0xfff07fff;
+pub const MATCH_CBO_ZERO: u32 = 0x40200f;
+pub const MASK_CBO_ZERO: u32 = 0xfff07fff;
+pub const MATCH_CLMUL: u32 = 0xa001033;
+pub const MASK_CLMUL: u32 = 0xfe00707f;
+pub const MATCH_CLMULH: u32 = 0xa003033;
+pub const MASK_CLMULH: u32 = 0xfe00707f;
+pub const MATCH_CLMULR: u32 = 0xa002033;
+pub const MASK_CLMULR: u32 = 0xfe00707f;
+pub const MATCH_CLZ: u32 = 0x60001013;
+pub const MASK_CLZ: u32 = 0xfff0707f;
+pub const MATCH_CLZW: u32 = 0x6000101b;
+pub const MASK_CLZW: u32 = 0xfff0707f;
+pub const MATCH_CM_JT: u32 = 0xa002;
+pub const MASK_CM_JT: u32 = 0xfc03;
+pub const MATCH_CM_MVA01S: u32 = 0xac62;
+pub const MASK_CM_MVA01S: u32 = 0xfc63;
+pub const MATCH_CM_MVSA01: u32 = 0xac22;
+pub const MASK_CM_MVSA01: u32 = 0xfc63;
+pub const MATCH_CM_POP: u32 = 0xba02;
+pub const MASK_CM_POP: u32 = 0xff03;
+pub const MATCH_CM_POPRET: u32 = 0xbe02;
+pub const MASK_CM_POPRET: u32 = 0xff03;
+pub const MATCH_CM_POPRETZ: u32 = 0xbc02;
+pub const MASK_CM_POPRETZ: u32 = 0xff03;
+pub const MATCH_CM_PUSH: u32 = 0xb802;
+pub const MASK_CM_PUSH: u32 = 0xff03;
+pub const MATCH_CPOP: u32 = 0x60201013;
+pub const MASK_CPOP: u32 = 0xfff0707f;
+pub const MATCH_CPOPW: u32 = 0x6020101b;
+pub const MASK_CPOPW: u32 = 0xfff0707f;
 pub const MATCH_CSRRC: u32 = 0x3073;
 pub const MASK_CSRRC: u32 = 0x707f;
 pub const MATCH_CSRRCI: u32 = 0x7073;
@@ -157,6 +321,14 @@ pub const MATCH_CSRRW: u32 = 0x1073;
 pub const MASK_CSRRW: u32 = 0x707f;
 pub const MATCH_CSRRWI: u32 = 0x5073;
 pub const MASK_CSRRWI: u32 = 0x707f;
+pub const MATCH_CTZ: u32 = 0x60101013;
+pub const MASK_CTZ: u32 = 0xfff0707f;
+pub const MATCH_CTZW: u32 = 0x6010101b;
+pub const MASK_CTZW: u32 = 0xfff0707f;
+pub const MATCH_CZERO_EQZ: u32 = 0xe005033;
+pub const MASK_CZERO_EQZ: u32 = 0xfe00707f;
+pub const MATCH_CZERO_NEZ: u32 = 0xe007033;
+pub const MASK_CZERO_NEZ: u32 = 0xfe00707f;
 pub const MATCH_DIV: u32 = 0x2004033;
 pub const MASK_DIV: u32 = 0xfe00707f;
 pub const MATCH_DIVU: u32 = 0x2005033;
@@ -171,12 +343,112 @@ pub const MATCH_EBREAK: u32 = 0x100073;
 pub const MASK_EBREAK: u32 = 0xffffffff;
 pub const MATCH_ECALL: u32 = 0x73;
 pub const MASK_ECALL: u32 = 0xffffffff;
+pub const MATCH_FADD_D: u32 = 0x2000053;
+pub const MASK_FADD_D: u32 = 0xfe00007f;
+pub const MATCH_FADD_S: u32 = 0x53;
+pub const MASK_FADD_S: u32 = 0xfe00007f;
+pub const MATCH_FCLASS_D: u32 = 0xe2001053;
+pub const MASK_FCLASS_D: u32 = 0xfff0707f;
+pub const MATCH_FCLASS_S: u32 = 0xe0001053;
+pub const MASK_FCLASS_S: u32 = 0xfff0707f;
+pub const MATCH_FCVT_D_L: u32 = 0xd2200053;
+pub const MASK_FCVT_D_L: u32 = 0xfff0007f;
+pub const MATCH_FCVT_D_LU: u32 = 0xd2300053;
+pub const MASK_FCVT_D_LU: u32 = 0xfff0007f;
+pub const MATCH_FCVT_D_S: u32 = 0x42000053;
+pub const MASK_FCVT_D_S: u32 = 0xfff0007f;
+pub const MATCH_FCVT_D_W: u32 = 0xd2000053;
+pub const MASK_FCVT_D_W: u32 = 0xfff0007f;
+pub const MATCH_FCVT_D_WU: u32 = 0xd2100053;
+pub const MASK_FCVT_D_WU: u32 = 0xfff0007f;
+pub const MATCH_FCVT_L_D: u32 = 0xc2200053;
+pub const MASK_FCVT_L_D: u32 = 0xfff0007f;
+pub const MATCH_FCVT_L_S: u32 = 0xc0200053;
+pub const MASK_FCVT_L_S: u32 = 0xfff0007f;
+pub const MATCH_FCVT_LU_D: u32 = 0xc2300053;
+pub const MASK_FCVT_LU_D: u32 = 0xfff0007f;
+pub const MATCH_FCVT_LU_S: u32 = 0xc0300053;
+pub const MASK_FCVT_LU_S: u32 = 0xfff0007f;
+pub const MATCH_FCVT_S_D: u32 = 0x40100053;
+pub const MASK_FCVT_S_D: u32 = 0xfff0007f;
+pub const MATCH_FCVT_S_L: u32 = 0xd0200053;
+pub const MASK_FCVT_S_L: u32 = 0xfff0007f;
+pub const MATCH_FCVT_S_LU: u32 = 0xd0300053;
+pub const MASK_FCVT_S_LU: u32 = 0xfff0007f;
+pub const MATCH_FCVT_S_W: u32 = 0xd0000053;
+pub const MASK_FCVT_S_W: u32 = 0xfff0007f;
+pub const MATCH_FCVT_S_WU: u32 = 0xd0100053;
+pub const MASK_FCVT_S_WU: u32 = 0xfff0007f;
+pub const MATCH_FCVT_W_D: u32 = 0xc2000053;
+pub const MASK_FCVT_W_D: u32 = 0xfff0007f;
+pub const MATCH_FCVT_W_S: u32 = 0xc0000053;
+pub const MASK_FCVT_W_S: u32 = 0xfff0007f;
+pub const MATCH_FCVT_WU_D: u32 = 0xc2100053;
+pub const MASK_FCVT_WU_D: u32 = 0xfff0007f;
+pub const MATCH_FCVT_WU_S: u32 = 0xc0100053;
+pub const MASK_FCVT_WU_S: u32 = 0xfff0007f;
+pub const MATCH_FDIV_D: u32 = 0x1a000053;
+pub const MASK_FDIV_D: u32 = 0xfe00007f;
+pub const MATCH_FDIV_S: u32 = 0x18000053;
+pub const MASK_FDIV_S: u32 = 0xfe00007f;
 pub const MATCH_FENCE: u32 = 0xf;
 pub const MASK_FENCE: u32 = 0x707f;
 pub const MATCH_FENCE_I: u32 = 0x100f;
 pub const MASK_FENCE_I: u32 = 0x707f;
 pub const MATCH_FENCE_TSO: u32 = 0x8330000f;
 pub const MASK_FENCE_TSO: u32 = 0xfff0707f;
+pub const MATCH_FEQ_D: u32 = 0xa2002053;
+pub const MASK_FEQ_D: u32 = 0xfe00707f;
+pub const MATCH_FEQ_S: u32 = 0xa0002053;
+pub const MASK_FEQ_S: u32 = 0xfe00707f;
+pub const MATCH_FLD: u32 = 0x3007;
+pub const MASK_FLD: u32 = 0x707f;
+pub const MATCH_FLE_D: u32 = 0xa2000053;
+pub const MASK_FLE_D: u32 = 0xfe00707f;
+pub const MATCH_FLE_S: u32 = 0xa0000053;
+pub const MASK_FLE_S: u32 = 0xfe00707f;
+pub const MATCH_FLT_D: u32 = 0xa2001053;
+pub const MASK_FLT_D: u32 = 0xfe00707f;
+pub const MATCH_FLT_S: u32 = 0xa0001053;
+pub const MASK_FLT_S: u32 = 0xfe00707f;
+pub const MATCH_FLW: u32 = 0x2007;
+pub const MASK_FLW: u32 = 0x707f;
+pub const MATCH_FMADD_D: u32 = 0x2000043;
+pub const MASK_FMADD_D: u32 = 0x600007f;
+pub const MATCH_FMADD_S: u32 = 0x43;
+pub const MASK_FMADD_S: u32 = 0x600007f;
+pub const MATCH_FMAX_D: u32 = 0x2a001053;
+pub const MASK_FMAX_D: u32 = 0xfe00707f;
+pub const MATCH_FMAX_S: u32 = 0x28001053;
+pub const MASK_FMAX_S: u32 = 0xfe00707f;
+pub const MATCH_FMIN_D: u32 = 0x2a000053;
+pub const MASK_FMIN_D: u32 = 0xfe00707f;
+pub const MATCH_FMIN_S: u32 = 0x28000053;
+pub const MASK_FMIN_S: u32 = 0xfe00707f;
+pub const MATCH_FMSUB_D: u32 = 0x2000047;
+pub const MASK_FMSUB_D: u32 = 0x600007f;
+pub const MATCH_FMSUB_S: u32 = 0x47;
+pub const MASK_FMSUB_S: u32 = 0x600007f;
+pub const MATCH_FMUL_D: u32 = 0x12000053;
+pub const MASK_FMUL_D: u32 = 0xfe00007f;
+pub const MATCH_FMUL_S: u32 = 0x10000053;
+pub const MASK_FMUL_S: u32 = 0xfe00007f;
+pub const MATCH_FMV_D_X: u32 = 0xf2000053;
+pub const MASK_FMV_D_X: u32 = 0xfff0707f;
+pub const MATCH_FMV_W_X: u32 = 0xf0000053;
+pub const MASK_FMV_W_X: u32 = 0xfff0707f;
+pub const MATCH_FMV_X_D: u32 = 0xe2000053;
+pub const MASK_FMV_X_D: u32 = 0xfff0707f;
+pub const MATCH_FMV_X_W: u32 = 0xe0000053;
+pub const MASK_FMV_X_W: u32 = 0xfff0707f;
+pub const MATCH_FNMADD_D: u32 = 0x200004f;
+pub const MASK_FNMADD_D: u32 = 0x600007f;
+pub const MATCH_FNMADD_S: u32 = 0x4f;
+pub const MASK_FNMADD_S: u32 = 0x600007f;
+pub const MATCH_FNMSUB_D: u32 = 0x200004b;
+pub const MASK_FNMSUB_D: u32 = 0x600007f;
+pub const MATCH_FNMSUB_S: u32 = 0x4b;
+pub const MASK_FNMSUB_S: u32 = 0x600007f;
 pub const MATCH_FRCSR: u32 = 0x302073;
 pub const MASK_FRCSR: u32 = 0xfffff07f;
 pub const MATCH_FRFLAGS: u32 = 0x102073;
@@ -185,14 +457,72 @@ pub const MATCH_FRRM: u32 = 0x202073;
 pub const MASK_FRRM: u32 = 0xfffff07f;
 pub const MATCH_FSCSR: u32 = 0x301073;
 pub const MASK_FSCSR: u32 = 0xfff0707f;
+pub const MATCH_FSD: u32 = 0x3027;
+pub const MASK_FSD: u32 = 0x707f;
 pub const MATCH_FSFLAGS: u32 = 0x101073;
 pub const MASK_FSFLAGS: u32 = 0xfff0707f;
 pub const MATCH_FSFLAGSI: u32 = 0x105073;
 pub const MASK_FSFLAGSI: u32 = 0xfff0707f;
+pub const MATCH_FSGNJ_D: u32 = 0x22000053;
+pub const MASK_FSGNJ_D: u32 = 0xfe00707f;
+pub const MATCH_FSGNJ_S: u32 = 0x20000053;
+pub const MASK_FSGNJ_S: u32 = 0xfe00707f;
+pub const MATCH_FSGNJN_D: u32 = 0x22001053;
+pub const MASK_FSGNJN_D: u32 = 0xfe00707f;
+pub const MATCH_FSGNJN_S: u32 = 0x20001053;
+pub const MASK_FSGNJN_S: u32 = 0xfe00707f;
+pub const MATCH_FSGNJX_D: u32 = 0x22002053;
+pub const MASK_FSGNJX_D: u32 = 0xfe00707f;
+pub const MATCH_FSGNJX_S: u32 = 0x20002053;
+pub const MASK_FSGNJX_S: u32 = 0xfe00707f;
+pub const MATCH_FSQRT_D: u32 = 0x5a000053;
+pub const MASK_FSQRT_D: u32 = 0xfff0007f;
+pub const MATCH_FSQRT_S: u32 = 0x58000053;
+pub const MASK_FSQRT_S: u32 = 0xfff0007f;
 pub const MATCH_FSRM: u32 = 0x201073;
 pub const MASK_FSRM: u32 = 0xfff0707f;
 pub const MATCH_FSRMI: u32 = 0x205073;
 pub const MASK_FSRMI: u32 = 0xfff0707f;
+pub const MATCH_FSUB_D: u32 = 0xa000053;
+pub const MASK_FSUB_D: u32 = 0xfe00007f;
+pub const MATCH_FSUB_S: u32 = 0x8000053;
+pub const MASK_FSUB_S: u32 = 0xfe00007f;
+pub const MATCH_FSW: u32 = 0x2027;
+pub const MASK_FSW: u32 = 0x707f;
+pub const MATCH_HFENCE_GVMA: u32 = 0x62000073;
+pub const MASK_HFENCE_GVMA: u32 = 0xfe007fff;
+pub const MATCH_HFENCE_VVMA: u32 = 0x22000073;
+pub const MASK_HFENCE_VVMA: u32 = 0xfe007fff;
+pub const MATCH_HINVAL_GVMA: u32 = 0x66000073;
+pub const MASK_HINVAL_GVMA: u32 = 0xfe007fff;
+pub const MATCH_HINVAL_VVMA: u32 = 0x26000073;
+pub const MASK_HINVAL_VVMA: u32 = 0xfe007fff;
+pub const MATCH_HLV_B: u32 = 0x60004073;
+pub const MASK_HLV_B: u32 = 0xfff0707f;
+pub const MATCH_HLV_BU: u32 = 0x60104073;
+pub const MASK_HLV_BU: u32 = 0xfff0707f;
+pub const MATCH_HLV_D: u32 = 0x6c004073;
+pub const MASK_HLV_D: u32 = 0xfff0707f;
+pub const MATCH_HLV_H: u32 = 0x64004073;
+pub const MASK_HLV_H: u32 = 0xfff0707f;
+pub const MATCH_HLV_HU: u32 = 0x64104073;
+pub const MASK_HLV_HU: u32 = 0xfff0707f;
+pub const MATCH_HLV_W: u32 = 0x68004073;
+pub const MASK_HLV_W: u32 = 0xfff0707f;
+pub const MATCH_HLV_WU: u32 = 0x68104073;
+pub const MASK_HLV_WU: u32 = 0xfff0707f;
+pub const MATCH_HLVX_HU: u32 = 0x64304073;
+pub const MASK_HLVX_HU: u32 = 0xfff0707f;
+pub const MATCH_HLVX_WU: u32 = 0x68304073;
+pub const MASK_HLVX_WU: u32 = 0xfff0707f;
+pub const MATCH_HSV_B: u32 = 0x62004073;
+pub const MASK_HSV_B: u32 = 0xfe007fff;
+pub const MATCH_HSV_D: u32 = 0x6e004073;
+pub const MASK_HSV_D: u32 = 0xfe007fff;
+pub const MATCH_HSV_H: u32 = 0x66004073;
+pub const MASK_HSV_H: u32 = 0xfe007fff;
+pub const MATCH_HSV_W: u32 = 0x6a004073;
+pub const MASK_HSV_W: u32 = 0xfe007fff;
 pub const MATCH_JAL: u32 = 0x6f;
 pub const MASK_JAL: u32 = 0x7f;
 pub const MATCH_JALR: u32 = 0x67;
@@ -217,6 +547,18 @@ pub const MATCH_LW: u32 = 0x2003;
 pub const MASK_LW: u32 = 0x707f;
 pub const MATCH_LWU: u32 = 0x6003;
 pub const MASK_LWU: u32 = 0x707f;
+pub const MATCH_MAX: u32 = 0xa006033;
+pub const MASK_MAX: u32 = 0xfe00707f;
+pub const MATCH_MAXU: u32 = 0xa007033;
+pub const MASK_MAXU: u32 = 0xfe00707f;
+pub const MATCH_MIN: u32 = 0xa004033;
+pub const MASK_MIN: u32 = 0xfe00707f;
+pub const MATCH_MINU: u32 = 0xa005033;
+pub const MASK_MINU: u32 = 0xfe00707f;
+pub const MATCH_MOP_R_N: u32 = 0x81c04073;
+pub const MASK_MOP_R_N: u32 = 0xb3c0707f;
+pub const MATCH_MOP_RR_N: u32 = 0x82004073;
+pub const MASK_MOP_RR_N: u32 = 0xb200707f;
 pub const MATCH_MRET: u32 = 0x30200073;
 pub const MASK_MRET: u32 = 0xffffffff;
 pub const MATCH_MUL: u32 = 0x2000033;
@@ -231,8 +573,18 @@ pub const MATCH_MULW: u32 = 0x200003b;
 pub const MASK_MULW: u32 = 0xfe00707f;
 pub const MATCH_OR: u32 = 0x6033;
 pub const MASK_OR: u32 = 0xfe00707f;
+pub const MATCH_ORC_B: u32 = 0x28705013;
+pub const MASK_ORC_B: u32 = 0xfff0707f;
 pub const MATCH_ORI: u32 = 0x6013;
 pub const MASK_ORI: u32 = 0x707f;
+pub const MATCH_ORN: u32 = 0x40006033;
+pub const MASK_ORN: u32 = 0xfe00707f;
+pub const MATCH_PACK: u32 = 0x8004033;
+pub const MASK_PACK: u32 = 0xfe00707f;
+pub const MATCH_PACKH: u32 = 0x8007033;
+pub const MASK_PACKH: u32 = 0xfe00707f;
+pub const MATCH_PACKW: u32 = 0x800403b;
+pub const MASK_PACKW: u32 = 0xfe00707f;
 pub const MATCH_PAUSE: u32 = 0x100000f;
 pub const MASK_PAUSE: u32 = 0xffffffff;
 pub const MATCH_RDCYCLE: u32 = 0xc0002073;
@@ -255,6 +607,20 @@ pub const MATCH_REMUW: u32 = 0x200703b;
 pub const MASK_REMUW: u32 = 0xfe00707f;
 pub const MATCH_REMW: u32 = 0x200603b;
 pub const MASK_REMW: u32 = 0xfe00707f;
+pub const MATCH_REV8: u32 = 0x6b805013;
+pub const MASK_REV8: u32 = 0xfff0707f;
+pub const MATCH_ROL: u32 = 0x60001033;
+pub const MASK_ROL: u32 = 0xfe00707f;
+pub const MATCH_ROLW: u32 = 0x6000103b;
+pub const MASK_ROLW: u32 = 0xfe00707f;
+pub const MATCH_ROR: u32 = 0x60005033;
+pub const MASK_ROR: u32 = 0xfe00707f;
+pub const MATCH_RORI: u32 = 0x60005013;
+pub const MASK_RORI: u32 = 0xfc00707f;
+pub const MATCH_RORIW: u32 = 0x6000501b;
+pub const MASK_RORIW: u32 = 0xfe00707f;
+pub const MATCH_RORW: u32 = 0x6000503b;
+pub const MASK_RORW: u32 = 0xfe00707f;
 pub const MATCH_SB: u32 = 0x23;
 pub const MASK_SB: u32 = 0x707f;
 pub const MATCH_SBREAK: u32 = 0x100073;
@@ -267,14 +633,54 @@ pub const MATCH_SCALL: u32 = 0x73;
 pub const MASK_SCALL: u32 = 0xffffffff;
 pub const MATCH_SD: u32 = 0x3023;
 pub const MASK_SD: u32 = 0x707f;
+pub const MATCH_SEXT_B: u32 = 0x60401013;
+pub const MASK_SEXT_B: u32 = 0xfff0707f;
+pub const MATCH_SEXT_H: u32 = 0x60501013;
+pub const MASK_SEXT_H: u32 = 0xfff0707f;
+pub const MATCH_SFENCE_INVAL_IR: u32 = 0x18100073;
+pub const MASK_SFENCE_INVAL_IR: u32 = 0xffffffff;
+pub const MATCH_SFENCE_W_INVAL: u32 = 0x18000073;
+pub const MASK_SFENCE_W_INVAL: u32 = 0xffffffff;
 pub const MATCH_SH: u32 = 0x1023;
 pub const MASK_SH: u32 = 0x707f;
+pub const MATCH_SH1ADD: u32 = 0x20002033;
+pub const MASK_SH1ADD: u32 = 0xfe00707f;
+pub const MATCH_SH1ADD_UW: u32 = 0x2000203b;
+pub const MASK_SH1ADD_UW: u32 = 0xfe00707f;
+pub const MATCH_SH2ADD: u32 = 0x20004033;
+pub const MASK_SH2ADD: u32 = 0xfe00707f;
+pub const MATCH_SH2ADD_UW: u32 = 0x2000403b;
+pub const MASK_SH2ADD_UW: u32 = 0xfe00707f;
+pub const MATCH_SH3ADD: u32 = 0x20006033;
+pub const MASK_SH3ADD: u32 = 0xfe00707f;
+pub const MATCH_SH3ADD_UW: u32 = 0x2000603b;
+pub const MASK_SH3ADD_UW: u32 = 0xfe00707f;
+pub const MATCH_SHA256SIG0: u32 = 0x10201013;
+pub const MASK_SHA256SIG0: u32 = 0xfff0707f;
+pub const MATCH_SHA256SIG1: u32 = 0x10301013;
+pub const MASK_SHA256SIG1: u32 = 0xfff0707f;
+pub const MATCH_SHA256SUM0: u32 = 0x10001013;
+pub const MASK_SHA256SUM0: u32 = 0xfff0707f;
+pub const MATCH_SHA256SUM1: u32 = 0x10101013;
+pub const MASK_SHA256SUM1: u32 = 0xfff0707f;
+pub const MATCH_SHA512SIG0: u32 = 0x10601013;
+pub const MASK_SHA512SIG0: u32 = 0xfff0707f;
+pub const MATCH_SHA512SIG1: u32 = 0x10701013;
+pub const MASK_SHA512SIG1: u32 = 0xfff0707f;
+pub const MATCH_SHA512SUM0: u32 = 0x10401013;
+pub const MASK_SHA512SUM0: u32 = 0xfff0707f;
+pub const MATCH_SHA512SUM1: u32 = 0x10501013;
+pub const MASK_SHA512SUM1: u32 = 0xfff0707f;
+pub const MATCH_SINVAL_VMA: u32 = 0x16000073;
+pub const MASK_SINVAL_VMA: u32 = 0xfe007fff;
 pub const MATCH_SLL: u32 = 0x1033;
 pub const MASK_SLL: u32 = 0xfe00707f;
 pub const MATCH_SLLI: u32 = 0x1013;
 pub const MASK_SLLI: u32 = 0xfc00707f;
 pub const MATCH_SLLI_RV32: u32 = 0x1013;
 pub const MASK_SLLI_RV32: u32 = 0xfe00707f;
+pub const MATCH_SLLI_UW: u32 = 0x800101b;
+pub const MASK_SLLI_UW: u32 = 0xfc00707f;
 pub const MATCH_SLLIW: u32 = 0x101b;
 pub const MASK_SLLIW: u32 = 0xfe00707f;
 pub const MATCH_SLLW: u32 = 0x103b;
@@ -287,6 +693,14 @@ pub const MATCH_SLTIU: u32 = 0x3013;
 pub const MASK_SLTIU: u32 = 0x707f;
 pub const MATCH_SLTU: u32 = 0x3033;
 pub const MASK_SLTU: u32 = 0xfe00707f;
+pub const MATCH_SM3P0: u32 = 0x10801013;
+pub const MASK_SM3P0: u32 = 0xfff0707f;
+pub const MATCH_SM3P1: u32 = 0x10901013;
+pub const MASK_SM3P1: u32 = 0xfff0707f;
+pub const MATCH_SM4ED: u32 = 0x30000033;
+pub const MASK_SM4ED: u32 = 0x3e00707f;
+pub const MATCH_SM4KS: u32 = 0x34000033;
+pub const MASK_SM4KS: u32 = 0x3e00707f;
 pub const MATCH_SRA: u32 = 0x40005033;
 pub const MASK_SRA: u32 = 0xfe00707f;
 pub const MATCH_SRAI: u32 = 0x40005013;
@@ -313,12 +727,542 @@ pub const MATCH_SUBW: u32 = 0x4000003b;
 pub const MASK_SUBW: u32 = 0xfe00707f;
 pub const MATCH_SW: u32 = 0x2023;
 pub const MASK_SW: u32 = 0x707f;
+pub const MATCH_VAADD_VV: u32 = 0x24002057;
+pub const MASK_VAADD_VV: u32 = 0xfc00707f;
+pub const MATCH_VAADD_VX: u32 = 0x24006057;
+pub const MASK_VAADD_VX: u32 = 0xfc00707f;
+pub const MATCH_VAADDU_VV: u32 = 0x20002057;
+pub const MASK_VAADDU_VV: u32 = 0xfc00707f;
+pub const MATCH_VAADDU_VX: u32 = 0x20006057;
+pub const MASK_VAADDU_VX: u32 = 0xfc00707f;
+pub const MATCH_VADC_VIM: u32 = 0x40003057;
+pub const MASK_VADC_VIM: u32 = 0xfe00707f;
+pub const MATCH_VADC_VVM: u32 = 0x40000057;
+pub const MASK_VADC_VVM: u32 = 0xfe00707f;
+pub const MATCH_VADC_VXM: u32 = 0x40004057;
+pub const MASK_VADC_VXM: u32 = 0xfe00707f;
+pub const MATCH_VADD_VI: u32 = 0x3057;
+pub const MASK_VADD_VI: u32 = 0xfc00707f;
+pub const MATCH_VADD_VV: u32 = 0x57;
+pub const MASK_VADD_VV: u32 = 0xfc00707f;
+pub const MATCH_VADD_VX: u32 = 0x4057;
+pub const MASK_VADD_VX: u32 = 0xfc00707f;
+pub const MATCH_VAND_VI: u32 = 0x24003057;
+pub const MASK_VAND_VI: u32 = 0xfc00707f;
+pub const MATCH_VAND_VV: u32 = 0x24000057;
+pub const MASK_VAND_VV: u32 = 0xfc00707f;
+pub const MATCH_VAND_VX: u32 = 0x24004057;
+pub const MASK_VAND_VX: u32 = 0xfc00707f;
+pub const MATCH_VASUB_VV: u32 = 0x2c002057;
+pub const MASK_VASUB_VV: u32 = 0xfc00707f;
+pub const MATCH_VASUB_VX: u32 = 0x2c006057;
+pub const MASK_VASUB_VX: u32 = 0xfc00707f;
+pub const MATCH_VASUBU_VV: u32 = 0x28002057;
+pub const MASK_VASUBU_VV: u32 = 0xfc00707f;
+pub const MATCH_VASUBU_VX: u32 = 0x28006057;
+pub const MASK_VASUBU_VX: u32 = 0xfc00707f;
+pub const MATCH_VCOMPRESS_VM: u32 = 0x5e002057;
+pub const MASK_VCOMPRESS_VM: u32 = 0xfe00707f;
+pub const MATCH_VCPOP_M: u32 = 0x40082057;
+pub const MASK_VCPOP_M: u32 = 0xfc0ff07f;
+pub const MATCH_VDIV_VV: u32 = 0x84002057;
+pub const MASK_VDIV_VV: u32 = 0xfc00707f;
+pub const MATCH_VDIV_VX: u32 = 0x84006057;
+pub const MASK_VDIV_VX: u32 = 0xfc00707f;
+pub const MATCH_VDIVU_VV: u32 = 0x80002057;
+pub const MASK_VDIVU_VV: u32 = 0xfc00707f;
+pub const MATCH_VDIVU_VX: u32 = 0x80006057;
+pub const MASK_VDIVU_VX: u32 = 0xfc00707f;
+pub const MATCH_VFIRST_M: u32 = 0x4008a057;
+pub const MASK_VFIRST_M: u32 = 0xfc0ff07f;
+pub const MATCH_VID_V: u32 = 0x5008a057;
+pub const MASK_VID_V: u32 = 0xfdfff07f;
+pub const MATCH_VIOTA_M: u32 = 0x50082057;
+pub const MASK_VIOTA_M: u32 = 0xfc0ff07f;
+pub const MATCH_VL1RE16_V: u32 = 0x2805007;
+pub const MASK_VL1RE16_V: u32 = 0xfff0707f;
+pub const MATCH_VL1RE32_V: u32 = 0x2806007;
+pub const MASK_VL1RE32_V: u32 = 0xfff0707f;
+pub const MATCH_VL1RE64_V: u32 = 0x2807007;
+pub const MASK_VL1RE64_V: u32 = 0xfff0707f;
+pub const MATCH_VL1RE8_V: u32 = 0x2800007;
+pub const MASK_VL1RE8_V: u32 = 0xfff0707f;
+pub const MATCH_VLE16_V: u32 = 0x5007;
+pub const MASK_VLE16_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE16FF_V: u32 = 0x1005007;
+pub const MASK_VLE16FF_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE32_V: u32 = 0x6007;
+pub const MASK_VLE32_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE32FF_V: u32 = 0x1006007;
+pub const MASK_VLE32FF_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE64_V: u32 = 0x7007;
+pub const MASK_VLE64_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE64FF_V: u32 = 0x1007007;
+pub const MASK_VLE64FF_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE8_V: u32 = 0x7;
+pub const MASK_VLE8_V: u32 = 0xfdf0707f;
+pub const MATCH_VLE8FF_V: u32 = 0x1000007;
+pub const MASK_VLE8FF_V: u32 = 0xfdf0707f;
+pub const MATCH_VLM_V: u32 = 0x2b00007;
+pub const MASK_VLM_V: u32 = 0xfff0707f;
+pub const MATCH_VLOXEI16_V: u32 = 0xc005007;
+pub const MASK_VLOXEI16_V: u32 = 0xfc00707f;
+pub const MATCH_VLOXEI32_V: u32 = 0xc006007;
+pub const MASK_VLOXEI32_V: u32 = 0xfc00707f;
+pub const MATCH_VLOXEI64_V: u32 = 0xc007007;
+pub const MASK_VLOXEI64_V: u32 = 0xfc00707f;
+pub const MATCH_VLOXEI8_V: u32 = 0xc000007;
+pub const MASK_VLOXEI8_V: u32 = 0xfc00707f;
+pub const MATCH_VLSE16_V: u32 = 0x8005007;
+pub const MASK_VLSE16_V: u32 = 0xfc00707f;
+pub const MATCH_VLSE32_V: u32 = 0x8006007;
+pub const MASK_VLSE32_V: u32 = 0xfc00707f;
+pub const MATCH_VLSE64_V: u32 = 0x8007007;
+pub const MASK_VLSE64_V: u32 = 0xfc00707f;
+pub const MATCH_VLSE8_V: u32 = 0x8000007;
+pub const MASK_VLSE8_V: u32 = 0xfc00707f;
+pub const MATCH_VLUXEI16_V: u32 = 0x4005007;
+pub const MASK_VLUXEI16_V: u32 = 0xfc00707f;
+pub const MATCH_VLUXEI32_V: u32 = 0x4006007;
+pub const MASK_VLUXEI32_V: u32 = 0xfc00707f;
+pub const MATCH_VLUXEI64_V: u32 = 0x4007007;
+pub const MASK_VLUXEI64_V: u32 = 0xfc00707f;
+pub const MATCH_VLUXEI8_V: u32 = 0x4000007;
+pub const MASK_VLUXEI8_V: u32 = 0xfc00707f;
+pub const MATCH_VMACC_VV: u32 = 0xb4002057;
+pub const MASK_VMACC_VV: u32 = 0xfc00707f;
+pub const MATCH_VMACC_VX: u32 = 0xb4006057;
+pub const MASK_VMACC_VX: u32 = 0xfc00707f;
+pub const MATCH_VMADC_VI: u32 = 0x46003057;
+pub const MASK_VMADC_VI: u32 = 0xfe00707f;
+pub const MATCH_VMADC_VIM: u32 = 0x44003057;
+pub const MASK_VMADC_VIM: u32 = 0xfe00707f;
+pub const MATCH_VMADC_VV: u32 = 0x46000057;
+pub const MASK_VMADC_VV: u32 = 0xfe00707f;
+pub const MATCH_VMADC_VVM: u32 = 0x44000057;
+pub const MASK_VMADC_VVM: u32 = 0xfe00707f;
+pub const MATCH_VMADC_VX: u32 = 0x46004057;
+pub const MASK_VMADC_VX: u32 = 0xfe00707f;
+pub const MATCH_VMADC_VXM: u32 = 0x44004057;
+pub const MASK_VMADC_VXM: u32 = 0xfe00707f;
+pub const MATCH_VMADD_VV: u32 = 0xa4002057;
+pub const MASK_VMADD_VV: u32 = 0xfc00707f;
+pub const MATCH_VMADD_VX: u32 = 0xa4006057;
+pub const MASK_VMADD_VX: u32 = 0xfc00707f;
+pub const MATCH_VMAND_MM: u32 = 0x66002057;
+pub const MASK_VMAND_MM: u32 = 0xfe00707f;
+pub const MATCH_VMANDN_MM: u32 = 0x62002057;
+pub const MASK_VMANDN_MM: u32 = 0xfe00707f;
+pub const MATCH_VMAX_VV: u32 = 0x1c000057;
+pub const MASK_VMAX_VV: u32 = 0xfc00707f;
+pub const MATCH_VMAX_VX: u32 = 0x1c004057;
+pub const MASK_VMAX_VX: u32 = 0xfc00707f;
+pub const MATCH_VMAXU_VV: u32 = 0x18000057;
+pub const MASK_VMAXU_VV: u32 = 0xfc00707f;
+pub const MATCH_VMAXU_VX: u32 = 0x18004057;
+pub const MASK_VMAXU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMERGE_VIM: u32 = 0x5c003057;
+pub const MASK_VMERGE_VIM: u32 = 0xfe00707f;
+pub const MATCH_VMERGE_VVM: u32 = 0x5c000057;
+pub const MASK_VMERGE_VVM: u32 = 0xfe00707f;
+pub const MATCH_VMERGE_VXM: u32 = 0x5c004057;
+pub const MASK_VMERGE_VXM: u32 = 0xfe00707f;
+pub const MATCH_VMIN_VV: u32 = 0x14000057;
+pub const MASK_VMIN_VV: u32 = 0xfc00707f;
+pub const MATCH_VMIN_VX: u32 = 0x14004057;
+pub const MASK_VMIN_VX: u32 = 0xfc00707f;
+pub const MATCH_VMINU_VV: u32 = 0x10000057;
+pub const MASK_VMINU_VV: u32 = 0xfc00707f;
+pub const MATCH_VMINU_VX: u32 = 0x10004057;
+pub const MASK_VMINU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMNAND_MM: u32 = 0x76002057;
+pub const MASK_VMNAND_MM: u32 = 0xfe00707f;
+pub const MATCH_VMNOR_MM: u32 = 0x7a002057;
+pub const MASK_VMNOR_MM: u32 = 0xfe00707f;
+pub const MATCH_VMOR_MM: u32 = 0x6a002057;
+pub const MASK_VMOR_MM: u32 = 0xfe00707f;
+pub const MATCH_VMORN_MM: u32 = 0x72002057;
+pub const MASK_VMORN_MM: u32 = 0xfe00707f;
+pub const MATCH_VMSBC_VV: u32 = 0x4e000057;
+pub const MASK_VMSBC_VV: u32 = 0xfe00707f;
+pub const MATCH_VMSBC_VVM: u32 = 0x4c000057;
+pub const MASK_VMSBC_VVM: u32 = 0xfe00707f;
+pub const MATCH_VMSBC_VX: u32 = 0x4e004057;
+pub const MASK_VMSBC_VX: u32 = 0xfe00707f;
+pub const MATCH_VMSBC_VXM: u32 = 0x4c004057;
+pub const MASK_VMSBC_VXM: u32 = 0xfe00707f;
+pub const MATCH_VMSBF_M: u32 = 0x5000a057;
+pub const MASK_VMSBF_M: u32 = 0xfc0ff07f;
+pub const MATCH_VMSEQ_VI: u32 = 0x60003057;
+pub const MASK_VMSEQ_VI: u32 = 0xfc00707f;
+pub const MATCH_VMSEQ_VV: u32 = 0x60000057;
+pub const MASK_VMSEQ_VV: u32 = 0xfc00707f;
+pub const MATCH_VMSEQ_VX: u32 = 0x60004057;
+pub const MASK_VMSEQ_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSGT_VI: u32 = 0x7c003057;
+pub const MASK_VMSGT_VI: u32 = 0xfc00707f;
+pub const MATCH_VMSGT_VX: u32 = 0x7c004057;
+pub const MASK_VMSGT_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSGTU_VI: u32 = 0x78003057;
+pub const MASK_VMSGTU_VI: u32 = 0xfc00707f;
+pub const MATCH_VMSGTU_VX: u32 = 0x78004057;
+pub const MASK_VMSGTU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSIF_M: u32 = 0x5001a057;
+pub const MASK_VMSIF_M: u32 = 0xfc0ff07f;
+pub const MATCH_VMSLE_VI: u32 = 0x74003057;
+pub const MASK_VMSLE_VI: u32 = 0xfc00707f;
+pub const MATCH_VMSLE_VV: u32 = 0x74000057;
+pub const MASK_VMSLE_VV: u32 = 0xfc00707f;
+pub const MATCH_VMSLE_VX: u32 = 0x74004057;
+pub const MASK_VMSLE_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSLEU_VI: u32 = 0x70003057;
+pub const MASK_VMSLEU_VI: u32 = 0xfc00707f;
+pub const MATCH_VMSLEU_VV: u32 = 0x70000057;
+pub const MASK_VMSLEU_VV: u32 = 0xfc00707f;
+pub const MATCH_VMSLEU_VX: u32 = 0x70004057;
+pub const MASK_VMSLEU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSLT_VV: u32 = 0x6c000057;
+pub const MASK_VMSLT_VV: u32 = 0xfc00707f;
+pub const MATCH_VMSLT_VX: u32 = 0x6c004057;
+pub const MASK_VMSLT_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSLTU_VV: u32 = 0x68000057;
+pub const MASK_VMSLTU_VV: u32 = 0xfc00707f;
+pub const MATCH_VMSLTU_VX: u32 = 0x68004057;
+pub const MASK_VMSLTU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSNE_VI: u32 = 0x64003057;
+pub const MASK_VMSNE_VI: u32 = 0xfc00707f;
+pub const MATCH_VMSNE_VV: u32 = 0x64000057;
+pub const MASK_VMSNE_VV: u32 = 0xfc00707f;
+pub const MATCH_VMSNE_VX: u32 = 0x64004057;
+pub const MASK_VMSNE_VX: u32 = 0xfc00707f;
+pub const MATCH_VMSOF_M: u32 = 0x50012057;
+pub const MASK_VMSOF_M: u32 = 0xfc0ff07f;
+pub const MATCH_VMUL_VV: u32 = 0x94002057;
+pub const MASK_VMUL_VV: u32 = 0xfc00707f;
+pub const MATCH_VMUL_VX: u32 = 0x94006057;
+pub const MASK_VMUL_VX: u32 = 0xfc00707f;
+pub const MATCH_VMULH_VV: u32 = 0x9c002057;
+pub const MASK_VMULH_VV: u32 = 0xfc00707f;
+pub const MATCH_VMULH_VX: u32 = 0x9c006057;
+pub const MASK_VMULH_VX: u32 = 0xfc00707f;
+pub const MATCH_VMULHSU_VV: u32 = 0x98002057;
+pub const MASK_VMULHSU_VV: u32 = 0xfc00707f;
+pub const MATCH_VMULHSU_VX: u32 = 0x98006057;
+pub const MASK_VMULHSU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMULHU_VV: u32 = 0x90002057;
+pub const MASK_VMULHU_VV: u32 = 0xfc00707f;
+pub const MATCH_VMULHU_VX: u32 = 0x90006057;
+pub const MASK_VMULHU_VX: u32 = 0xfc00707f;
+pub const MATCH_VMV_S_X: u32 = 0x42006057;
+pub const MASK_VMV_S_X: u32 = 0xfff0707f;
+pub const MATCH_VMV_V_I: u32 = 0x5e003057;
+pub const MASK_VMV_V_I: u32 = 0xfff0707f;
+pub const MATCH_VMV_V_V: u32 = 0x5e000057;
+pub const MASK_VMV_V_V: u32 = 0xfff0707f;
+pub const MATCH_VMV_V_X: u32 = 0x5e004057;
+pub const MASK_VMV_V_X: u32 = 0xfff0707f;
+pub const MATCH_VMV_X_S: u32 = 0x42002057;
+pub const MASK_VMV_X_S: u32 = 0xfe0ff07f;
+pub const MATCH_VMV1R_V: u32 = 0x9e003057;
+pub const MASK_VMV1R_V: u32 = 0xfe0ff07f;
+pub const MATCH_VMV2R_V: u32 = 0x9e00b057;
+pub const MASK_VMV2R_V: u32 = 0xfe0ff07f;
+pub const MATCH_VMV4R_V: u32 = 0x9e01b057;
+pub const MASK_VMV4R_V: u32 = 0xfe0ff07f;
+pub const MATCH_VMV8R_V: u32 = 0x9e03b057;
+pub const MASK_VMV8R_V: u32 = 0xfe0ff07f;
+pub const MATCH_VMXNOR_MM: u32 = 0x7e002057;
+pub const MASK_VMXNOR_MM: u32 = 0xfe00707f;
+pub const MATCH_VMXOR_MM: u32 = 0x6e002057;
+pub const MASK_VMXOR_MM: u32 = 0xfe00707f;
+pub const MATCH_VNCLIP_WI: u32 = 0xbc003057;
+pub const MASK_VNCLIP_WI: u32 = 0xfc00707f;
+pub const MATCH_VNCLIP_WV: u32 = 0xbc000057;
+pub const MASK_VNCLIP_WV: u32 = 0xfc00707f;
+pub const MATCH_VNCLIP_WX: u32 = 0xbc004057;
+pub const MASK_VNCLIP_WX: u32 = 0xfc00707f;
+pub const MATCH_VNCLIPU_WI: u32 = 0xb8003057;
+pub const MASK_VNCLIPU_WI: u32 = 0xfc00707f;
+pub const MATCH_VNCLIPU_WV: u32 = 0xb8000057;
+pub const MASK_VNCLIPU_WV: u32 = 0xfc00707f;
+pub const MATCH_VNCLIPU_WX: u32 = 0xb8004057;
+pub const MASK_VNCLIPU_WX: u32 = 0xfc00707f;
+pub const MATCH_VNMSAC_VV: u32 = 0xbc002057;
+pub const MASK_VNMSAC_VV: u32 = 0xfc00707f;
+pub const MATCH_VNMSAC_VX: u32 = 0xbc006057;
+pub const MASK_VNMSAC_VX: u32 = 0xfc00707f;
+pub const MATCH_VNMSUB_VV: u32 = 0xac002057;
+pub const MASK_VNMSUB_VV: u32 = 0xfc00707f;
+pub const MATCH_VNMSUB_VX: u32 = 0xac006057;
+pub const MASK_VNMSUB_VX: u32 = 0xfc00707f;
+pub const MATCH_VNSRA_WI: u32 = 0xb4003057;
+pub const MASK_VNSRA_WI: u32 = 0xfc00707f;
+pub const MATCH_VNSRA_WV: u32 = 0xb4000057;
+pub const MASK_VNSRA_WV: u32 = 0xfc00707f;
+pub const MATCH_VNSRA_WX: u32 = 0xb4004057;
+pub const MASK_VNSRA_WX: u32 = 0xfc00707f;
+pub const MATCH_VNSRL_WI: u32 = 0xb0003057;
+pub const MASK_VNSRL_WI: u32 = 0xfc00707f;
+pub const MATCH_VNSRL_WV: u32 = 0xb0000057;
+pub const MASK_VNSRL_WV: u32 = 0xfc00707f;
+pub const MATCH_VNSRL_WX: u32 = 0xb0004057;
+pub const MASK_VNSRL_WX: u32 = 0xfc00707f;
+pub const MATCH_VOR_VI: u32 = 0x28003057;
+pub const MASK_VOR_VI: u32 = 0xfc00707f;
+pub const MATCH_VOR_VV: u32 = 0x28000057;
+pub const MASK_VOR_VV: u32 = 0xfc00707f;
+pub const MATCH_VOR_VX: u32 = 0x28004057;
+pub const MASK_VOR_VX: u32 = 0xfc00707f;
+pub const MATCH_VREDAND_VS: u32 = 0x4002057;
+pub const MASK_VREDAND_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDMAX_VS: u32 = 0x1c002057;
+pub const MASK_VREDMAX_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDMAXU_VS: u32 = 0x18002057;
+pub const MASK_VREDMAXU_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDMIN_VS: u32 = 0x14002057;
+pub const MASK_VREDMIN_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDMINU_VS: u32 = 0x10002057;
+pub const MASK_VREDMINU_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDOR_VS: u32 = 0x8002057;
+pub const MASK_VREDOR_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDSUM_VS: u32 = 0x2057;
+pub const MASK_VREDSUM_VS: u32 = 0xfc00707f;
+pub const MATCH_VREDXOR_VS: u32 = 0xc002057;
+pub const MASK_VREDXOR_VS: u32 = 0xfc00707f;
+pub const MATCH_VREM_VV: u32 = 0x8c002057;
+pub const MASK_VREM_VV: u32 = 0xfc00707f;
+pub const MATCH_VREM_VX: u32 = 0x8c006057;
+pub const MASK_VREM_VX: u32 = 0xfc00707f;
+pub const MATCH_VREMU_VV: u32 = 0x88002057;
+pub const MASK_VREMU_VV: u32 = 0xfc00707f;
+pub const MATCH_VREMU_VX: u32 = 0x88006057;
+pub const MASK_VREMU_VX: u32 = 0xfc00707f;
+pub const MATCH_VRGATHER_VI: u32 = 0x30003057;
+pub const MASK_VRGATHER_VI: u32 = 0xfc00707f;
+pub const MATCH_VRGATHER_VV: u32 = 0x30000057;
+pub const MASK_VRGATHER_VV: u32 = 0xfc00707f;
+pub const MATCH_VRGATHER_VX: u32 = 0x30004057;
+pub const MASK_VRGATHER_VX: u32 = 0xfc00707f;
+pub const MATCH_VRGATHEREI16_VV: u32 = 0x38000057;
+pub const MASK_VRGATHEREI16_VV: u32 = 0xfc00707f;
+pub const MATCH_VRSUB_VI: u32 = 0xc003057;
+pub const MASK_VRSUB_VI: u32 = 0xfc00707f;
+pub const MATCH_VRSUB_VX: u32 = 0xc004057;
+pub const MASK_VRSUB_VX: u32 = 0xfc00707f;
+pub const MATCH_VS1R_V: u32 = 0x2800027;
+pub const MASK_VS1R_V: u32 = 0xfff0707f;
+pub const MATCH_VSADD_VI: u32 = 0x84003057;
+pub const MASK_VSADD_VI: u32 = 0xfc00707f;
+pub const MATCH_VSADD_VV: u32 = 0x84000057;
+pub const MASK_VSADD_VV: u32 = 0xfc00707f;
+pub const MATCH_VSADD_VX: u32 = 0x84004057;
+pub const MASK_VSADD_VX: u32 = 0xfc00707f;
+pub const MATCH_VSADDU_VI: u32 = 0x80003057;
+pub const MASK_VSADDU_VI: u32 = 0xfc00707f;
+pub const MATCH_VSADDU_VV: u32 = 0x80000057;
+pub const MASK_VSADDU_VV: u32 = 0xfc00707f;
+pub const MATCH_VSADDU_VX: u32 = 0x80004057;
+pub const MASK_VSADDU_VX: u32 = 0xfc00707f;
+pub const MATCH_VSBC_VVM: u32 = 0x48000057;
+pub const MASK_VSBC_VVM: u32 = 0xfe00707f;
+pub const MATCH_VSBC_VXM: u32 = 0x48004057;
+pub const MASK_VSBC_VXM: u32 = 0xfe00707f;
+pub const MATCH_VSE16_V: u32 = 0x5027;
+pub const MASK_VSE16_V: u32 = 0xfdf0707f;
+pub const MATCH_VSE32_V: u32 = 0x6027;
+pub const MASK_VSE32_V: u32 = 0xfdf0707f;
+pub const MATCH_VSE64_V: u32 = 0x7027;
+pub const MASK_VSE64_V: u32 = 0xfdf0707f;
+pub const MATCH_VSE8_V: u32 = 0x27;
+pub const MASK_VSE8_V: u32 = 0xfdf0707f;
+pub const MATCH_VSETIVLI: u32 = 0xc0007057;
+pub const MASK_VSETIVLI: u32 = 0xc000707f;
+pub const MATCH_VSETVL: u32 = 0x80007057;
+pub const MASK_VSETVL: u32 = 0xfe00707f;
+pub const MATCH_VSETVLI: u32 = 0x7057;
+pub const MASK_VSETVLI: u32 = 0x8000707f;
+pub const MATCH_VSEXT_VF2: u32 = 0x4803a057;
+pub const MASK_VSEXT_VF2: u32 = 0xfc0ff07f;
+pub const MATCH_VSEXT_VF4: u32 = 0x4802a057;
+pub const MASK_VSEXT_VF4: u32 = 0xfc0ff07f;
+pub const MATCH_VSEXT_VF8: u32 = 0x4801a057;
+pub const MASK_VSEXT_VF8: u32 = 0xfc0ff07f;
+pub const MATCH_VSLIDE1DOWN_VX: u32 = 0x3c006057;
+pub const MASK_VSLIDE1DOWN_VX: u32 = 0xfc00707f;
+pub const MATCH_VSLIDE1UP_VX: u32 = 0x38006057;
+pub const MASK_VSLIDE1UP_VX: u32 = 0xfc00707f;
+pub const MATCH_VSLIDEDOWN_VI: u32 = 0x3c003057;
+pub const MASK_VSLIDEDOWN_VI: u32 = 0xfc00707f;
+pub const MATCH_VSLIDEDOWN_VX: u32 = 0x3c004057;
+pub const MASK_VSLIDEDOWN_VX: u32 = 0xfc00707f;
+pub const MATCH_VSLIDEUP_VI: u32 = 0x38003057;
+pub const MASK_VSLIDEUP_VI: u32 = 0xfc00707f;
+pub const MATCH_VSLIDEUP_VX: u32 = 0x38004057;
+pub const MASK_VSLIDEUP_VX: u32 = 0xfc00707f;
+pub const MATCH_VSLL_VI: u32 = 0x94003057;
+pub const MASK_VSLL_VI: u32 = 0xfc00707f;
+pub const MATCH_VSLL_VV: u32 = 0x94000057;
+pub const MASK_VSLL_VV: u32 = 0xfc00707f;
+pub const MATCH_VSLL_VX: u32 = 0x94004057;
+pub const MASK_VSLL_VX: u32 = 0xfc00707f;
+pub const MATCH_VSM_V: u32 = 0x2b00027;
+pub const MASK_VSM_V: u32 = 0xfff0707f;
+pub const MATCH_VSMUL_VV: u32 = 0x9c000057;
+pub const MASK_VSMUL_VV: u32 = 0xfc00707f;
+pub const MATCH_VSMUL_VX: u32 = 0x9c004057;
+pub const MASK_VSMUL_VX: u32 = 0xfc00707f;
+pub const MATCH_VSOXEI16_V: u32 = 0xc005027;
+pub const MASK_VSOXEI16_V: u32 = 0xfc00707f;
+pub const MATCH_VSOXEI32_V: u32 = 0xc006027;
+pub const MASK_VSOXEI32_V: u32 = 0xfc00707f;
+pub const MATCH_VSOXEI64_V: u32 = 0xc007027;
+pub const MASK_VSOXEI64_V: u32 = 0xfc00707f;
+pub const MATCH_VSOXEI8_V: u32 = 0xc000027;
+pub const MASK_VSOXEI8_V: u32 = 0xfc00707f;
+pub const MATCH_VSRA_VI: u32 = 0xa4003057;
+pub const MASK_VSRA_VI: u32 = 0xfc00707f;
+pub const MATCH_VSRA_VV: u32 = 0xa4000057;
+pub const MASK_VSRA_VV: u32 = 0xfc00707f;
+pub const MATCH_VSRA_VX: u32 = 0xa4004057;
+pub const MASK_VSRA_VX: u32 = 0xfc00707f;
+pub const MATCH_VSRL_VI: u32 = 0xa0003057;
+pub const MASK_VSRL_VI: u32 = 0xfc00707f;
+pub const MATCH_VSRL_VV: u32 = 0xa0000057;
+pub const MASK_VSRL_VV: u32 = 0xfc00707f;
+pub const MATCH_VSRL_VX: u32 = 0xa0004057;
+pub const MASK_VSRL_VX: u32 = 0xfc00707f;
+pub const MATCH_VSSE16_V: u32 = 0x8005027;
+pub const MASK_VSSE16_V: u32 = 0xfc00707f;
+pub const MATCH_VSSE32_V: u32 = 0x8006027;
+pub const MASK_VSSE32_V: u32 = 0xfc00707f;
+pub const MATCH_VSSE64_V: u32 = 0x8007027;
+pub const MASK_VSSE64_V: u32 = 0xfc00707f;
+pub const MATCH_VSSE8_V: u32 = 0x8000027;
+pub const MASK_VSSE8_V: u32 = 0xfc00707f;
+pub const MATCH_VSSRA_VI: u32 = 0xac003057;
+pub const MASK_VSSRA_VI: u32 = 0xfc00707f;
+pub const MATCH_VSSRA_VV: u32 = 0xac000057;
+pub const MASK_VSSRA_VV: u32 = 0xfc00707f;
+pub const MATCH_VSSRA_VX: u32 = 0xac004057;
+pub const MASK_VSSRA_VX: u32 = 0xfc00707f;
+pub const MATCH_VSSRL_VI: u32 = 0xa8003057;
+pub const MASK_VSSRL_VI: u32 = 0xfc00707f;
+pub const MATCH_VSSRL_VV: u32 = 0xa8000057;
+pub const MASK_VSSRL_VV: u32 = 0xfc00707f;
+pub const MATCH_VSSRL_VX: u32 = 0xa8004057;
+pub const MASK_VSSRL_VX: u32 = 0xfc00707f;
+pub const MATCH_VSSUB_VV: u32 = 0x8c000057;
+pub const MASK_VSSUB_VV: u32 = 0xfc00707f;
+pub const MATCH_VSSUB_VX: u32 = 0x8c004057;
+pub const MASK_VSSUB_VX: u32 = 0xfc00707f;
+pub const MATCH_VSSUBU_VV: u32 = 0x88000057;
+pub const MASK_VSSUBU_VV: u32 = 0xfc00707f;
+pub const MATCH_VSSUBU_VX: u32 = 0x88004057;
+pub const MASK_VSSUBU_VX: u32 = 0xfc00707f;
+pub const MATCH_VSUB_VV: u32 = 0x8000057;
+pub const MASK_VSUB_VV: u32 = 0xfc00707f;
+pub const MATCH_VSUB_VX: u32 = 0x8004057;
+pub const MASK_VSUB_VX: u32 = 0xfc00707f;
+pub const MATCH_VSUXEI16_V: u32 = 0x4005027;
+pub const MASK_VSUXEI16_V: u32 = 0xfc00707f;
+pub const MATCH_VSUXEI32_V: u32 = 0x4006027;
+pub const MASK_VSUXEI32_V: u32 = 0xfc00707f;
+pub const MATCH_VSUXEI64_V: u32 = 0x4007027;
+pub const MASK_VSUXEI64_V: u32 = 0xfc00707f;
+pub const MATCH_VSUXEI8_V: u32 = 0x4000027;
+pub const MASK_VSUXEI8_V: u32 = 0xfc00707f;
+pub const MATCH_VWADD_VV: u32 = 0xc4002057;
+pub const MASK_VWADD_VV: u32 = 0xfc00707f;
+pub const MATCH_VWADD_VX: u32 = 0xc4006057;
+pub const MASK_VWADD_VX: u32 = 0xfc00707f;
+pub const MATCH_VWADD_WV: u32 = 0xd4002057;
+pub const MASK_VWADD_WV: u32 = 0xfc00707f;
+pub const MATCH_VWADD_WX: u32 = 0xd4006057;
+pub const MASK_VWADD_WX: u32 = 0xfc00707f;
+pub const MATCH_VWADDU_VV: u32 = 0xc0002057;
+pub const MASK_VWADDU_VV: u32 = 0xfc00707f;
+pub const MATCH_VWADDU_VX: u32 = 0xc0006057;
+pub const MASK_VWADDU_VX: u32 = 0xfc00707f;
+pub const MATCH_VWADDU_WV: u32 = 0xd0002057;
+pub const MASK_VWADDU_WV: u32 = 0xfc00707f;
+pub const MATCH_VWADDU_WX: u32 = 0xd0006057;
+pub const MASK_VWADDU_WX: u32 = 0xfc00707f;
+pub const MATCH_VWMACC_VV: u32 = 0xf4002057;
+pub const MASK_VWMACC_VV: u32 = 0xfc00707f;
+pub const MATCH_VWMACC_VX: u32 = 0xf4006057;
+pub const MASK_VWMACC_VX: u32 = 0xfc00707f;
+pub const MATCH_VWMACCSU_VV: u32 = 0xfc002057;
+pub const MASK_VWMACCSU_VV: u32 = 0xfc00707f;
+pub const MATCH_VWMACCSU_VX: u32 = 0xfc006057;
+pub const MASK_VWMACCSU_VX: u32 = 0xfc00707f;
+pub const MATCH_VWMACCU_VV: u32 = 0xf0002057;
+pub const MASK_VWMACCU_VV: u32 = 0xfc00707f;
+pub const MATCH_VWMACCU_VX: u32 = 0xf0006057;
+pub const MASK_VWMACCU_VX: u32 = 0xfc00707f;
+pub const MATCH_VWMACCUS_VX: u32 = 0xf8006057;
+pub const MASK_VWMACCUS_VX: u32 = 0xfc00707f;
+pub const MATCH_VWMUL_VV: u32 = 0xec002057;
+pub const MASK_VWMUL_VV: u32 = 0xfc00707f;
+pub const MATCH_VWMUL_VX: u32 = 0xec006057;
+pub const MASK_VWMUL_VX: u32 = 0xfc00707f;
+pub const MATCH_VWMULSU_VV: u32 = 0xe8002057;
+pub const MASK_VWMULSU_VV: u32 = 0xfc00707f;
+pub const MATCH_VWMULSU_VX: u32 = 0xe8006057;
+pub const MASK_VWMULSU_VX: u32 = 0xfc00707f;
+pub const MATCH_VWMULU_VV: u32 = 0xe0002057;
+pub const MASK_VWMULU_VV: u32 = 0xfc00707f;
+pub const MATCH_VWMULU_VX: u32 = 0xe0006057;
+pub const MASK_VWMULU_VX: u32 = 0xfc00707f;
+pub const MATCH_VWREDSUM_VS: u32 = 0xc4000057;
+pub const MASK_VWREDSUM_VS: u32 = 0xfc00707f;
+pub const MATCH_VWREDSUMU_VS: u32 = 0xc0000057;
+pub const MASK_VWREDSUMU_VS: u32 = 0xfc00707f;
+pub const MATCH_VWSUB_VV: u32 = 0xcc002057;
+pub const MASK_VWSUB_VV: u32 = 0xfc00707f;
+pub const MATCH_VWSUB_VX: u32 = 0xcc006057;
+pub const MASK_VWSUB_VX: u32 = 0xfc00707f;
+pub const MATCH_VWSUB_WV: u32 = 0xdc002057;
+pub const MASK_VWSUB_WV: u32 = 0xfc00707f;
+pub const MATCH_VWSUB_WX: u32 = 0xdc006057;
+pub const MASK_VWSUB_WX: u32 = 0xfc00707f;
+pub const MATCH_VWSUBU_VV: u32 = 0xc8002057;
+pub const MASK_VWSUBU_VV: u32 = 0xfc00707f;
+pub const MATCH_VWSUBU_VX: u32 = 0xc8006057;
+pub const MASK_VWSUBU_VX: u32 = 0xfc00707f;
+pub const MATCH_VWSUBU_WV: u32 = 0xd8002057;
+pub const MASK_VWSUBU_WV: u32 = 0xfc00707f;
+pub const MATCH_VWSUBU_WX: u32 = 0xd8006057;
+pub const MASK_VWSUBU_WX: u32 = 0xfc00707f;
+pub const MATCH_VXOR_VI: u32 = 0x2c003057;
+pub const MASK_VXOR_VI: u32 = 0xfc00707f;
+pub const MATCH_VXOR_VV: u32 = 0x2c000057;
+pub const MASK_VXOR_VV: u32 = 0xfc00707f;
+pub const MATCH_VXOR_VX: u32 = 0x2c004057;
+pub const MASK_VXOR_VX: u32 = 0xfc00707f;
+pub const MATCH_VZEXT_VF2: u32 = 0x48032057;
+pub const MASK_VZEXT_VF2: u32 = 0xfc0ff07f;
+pub const MATCH_VZEXT_VF4: u32 = 0x48022057;
+pub const MASK_VZEXT_VF4: u32 = 0xfc0ff07f;
+pub const MATCH_VZEXT_VF8: u32 = 0x48012057;
+pub const MASK_VZEXT_VF8: u32 = 0xfc0ff07f;
 pub const MATCH_WFI: u32 = 0x10500073;
 pub const MASK_WFI: u32 = 0xffffffff;
+pub const MATCH_WRS_NTO: u32 = 0xd00073;
+pub const MASK_WRS_NTO: u32 = 0xffffffff;
+pub const MATCH_WRS_STO: u32 = 0x1d00073;
+pub const MASK_WRS_STO: u32 = 0xffffffff;
+pub const MATCH_XNOR: u32 = 0x40004033;
+pub const MASK_XNOR: u32 = 0xfe00707f;
 pub const MATCH_XOR: u32 = 0x4033;
 pub const MASK_XOR: u32 = 0xfe00707f;
 pub const MATCH_XORI: u32 = 0x4013;
 pub const MASK_XORI: u32 = 0x707f;
+pub const MATCH_XPERM4: u32 = 0x28002033;
+pub const MASK_XPERM4: u32 = 0xfe00707f;
+pub const MATCH_XPERM8: u32 = 0x28004033;
+pub const MASK_XPERM8: u32 = 0xfe00707f;
+pub const MATCH_ZEXT_H: u32 = 0x800403b;
+pub const MASK_ZEXT_H: u32 = 0xfff0707f;
 pub const CSR_FFLAGS: u16 = 0x1;
 pub const CSR_FRM: u16 = 0x2;
 pub const CSR_FCSR: u16 = 0x3;
@@ -749,27 +1693,24 @@ pub struct Instruction {
     pub mask: u32,
     pub match_data: u32,
     pub name: &'static str,
-    pub operation: fn(cpu: &mut CpuCore, word: u32, address: u64) -> Result<(), TrapType>,
+    pub operation:
+        fn(cpu: &mut CpuCore, word: u32, address: u64, ops: &Operands) -> Result<(), TrapType>,
 }
 
 impl Instruction {
     /**
      * Compare two instructions for sorting.
-     * The comparison is done by comparing the match_data first, then the mask.
-     * This is done to ensure that the most specific match is found first.
-     * The mask is used to differentiate between instructions with the same match_data.
-     * Because some instructions have the same match_data, but different masks.
-     * The priority is given to the instruction (same match_data) with the biggest mask.
-     *
+     * The instruction with more fixed bits in its mask sorts first, so an encoding
+     * carved out of a wider one (c.nop from c.addi, cm.push from c.fsdsp) is found
+     * before it. Instructions as specific as each other are ordered by match_data,
+     * then by mask, to keep the order stable.
      */
     pub fn inst_cmp(lhs: &Instruction, rhs: &Instruction) -> Ordering {
-        match (lhs.match_data.cmp(&rhs.match_data), lhs.mask.cmp(&rhs.mask)) {
-            (Ordering::Less, _) => Ordering::Greater,
-            (Ordering::Greater, _) => Ordering::Less,
-            (Ordering::Equal, Ordering::Equal) => Ordering::Equal,
-            (Ordering::Equal, Ordering::Greater) => Ordering::Less,
-            (Ordering::Equal, Ordering::Less) => Ordering::Greater,
-        }
+        rhs.mask
+            .count_ones()
+            .cmp(&lhs.mask.count_ones())
+            .then(rhs.match_data.cmp(&lhs.match_data))
+            .then(rhs.mask.cmp(&lhs.mask))
     }
 }
 
@@ -829,6 +1770,13 @@ pub struct FormatR {
     pub rs1: u64,
     pub rs2: u64,
 }
+pub struct FormatR4 {
+    pub rd: u64,
+    pub rs1: u64,
+    pub rs2: u64,
+    pub rs3: u64,
+    pub rm: u8,
+}
 pub struct FormatS {
     pub rs1: u64,
     pub rs2: u64,
@@ -896,6 +1844,16 @@ pub fn parse_format_r(word: u32) -> FormatR {
     }
 }
 
+pub fn parse_format_r4(word: u32) -> FormatR4 {
+    FormatR4 {
+        rd: ((word >> 7) & 0x1f) as u64,   // [11:7]
+        rs1: ((word >> 15) & 0x1f) as u64, // [19:15]
+        rs2: ((word >> 20) & 0x1f) as u64, // [24:20]
+        rs3: ((word >> 27) & 0x1f) as u64, // [31:27]
+        rm: ((word >> 12) & 0x7) as u8,    // [14:12]
+    }
+}
+
 pub fn parse_format_s(word: u32) -> FormatS {
     FormatS {
         rs1: ((word >> 15) & 0x1f) as u64, // [19:15]
@@ -926,6 +1884,152 @@ pub fn parse_format_u(word: u32) -> FormatU {
     }
 }
 
+// The operand fields of an instruction, decoded once along with its handler.
+// The immediate follows the format of the major opcode and is zero for the others,
+// the fused multiply-adds carry rs3 in it. A compressed instruction gets the fields
+// of its format, with the 3-bit registers mapped to x8-x15, and the immediate of
+// the instruction its quadrant and funct3 select. Zcmp/Zcmt decode their own fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Operands {
+    pub rd: u64,
+    pub rs1: u64,
+    pub rs2: u64,
+    pub imm: u64,
+}
+
+impl Operands {
+    pub fn decode(word: u32, rv32: bool) -> Self {
+        if is_compressed_instruction(word) {
+            return Operands::decode_compressed(word, rv32);
+        }
+        let imm = match word & 0x7f {
+            // LUI, AUIPC
+            0x37 | 0x17 => parse_format_u(word).imm,
+            // JAL
+            0x6f => parse_format_j(word).imm,
+            // BRANCH
+            0x63 => parse_format_b(word).imm,
+            // STORE, STORE-FP
+            0x23 | 0x27 => parse_format_s(word).imm as u64,
+            // LOAD, LOAD-FP, MISC-MEM, OP-IMM, OP-IMM-32, JALR, SYSTEM
+            0x03 | 0x07 | 0x0f | 0x13 | 0x1b | 0x67 | 0x73 => parse_format_i(word).imm as u64,
+            // FMADD, FMSUB, FNMSUB, FNMADD
+            0x43 | 0x47 | 0x4b | 0x4f => parse_format_r4(word).rs3,
+            _ => 0,
+        };
+        let f = parse_format_r(word);
+        Operands {
+            rd: f.rd,
+            rs1: f.rs1,
+            rs2: f.rs2,
+            imm,
+        }
+    }
+
+    // quadrant 0 and 2 funct3 0b011/0b111 are c.flw/c.fsw/c.flwsp/c.fswsp on RV32
+    // and c.ld/c.sd/c.ldsp/c.sdsp on RV64, q1 funct3 0b001 is c.jal or c.addiw
+    fn decode_compressed(word: u32, rv32: bool) -> Self {
+        let ops = |rd, rs1, rs2, imm: i64| Operands {
+            rd,
+            rs1,
+            rs2,
+            imm: imm as u64,
+        };
+        let r = ((word >> 7) & 0x1f) as u64; // [11:7]
+        let r2 = ((word >> 2) & 0x1f) as u64; // [6:2]
+        let rc = ((word >> 7) & 0b111) as u64 + 8; // [9:7]
+        let rc2 = ((word >> 2) & 0b111) as u64 + 8; // [4:2]
+        match (word & 0b11, (word >> 13) & 0b111) {
+            // c.addi4spn
+            (0, 0) => ops(rc2, 2, 0, FormatCIW::new(word).imm_c_addi4spn() as i64),
+            (0, 1) => ops(rc2, rc, 0, FormatCL::new(word).imm_c_fld() as i64),
+            (0, 2) => ops(rc2, rc, 0, FormatCL::new(word).imm_c_lw() as i64),
+            (0, 3) if rv32 => ops(rc2, rc, 0, FormatCL::new(word).imm_c_flw() as i64),
+            (0, 3) => ops(rc2, rc, 0, FormatCL::new(word).imm_c_ld() as i64),
+            // Zcb c.lbu, c.lhu/c.lh, c.sb, c.sh
+            (0, 4) => match (word >> 10) & 0b11 {
+                0 => ops(rc2, rc, 0, FormatCL::new(word).imm_c_lbu() as i64),
+                1 => ops(rc2, rc, 0, FormatCL::new(word).imm_c_lhu() as i64),
+                2 => ops(0, rc, rc2, FormatCS::new(word).imm_c_sb() as i64),
+                _ => ops(0, rc, rc2, FormatCS::new(word).imm_c_sh() as i64),
+            },
+            (0, 5) => ops(0, rc, rc2, FormatCS::new(word).imm_c_fsd() as i64),
+            (0, 6) => ops(0, rc, rc2, FormatCS::new(word).imm_c_sw() as i64),
+            (0, 7) if rv32 => ops(0, rc, rc2, FormatCS::new(word).imm_c_fsw() as i64),
+            (0, 7) => ops(0, rc, rc2, FormatCS::new(word).imm_c_sd() as i64),
+            // c.addi, c.nop
+            (1, 0) => ops(r, r, 0, FormatCI::new(word).imm_c_addi() as i64),
+            (1, 1) if rv32 => ops(1, 0, 0, FormatCJ::new(word).imm_c_jal() as i64),
+            (1, 1) => ops(r, r, 0, FormatCI::new(word).imm_c_addiw() as i64),
+            (1, 2) => ops(r, 0, 0, FormatCI::new(word).imm_c_li() as i64),
+            (1, 3) if r == 2 => ops(2, 2, 0, FormatCI::new(word).imm_c_addi16sp() as i64),
+            (1, 3) => ops(r, 0, 0, FormatCI::new(word).imm_c_lui() as i64),
+            // c.srli/c.srai, c.andi, then the CA-format arithmetic and Zcb unary ops
+            (1, 4) => match (word >> 10) & 0b11 {
+                0 | 1 => ops(rc, rc, 0, FormatCB::new(word).imm_c_srli() as i64),
+                2 => ops(rc, rc, 0, FormatCB::new(word).imm_c_andi() as i64),
+                _ => ops(rc, rc, rc2, 0),
+            },
+            (1, 5) => ops(0, 0, 0, FormatCJ::new(word).imm_c_j() as i64),
+            // c.beqz, c.bnez
+            (1, _) => ops(0, rc, 0, FormatCB::new(word).imm_c_beqz() as i64),
+            (2, 0) => ops(r, r, 0, FormatCI::new(word).imm_c_slli() as i64),
+            (2, 1) => ops(r, 2, 0, FormatCI::new(word).imm_c_fldsp() as i64),
+            (2, 2) => ops(r, 2, 0, FormatCI::new(word).imm_c_lwsp() as i64),
+            (2, 3) if rv32 => ops(r, 2, 0, FormatCI::new(word).imm_c_flwsp() as i64),
+            (2, 3) => ops(r, 2, 0, FormatCI::new(word).imm_c_ldsp() as i64),
+            // c.jr, c.mv, c.ebreak, c.jalr, c.add
+            (2, 4) => ops(r, r, r2, 0),
+            (2, 5) => ops(0, 2, r2, FormatCSS::new(word).imm_c_fsdsp() as i64),
+            (2, 6) => ops(0, 2, r2, FormatCSS::new(word).imm_c_swsp() as i64),
+            (2, 7) if rv32 => ops(0, 2, r2, FormatCSS::new(word).imm_c_fswsp() as i64),
+            (2, 7) => ops(0, 2, r2, FormatCSS::new(word).imm_c_sdsp() as i64),
+            _ => Operands::default(),
+        }
+    }
+}
+
+#[test]
+fn operands_test() {
+    let ops = |rd, rs1, rs2, imm| Operands { rd, rs1, rs2, imm };
+    let decode = |word| Operands::decode(word, false);
+    // addi a0, a1, -1
+    assert_eq!(decode(0xfff5_8513), ops(10, 11, 31, u64::MAX));
+    // sd a1, -8(sp)
+    assert_eq!(decode(0xfeb1_3c23), ops(24, 2, 11, -8_i64 as u64));
+    // lui a0, 0x12345
+    assert_eq!(decode(0x1234_5537), ops(10, 8, 3, 0x1234_5000));
+    // jal ra, 8
+    assert_eq!(decode(0x0080_00ef), ops(1, 0, 8, 8));
+    // add a0, a1, a2
+    assert_eq!(decode(0x00c5_8533), ops(10, 11, 12, 0));
+    // fmadd.s fa0, fa1, fa2, fa3
+    assert_eq!(decode(0x68c5_f543), ops(10, 11, 12, 13));
+}
+
+#[test]
+fn operands_compressed_test() {
+    let ops = |rd, rs1, rs2, imm| Operands { rd, rs1, rs2, imm };
+    // c.addi ra, 1
+    assert_eq!(Operands::decode(0x0085, false), ops(1, 1, 0, 1));
+    // c.addi4spn a0, sp, 8
+    assert_eq!(Operands::decode(0x0028, false), ops(10, 2, 0, 8));
+    // c.lw a0, 4(a1)
+    assert_eq!(Operands::decode(0x41c8, false), ops(10, 11, 0, 4));
+    // c.ld a0, 128(a1) on RV64, c.flw fa0, 4(a1) on RV32
+    assert_eq!(Operands::decode(0x61c8, false), ops(10, 11, 0, 128));
+    assert_eq!(Operands::decode(0x61c8, true), ops(10, 11, 0, 4));
+    // c.addiw zero, 8 on RV64, c.jal 8 on RV32
+    assert_eq!(Operands::decode(0x2021, false), ops(0, 0, 0, 8));
+    assert_eq!(Operands::decode(0x2021, true), ops(1, 0, 0, 8));
+    // c.sub s0, s1
+    assert_eq!(Operands::decode(0x8c05, false), ops(8, 8, 9, 0));
+    // c.add a0, a1
+    assert_eq!(Operands::decode(0x952e, false), ops(10, 10, 11, 0));
+    // c.sdsp ra, 8(sp)
+    assert_eq!(Operands::decode(0xe406, false), ops(0, 2, 1, 8));
+}
+
 pub struct FormatCSR {
     pub csr: u64,
     pub rs1: u64,
@@ -940,6 +2044,31 @@ pub fn parse_format_csr(word: u32) -> FormatCSR {
     }
 }
 
+// vector arithmetic and memory instructions
+pub struct FormatV {
+    pub vd: usize,
+    pub vs1: usize, // also rs1 and imm[4:0]
+    pub vs2: usize, // also rs2 and lumop/sumop
+    pub vm: bool,
+    pub nf: usize,
+}
+
+impl FormatV {
+    pub fn simm5(&self) -> i64 {
+        sign_extended(self.vs1 as isize, 5) as i64
+    }
+}
+
+pub fn parse_format_v(word: u32) -> FormatV {
+    FormatV {
+        vd: ((word >> 7) & 0x1f) as usize,  // [11:7]
+        vs1: ((word >> 15) & 0x1f) as usize, // [19:15]
+        vs2: ((word >> 20) & 0x1f) as usize, // [24:20]
+        vm: (word >> 25) & 0x1 != 0,         // [25]
+        nf: ((word >> 29) & 0x7) as usize,   // [31:29]
+    }
+}
+
 pub struct FormatCR {
     rd_rs1: u64,
     rs2: u64,
@@ -1172,6 +2301,18 @@ impl FormatCL {
     pub fn imm_c_fld(&self) -> usize {
         self.imm_c_ld()
     }
+
+    pub fn imm_c_lbu(&self) -> usize {
+        let offset1 = (self.imm5_6 & 0b1) as usize;
+        let offset0 = ((self.imm5_6 >> 1) & 0b1) as usize;
+        (offset1 << 1) | offset0
+    }
+    pub fn imm_c_lhu(&self) -> usize {
+        ((self.imm5_6 & 0b1) as usize) << 1
+    }
+    pub fn imm_c_lh(&self) -> usize {
+        self.imm_c_lhu()
+    }
 }
 
 pub struct FormatCS {
@@ -1225,6 +2366,15 @@ impl FormatCS {
     pub fn imm_c_fsd(&self) -> usize {
         self.imm_c_sd()
     }
+
+    pub fn imm_c_sb(&self) -> usize {
+        let offset1 = self.imm5_6 & 0b1;
+        let offset0 = (self.imm5_6 >> 1) & 0b1;
+        (offset1 << 1) | offset0
+    }
+    pub fn imm_c_sh(&self) -> usize {
+        (self.imm5_6 & 0b1) << 1
+    }
 }
 
 pub struct FormatCA {
@@ -1362,6 +2512,141 @@ fn c_j_test() {
     assert_eq!(f.imm_c_j(), 2046);
 }
 
+#[test]
+fn c_fp_load_store_imm_test() {
+    // c.fld fa0, 248(a1)
+    let f = FormatCL::new(0x3de8);
+    assert_eq!((f.rd(), f.rs1(), f.imm_c_fld()), (10, 11, 248));
+    // c.fld fs0, 168(a5)
+    let f = FormatCL::new(0x37c0);
+    assert_eq!((f.rd(), f.rs1(), f.imm_c_fld()), (8, 15, 168));
+
+    // c.fsd fa5, 248(a0)
+    let f = FormatCS::new(0xbd7c);
+    assert_eq!((f.rs2(), f.rs1(), f.imm_c_fsd()), (15, 10, 248));
+    // c.fsd fs1, 80(s0)
+    let f = FormatCS::new(0xa824);
+    assert_eq!((f.rs2(), f.rs1(), f.imm_c_fsd()), (9, 8, 80));
+
+    // c.fldsp fa0, 504(sp)
+    let f = FormatCI::new(0x357e);
+    assert_eq!((f.rd(), f.imm_c_fldsp()), (10, 504));
+    // c.fldsp ft1, 328(sp)
+    let f = FormatCI::new(0x20b6);
+    assert_eq!((f.rd(), f.imm_c_fldsp()), (1, 328));
+
+    // c.fsdsp fa0, 504(sp)
+    let f = FormatCSS::new(0xbfaa);
+    assert_eq!((f.rs2(), f.imm_c_fsdsp()), (10, 504));
+    // c.fsdsp ft11, 72(sp)
+    let f = FormatCSS::new(0xa4fe);
+    assert_eq!((f.rs2(), f.imm_c_fsdsp()), (31, 72));
+}
+
+// Zcmp cm.push/cm.pop/cm.popret/cm.popretz
+pub struct FormatCMPP {
+    rlist: usize,
+    spimm: usize,
+}
+
+impl FormatCMPP {
+    pub fn new(word: u32) -> FormatCMPP {
+        FormatCMPP {
+            rlist: ((word >> 4) & 0b1111) as usize, // [7:4]
+            spimm: ((word >> 2) & 0b11) as usize,   // [3:2]
+        }
+    }
+
+    // rlist 0~3 are reserved
+    pub fn is_reserved(&self) -> bool {
+        self.rlist < 4
+    }
+
+    // {ra}, {ra, s0}, {ra, s0-s1} ... {ra, s0-s9}, then {ra, s0-s11}: s10 is never alone
+    pub fn reg_list(&self) -> impl Iterator<Item = u64> {
+        let sregs = match self.rlist {
+            15 => 12,
+            rlist => rlist - 4,
+        };
+        core::iter::once(1).chain((0..sregs).map(FormatCMMV::sreg))
+    }
+
+    pub fn stack_adj(&self) -> u64 {
+        let bytes = self.reg_list().count() as u64 * 8;
+        let stack_adj_base = (bytes + 15) & !15;
+        stack_adj_base + self.spimm as u64 * 16
+    }
+}
+
+// Zcmp cm.mvsa01/cm.mva01s, the two s-registers r1s' and r2s'
+pub struct FormatCMMV {
+    r1s: usize,
+    r2s: usize,
+}
+
+impl FormatCMMV {
+    pub fn new(word: u32) -> FormatCMMV {
+        FormatCMMV {
+            r1s: ((word >> 7) & 0b111) as usize, // [9:7]
+            r2s: ((word >> 2) & 0b111) as usize, // [4:2]
+        }
+    }
+
+    // s0~s1 are x8~x9, s2~s11 are x18~x27
+    pub fn sreg(index: usize) -> u64 {
+        match index {
+            0 | 1 => 8 + index as u64,
+            _ => 16 + index as u64,
+        }
+    }
+
+    pub fn r1s(&self) -> u64 {
+        Self::sreg(self.r1s)
+    }
+
+    pub fn r2s(&self) -> u64 {
+        Self::sreg(self.r2s)
+    }
+}
+
+// Zcmt cm.jt/cm.jalt, the index into the jvt jump table
+pub struct FormatCMJT {
+    index: usize,
+}
+
+impl FormatCMJT {
+    pub fn new(word: u32) -> FormatCMJT {
+        FormatCMJT {
+            index: ((word >> 2) & 0xff) as usize, // [9:2]
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // index 32~255 are cm.jalt, which also links ra
+    pub fn is_jalt(&self) -> bool {
+        self.index >= 32
+    }
+}
+
+#[test]
+fn cm_push_pop_test() {
+    // cm.push {ra, s0-s11}, -112
+    let f = FormatCMPP::new(0xb8f2);
+    assert!(f
+        .reg_list()
+        .eq([1, 8, 9, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27]));
+    assert_eq!(f.stack_adj(), 112);
+
+    // cm.pop {ra, s0-s2}, 32 + 3 * 16
+    let f = FormatCMPP::new(0xba7e);
+    assert_eq!(f.reg_list().count(), 4);
+    assert_eq!(f.stack_adj(), 80);
+    assert!(FormatCMPP::new(0xba32).is_reserved());
+}
+
 #[derive(Debug)]
 pub enum RVerr {
     AddrMisalign,
@@ -1410,6 +2695,15 @@ impl AccessType {
     pub fn is_fetch(&self) -> bool {
         self == &AccessType::Fetch(0)
     }
+    // the same access type with another tval
+    pub fn with_tval(&self, tval: u64) -> AccessType {
+        match self {
+            AccessType::Fetch(_) => AccessType::Fetch(tval),
+            AccessType::Load(_) => AccessType::Load(tval),
+            AccessType::Store(_) => AccessType::Store(tval),
+            AccessType::Amo(_) => AccessType::Amo(tval),
+        }
+    }
 
     pub fn throw_page_exception(&self) -> TrapType {
         match self {
@@ -1420,6 +2714,17 @@ impl AccessType {
         }
     }
 
+    // a fault of the G-stage translation, gpa is the guest physical address
+    pub fn throw_guest_page_exception(&self, gpa: u64) -> TrapType {
+        match self {
+            AccessType::Fetch(tval) => TrapType::InstructionGuestPageFault(*tval, gpa),
+            AccessType::Load(tval) => TrapType::LoadGuestPageFault(*tval, gpa),
+            AccessType::Store(tval) | AccessType::Amo(tval) => {
+                TrapType::StoreGuestPageFault(*tval, gpa)
+            }
+        }
+    }
+
     pub fn throw_access_exception(&self) -> TrapType {
         match self {
             AccessType::Fetch(tval) => TrapType::InstructionAccessFault(*tval),
@@ -1510,3 +2815,11 @@ impl FesvrCmd {
 pub fn is_compressed_instruction(inst: u32) -> bool {
     inst & 0b11 != 0b11
 }
+
+// the conditional branches: BEQ ~ BGEU, C.BEQZ and C.BNEZ
+pub fn is_branch(inst: u32) -> bool {
+    match is_compressed_instruction(inst) {
+        true => inst & 0b11 == 0b01 && (inst >> 13) & 0b110 == 0b110,
+        false => inst & 0x7f == 0b110_0011,
+    }
+}
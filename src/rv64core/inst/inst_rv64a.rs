@@ -1,24 +1,79 @@
-use crate::rv64core::inst::inst_base::*;
+use core::cell::{Cell, RefCell};
 
+use alloc::vec::Vec;
+
+use crate::{
+    rv64core::{cpu_core::CpuCore, inst::inst_base::*, traptype::TrapType},
+    tools::check_aligned,
+};
+
+// A reservation set is the cache line of the reserved address.
+const LR_SC_LINE_SIZE: u64 = 64;
+const NO_RESERVATION: u64 = u64::MAX;
+// the longest constrained LR/SC loop, which is guaranteed to make progress
+pub const LR_SC_WINDOW: usize = 16;
+
+// The LR/SC reservations of all harts, shared through the bus. A store or AMO
+// from any hart to a reserved line breaks the reservation.
 pub struct LrScReservation {
-    pub val: u64,
+    lines: RefCell<Vec<u64>>,
+    // the number of harts holding a reservation, to keep stores cheap
+    live: Cell<usize>,
 }
 
 impl LrScReservation {
     pub fn new() -> Self {
-        LrScReservation { val: u64::MAX }
+        LrScReservation {
+            lines: RefCell::new(Vec::new()),
+            live: Cell::new(0),
+        }
+    }
+
+    // returns the slot of the new hart
+    pub fn add_hart(&self) -> usize {
+        let mut lines = self.lines.borrow_mut();
+        lines.push(NO_RESERVATION);
+        lines.len() - 1
     }
 
-    pub fn check_and_clear(&mut self, addr: u64) -> bool {
-        let ret = self.val == addr;
-        self.clear();
+    pub fn set(&self, slot: usize, paddr: u64) {
+        self.clear(slot);
+        self.lines.borrow_mut()[slot] = paddr & !(LR_SC_LINE_SIZE - 1);
+        self.live.set(self.live.get() + 1);
+    }
+
+    pub fn check_and_clear(&self, slot: usize, paddr: u64) -> bool {
+        let ret = self.lines.borrow()[slot] == paddr & !(LR_SC_LINE_SIZE - 1);
+        self.clear(slot);
         ret
     }
-    pub fn set(&mut self, addr: u64) {
-        self.val = addr
+
+    pub fn clear(&self, slot: usize) {
+        let mut lines = self.lines.borrow_mut();
+        if lines[slot] != NO_RESERVATION {
+            lines[slot] = NO_RESERVATION;
+            self.live.set(self.live.get() - 1);
+        }
+    }
+
+    pub fn is_reserved(&self, slot: usize) -> bool {
+        self.lines.borrow()[slot] != NO_RESERVATION
     }
-    pub fn clear(&mut self) {
-        self.val = u64::MAX
+
+    // a store of len bytes at paddr
+    pub fn invalidate(&self, paddr: u64, len: usize) {
+        if self.live.get() == 0 {
+            return;
+        }
+        let first = paddr & !(LR_SC_LINE_SIZE - 1);
+        let last = (paddr + len as u64 - 1) & !(LR_SC_LINE_SIZE - 1);
+        let harts = self.lines.borrow().len();
+        for slot in 0..harts {
+            let line = self.lines.borrow()[slot];
+            if line == first || line == last {
+                self.clear(slot);
+            }
+        }
     }
 }
 
@@ -34,35 +89,10 @@ pub const INSTRUCTIONS_A: &[Instruction] = &[
         mask: MASK_LR_W,
         match_data: MATCH_LR_W,
         name: "LR_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let r_data = match cpu.read(rs1_data, 4, AccessType::Load(rs1_data)) {
-                Ok(data) => data as i32 as i64,
-                Err(trap_type) => return Err(trap_type),
-            };
-
-            cpu.lr_sc_reservation_set(rs1_data);
-            cpu.gpr.write(f.rd, r_data as u64);
-
-            Ok(())
-        },
-    },
-    Instruction {
-        mask: MASK_LR_D,
-        match_data: MATCH_LR_D,
-        name: "LR_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let r_data = match cpu.read(rs1_data, 8, AccessType::Load(rs1_data)) {
-                Ok(data) => data,
-                Err(trap_type) => return Err(trap_type),
-            };
-
-            cpu.lr_sc_reservation_set(rs1_data);
-
-            cpu.gpr.write(f.rd, r_data);
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let r_data = cpu.load_reserved(rs1_data, 4)? as i32 as i64;
+            cpu.gpr.write(ops.rd, r_data as u64);
 
             Ok(())
         },
@@ -71,44 +101,12 @@ pub const INSTRUCTIONS_A: &[Instruction] = &[
         mask: MASK_SC_W,
         match_data: MATCH_SC_W,
         name: "SC_W",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
-
-            if cpu.lr_sc_reservation_check_and_clear(rs1_data) {
-                match cpu.write(rs1_data, rs2_data, 4, AccessType::Store(rs1_data)) {
-                    Ok(_) => {}
-                    Err(trap_type) => return Err(trap_type),
-                }
-                cpu.gpr.write(f.rd, 0);
-            } else {
-                cpu.gpr.write(f.rd, 1);
-            }
-            Ok(())
-        },
-    },
-    Instruction {
-        mask: MASK_SC_D,
-        match_data: MATCH_SC_D,
-        name: "SC_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
-
-            if cpu.lr_sc_reservation_check_and_clear(rs1_data) {
-                // todo!
-                match cpu.write(rs1_data, rs2_data, 8, AccessType::Store(rs1_data)) {
-                    Ok(_) => {}
-                    Err(trap_type) => return Err(trap_type),
-                };
-                cpu.gpr.write(f.rd, 0);
-            } else {
-                cpu.gpr.write(f.rd, 1);
-            }
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let success = cpu.store_conditional(rs1_data, rs2_data, 4)?;
+            cpu.gpr.write(ops.rd, !success as u64);
             Ok(())
         },
     },
@@ -116,395 +114,374 @@ pub const INSTRUCTIONS_A: &[Instruction] = &[
         mask: MASK_AMOSWAP_W,
         match_data: MATCH_AMOSWAP_W,
         name: "AMOSWAP_W",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // Atomic Memory Operation: Swap Word. R-type, RV32A and RV64A.
             // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
             // word to x[rs2]. Set x[rd] to the sign extension of t.
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
             let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            // no err happenes here
-            cpu.write(rs1_data, rs2_data, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as u32 as i32 as i64 as u64);
+            cpu.write(rs1_data, rs2_data, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as u32 as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOSWAP_D,
-        match_data: MATCH_AMOSWAP_D,
-        name: "AMOSWAP_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOXOR_W,
+        match_data: MATCH_AMOXOR_W,
+        name: "AMOXOR_W",
+        operation: |cpu, inst, pc, ops| {
+            /*Atomic Memory Operation: XOR Word. R-type, RV32A and RV64A.
+              Atomically, let t be the value of the memory word at address x[rs1], then set that memory
+              word to the bitwise XOR of t and x[rs2]. Set x[rd] to the sign extension of t.
+            */
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            // no err happenes here
-            cpu.write(rs1_data, rs2_data, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            cpu.write(rs1_data, tmp ^ rs2_data, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as u32 as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOXOR_W,
-        match_data: MATCH_AMOXOR_W,
-        name: "AMOXOR_W",
-        operation: |cpu, inst, pc| {
-            /*Atomic Memory Operation: XOR Word. R-type, RV32A and RV64A.
-              Atomically, let t be the value of the memory word at address x[rs1], then set that memory
-              word to the bitwise XOR of t and x[rs2]. Set x[rd] to the sign extension of t.
-            */
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOOR_W,
+        match_data: MATCH_AMOOR_W,
+        name: "AMOOR_W",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
             let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            // no err happenes here
-            cpu.write(rs1_data, tmp ^ rs2_data, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as u32 as i32 as i64 as u64);
+            cpu.write(rs1_data, tmp | rs2_data, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as u32 as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOXOR_D,
-        match_data: MATCH_AMOXOR_D,
-        name: "AMOXOR_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOMINU_W,
+        match_data: MATCH_AMOMINU_W,
+        name: "AMOMINU_W",
+        operation: |cpu, inst, pc, ops| {
+            // Atomic Memory Operation: Minimum Word, Unsigned. R-type, RV32A and RV64A.
+            // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
+            // word to the smaller of t and x[rs2], using an unsigned comparison. Set x[rd] to the sign
+            // extension of t.
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as u32;
 
-            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            // no err happenes here
-            cpu.write(rs1_data, tmp ^ rs2_data, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+
+            let amo_write = (tmp as u32).min(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOOR_W,
-        match_data: MATCH_AMOOR_W,
-        name: "AMOOR_W",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOMIN_W,
+        match_data: MATCH_AMOMIN_W,
+        name: "AMOMIN_W",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as i32;
 
             let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            // no err happenes here
-            cpu.write(rs1_data, tmp | rs2_data, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as u32 as i32 as i64 as u64);
+
+            let amo_write = (tmp as i32).min(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOOR_D,
-        match_data: MATCH_AMOOR_D,
-        name: "AMOOR_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOMAXU_W,
+        match_data: MATCH_AMOMAXU_W,
+        name: "AMOMAXU_W",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as u32;
 
-            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            // no err happenes here
-            cpu.write(rs1_data, tmp | rs2_data, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+
+            let amo_write = (tmp as u32).max(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMINU_W,
-        match_data: MATCH_AMOMINU_W,
-        name: "AMOMINU_W",
-        operation: |cpu, inst, pc| {
-            // Atomic Memory Operation: Minimum Word, Unsigned. R-type, RV32A and RV64A.
-            // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
-            // word to the smaller of t and x[rs2], using an unsigned comparison. Set x[rd] to the sign
-            // extension of t.
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as u32;
+        mask: MASK_AMOMAX_W,
+        match_data: MATCH_AMOMAX_W,
+        name: "AMOMAX_W",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as i32;
 
             let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = (tmp as u32).min(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as i32 as i64 as u64);
+            let amo_write = (tmp as i32).max(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMINU_D,
-        match_data: MATCH_AMOMINU_D,
-        name: "AMOMINU_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOAND_W,
+        match_data: MATCH_AMOAND_W,
+        name: "AMOAND_W",
+        operation: |cpu, inst, pc, ops| {
+            // Atomic Memory Operation: AND Word. R-type, RV32A and RV64A.
+            // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
+            // word to the bitwise AND of t and x[rs2]. Set x[rd] to the sign extension of t.
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as u32;
 
-            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = tmp.min(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            let amo_write = (tmp as u32) & rs2_data;
+            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as i32 as i64 as u64);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMIN_W,
-        match_data: MATCH_AMOMIN_W,
-        name: "AMOMIN_W",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as i32;
+        mask: MASK_AMOADD_W,
+        match_data: MATCH_AMOADD_W,
+        name: "AMOADD_W",
+        operation: |cpu, inst, pc, ops| {
+            // Atomic Memory Operation: Add Word. R-type, RV32A and RV64A.
+            // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
+            // word to t + x[rs2]. Set x[rd] to the sign extension of t.
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as u32;
 
             let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = (tmp as i32).min(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as i32 as i64 as u64);
+            let amo_write = (tmp as u32).wrapping_add(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp as i32 as i64 as u64);
 
             Ok(())
         },
     },
+];
+
+// the doubleword atomics of RV64A
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_A_RV64: &[Instruction] = &[
     Instruction {
-        mask: MASK_AMOMIN_D,
-        match_data: MATCH_AMOMIN_D,
-        name: "AMOMIN_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as i64;
+        mask: MASK_LR_D,
+        match_data: MATCH_LR_D,
+        name: "LR_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let r_data = cpu.load_reserved(rs1_data, 8)?;
+            cpu.gpr.write(ops.rd, r_data);
+
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SC_D,
+        match_data: MATCH_SC_D,
+        name: "SC_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let success = cpu.store_conditional(rs1_data, rs2_data, 8)?;
+            cpu.gpr.write(ops.rd, !success as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_AMOSWAP_D,
+        match_data: MATCH_AMOSWAP_D,
+        name: "AMOSWAP_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
             let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-
-            let amo_write = (tmp as i64).min(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            cpu.write(rs1_data, rs2_data, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMAXU_W,
-        match_data: MATCH_AMOMAXU_W,
-        name: "AMOMAXU_W",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as u32;
+        mask: MASK_AMOXOR_D,
+        match_data: MATCH_AMOXOR_D,
+        name: "AMOXOR_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-
-            let amo_write = (tmp as u32).max(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as i32 as i64 as u64);
+            cpu.write(rs1_data, tmp ^ rs2_data, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMAXU_D,
-        match_data: MATCH_AMOMAXU_D,
-        name: "AMOMAXU_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOOR_D,
+        match_data: MATCH_AMOOR_D,
+        name: "AMOOR_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
             let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-
-            let amo_write = tmp.max(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            cpu.write(rs1_data, tmp | rs2_data, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMAX_W,
-        match_data: MATCH_AMOMAX_W,
-        name: "AMOMAX_W",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as i32;
+        mask: MASK_AMOMINU_D,
+        match_data: MATCH_AMOMINU_D,
+        name: "AMOMINU_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = (tmp as i32).max(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as i32 as i64 as u64);
+            let amo_write = tmp.min(rs2_data);
+            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOMAX_D,
-        match_data: MATCH_AMOMAX_D,
-        name: "AMOMAX_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as i64;
+        mask: MASK_AMOMIN_D,
+        match_data: MATCH_AMOMIN_D,
+        name: "AMOMIN_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as i64;
 
             let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = (tmp as i64).max(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            let amo_write = (tmp as i64).min(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOAND_W,
-        match_data: MATCH_AMOAND_W,
-        name: "AMOAND_W",
-        operation: |cpu, inst, pc| {
-            // Atomic Memory Operation: AND Word. R-type, RV32A and RV64A.
-            // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
-            // word to the bitwise AND of t and x[rs2]. Set x[rd] to the sign extension of t.
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as u32;
+        mask: MASK_AMOMAXU_D,
+        match_data: MATCH_AMOMAXU_D,
+        name: "AMOMAXU_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = (tmp as u32) & rs2_data;
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as i32 as i64 as u64);
+            let amo_write = tmp.max(rs2_data);
+            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOAND_D,
-        match_data: MATCH_AMOAND_D,
-        name: "AMOAND_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        mask: MASK_AMOMAX_D,
+        match_data: MATCH_AMOMAX_D,
+        name: "AMOMAX_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2) as i64;
 
             let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = tmp & rs2_data;
-            // no err happenes here
-            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            let amo_write = (tmp as i64).max(rs2_data);
+            cpu.write(rs1_data, amo_write as u64, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_AMOADD_W,
-        match_data: MATCH_AMOADD_W,
-        name: "AMOADD_W",
-        operation: |cpu, inst, pc| {
-            // Atomic Memory Operation: Add Word. R-type, RV32A and RV64A.
-            // Atomically, let t be the value of the memory word at address x[rs1], then set that memory
-            // word to t + x[rs2]. Set x[rd] to the sign extension of t.
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2) as u32;
+        mask: MASK_AMOAND_D,
+        match_data: MATCH_AMOAND_D,
+        name: "AMOAND_D",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let tmp = match cpu.read(rs1_data, 4, AccessType::Amo(rs1_data)) {
+            let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
 
-            let amo_write = (tmp as u32).wrapping_add(rs2_data);
-            // no err happenes here
-            cpu.write(rs1_data, amo_write as u64, 4, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp as i32 as i64 as u64);
+            let amo_write = tmp & rs2_data;
+            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
@@ -513,10 +490,9 @@ pub const INSTRUCTIONS_A: &[Instruction] = &[
         mask: MASK_AMOADD_D,
         match_data: MATCH_AMOADD_D,
         name: "AMOADD_D",
-        operation: |cpu, inst, pc| {
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
             let tmp = match cpu.read(rs1_data, 8, AccessType::Amo(rs1_data)) {
                 Ok(data) => data,
@@ -525,12 +501,354 @@ pub const INSTRUCTIONS_A: &[Instruction] = &[
 
             let amo_write = tmp.wrapping_add(rs2_data);
 
-            // no err happenes here
-            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))
-                .unwrap();
-            cpu.gpr.write(f.rd, tmp);
+            cpu.write(rs1_data, amo_write, 8, AccessType::Amo(rs1_data))?;
+            cpu.gpr.write(ops.rd, tmp);
 
             Ok(())
         },
     },
 ];
+
+// Zabha: the AMOs on a byte or a halfword. op gets the old memory value and
+// rs2, both sign-extended from the access width, and rd gets the old value.
+fn amo_narrow(
+    cpu: &mut CpuCore,
+    ops: &Operands,
+    len: usize,
+    op: fn(u64, u64) -> u64,
+) -> Result<(), TrapType> {
+    let rs1_data = cpu.gpr.read(ops.rs1);
+    let rs2_data = sign_extend(cpu.gpr.read(ops.rs2), len);
+
+    let tmp = sign_extend(cpu.read(rs1_data, len, AccessType::Amo(rs1_data))?, len);
+    cpu.write(rs1_data, op(tmp, rs2_data), len, AccessType::Amo(rs1_data))?;
+    cpu.gpr.write(ops.rd, tmp);
+    Ok(())
+}
+
+// Zacas: rd holds the expected value and receives the old one, the memory is
+// only written when they are equal
+fn amo_cas(cpu: &mut CpuCore, ops: &Operands, len: usize) -> Result<(), TrapType> {
+    let rs1_data = cpu.gpr.read(ops.rs1);
+    let rs2_data = cpu.gpr.read(ops.rs2);
+    let expected = sign_extend(cpu.gpr.read(ops.rd), len);
+
+    let tmp = sign_extend(cpu.read(rs1_data, len, AccessType::Amo(rs1_data))?, len);
+    if tmp == expected {
+        cpu.write(rs1_data, rs2_data, len, AccessType::Amo(rs1_data))?;
+    }
+    cpu.gpr.write(ops.rd, tmp);
+    Ok(())
+}
+
+// AMOCAS.Q works on the even-odd register pairs rd, rd+1 and rs2, rs2+1,
+// the pair of x0 reads as zero and is never written.
+fn amo_cas_q(cpu: &mut CpuCore, inst: u32, ops: &Operands) -> Result<(), TrapType> {
+    if ops.rd & 1 != 0 || ops.rs2 & 1 != 0 {
+        return Err(TrapType::IllegalInstruction(inst.into()));
+    }
+    let read_pair = |cpu: &CpuCore, reg: u64| match reg {
+        0 => (0, 0),
+        reg => (cpu.gpr.read(reg), cpu.gpr.read(reg + 1)),
+    };
+    let rs1_data = cpu.gpr.read(ops.rs1);
+    if !check_aligned(rs1_data, 16) {
+        return Err(TrapType::StoreAddressMisaligned(rs1_data));
+    }
+    let hi_addr = rs1_data + 8;
+
+    let lo = cpu.read(rs1_data, 8, AccessType::Amo(rs1_data))?;
+    let hi = cpu.read(hi_addr, 8, AccessType::Amo(hi_addr))?;
+    if (lo, hi) == read_pair(cpu, ops.rd) {
+        let (swap_lo, swap_hi) = read_pair(cpu, ops.rs2);
+        cpu.write(rs1_data, swap_lo, 8, AccessType::Amo(rs1_data))?;
+        cpu.write(hi_addr, swap_hi, 8, AccessType::Amo(hi_addr))?;
+    }
+    if ops.rd != 0 {
+        cpu.gpr.write(ops.rd, lo);
+        cpu.gpr.write(ops.rd + 1, hi);
+    }
+    Ok(())
+}
+
+fn sign_extend(val: u64, len: usize) -> u64 {
+    match len {
+        1 => val as i8 as u64,
+        2 => val as i16 as u64,
+        4 => val as i32 as u64,
+        _ => val,
+    }
+}
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZACAS: &[Instruction] = &[
+    Instruction {
+        mask: MASK_AMOCAS_W,
+        match_data: MATCH_AMOCAS_W,
+        name: "AMOCAS_W",
+        operation: |cpu, inst, pc, ops| amo_cas(cpu, ops, 4),
+    },
+    Instruction {
+        mask: MASK_AMOCAS_D,
+        match_data: MATCH_AMOCAS_D,
+        name: "AMOCAS_D",
+        operation: |cpu, inst, pc, ops| amo_cas(cpu, ops, 8),
+    },
+    Instruction {
+        mask: MASK_AMOCAS_Q,
+        match_data: MATCH_AMOCAS_Q,
+        name: "AMOCAS_Q",
+        operation: |cpu, inst, pc, ops| amo_cas_q(cpu, inst, ops),
+    },
+];
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZABHA: &[Instruction] = &[
+    Instruction {
+        mask: MASK_AMOSWAP_B,
+        match_data: MATCH_AMOSWAP_B,
+        name: "AMOSWAP_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |_, src| src),
+    },
+    Instruction {
+        mask: MASK_AMOSWAP_H,
+        match_data: MATCH_AMOSWAP_H,
+        name: "AMOSWAP_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |_, src| src),
+    },
+    Instruction {
+        mask: MASK_AMOADD_B,
+        match_data: MATCH_AMOADD_B,
+        name: "AMOADD_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |mem, src| mem.wrapping_add(src)),
+    },
+    Instruction {
+        mask: MASK_AMOADD_H,
+        match_data: MATCH_AMOADD_H,
+        name: "AMOADD_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |mem, src| mem.wrapping_add(src)),
+    },
+    Instruction {
+        mask: MASK_AMOXOR_B,
+        match_data: MATCH_AMOXOR_B,
+        name: "AMOXOR_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |mem, src| mem ^ src),
+    },
+    Instruction {
+        mask: MASK_AMOXOR_H,
+        match_data: MATCH_AMOXOR_H,
+        name: "AMOXOR_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |mem, src| mem ^ src),
+    },
+    Instruction {
+        mask: MASK_AMOAND_B,
+        match_data: MATCH_AMOAND_B,
+        name: "AMOAND_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |mem, src| mem & src),
+    },
+    Instruction {
+        mask: MASK_AMOAND_H,
+        match_data: MATCH_AMOAND_H,
+        name: "AMOAND_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |mem, src| mem & src),
+    },
+    Instruction {
+        mask: MASK_AMOOR_B,
+        match_data: MATCH_AMOOR_B,
+        name: "AMOOR_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |mem, src| mem | src),
+    },
+    Instruction {
+        mask: MASK_AMOOR_H,
+        match_data: MATCH_AMOOR_H,
+        name: "AMOOR_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |mem, src| mem | src),
+    },
+    Instruction {
+        mask: MASK_AMOMIN_B,
+        match_data: MATCH_AMOMIN_B,
+        name: "AMOMIN_B",
+        operation: |cpu, inst, pc, ops| {
+            amo_narrow(cpu, ops, 1, |mem, src| (mem as i64).min(src as i64) as u64)
+        },
+    },
+    Instruction {
+        mask: MASK_AMOMIN_H,
+        match_data: MATCH_AMOMIN_H,
+        name: "AMOMIN_H",
+        operation: |cpu, inst, pc, ops| {
+            amo_narrow(cpu, ops, 2, |mem, src| (mem as i64).min(src as i64) as u64)
+        },
+    },
+    Instruction {
+        mask: MASK_AMOMAX_B,
+        match_data: MATCH_AMOMAX_B,
+        name: "AMOMAX_B",
+        operation: |cpu, inst, pc, ops| {
+            amo_narrow(cpu, ops, 1, |mem, src| (mem as i64).max(src as i64) as u64)
+        },
+    },
+    Instruction {
+        mask: MASK_AMOMAX_H,
+        match_data: MATCH_AMOMAX_H,
+        name: "AMOMAX_H",
+        operation: |cpu, inst, pc, ops| {
+            amo_narrow(cpu, ops, 2, |mem, src| (mem as i64).max(src as i64) as u64)
+        },
+    },
+    Instruction {
+        mask: MASK_AMOMINU_B,
+        match_data: MATCH_AMOMINU_B,
+        name: "AMOMINU_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |mem, src| mem.min(src)),
+    },
+    Instruction {
+        mask: MASK_AMOMINU_H,
+        match_data: MATCH_AMOMINU_H,
+        name: "AMOMINU_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |mem, src| mem.min(src)),
+    },
+    Instruction {
+        mask: MASK_AMOMAXU_B,
+        match_data: MATCH_AMOMAXU_B,
+        name: "AMOMAXU_B",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 1, |mem, src| mem.max(src)),
+    },
+    Instruction {
+        mask: MASK_AMOMAXU_H,
+        match_data: MATCH_AMOMAXU_H,
+        name: "AMOMAXU_H",
+        operation: |cpu, inst, pc, ops| amo_narrow(cpu, ops, 2, |mem, src| mem.max(src)),
+    },
+];
+
+// AMOCAS.B and AMOCAS.H need both Zabha and Zacas
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZABHA_ZACAS: &[Instruction] = &[
+    Instruction {
+        mask: MASK_AMOCAS_B,
+        match_data: MATCH_AMOCAS_B,
+        name: "AMOCAS_B",
+        operation: |cpu, inst, pc, ops| amo_cas(cpu, ops, 1),
+    },
+    Instruction {
+        mask: MASK_AMOCAS_H,
+        match_data: MATCH_AMOCAS_H,
+        name: "AMOCAS_H",
+        operation: |cpu, inst, pc, ops| amo_cas(cpu, ops, 2),
+    },
+];
+
+#[test]
+fn lr_sc_reservation_test() {
+    let set = LrScReservation::new();
+    let hart0 = set.add_hart();
+    let hart1 = set.add_hart();
+
+    // an LR on another hart keeps the reservation
+    set.set(hart0, 0x8000_1000);
+    set.set(hart1, 0x8000_2000);
+    assert!(set.check_and_clear(hart0, 0x8000_1008));
+    assert!(!set.check_and_clear(hart0, 0x8000_1000));
+
+    // a store to the same line from any hart breaks it
+    set.set(hart0, 0x8000_1000);
+    set.invalidate(0x8000_1040, 8);
+    assert!(set.is_reserved(hart0));
+    set.invalidate(0x8000_103c, 8);
+    assert!(!set.is_reserved(hart0));
+    assert!(set.is_reserved(hart1));
+
+    set.clear(hart1);
+    assert!(!set.check_and_clear(hart1, 0x8000_2000));
+}
+
+#[test]
+fn amocas_test() {
+    use crate::{config::Config, rv64core::cpu_core::cpu_for_test};
+
+    let mut config = Config::new();
+    config.set_isa("rv64imac_zacas").unwrap();
+    let mut cpu = cpu_for_test(config);
+    let run = |cpu: &mut CpuCore, inst: u32| {
+        cpu.npc = 0x8000_0000;
+        cpu.csr_regs.mcause.set(0.into());
+        cpu.exec_for_test(inst);
+        cpu.csr_regs.mcause.get().exception_code()
+    };
+    // amocas rd, rs2, (a1)
+    let amocas = |m: u32, rd: u32, rs2: u32| m | rd << 7 | 11 << 15 | rs2 << 20;
+    let mem = 0x8000_1000;
+    cpu.gpr.write(11, mem);
+
+    // AMOCAS.W compares the low 32 bits of rd with the loaded word, rd gets
+    // it sign-extended and only the low 32 bits of rs2 are stored
+    cpu.mem_write(mem, 0x5555_5555_8000_0000, 8);
+    cpu.gpr.write(10, 0x8000_0000);
+    cpu.gpr.write(12, 0x1234_5678_9abc_def0);
+    run(&mut cpu, amocas(MATCH_AMOCAS_W, 10, 12));
+    assert_eq!(cpu.gpr.read(10), 0xffff_ffff_8000_0000);
+    assert_eq!(cpu.mem_read(mem, 8), 0x5555_5555_9abc_def0);
+    // a mismatch leaves the memory alone
+    cpu.gpr.write(12, 0);
+    run(&mut cpu, amocas(MATCH_AMOCAS_W, 10, 12));
+    assert_eq!(cpu.gpr.read(10), 0xffff_ffff_9abc_def0);
+    assert_eq!(cpu.mem_read(mem, 8), 0x5555_5555_9abc_def0);
+
+    // AMOCAS.Q is reserved with an odd rd or rs2, and needs a 16-byte aligned address
+    assert_eq!(run(&mut cpu, amocas(MATCH_AMOCAS_Q, 13, 12)), 2);
+    assert_eq!(run(&mut cpu, amocas(MATCH_AMOCAS_Q, 14, 13)), 2);
+    cpu.gpr.write(11, mem + 8);
+    assert_eq!(run(&mut cpu, amocas(MATCH_AMOCAS_Q, 14, 12)), 6);
+    assert_eq!(cpu.csr_regs.mtval.get(), mem + 8);
+    cpu.gpr.write(11, mem);
+
+    // the x0 pair reads as zero and rd=x0 leaves x1 alone
+    cpu.mem_write(mem, 0, 8);
+    cpu.mem_write(mem + 8, 0, 8);
+    cpu.gpr.write(1, 0xdead);
+    cpu.gpr.write(12, 1);
+    cpu.gpr.write(13, 2);
+    assert_eq!(run(&mut cpu, amocas(MATCH_AMOCAS_Q, 0, 12)), 0);
+    assert_eq!((cpu.mem_read(mem, 8), cpu.mem_read(mem + 8, 8)), (1, 2));
+    assert_eq!(cpu.gpr.read(1), 0xdead);
+    cpu.gpr.write(14, 1);
+    cpu.gpr.write(15, 2);
+    run(&mut cpu, amocas(MATCH_AMOCAS_Q, 14, 0));
+    assert_eq!((cpu.mem_read(mem, 8), cpu.mem_read(mem + 8, 8)), (0, 0));
+    assert_eq!((cpu.gpr.read(14), cpu.gpr.read(15)), (1, 2));
+}
+
+#[test]
+fn amo_narrow_min_max_test() {
+    use crate::{config::Config, rv64core::cpu_core::cpu_for_test};
+
+    let mut config = Config::new();
+    config.set_isa("rv64imac_zabha").unwrap();
+    let mut cpu = cpu_for_test(config);
+    let mem = 0x8000_1000;
+    cpu.gpr.write(11, mem);
+    cpu.gpr.write(12, 1);
+
+    // the memory holds the most negative value, a2 holds 1
+    for (m, len, expected) in [
+        (MATCH_AMOMIN_B, 1, 0x80),
+        (MATCH_AMOMINU_B, 1, 0x01),
+        (MATCH_AMOMAX_B, 1, 0x01),
+        (MATCH_AMOMAXU_B, 1, 0x80),
+        (MATCH_AMOMIN_H, 2, 0x8000),
+        (MATCH_AMOMINU_H, 2, 0x0001),
+        (MATCH_AMOMAX_H, 2, 0x0001),
+        (MATCH_AMOMAXU_H, 2, 0x8000),
+    ] {
+        let mask = (1_u64 << (8 * len)) - 1;
+        let negative = 1 << (8 * len - 1);
+        let fill = 0x5555_5555_5555_5555 & !mask;
+        cpu.mem_write(mem, fill | negative, 8);
+        cpu.npc = 0x8000_0000;
+        // amo a0, a2, (a1)
+        cpu.exec_for_test(m | 10 << 7 | 11 << 15 | 12 << 20);
+        assert_eq!(cpu.gpr.read(10), sign_extend(negative, len));
+        assert_eq!(cpu.mem_read(mem, 8), fill | expected);
+    }
+}
@@ -1,8 +1,9 @@
-use crate::{rv64core::{inst::inst_base::*, traptype::TrapType}, tools::check_aligned};
+use crate::{rv64core::{cpu_core::CpuCore, inst::inst_base::*, traptype::TrapType}, tools::check_aligned};
 
 #[cfg(feature = "rvc_debug_trace")]
 use crate::trace::traces::TraceType;
 
+use super::inst_rv64f::{fp_check_enable, fp_set_dirty};
 use super::inst_rv64z::handle_ebreak;
 // https://stackoverflow.com/questions/50241218/risc-v-compressed-instructions-can-compiler-be-forced-to-align-32bit-instructio
 #[allow(unused_variables)]
@@ -11,38 +12,14 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_LWSP,
         match_data: MATCH_C_LWSP,
         name: "c.lwsp",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let imm = f.imm_c_lwsp() as u64;
-            let rd = f.rd() as u64;
-            let x2 = cpu.gpr.read(2);
-            let mem_addr = x2.wrapping_add(imm);
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
             let mem_data = match cpu.read(mem_addr, 4, AccessType::Load(mem_addr)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            cpu.gpr.write(rd, mem_data as i32 as i64 as u64);
-
-            Ok(())
-        },
-    },
-    Instruction {
-        mask: MASK_C_LDSP,
-        match_data: MATCH_C_LDSP,
-        name: "c.ldsp",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let imm = f.imm_c_ldsp() as u64;
-            let rd = f.rd() as u64;
-            let x2 = cpu.gpr.read(2);
-            let mem_addr = x2.wrapping_add(imm);
-
-            let mem_data = match cpu.read(mem_addr, 8, AccessType::Load(mem_addr)) {
-                Ok(data) => data,
-                Err(trap_type) => return Err(trap_type),
-            };
-            cpu.gpr.write(rd, mem_data);
+            cpu.gpr.write(ops.rd, mem_data as i32 as i64 as u64);
 
             Ok(())
         },
@@ -51,12 +28,9 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_SWSP,
         match_data: MATCH_C_SWSP,
         name: "c.swsp",
-        operation: |cpu, inst, pc| {
-            let f = FormatCSS::new(inst);
-            let imm = f.imm_c_swsp() as u64;
-            let rs2 = cpu.gpr.read(f.rs2() as u64);
-            let x2 = cpu.gpr.read(2);
-            let mem_addr = x2.wrapping_add(imm);
+        operation: |cpu, inst, pc, ops| {
+            let rs2 = cpu.gpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
             match cpu.write(mem_addr, rs2, 4, AccessType::Store(mem_addr)) {
                 Ok(_) => Ok(()),
@@ -64,59 +38,18 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
             }
         },
     },
-    Instruction {
-        mask: MASK_C_SDSP,
-        match_data: MATCH_C_SDSP,
-        name: "c.sdsp",
-        operation: |cpu, inst, pc| {
-            let f = FormatCSS::new(inst);
-            let imm = f.imm_c_sdsp() as u64;
-            let rs2 = cpu.gpr.read(f.rs2() as u64);
-            let x2 = cpu.gpr.read(2);
-            let mem_addr = x2.wrapping_add(imm);
-
-            match cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr)) {
-                Ok(_) => Ok(()),
-                Err(trap_type) => Err(trap_type),
-            }
-        },
-    },
     Instruction {
         mask: MASK_C_LW,
         match_data: MATCH_C_LW,
         name: "c.lw",
-        operation: |cpu, inst, pc| {
-            let f = FormatCL::new(inst);
-            let imm = f.imm_c_lw() as u64;
-            let rs1_data = cpu.gpr.read(f.rs1() as u64);
-            let rd = f.rd() as u64;
-            let mem_addr = rs1_data.wrapping_add(imm);
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
             let mem_data = match cpu.read(mem_addr, 4, AccessType::Load(mem_addr)) {
                 Ok(data) => data,
                 Err(trap_type) => return Err(trap_type),
             };
-            cpu.gpr.write(rd, mem_data as i32 as i64 as u64);
-
-            Ok(())
-        },
-    },
-    Instruction {
-        mask: MASK_C_LD,
-        match_data: MATCH_C_LD,
-        name: "c.ld",
-        operation: |cpu, inst, pc| {
-            let f = FormatCL::new(inst);
-            let imm = f.imm_c_ld() as u64;
-            let rs1_data = cpu.gpr.read(f.rs1() as u64);
-            let rd = f.rd() as u64;
-            let mem_addr = rs1_data.wrapping_add(imm);
-
-            let mem_data = match cpu.read(mem_addr, 8, AccessType::Load(mem_addr)) {
-                Ok(data) => data,
-                Err(trap_type) => return Err(trap_type),
-            };
-            cpu.gpr.write(rd, mem_data);
+            cpu.gpr.write(ops.rd, mem_data as i32 as i64 as u64);
 
             Ok(())
         },
@@ -125,12 +58,9 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_SW,
         match_data: MATCH_C_SW,
         name: "c.sw",
-        operation: |cpu, inst, pc| {
-            let f = FormatCS::new(inst);
-            let imm = f.imm_c_sw() as u64;
-            let rs2 = cpu.gpr.read(f.rs2() as u64);
-            let rs1 = cpu.gpr.read(f.rs1() as u64);
-            let mem_addr = rs1.wrapping_add(imm);
+        operation: |cpu, inst, pc, ops| {
+            let rs2 = cpu.gpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
             match cpu.write(mem_addr, rs2, 4, AccessType::Store(mem_addr)) {
                 Ok(_) => Ok(()),
@@ -138,34 +68,14 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
             }
         },
     },
-    Instruction {
-        mask: MASK_C_SD,
-        match_data: MATCH_C_SD,
-        name: "c.sd",
-        operation: |cpu, inst, pc| {
-            let f = FormatCS::new(inst);
-            let imm = f.imm_c_sd() as u64;
-            let rs2 = cpu.gpr.read(f.rs2() as u64);
-            let rs1 = cpu.gpr.read(f.rs1() as u64);
-            let mem_addr = rs1.wrapping_add(imm);
-
-            match cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr)) {
-                Ok(_) => Ok(()),
-                Err(trap_type) => Err(trap_type),
-            }
-        },
-    },
     Instruction {
         mask: MASK_C_J,
         match_data: MATCH_C_J,
         name: "c.j",
-        operation: |cpu, inst, pc| {
-            let f = FormatCJ::new(inst);
-            let imm = f.imm_c_j() as i64;
-
-            let next_pc = cpu.pc.wrapping_add(imm as u64);
+        operation: |cpu, inst, pc, ops| {
+            let next_pc = pc.wrapping_add(ops.imm);
             #[cfg(feature = "rvc_debug_trace")]
-            if f.is_call() {
+            if FormatCJ::new(inst).is_call() {
                 if let Some(sender) = &cpu.trace_sender {
                     sender.send(TraceType::Call(pc, next_pc)).unwrap();
                 };
@@ -174,37 +84,14 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
             Ok(())
         },
     },
-    // Instruction {
-    //     mask: MASK_C_JAL,
-    //     match_data: MATCH_C_JAL,
-    //     name: "c.jal",
-    //     operation: |cpu, inst, pc| {
-    //         let f = FormatCJ::new(inst);
-    //         let imm = f.imm_c_jal() as i64;
-    //         panic!("c.jal is rv32 only");
-    //         // todo! check align
-    //         let next_pc = cpu.pc.wrapping_add(imm as u64);
-    //         #[cfg(feature = "rvc_debug_trace")]
-    //         if f.is_call() {
-    //             if let Some(sender) = &cpu.trace_sender {
-    //                 sender.send(TraceType::Call(pc, next_pc)).unwrap();
-    //             };
-    //         };
-    //         cpu.npc = next_pc;
-    //         Ok(())
-    //     },
-    // },
     Instruction {
         mask: MASK_C_JR,
         match_data: MATCH_C_JR,
         name: "c.jr",
-        operation: |cpu, inst, pc| {
-            let f = FormatCR::new(inst);
-            let rs1_data = cpu.gpr.read(f.rs1());
-
-            let next_pc = rs1_data;
+        operation: |cpu, inst, pc, ops| {
+            let next_pc = cpu.gpr.read(ops.rs1);
             #[cfg(feature = "rvc_debug_trace")]
-            if f.is_call() {
+            if FormatCR::new(inst).is_call() {
                 if let Some(sender) = &cpu.trace_sender {
                     sender.send(TraceType::Call(pc, next_pc)).unwrap();
                 };
@@ -217,21 +104,17 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_JALR,
         match_data: MATCH_C_JALR,
         name: "c.jalr",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // t = pc+2; pc = x[rs1]; x[1] = t
-            let f = FormatCR::new(inst);
-            let rs1_data = cpu.gpr.read(f.rs1());
-            let wdata = pc.wrapping_add(2);
-
-            let next_pc = rs1_data;
+            let next_pc = cpu.gpr.read(ops.rs1);
             #[cfg(feature = "rvc_debug_trace")]
-            if f.is_call() {
+            if FormatCR::new(inst).is_call() {
                 if let Some(sender) = &cpu.trace_sender {
                     sender.send(TraceType::Call(pc, next_pc)).unwrap();
                 };
             };
             cpu.npc = next_pc;
-            cpu.gpr.write(1, wdata);
+            cpu.gpr.write(1, pc.wrapping_add(2));
 
             Ok(())
         },
@@ -240,14 +123,12 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_BEQZ,
         match_data: MATCH_C_BEQZ,
         name: "c.beqz",
-        operation: |cpu, inst, pc| {
-            // if (rs1 == rs2) pc += sext(offset)
-            let f = FormatCB::new(inst);
-            let rs1 = cpu.gpr.read(f.rs1() as u64);
-            let imm = f.imm_c_beqz() as i64 as u64;
+        operation: |cpu, inst, pc, ops| {
+            // if (x[rs1] == 0) pc += sext(offset)
+            let rs1 = cpu.gpr.read(ops.rs1);
 
             if rs1 == 0 {
-                let next_pc = pc.wrapping_add(imm);
+                let next_pc = pc.wrapping_add(ops.imm);
                 if !check_aligned(next_pc, 2) {
                     // todo! not clear
                     return Err(TrapType::InstructionAddressMisaligned(next_pc));
@@ -261,13 +142,12 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_BNEZ,
         match_data: MATCH_C_BNEZ,
         name: "c.bnez",
-        operation: |cpu, inst, pc| {
-            let f = FormatCB::new(inst);
-            let rs1 = cpu.gpr.read(f.rs1() as u64);
-            let imm = f.imm_c_bnez() as i64 as u64;
+        operation: |cpu, inst, pc, ops| {
+            // if (x[rs1] != 0) pc += sext(offset)
+            let rs1 = cpu.gpr.read(ops.rs1);
 
             if rs1 != 0 {
-                let next_pc = pc.wrapping_add(imm);
+                let next_pc = pc.wrapping_add(ops.imm);
                 if !check_aligned(next_pc, 2) {
                     // todo! not clear
                     return Err(TrapType::InstructionAddressMisaligned(next_pc));
@@ -281,12 +161,8 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_LI,
         match_data: MATCH_C_LI,
         name: "c.li",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let rd = f.rd();
-            let imm = f.imm_c_li() as i64 as u64;
-
-            cpu.gpr.write(rd as u64, imm);
+        operation: |cpu, inst, pc, ops| {
+            cpu.gpr.write(ops.rd, ops.imm);
             Ok(())
         },
     },
@@ -294,12 +170,8 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_LUI,
         match_data: MATCH_C_LUI,
         name: "c.lui",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let rd = f.rd();
-            let imm = f.imm_c_lui() as i64 as u64;
-
-            cpu.gpr.write(rd as u64, imm);
+        operation: |cpu, inst, pc, ops| {
+            cpu.gpr.write(ops.rd, ops.imm);
             Ok(())
         },
     },
@@ -307,29 +179,10 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_ADDI,
         match_data: MATCH_C_ADDI,
         name: "c.addi",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let imm = f.imm_c_addi() as i64 as u64;
-            let rd = f.rd() as u64;
-            let rd_data = cpu.gpr.read(rd);
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
 
-            let wb = rd_data.wrapping_add(imm);
-            cpu.gpr.write(rd, wb);
-            Ok(())
-        },
-    },
-    Instruction {
-        mask: MASK_C_ADDIW,
-        match_data: MATCH_C_ADDIW,
-        name: "c.addiw",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let imm = f.imm_c_addiw() as i64 as u64;
-            let rd = f.rd() as u64;
-            let rd_data = cpu.gpr.read(rd);
-
-            let wb = rd_data.wrapping_add(imm) as i32;
-            cpu.gpr.write(rd, wb as i64 as u64);
+            cpu.gpr.write(ops.rd, rs1_data.wrapping_add(ops.imm));
             Ok(())
         },
     },
@@ -337,15 +190,10 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_ADDI16SP,
         match_data: MATCH_C_ADDI16SP,
         name: "c.addi16sp",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let imm = f.imm_c_addi16sp() as i64;
-            let rd = 2;
-            let rd_data = cpu.gpr.read(rd) as i64;
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
 
-            let wb = rd_data.wrapping_add(imm);
-
-            cpu.gpr.write(rd, wb as u64);
+            cpu.gpr.write(ops.rd, rs1_data.wrapping_add(ops.imm));
             Ok(())
         },
     },
@@ -353,20 +201,191 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_ADDI4SPN,
         match_data: MATCH_C_ADDI4SPN,
         name: "c.addi4spn",
-        operation: |cpu, inst, pc| {
-            let f = FormatCIW::new(inst);
-            let imm = f.imm_c_addi4spn() as u64 as i64;
-
-            if imm == 0 {
+        operation: |cpu, inst, pc, ops| {
+            if ops.imm == 0 {
                 return Err(TrapType::IllegalInstruction(inst.into()));
             }
 
-            let x2_data = cpu.gpr.read(2) as i64;
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            cpu.gpr.write(ops.rd, rs1_data.wrapping_add(ops.imm));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_ANDI,
+        match_data: MATCH_C_ANDI,
+        name: "c.andi",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+
+            cpu.gpr.write(ops.rd, rs1_data & ops.imm);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_MV,
+        match_data: MATCH_C_MV,
+        name: "c.mv",
+        operation: |cpu, inst, pc, ops| {
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            cpu.gpr.write(ops.rd, rs2_data);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_ADD,
+        match_data: MATCH_C_ADD,
+        name: "c.add",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let wb = rs1_data.wrapping_add(rs2_data);
+            cpu.gpr.write(ops.rd, wb);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_AND,
+        match_data: MATCH_C_AND,
+        name: "c.and",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let wb = rs1_data & rs2_data;
+            cpu.gpr.write(ops.rd, wb);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_OR,
+        match_data: MATCH_C_OR,
+        name: "c.or",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let wb = rs1_data | rs2_data;
+            cpu.gpr.write(ops.rd, wb);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_XOR,
+        match_data: MATCH_C_XOR,
+        name: "c.xor",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let wb = rs1_data ^ rs2_data;
+            cpu.gpr.write(ops.rd, wb);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SUB,
+        match_data: MATCH_C_SUB,
+        name: "c.sub",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
+
+            let wb = rs1_data.wrapping_sub(rs2_data);
+            cpu.gpr.write(ops.rd, wb);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_NOP,
+        match_data: MATCH_C_NOP,
+        name: "c.nop",
+        operation: |cpu, inst, pc, ops| Ok(()),
+    },
+    Instruction {
+        mask: MASK_C_EBREAK,
+        match_data: MATCH_C_EBREAK,
+        name: "c.ebreak",
+        operation: |cpu, inst, pc, ops| {
+            handle_ebreak(cpu,pc)
+        },
+    },
+];
+
+// RV64C only, c.addiw takes the c.jal encoding and c.ld/c.sd take the c.flw/c.fsw ones
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_C_RV64: &[Instruction] = &[
+    Instruction {
+        mask: MASK_C_LDSP,
+        match_data: MATCH_C_LDSP,
+        name: "c.ldsp",
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            let mem_data = match cpu.read(mem_addr, 8, AccessType::Load(mem_addr)) {
+                Ok(data) => data,
+                Err(trap_type) => return Err(trap_type),
+            };
+            cpu.gpr.write(ops.rd, mem_data);
+
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SDSP,
+        match_data: MATCH_C_SDSP,
+        name: "c.sdsp",
+        operation: |cpu, inst, pc, ops| {
+            let rs2 = cpu.gpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            match cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr)) {
+                Ok(_) => Ok(()),
+                Err(trap_type) => Err(trap_type),
+            }
+        },
+    },
+    Instruction {
+        mask: MASK_C_LD,
+        match_data: MATCH_C_LD,
+        name: "c.ld",
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            let mem_data = match cpu.read(mem_addr, 8, AccessType::Load(mem_addr)) {
+                Ok(data) => data,
+                Err(trap_type) => return Err(trap_type),
+            };
+            cpu.gpr.write(ops.rd, mem_data);
+
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SD,
+        match_data: MATCH_C_SD,
+        name: "c.sd",
+        operation: |cpu, inst, pc, ops| {
+            let rs2 = cpu.gpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
+            match cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr)) {
+                Ok(_) => Ok(()),
+                Err(trap_type) => Err(trap_type),
+            }
+        },
+    },
+    Instruction {
+        mask: MASK_C_ADDIW,
+        match_data: MATCH_C_ADDIW,
+        name: "c.addiw",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
 
-            let wb = x2_data.wrapping_add(imm);
-            cpu.gpr.write(rd, wb as u64);
+            let wb = rs1_data.wrapping_add(ops.imm) as i32;
+            cpu.gpr.write(ops.rd, wb as i64 as u64);
             Ok(())
         },
     },
@@ -374,14 +393,10 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_SLLI,
         match_data: MATCH_C_SLLI,
         name: "c.slli",
-        operation: |cpu, inst, pc| {
-            let f = FormatCI::new(inst);
-            let shamt = f.imm_c_slli() as u64;
-            let rd: u64 = f.rd() as u64;
-            let rd_data = cpu.gpr.read(rd);
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
 
-            let wb = rd_data << shamt;
-            cpu.gpr.write(rd, wb);
+            cpu.gpr.write(ops.rd, rs1_data << ops.imm);
             Ok(())
         },
     },
@@ -389,14 +404,10 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_SRLI,
         match_data: MATCH_C_SRLI,
         name: "c.srli",
-        operation: |cpu, inst, pc| {
-            let f = FormatCB::new(inst);
-            let shamt = f.imm_c_srli() as u64;
-            let rd: u64 = f.rd() as u64;
-            let rd_data = cpu.gpr.read(rd);
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
 
-            let wb = rd_data >> shamt;
-            cpu.gpr.write(rd, wb);
+            cpu.gpr.write(ops.rd, rs1_data >> ops.imm);
             Ok(())
         },
     },
@@ -404,187 +415,493 @@ pub const INSTRUCTIONS_C: &[Instruction] = &[
         mask: MASK_C_SRAI,
         match_data: MATCH_C_SRAI,
         name: "c.srai",
-        operation: |cpu, inst, pc| {
-            let f = FormatCB::new(inst);
-            let shamt = f.imm_c_srai() as u64;
-            let rd = f.rd() as u64;
-            let rd_data = cpu.gpr.read(rd) as i64;
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1) as i64;
 
-            let wb = rd_data >> shamt;
-            cpu.gpr.write(rd, wb as u64);
+            let wb = rs1_data >> ops.imm;
+            cpu.gpr.write(ops.rd, wb as u64);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_ANDI,
-        match_data: MATCH_C_ANDI,
-        name: "c.andi",
-        operation: |cpu, inst, pc| {
-            let f = FormatCB::new(inst);
-            let imm = f.imm_c_andi() as i64;
-            let rd: u64 = f.rd() as u64;
-            let rd_data = cpu.gpr.read(rd) as i64;
+        mask: MASK_C_ADDW,
+        match_data: MATCH_C_ADDW,
+        name: "c.addw",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let wb = rd_data & imm;
-            cpu.gpr.write(rd, wb as u64);
+            let wb = rs1_data.wrapping_add(rs2_data) as i32;
+            cpu.gpr.write(ops.rd, wb as i64 as u64);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_MV,
-        match_data: MATCH_C_MV,
-        name: "c.mv",
-        operation: |cpu, inst, pc| {
-            let f = FormatCR::new(inst);
+        mask: MASK_C_SUBW,
+        match_data: MATCH_C_SUBW,
+        name: "c.subw",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
+            let rs2_data = cpu.gpr.read(ops.rs2);
 
-            let rd = f.rd();
-            let rs2 = f.rs2();
+            let wb = rs1_data.wrapping_sub(rs2_data) as i32;
+            cpu.gpr.write(ops.rd, wb as i64 as u64);
+            Ok(())
+        },
+    },
+];
+
+// RV32C, the shifts with shamt[5]=1 are reserved
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_C_RV32: &[Instruction] = &[
+    Instruction {
+        mask: MASK_C_JAL,
+        match_data: MATCH_C_JAL,
+        name: "c.jal",
+        operation: |cpu, inst, pc, ops| {
+            // x[1] = pc+2; pc += sext(offset)
+            cpu.npc = pc.wrapping_add(ops.imm);
+            cpu.gpr.write(1, pc.wrapping_add(2));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SLLI_RV32,
+        match_data: MATCH_C_SLLI_RV32,
+        name: "c.slli",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1);
 
-            let rs2_data = cpu.gpr.read(rs2);
+            cpu.gpr.write(ops.rd, rs1_data << ops.imm);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SRLI_RV32,
+        match_data: MATCH_C_SRLI_RV32,
+        name: "c.srli",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1) as u32;
 
-            let wb = rs2_data;
-            cpu.gpr.write(rd, wb);
+            let wb = rs1_data >> ops.imm;
+            cpu.gpr.write(ops.rd, wb as u64);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_ADD,
-        match_data: MATCH_C_ADD,
-        name: "c.add",
-        operation: |cpu, inst, pc| {
-            let f = FormatCR::new(inst);
+        mask: MASK_C_SRAI_RV32,
+        match_data: MATCH_C_SRAI_RV32,
+        name: "c.srai",
+        operation: |cpu, inst, pc, ops| {
+            let rs1_data = cpu.gpr.read(ops.rs1) as i32;
 
-            let rd: u64 = f.rd();
-            let rs2 = f.rs2();
+            let wb = rs1_data >> ops.imm;
+            cpu.gpr.write(ops.rd, wb as u64);
+            Ok(())
+        },
+    },
+];
+
+// compressed single-precision loads and stores, RV32 with both C and F
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_C_F_RV32: &[Instruction] = &[
+    Instruction {
+        mask: MASK_C_FLW,
+        match_data: MATCH_C_FLW,
+        name: "c.flw",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rs2_data = cpu.gpr.read(rs2) as i64;
-            let rd_data = cpu.gpr.read(rd) as i64;
+            let mem_data = cpu.read(mem_addr, 4, AccessType::Load(mem_addr))?;
+            cpu.fpr.write_f32(ops.rd, mem_data as u32);
+            fp_set_dirty(cpu);
 
-            let wb = rs2_data.wrapping_add(rd_data);
-            cpu.gpr.write(rd, wb as u64);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_AND,
-        match_data: MATCH_C_AND,
-        name: "c.and",
-        operation: |cpu, inst, pc| {
-            let f = FormatCA::new(inst);
+        mask: MASK_C_FSW,
+        match_data: MATCH_C_FSW,
+        name: "c.fsw",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let rs2 = cpu.fpr.read(ops.rs2) as u32;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
-            let rs2 = f.rs2() as u64;
+            cpu.write(mem_addr, rs2 as u64, 4, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_FLWSP,
+        match_data: MATCH_C_FLWSP,
+        name: "c.flwsp",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rs2_data = cpu.gpr.read(rs2);
-            let rd_data = cpu.gpr.read(rd);
+            let mem_data = cpu.read(mem_addr, 4, AccessType::Load(mem_addr))?;
+            cpu.fpr.write_f32(ops.rd, mem_data as u32);
+            fp_set_dirty(cpu);
 
-            let wb = rs2_data & rd_data;
-            cpu.gpr.write(rd, wb);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_OR,
-        match_data: MATCH_C_OR,
-        name: "c.or",
-        operation: |cpu, inst, pc| {
-            let f = FormatCA::new(inst);
+        mask: MASK_C_FSWSP,
+        match_data: MATCH_C_FSWSP,
+        name: "c.fswsp",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let rs2 = cpu.fpr.read(ops.rs2) as u32;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
-            let rs2 = f.rs2() as u64;
+            cpu.write(mem_addr, rs2 as u64, 4, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+];
 
-            let rs2_data = cpu.gpr.read(rs2);
-            let rd_data = cpu.gpr.read(rd);
+// compressed double-precision loads and stores, only enabled with both C and D
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_C_D: &[Instruction] = &[
+    Instruction {
+        mask: MASK_C_FLD,
+        match_data: MATCH_C_FLD,
+        name: "c.fld",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            let mem_data = cpu.read(mem_addr, 8, AccessType::Load(mem_addr))?;
+            cpu.fpr.write(ops.rd, mem_data);
+            fp_set_dirty(cpu);
 
-            let wb = rs2_data | rd_data;
-            cpu.gpr.write(rd, wb);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_XOR,
-        match_data: MATCH_C_XOR,
-        name: "c.xor",
-        operation: |cpu, inst, pc| {
-            let f = FormatCA::new(inst);
+        mask: MASK_C_FSD,
+        match_data: MATCH_C_FSD,
+        name: "c.fsd",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let rs2 = cpu.fpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
-            let rs2 = f.rs2() as u64;
+            cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_FLDSP,
+        match_data: MATCH_C_FLDSP,
+        name: "c.fldsp",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rs2_data = cpu.gpr.read(rs2);
-            let rd_data = cpu.gpr.read(rd);
+            let mem_data = cpu.read(mem_addr, 8, AccessType::Load(mem_addr))?;
+            cpu.fpr.write(ops.rd, mem_data);
+            fp_set_dirty(cpu);
 
-            let wb = rs2_data ^ rd_data;
-            cpu.gpr.write(rd, wb);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_SUB,
-        match_data: MATCH_C_SUB,
-        name: "c.sub",
-        operation: |cpu, inst, pc| {
-            let f = FormatCA::new(inst);
+        mask: MASK_C_FSDSP,
+        match_data: MATCH_C_FSDSP,
+        name: "c.fsdsp",
+        operation: |cpu, inst, pc, ops| {
+            fp_check_enable(cpu, inst)?;
+            let rs2 = cpu.fpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
-            let rs2 = f.rs2() as u64;
+            cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+];
 
-            let rs2_data = cpu.gpr.read(rs2) as i64;
-            let rd_data = cpu.gpr.read(rd) as i64;
+// Zcb: compressed byte/halfword loads and stores, and the simple unary operations
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZCB: &[Instruction] = &[
+    Instruction {
+        mask: MASK_C_LBU,
+        match_data: MATCH_C_LBU,
+        name: "c.lbu",
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let wb = rd_data.wrapping_sub(rs2_data);
-            cpu.gpr.write(rd, wb as u64);
+            let mem_data = cpu.read(mem_addr, 1, AccessType::Load(mem_addr))?;
+            cpu.gpr.write(ops.rd, mem_data as u8 as u64);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_ADDW,
-        match_data: MATCH_C_ADDW,
-        name: "c.addw",
-        operation: |cpu, inst, pc| {
-            let f = FormatCA::new(inst);
+        mask: MASK_C_LHU,
+        match_data: MATCH_C_LHU,
+        name: "c.lhu",
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
-            let rs2 = f.rs2() as u64;
+            let mem_data = cpu.read(mem_addr, 2, AccessType::Load(mem_addr))?;
+            cpu.gpr.write(ops.rd, mem_data as u16 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_LH,
+        match_data: MATCH_C_LH,
+        name: "c.lh",
+        operation: |cpu, inst, pc, ops| {
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rs2_data = cpu.gpr.read(rs2) as i64;
-            let rd_data = cpu.gpr.read(rd) as i64;
+            let mem_data = cpu.read(mem_addr, 2, AccessType::Load(mem_addr))?;
+            cpu.gpr.write(ops.rd, mem_data as i16 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SB,
+        match_data: MATCH_C_SB,
+        name: "c.sb",
+        operation: |cpu, inst, pc, ops| {
+            let rs2 = cpu.gpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let wb = rs2_data.wrapping_add(rd_data) as i32;
-            cpu.gpr.write(rd, wb as i64 as u64);
+            cpu.write(mem_addr, rs2, 1, AccessType::Store(mem_addr))?;
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_SUBW,
-        match_data: MATCH_C_SUBW,
-        name: "c.subw",
-        operation: |cpu, inst, pc| {
-            let f = FormatCA::new(inst);
+        mask: MASK_C_SH,
+        match_data: MATCH_C_SH,
+        name: "c.sh",
+        operation: |cpu, inst, pc, ops| {
+            let rs2 = cpu.gpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
 
-            let rd: u64 = f.rd() as u64;
-            let rs2 = f.rs2() as u64;
+            cpu.write(mem_addr, rs2, 2, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_ZEXT_B,
+        match_data: MATCH_C_ZEXT_B,
+        name: "c.zext.b",
+        operation: |cpu, inst, pc, ops| {
+            let rd_data = cpu.gpr.read(ops.rd);
 
-            let rs2_data = cpu.gpr.read(rs2) as i64;
-            let rd_data = cpu.gpr.read(rd) as i64;
+            cpu.gpr.write(ops.rd, rd_data as u8 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SEXT_B,
+        match_data: MATCH_C_SEXT_B,
+        name: "c.sext.b",
+        operation: |cpu, inst, pc, ops| {
+            let rd_data = cpu.gpr.read(ops.rd);
 
-            let wb = rd_data.wrapping_sub(rs2_data) as i32;
-            cpu.gpr.write(rd, wb as i64 as u64);
+            cpu.gpr.write(ops.rd, rd_data as i8 as i64 as u64);
             Ok(())
         },
     },
     Instruction {
-        mask: MASK_C_NOP,
-        match_data: MATCH_C_NOP,
-        name: "c.nop",
-        operation: |cpu, inst, pc| Ok(()),
+        mask: MASK_C_NOT,
+        match_data: MATCH_C_NOT,
+        name: "c.not",
+        operation: |cpu, inst, pc, ops| {
+            let rd_data = cpu.gpr.read(ops.rd);
+
+            cpu.gpr.write(ops.rd, !rd_data);
+            Ok(())
+        },
     },
+];
+
+// Zcb instructions that also need Zbb
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZCB_ZBB: &[Instruction] = &[
     Instruction {
-        mask: MASK_C_EBREAK,
-        match_data: MATCH_C_EBREAK,
-        name: "c.ebreak",
-        operation: |cpu, inst, pc| {
-            handle_ebreak(cpu,pc)
+        mask: MASK_C_ZEXT_H,
+        match_data: MATCH_C_ZEXT_H,
+        name: "c.zext.h",
+        operation: |cpu, inst, pc, ops| {
+            let rd_data = cpu.gpr.read(ops.rd);
+
+            cpu.gpr.write(ops.rd, rd_data as u16 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_C_SEXT_H,
+        match_data: MATCH_C_SEXT_H,
+        name: "c.sext.h",
+        operation: |cpu, inst, pc, ops| {
+            let rd_data = cpu.gpr.read(ops.rd);
+
+            cpu.gpr.write(ops.rd, rd_data as i16 as i64 as u64);
+            Ok(())
+        },
+    },
+];
+
+// Zcb instructions that also need Zba
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZCB_ZBA: &[Instruction] = &[Instruction {
+    mask: MASK_C_ZEXT_W,
+    match_data: MATCH_C_ZEXT_W,
+    name: "c.zext.w",
+    operation: |cpu, inst, pc, ops| {
+        let rd_data = cpu.gpr.read(ops.rd);
+
+        cpu.gpr.write(ops.rd, rd_data as u32 as u64);
+        Ok(())
+    },
+}];
+
+// Zcb instructions that also need M
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZCB_M: &[Instruction] = &[Instruction {
+    mask: MASK_C_MUL,
+    match_data: MATCH_C_MUL,
+    name: "c.mul",
+    operation: |cpu, inst, pc, ops| {
+        let rs1_data = cpu.gpr.read(ops.rs1);
+        let rs2_data = cpu.gpr.read(ops.rs2);
+
+        cpu.gpr.write(ops.rd, rs1_data.wrapping_mul(rs2_data));
+        Ok(())
+    },
+}];
+
+// Push the registers of rlist below sp, the highest numbered one at sp-8, then
+// move sp down by stack_adj. sp is only updated after all the stores succeed.
+fn cm_push(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    let f = FormatCMPP::new(inst);
+    if f.is_reserved() {
+        return Err(TrapType::IllegalInstruction(inst.into()));
+    }
+    let sp = cpu.gpr.read(2);
+    let num = f.reg_list().count() as u64;
+    for (i, reg) in f.reg_list().enumerate() {
+        let mem_addr = sp.wrapping_sub((num - i as u64) * 8);
+        let data = cpu.gpr.read(reg);
+        cpu.write(mem_addr, data, 8, AccessType::Store(mem_addr))?;
+    }
+    cpu.gpr.write(2, sp.wrapping_sub(f.stack_adj()));
+    Ok(())
+}
+
+// the inverse of cm_push, ret follows for cm.popret and cm.popretz
+fn cm_pop(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    let f = FormatCMPP::new(inst);
+    if f.is_reserved() {
+        return Err(TrapType::IllegalInstruction(inst.into()));
+    }
+    let new_sp = cpu.gpr.read(2).wrapping_add(f.stack_adj());
+    let num = f.reg_list().count() as u64;
+    let mut data = [0_u64; 13];
+    for (i, data) in data.iter_mut().take(num as usize).enumerate() {
+        let mem_addr = new_sp.wrapping_sub((num - i as u64) * 8);
+        *data = cpu.read(mem_addr, 8, AccessType::Load(mem_addr))?;
+    }
+    for (reg, data) in f.reg_list().zip(data) {
+        cpu.gpr.write(reg, data);
+    }
+    cpu.gpr.write(2, new_sp);
+    Ok(())
+}
+
+// Zcmp: push/pop and the double moves between s-registers and a0/a1
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZCMP: &[Instruction] = &[
+    Instruction {
+        mask: MASK_CM_PUSH,
+        match_data: MATCH_CM_PUSH,
+        name: "cm.push",
+        operation: |cpu, inst, pc, ops| cm_push(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_CM_POP,
+        match_data: MATCH_CM_POP,
+        name: "cm.pop",
+        operation: |cpu, inst, pc, ops| cm_pop(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_CM_POPRET,
+        match_data: MATCH_CM_POPRET,
+        name: "cm.popret",
+        operation: |cpu, inst, pc, ops| {
+            cm_pop(cpu, inst)?;
+            cpu.npc = cpu.gpr.read(1);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CM_POPRETZ,
+        match_data: MATCH_CM_POPRETZ,
+        name: "cm.popretz",
+        operation: |cpu, inst, pc, ops| {
+            cm_pop(cpu, inst)?;
+            cpu.gpr.write(10, 0);
+            cpu.npc = cpu.gpr.read(1);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CM_MVSA01,
+        match_data: MATCH_CM_MVSA01,
+        name: "cm.mvsa01",
+        operation: |cpu, inst, pc, ops| {
+            let f = FormatCMMV::new(inst);
+            if f.r1s() == f.r2s() {
+                return Err(TrapType::IllegalInstruction(inst.into()));
+            }
+            let a0 = cpu.gpr.read(10);
+            let a1 = cpu.gpr.read(11);
+
+            cpu.gpr.write(f.r1s(), a0);
+            cpu.gpr.write(f.r2s(), a1);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CM_MVA01S,
+        match_data: MATCH_CM_MVA01S,
+        name: "cm.mva01s",
+        operation: |cpu, inst, pc, ops| {
+            let f = FormatCMMV::new(inst);
+            let r1s = cpu.gpr.read(f.r1s());
+            let r2s = cpu.gpr.read(f.r2s());
+
+            cpu.gpr.write(10, r1s);
+            cpu.gpr.write(11, r2s);
+            Ok(())
         },
     },
 ];
+
+// Zcmt: cm.jt and cm.jalt share one encoding, split by the table index.
+// The table is read like instruction memory, so it needs fence.i after being written.
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZCMT: &[Instruction] = &[Instruction {
+    mask: MASK_CM_JT,
+    match_data: MATCH_CM_JT,
+    name: "cm.jt",
+    operation: |cpu, inst, pc, ops| {
+        let f = FormatCMJT::new(inst);
+        let entry_addr = cpu.csr_regs.jvt.get().table_addr() + f.index() as u64 * 8;
+        let lo = cpu.icahce_read(entry_addr, 4)?;
+        let hi = cpu.icahce_read(entry_addr + 4, 4)?;
+
+        if f.is_jalt() {
+            cpu.gpr.write(1, pc.wrapping_add(2));
+        }
+        cpu.npc = (hi << 32 | lo) & !1;
+        Ok(())
+    },
+}];
@@ -0,0 +1,236 @@
+use crate::rv64core::{
+    inst::{inst_base::*, inst_rv64f::*},
+    softfloat::{self, F32, F64},
+};
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_D: &[Instruction] = &[
+    Instruction {
+        mask: MASK_FLD,
+        match_data: MATCH_FLD,
+        name: "FLD",
+        operation: |cpu, inst, pc, ops| {
+            // f[rd] = M[x[rs1] + sext(offset)][63:0]
+            fp_check_enable(cpu, inst)?;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            let mem_data = cpu.read(mem_addr, 8, AccessType::Load(mem_addr))?;
+            cpu.fpr.write(ops.rd, mem_data);
+            fp_set_dirty(cpu);
+
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_FSD,
+        match_data: MATCH_FSD,
+        name: "FSD",
+        operation: |cpu, inst, pc, ops| {
+            // M[x[rs1] + sext(offset)] = f[rs2][63:0]
+            fp_check_enable(cpu, inst)?;
+            let rs2 = cpu.fpr.read(ops.rs2);
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            cpu.write(mem_addr, rs2, 8, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_FMADD_D,
+        match_data: MATCH_FMADD_D,
+        name: "FMADD_D",
+        operation: |cpu, inst, pc, ops| fp_fused::<F64>(cpu, inst, ops, false, false),
+    },
+    Instruction {
+        mask: MASK_FMSUB_D,
+        match_data: MATCH_FMSUB_D,
+        name: "FMSUB_D",
+        operation: |cpu, inst, pc, ops| fp_fused::<F64>(cpu, inst, ops, false, true),
+    },
+    Instruction {
+        mask: MASK_FNMSUB_D,
+        match_data: MATCH_FNMSUB_D,
+        name: "FNMSUB_D",
+        operation: |cpu, inst, pc, ops| fp_fused::<F64>(cpu, inst, ops, true, false),
+    },
+    Instruction {
+        mask: MASK_FNMADD_D,
+        match_data: MATCH_FNMADD_D,
+        name: "FNMADD_D",
+        operation: |cpu, inst, pc, ops| fp_fused::<F64>(cpu, inst, ops, true, true),
+    },
+    Instruction {
+        mask: MASK_FADD_D,
+        match_data: MATCH_FADD_D,
+        name: "FADD_D",
+        operation: |cpu, inst, pc, ops| fp_binary::<F64>(cpu, inst, ops, softfloat::add::<F64>),
+    },
+    Instruction {
+        mask: MASK_FSUB_D,
+        match_data: MATCH_FSUB_D,
+        name: "FSUB_D",
+        operation: |cpu, inst, pc, ops| fp_binary::<F64>(cpu, inst, ops, softfloat::sub::<F64>),
+    },
+    Instruction {
+        mask: MASK_FMUL_D,
+        match_data: MATCH_FMUL_D,
+        name: "FMUL_D",
+        operation: |cpu, inst, pc, ops| fp_binary::<F64>(cpu, inst, ops, softfloat::mul::<F64>),
+    },
+    Instruction {
+        mask: MASK_FDIV_D,
+        match_data: MATCH_FDIV_D,
+        name: "FDIV_D",
+        operation: |cpu, inst, pc, ops| fp_binary::<F64>(cpu, inst, ops, softfloat::div::<F64>),
+    },
+    Instruction {
+        mask: MASK_FSQRT_D,
+        match_data: MATCH_FSQRT_D,
+        name: "FSQRT_D",
+        operation: |cpu, inst, pc, ops| fp_sqrt::<F64>(cpu, inst, ops),
+    },
+    Instruction {
+        mask: MASK_FSGNJ_D,
+        match_data: MATCH_FSGNJ_D,
+        name: "FSGNJ_D",
+        operation: |cpu, inst, pc, ops| fp_sgnj::<F64>(cpu, inst, ops, |rs1, rs2| rs2),
+    },
+    Instruction {
+        mask: MASK_FSGNJN_D,
+        match_data: MATCH_FSGNJN_D,
+        name: "FSGNJN_D",
+        operation: |cpu, inst, pc, ops| fp_sgnj::<F64>(cpu, inst, ops, |rs1, rs2| !rs2),
+    },
+    Instruction {
+        mask: MASK_FSGNJX_D,
+        match_data: MATCH_FSGNJX_D,
+        name: "FSGNJX_D",
+        operation: |cpu, inst, pc, ops| fp_sgnj::<F64>(cpu, inst, ops, |rs1, rs2| rs1 ^ rs2),
+    },
+    Instruction {
+        mask: MASK_FMIN_D,
+        match_data: MATCH_FMIN_D,
+        name: "FMIN_D",
+        operation: |cpu, inst, pc, ops| fp_min_max::<F64>(cpu, inst, ops, softfloat::min::<F64>),
+    },
+    Instruction {
+        mask: MASK_FMAX_D,
+        match_data: MATCH_FMAX_D,
+        name: "FMAX_D",
+        operation: |cpu, inst, pc, ops| fp_min_max::<F64>(cpu, inst, ops, softfloat::max::<F64>),
+    },
+    Instruction {
+        mask: MASK_FCVT_W_D,
+        match_data: MATCH_FCVT_W_D,
+        name: "FCVT_W_D",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F64>(cpu, inst, ops, true, 32),
+    },
+    Instruction {
+        mask: MASK_FCVT_WU_D,
+        match_data: MATCH_FCVT_WU_D,
+        name: "FCVT_WU_D",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F64>(cpu, inst, ops, false, 32),
+    },
+    Instruction {
+        mask: MASK_FCVT_D_W,
+        match_data: MATCH_FCVT_D_W,
+        name: "FCVT_D_W",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F64>(cpu, inst, ops, true, 32),
+    },
+    Instruction {
+        mask: MASK_FCVT_D_WU,
+        match_data: MATCH_FCVT_D_WU,
+        name: "FCVT_D_WU",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F64>(cpu, inst, ops, false, 32),
+    },
+    Instruction {
+        mask: MASK_FEQ_D,
+        match_data: MATCH_FEQ_D,
+        name: "FEQ_D",
+        operation: |cpu, inst, pc, ops| fp_compare::<F64>(cpu, inst, ops, softfloat::eq::<F64>),
+    },
+    Instruction {
+        mask: MASK_FLT_D,
+        match_data: MATCH_FLT_D,
+        name: "FLT_D",
+        operation: |cpu, inst, pc, ops| fp_compare::<F64>(cpu, inst, ops, softfloat::lt::<F64>),
+    },
+    Instruction {
+        mask: MASK_FLE_D,
+        match_data: MATCH_FLE_D,
+        name: "FLE_D",
+        operation: |cpu, inst, pc, ops| fp_compare::<F64>(cpu, inst, ops, softfloat::le::<F64>),
+    },
+    Instruction {
+        mask: MASK_FCLASS_D,
+        match_data: MATCH_FCLASS_D,
+        name: "FCLASS_D",
+        operation: |cpu, inst, pc, ops| fp_classify::<F64>(cpu, inst, ops),
+    },
+    Instruction {
+        mask: MASK_FCVT_S_D,
+        match_data: MATCH_FCVT_S_D,
+        name: "FCVT_S_D",
+        operation: |cpu, inst, pc, ops| fp_convert::<F64, F32>(cpu, inst, ops),
+    },
+    Instruction {
+        mask: MASK_FCVT_D_S,
+        match_data: MATCH_FCVT_D_S,
+        name: "FCVT_D_S",
+        operation: |cpu, inst, pc, ops| fp_convert::<F32, F64>(cpu, inst, ops),
+    },
+];
+
+// conversions between double-precision and 64-bit integers, RV64D only
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_D_RV64: &[Instruction] = &[
+    Instruction {
+        mask: MASK_FCVT_L_D,
+        match_data: MATCH_FCVT_L_D,
+        name: "FCVT_L_D",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F64>(cpu, inst, ops, true, 64),
+    },
+    Instruction {
+        mask: MASK_FCVT_LU_D,
+        match_data: MATCH_FCVT_LU_D,
+        name: "FCVT_LU_D",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F64>(cpu, inst, ops, false, 64),
+    },
+    Instruction {
+        mask: MASK_FCVT_D_L,
+        match_data: MATCH_FCVT_D_L,
+        name: "FCVT_D_L",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F64>(cpu, inst, ops, true, 64),
+    },
+    Instruction {
+        mask: MASK_FCVT_D_LU,
+        match_data: MATCH_FCVT_D_LU,
+        name: "FCVT_D_LU",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F64>(cpu, inst, ops, false, 64),
+    },
+    Instruction {
+        mask: MASK_FMV_X_D,
+        match_data: MATCH_FMV_X_D,
+        name: "FMV_X_D",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = f[rs1][63:0]
+            fp_check_enable(cpu, inst)?;
+            let rs1 = cpu.fpr.read(ops.rs1);
+            cpu.gpr.write(ops.rd, rs1);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_FMV_D_X,
+        match_data: MATCH_FMV_D_X,
+        name: "FMV_D_X",
+        operation: |cpu, inst, pc, ops| {
+            // f[rd] = x[rs1][63:0]
+            fp_check_enable(cpu, inst)?;
+            let rs1 = cpu.gpr.read(ops.rs1);
+            cpu.fpr.write(ops.rd, rs1);
+            fp_set_dirty(cpu);
+            Ok(())
+        },
+    },
+];
@@ -0,0 +1,475 @@
+use crate::rv64core::{
+    cpu_core::CpuCore,
+    inst::inst_base::*,
+    softfloat::{self, FFlags, FloatFormat, RoundingMode, F32, F64},
+    traptype::TrapType,
+};
+
+// All floating-point instructions raise an illegal instruction exception when mstatus.FS is Off,
+// or when vsstatus.FS is Off in VS/VU-mode
+pub fn fp_check_enable(cpu: &CpuCore, inst: u32) -> Result<(), TrapType> {
+    let vs_fs_off = cpu.cur_virt.get() && cpu.csr_regs.vsstatus.get().fs_off();
+    match cpu.csr_regs.xstatus.get().fs_off() || vs_fs_off {
+        true => Err(TrapType::IllegalInstruction(inst.into())),
+        false => Ok(()),
+    }
+}
+
+// rm field [14:12], 0b111 selects the dynamic rounding mode in frm
+pub fn fp_get_rm(cpu: &CpuCore, inst: u32) -> Result<RoundingMode, TrapType> {
+    let rm = match ((inst >> 12) & 0x7) as u8 {
+        0b111 => cpu.csr_regs.fcsr.get().frm(),
+        rm => rm,
+    };
+    RoundingMode::from_bits(rm).ok_or(TrapType::IllegalInstruction(inst.into()))
+}
+
+pub fn fp_set_dirty(cpu: &mut CpuCore) {
+    let mut mstatus = cpu.csr_regs.xstatus.get();
+    mstatus.set_fs_dirty();
+    cpu.csr_regs.xstatus.set(mstatus);
+    if cpu.cur_virt.get() {
+        let mut vsstatus = cpu.csr_regs.vsstatus.get();
+        vsstatus.set_fs_dirty();
+        cpu.csr_regs.vsstatus.set(vsstatus);
+    }
+}
+
+// accrue exception flags into fflags, which also dirties the fp state
+pub fn fp_accrue_flags(cpu: &mut CpuCore, flags: FFlags) {
+    let flags = u8::from(flags);
+    if flags != 0 {
+        let mut fcsr = cpu.csr_regs.fcsr.get();
+        fcsr.set_fflags(fcsr.fflags() | flags);
+        cpu.csr_regs.fcsr.set(fcsr);
+        fp_set_dirty(cpu);
+    }
+}
+
+// register access for each precision, narrower values are NaN-boxed
+pub trait FpRegister: FloatFormat {
+    fn read_reg(cpu: &CpuCore, idx: u64) -> u64;
+    fn write_reg(cpu: &mut CpuCore, idx: u64, data: u64);
+}
+
+impl FpRegister for F32 {
+    fn read_reg(cpu: &CpuCore, idx: u64) -> u64 {
+        cpu.fpr.read_f32(idx) as u64
+    }
+    fn write_reg(cpu: &mut CpuCore, idx: u64, data: u64) {
+        cpu.fpr.write_f32(idx, data as u32);
+    }
+}
+
+impl FpRegister for F64 {
+    fn read_reg(cpu: &CpuCore, idx: u64) -> u64 {
+        cpu.fpr.read(idx)
+    }
+    fn write_reg(cpu: &mut CpuCore, idx: u64, data: u64) {
+        cpu.fpr.write(idx, data);
+    }
+}
+
+type FpBinaryOp = fn(u64, u64, RoundingMode, &mut FFlags) -> u64;
+
+// f[rd] = op(f[rs1], f[rs2])
+pub fn fp_binary<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    op: FpBinaryOp,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rm = fp_get_rm(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+    let rs2 = F::read_reg(cpu, ops.rs2);
+
+    let mut flags = FFlags::new();
+    let ret = op(rs1, rs2, rm, &mut flags);
+    F::write_reg(cpu, ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+// f[rd] = (+/-)(f[rs1] * f[rs2]) (+/-) f[rs3], the operands carry rs3 in imm
+pub fn fp_fused<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    negate_product: bool,
+    negate_addend: bool,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rm = fp_get_rm(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+    let rs2 = F::read_reg(cpu, ops.rs2);
+    let rs3 = F::read_reg(cpu, ops.imm);
+
+    let mut flags = FFlags::new();
+    let ret = softfloat::mul_add::<F>(rs1, rs2, rs3, negate_product, negate_addend, rm, &mut flags);
+    F::write_reg(cpu, ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+// f[rd] = sqrt(f[rs1])
+pub fn fp_sqrt<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rm = fp_get_rm(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+
+    let mut flags = FFlags::new();
+    let ret = softfloat::sqrt::<F>(rs1, rm, &mut flags);
+    F::write_reg(cpu, ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+// f[rd] = op(f[rs1], f[rs2]), FMIN/FMAX have no rounding mode
+pub fn fp_min_max<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    op: fn(u64, u64, &mut FFlags) -> u64,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+    let rs2 = F::read_reg(cpu, ops.rs2);
+
+    let mut flags = FFlags::new();
+    let ret = op(rs1, rs2, &mut flags);
+    F::write_reg(cpu, ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+// sign injection never raises exceptions, the result sign comes from `sign_of(rs1, rs2)`
+pub fn fp_sgnj<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    sign_of: fn(u64, u64) -> u64,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+    let rs2 = F::read_reg(cpu, ops.rs2);
+    let sign = sign_of(rs1, rs2) & F::SIGN_MASK;
+    F::write_reg(cpu, ops.rd, (rs1 & !F::SIGN_MASK) | sign);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+// x[rd] = cmp(f[rs1], f[rs2])
+pub fn fp_compare<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    cmp: fn(u64, u64, &mut FFlags) -> bool,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+    let rs2 = F::read_reg(cpu, ops.rs2);
+
+    let mut flags = FFlags::new();
+    let ret = cmp(rs1, rs2, &mut flags);
+    cpu.gpr.write(ops.rd, ret as u64);
+    fp_accrue_flags(cpu, flags);
+    Ok(())
+}
+
+// x[rd] = fclass(f[rs1])
+pub fn fp_classify<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+    cpu.gpr.write(ops.rd, softfloat::classify::<F>(rs1));
+    Ok(())
+}
+
+// x[rd] = convert f[rs1] to an integer
+pub fn fp_to_int<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    signed: bool,
+    width: u32,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rm = fp_get_rm(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+
+    let mut flags = FFlags::new();
+    let ret = softfloat::to_int::<F>(rs1, signed, width, rm, &mut flags);
+    cpu.gpr.write(ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    Ok(())
+}
+
+// f[rd] = convert x[rs1] from an integer
+pub fn fp_from_int<F: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+    signed: bool,
+    width: u32,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rm = fp_get_rm(cpu, inst)?;
+    let rs1 = cpu.gpr.read(ops.rs1);
+
+    let mut flags = FFlags::new();
+    let ret = softfloat::from_int::<F>(rs1, signed, width, rm, &mut flags);
+    F::write_reg(cpu, ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+// f[rd] = convert f[rs1] from format `F` to format `T`
+pub fn fp_convert<F: FpRegister, T: FpRegister>(
+    cpu: &mut CpuCore,
+    inst: u32,
+    ops: &Operands,
+) -> Result<(), TrapType> {
+    fp_check_enable(cpu, inst)?;
+    let rm = fp_get_rm(cpu, inst)?;
+    let rs1 = F::read_reg(cpu, ops.rs1);
+
+    let mut flags = FFlags::new();
+    let ret = softfloat::convert::<F, T>(rs1, rm, &mut flags);
+    T::write_reg(cpu, ops.rd, ret);
+    fp_accrue_flags(cpu, flags);
+    fp_set_dirty(cpu);
+    Ok(())
+}
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_F: &[Instruction] = &[
+    Instruction {
+        mask: MASK_FLW,
+        match_data: MATCH_FLW,
+        name: "FLW",
+        operation: |cpu, inst, pc, ops| {
+            // f[rd] = M[x[rs1] + sext(offset)][31:0]
+            fp_check_enable(cpu, inst)?;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            let mem_data = cpu.read(mem_addr, 4, AccessType::Load(mem_addr))?;
+            cpu.fpr.write_f32(ops.rd, mem_data as u32);
+            fp_set_dirty(cpu);
+
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_FSW,
+        match_data: MATCH_FSW,
+        name: "FSW",
+        operation: |cpu, inst, pc, ops| {
+            // M[x[rs1] + sext(offset)] = f[rs2][31:0], the bits are stored unmodified
+            fp_check_enable(cpu, inst)?;
+            let rs2 = cpu.fpr.read(ops.rs2) as u32;
+            let mem_addr = cpu.gpr.read(ops.rs1).wrapping_add(ops.imm);
+
+            cpu.write(mem_addr, rs2 as u64, 4, AccessType::Store(mem_addr))?;
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_FMADD_S,
+        match_data: MATCH_FMADD_S,
+        name: "FMADD_S",
+        operation: |cpu, inst, pc, ops| fp_fused::<F32>(cpu, inst, ops, false, false),
+    },
+    Instruction {
+        mask: MASK_FMSUB_S,
+        match_data: MATCH_FMSUB_S,
+        name: "FMSUB_S",
+        operation: |cpu, inst, pc, ops| fp_fused::<F32>(cpu, inst, ops, false, true),
+    },
+    Instruction {
+        mask: MASK_FNMSUB_S,
+        match_data: MATCH_FNMSUB_S,
+        name: "FNMSUB_S",
+        operation: |cpu, inst, pc, ops| fp_fused::<F32>(cpu, inst, ops, true, false),
+    },
+    Instruction {
+        mask: MASK_FNMADD_S,
+        match_data: MATCH_FNMADD_S,
+        name: "FNMADD_S",
+        operation: |cpu, inst, pc, ops| fp_fused::<F32>(cpu, inst, ops, true, true),
+    },
+    Instruction {
+        mask: MASK_FADD_S,
+        match_data: MATCH_FADD_S,
+        name: "FADD_S",
+        operation: |cpu, inst, pc, ops| fp_binary::<F32>(cpu, inst, ops, softfloat::add::<F32>),
+    },
+    Instruction {
+        mask: MASK_FSUB_S,
+        match_data: MATCH_FSUB_S,
+        name: "FSUB_S",
+        operation: |cpu, inst, pc, ops| fp_binary::<F32>(cpu, inst, ops, softfloat::sub::<F32>),
+    },
+    Instruction {
+        mask: MASK_FMUL_S,
+        match_data: MATCH_FMUL_S,
+        name: "FMUL_S",
+        operation: |cpu, inst, pc, ops| fp_binary::<F32>(cpu, inst, ops, softfloat::mul::<F32>),
+    },
+    Instruction {
+        mask: MASK_FDIV_S,
+        match_data: MATCH_FDIV_S,
+        name: "FDIV_S",
+        operation: |cpu, inst, pc, ops| fp_binary::<F32>(cpu, inst, ops, softfloat::div::<F32>),
+    },
+    Instruction {
+        mask: MASK_FSQRT_S,
+        match_data: MATCH_FSQRT_S,
+        name: "FSQRT_S",
+        operation: |cpu, inst, pc, ops| fp_sqrt::<F32>(cpu, inst, ops),
+    },
+    Instruction {
+        mask: MASK_FSGNJ_S,
+        match_data: MATCH_FSGNJ_S,
+        name: "FSGNJ_S",
+        operation: |cpu, inst, pc, ops| fp_sgnj::<F32>(cpu, inst, ops, |rs1, rs2| rs2),
+    },
+    Instruction {
+        mask: MASK_FSGNJN_S,
+        match_data: MATCH_FSGNJN_S,
+        name: "FSGNJN_S",
+        operation: |cpu, inst, pc, ops| fp_sgnj::<F32>(cpu, inst, ops, |rs1, rs2| !rs2),
+    },
+    Instruction {
+        mask: MASK_FSGNJX_S,
+        match_data: MATCH_FSGNJX_S,
+        name: "FSGNJX_S",
+        operation: |cpu, inst, pc, ops| fp_sgnj::<F32>(cpu, inst, ops, |rs1, rs2| rs1 ^ rs2),
+    },
+    Instruction {
+        mask: MASK_FMIN_S,
+        match_data: MATCH_FMIN_S,
+        name: "FMIN_S",
+        operation: |cpu, inst, pc, ops| fp_min_max::<F32>(cpu, inst, ops, softfloat::min::<F32>),
+    },
+    Instruction {
+        mask: MASK_FMAX_S,
+        match_data: MATCH_FMAX_S,
+        name: "FMAX_S",
+        operation: |cpu, inst, pc, ops| fp_min_max::<F32>(cpu, inst, ops, softfloat::max::<F32>),
+    },
+    Instruction {
+        mask: MASK_FCVT_W_S,
+        match_data: MATCH_FCVT_W_S,
+        name: "FCVT_W_S",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F32>(cpu, inst, ops, true, 32),
+    },
+    Instruction {
+        mask: MASK_FCVT_WU_S,
+        match_data: MATCH_FCVT_WU_S,
+        name: "FCVT_WU_S",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F32>(cpu, inst, ops, false, 32),
+    },
+    Instruction {
+        mask: MASK_FCVT_S_W,
+        match_data: MATCH_FCVT_S_W,
+        name: "FCVT_S_W",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F32>(cpu, inst, ops, true, 32),
+    },
+    Instruction {
+        mask: MASK_FCVT_S_WU,
+        match_data: MATCH_FCVT_S_WU,
+        name: "FCVT_S_WU",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F32>(cpu, inst, ops, false, 32),
+    },
+    Instruction {
+        mask: MASK_FEQ_S,
+        match_data: MATCH_FEQ_S,
+        name: "FEQ_S",
+        operation: |cpu, inst, pc, ops| fp_compare::<F32>(cpu, inst, ops, softfloat::eq::<F32>),
+    },
+    Instruction {
+        mask: MASK_FLT_S,
+        match_data: MATCH_FLT_S,
+        name: "FLT_S",
+        operation: |cpu, inst, pc, ops| fp_compare::<F32>(cpu, inst, ops, softfloat::lt::<F32>),
+    },
+    Instruction {
+        mask: MASK_FLE_S,
+        match_data: MATCH_FLE_S,
+        name: "FLE_S",
+        operation: |cpu, inst, pc, ops| fp_compare::<F32>(cpu, inst, ops, softfloat::le::<F32>),
+    },
+    Instruction {
+        mask: MASK_FCLASS_S,
+        match_data: MATCH_FCLASS_S,
+        name: "FCLASS_S",
+        operation: |cpu, inst, pc, ops| fp_classify::<F32>(cpu, inst, ops),
+    },
+    Instruction {
+        mask: MASK_FMV_X_W,
+        match_data: MATCH_FMV_X_W,
+        name: "FMV_X_W",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = sext(f[rs1][31:0]), the raw bits are moved without NaN unboxing
+            fp_check_enable(cpu, inst)?;
+            let rs1 = cpu.fpr.read(ops.rs1);
+            cpu.gpr.write(ops.rd, rs1 as i32 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_FMV_W_X,
+        match_data: MATCH_FMV_W_X,
+        name: "FMV_W_X",
+        operation: |cpu, inst, pc, ops| {
+            // f[rd] = x[rs1][31:0]
+            fp_check_enable(cpu, inst)?;
+            let rs1 = cpu.gpr.read(ops.rs1);
+            cpu.fpr.write_f32(ops.rd, rs1 as u32);
+            fp_set_dirty(cpu);
+            Ok(())
+        },
+    },
+];
+
+// conversions between single-precision and 64-bit integers, RV64F only
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_F_RV64: &[Instruction] = &[
+    Instruction {
+        mask: MASK_FCVT_L_S,
+        match_data: MATCH_FCVT_L_S,
+        name: "FCVT_L_S",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F32>(cpu, inst, ops, true, 64),
+    },
+    Instruction {
+        mask: MASK_FCVT_LU_S,
+        match_data: MATCH_FCVT_LU_S,
+        name: "FCVT_LU_S",
+        operation: |cpu, inst, pc, ops| fp_to_int::<F32>(cpu, inst, ops, false, 64),
+    },
+    Instruction {
+        mask: MASK_FCVT_S_L,
+        match_data: MATCH_FCVT_S_L,
+        name: "FCVT_S_L",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F32>(cpu, inst, ops, true, 64),
+    },
+    Instruction {
+        mask: MASK_FCVT_S_LU,
+        match_data: MATCH_FCVT_S_LU,
+        name: "FCVT_S_LU",
+        operation: |cpu, inst, pc, ops| fp_from_int::<F32>(cpu, inst, ops, false, 64),
+    },
+];
@@ -0,0 +1,207 @@
+use crate::rv64core::{cpu_core::CpuCore, inst::inst_base::*, traptype::TrapType};
+
+// HLV/HSV are only allowed in M-mode and HS-mode, or in U-mode when hstatus.HU=1.
+// Executed in VS/VU-mode they raise a virtual instruction exception.
+fn h_check_ls(cpu: &CpuCore, inst: u32) -> Result<(), TrapType> {
+    if cpu.cur_virt.get() {
+        return Err(TrapType::VirtualInstruction(inst.into()));
+    }
+    if cpu.cur_priv.get() == PrivilegeLevels::User && !cpu.csr_regs.hstatus.get().hu() {
+        return Err(TrapType::IllegalInstruction(inst.into()));
+    }
+    Ok(())
+}
+
+// HFENCE.VVMA and HFENCE.GVMA need HS-mode, HFENCE.GVMA is also trapped by mstatus.TVM
+fn h_check_fence(cpu: &CpuCore, inst: u32, check_tvm: bool) -> Result<(), TrapType> {
+    if cpu.cur_virt.get() {
+        return Err(TrapType::VirtualInstruction(inst.into()));
+    }
+    let require_priv = if check_tvm && cpu.csr_regs.xstatus.get().tvm() {
+        PrivilegeLevels::Machine
+    } else {
+        PrivilegeLevels::Supervisor
+    };
+    match require_priv.check_priv(cpu.cur_priv.get()) {
+        true => Ok(()),
+        false => Err(TrapType::IllegalInstruction(inst.into())),
+    }
+}
+
+// The access is translated and protected as though V=1 and the privilege were hstatus.SPVP.
+// HLVX also requires execute permission instead of read permission.
+fn hlv(cpu: &mut CpuCore, inst: u32, len: usize, hlvx: bool) -> Result<u64, TrapType> {
+    h_check_ls(cpu, inst)?;
+    let f = parse_format_r(inst);
+    let addr = cpu.gpr.read(f.rs1);
+
+    cpu.mmu.hlsv = true;
+    cpu.mmu.hlvx = hlvx;
+    let ret = cpu.read(addr, len, AccessType::Load(addr));
+    cpu.mmu.hlsv = false;
+    cpu.mmu.hlvx = false;
+    ret
+}
+
+fn hsv(cpu: &mut CpuCore, inst: u32, len: usize) -> Result<(), TrapType> {
+    h_check_ls(cpu, inst)?;
+    let f = parse_format_r(inst);
+    let addr = cpu.gpr.read(f.rs1);
+    let data = cpu.gpr.read(f.rs2);
+
+    cpu.mmu.hlsv = true;
+    let ret = cpu.write(addr, data, len, AccessType::Store(addr));
+    cpu.mmu.hlsv = false;
+    ret.map(|_| ())
+}
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_H: &[Instruction] = &[
+    Instruction {
+        mask: MASK_HLV_B,
+        match_data: MATCH_HLV_B,
+        name: "HLV_B",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 1, false)?;
+            cpu.gpr
+                .write(parse_format_r(inst).rd, data as i8 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLV_BU,
+        match_data: MATCH_HLV_BU,
+        name: "HLV_BU",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 1, false)?;
+            cpu.gpr.write(parse_format_r(inst).rd, data as u8 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLV_H,
+        match_data: MATCH_HLV_H,
+        name: "HLV_H",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 2, false)?;
+            cpu.gpr
+                .write(parse_format_r(inst).rd, data as i16 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLV_HU,
+        match_data: MATCH_HLV_HU,
+        name: "HLV_HU",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 2, false)?;
+            cpu.gpr.write(parse_format_r(inst).rd, data as u16 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLV_W,
+        match_data: MATCH_HLV_W,
+        name: "HLV_W",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 4, false)?;
+            cpu.gpr
+                .write(parse_format_r(inst).rd, data as i32 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLV_WU,
+        match_data: MATCH_HLV_WU,
+        name: "HLV_WU",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 4, false)?;
+            cpu.gpr.write(parse_format_r(inst).rd, data as u32 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLV_D,
+        match_data: MATCH_HLV_D,
+        name: "HLV_D",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 8, false)?;
+            cpu.gpr.write(parse_format_r(inst).rd, data);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLVX_HU,
+        match_data: MATCH_HLVX_HU,
+        name: "HLVX_HU",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 2, true)?;
+            cpu.gpr.write(parse_format_r(inst).rd, data as u16 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HLVX_WU,
+        match_data: MATCH_HLVX_WU,
+        name: "HLVX_WU",
+        operation: |cpu, inst, pc, ops| {
+            let data = hlv(cpu, inst, 4, true)?;
+            cpu.gpr.write(parse_format_r(inst).rd, data as u32 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_HSV_B,
+        match_data: MATCH_HSV_B,
+        name: "HSV_B",
+        operation: |cpu, inst, pc, ops| hsv(cpu, inst, 1),
+    },
+    Instruction {
+        mask: MASK_HSV_H,
+        match_data: MATCH_HSV_H,
+        name: "HSV_H",
+        operation: |cpu, inst, pc, ops| hsv(cpu, inst, 2),
+    },
+    Instruction {
+        mask: MASK_HSV_W,
+        match_data: MATCH_HSV_W,
+        name: "HSV_W",
+        operation: |cpu, inst, pc, ops| hsv(cpu, inst, 4),
+    },
+    Instruction {
+        mask: MASK_HSV_D,
+        match_data: MATCH_HSV_D,
+        name: "HSV_D",
+        operation: |cpu, inst, pc, ops| hsv(cpu, inst, 8),
+    },
+    // guest translations are never cached in the TLB, so the fences only check permissions
+    Instruction {
+        mask: MASK_HFENCE_VVMA,
+        match_data: MATCH_HFENCE_VVMA,
+        name: "HFENCE_VVMA",
+        operation: |cpu, inst, pc, ops| h_check_fence(cpu, inst, false),
+    },
+    Instruction {
+        mask: MASK_HFENCE_GVMA,
+        match_data: MATCH_HFENCE_GVMA,
+        name: "HFENCE_GVMA",
+        operation: |cpu, inst, pc, ops| h_check_fence(cpu, inst, true),
+    },
+];
+
+// HINVAL.VVMA and HINVAL.GVMA have the same permission checks as the HFENCE instructions
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_H_SVINVAL: &[Instruction] = &[
+    Instruction {
+        mask: MASK_HINVAL_VVMA,
+        match_data: MATCH_HINVAL_VVMA,
+        name: "HINVAL_VVMA",
+        operation: |cpu, inst, pc, ops| h_check_fence(cpu, inst, false),
+    },
+    Instruction {
+        mask: MASK_HINVAL_GVMA,
+        match_data: MATCH_HINVAL_GVMA,
+        name: "HINVAL_GVMA",
+        operation: |cpu, inst, pc, ops| h_check_fence(cpu, inst, true),
+    },
+];
@@ -0,0 +1,2883 @@
+use core::cmp::Ordering;
+
+use crate::rv64core::{
+    cpu_core::CpuCore, csr_regs_define::VtypeIn, inst::inst_base::*, traptype::TrapType,
+};
+
+// funct3 of the OP-V major opcode
+const OPIVV: u32 = 0b000;
+const OPMVV: u32 = 0b010;
+const OPIVI: u32 = 0b011;
+
+// nf [31:29] holds the number of fields (or registers) minus one, the segment forms
+// vlseg<nf>e/vsseg<nf>e and vl<nf>r share the entries of the single-field instructions
+const MASK_NF: u32 = 0xe000_0000;
+
+// All vector instructions raise an illegal instruction exception when mstatus.VS is Off,
+// or when vsstatus.VS is Off in VS/VU-mode
+pub fn v_check_enable(cpu: &CpuCore, inst: u32) -> Result<(), TrapType> {
+    let vs_vs_off = cpu.cur_virt.get() && cpu.csr_regs.vsstatus.get().vs_off();
+    match cpu.csr_regs.xstatus.get().vs_off() || vs_vs_off {
+        true => Err(TrapType::IllegalInstruction(inst.into())),
+        false => Ok(()),
+    }
+}
+
+pub fn v_set_dirty(cpu: &mut CpuCore) {
+    let mut mstatus = cpu.csr_regs.xstatus.get();
+    mstatus.set_vs_dirty();
+    cpu.csr_regs.xstatus.set(mstatus);
+    if cpu.cur_virt.get() {
+        let mut vsstatus = cpu.csr_regs.vsstatus.get();
+        vsstatus.set_vs_dirty();
+        cpu.csr_regs.vsstatus.set(vsstatus);
+    }
+}
+
+// reserved encodings and unsupported configurations are illegal
+fn v_require(cond: bool, inst: u32) -> Result<(), TrapType> {
+    match cond {
+        true => Ok(()),
+        false => Err(TrapType::IllegalInstruction(inst.into())),
+    }
+}
+
+fn sext(data: u64, width: usize) -> i64 {
+    ((data << (64 - width)) as i64) >> (64 - width)
+}
+
+fn umax(width: usize) -> u64 {
+    u64::MAX >> (64 - width)
+}
+
+fn smax(width: usize) -> i64 {
+    (umax(width) >> 1) as i64
+}
+
+fn smin(width: usize) -> i64 {
+    !smax(width)
+}
+
+// VLMAX = LMUL * VLEN / SEW, lmul is log2(LMUL)
+pub fn v_vlmax(vlen: usize, sew: usize, lmul: i32) -> usize {
+    match lmul >= 0 {
+        true => (vlen << lmul) / sew,
+        false => (vlen >> -lmul) / sew,
+    }
+}
+
+// number of registers in a group, emul is log2(EMUL)
+fn v_regs(emul: i32) -> usize {
+    1 << emul.max(0)
+}
+
+fn v_aligned(reg: usize, emul: i32) -> bool {
+    reg.is_multiple_of(v_regs(emul))
+}
+
+// The destination group may only overlap a source group of a different EEW in its
+// lowest-numbered part when narrowing, or in its highest-numbered part when widening
+// from a source with EMUL >= 1. Widths are in bits, a mask register has EEW 1.
+fn v_overlap_ok(
+    vd: usize,
+    vd_emul: i32,
+    vd_eew: usize,
+    vs: usize,
+    vs_emul: i32,
+    vs_eew: usize,
+) -> bool {
+    let (vd_regs, vs_regs) = (v_regs(vd_emul), v_regs(vs_emul));
+    if vd + vd_regs <= vs || vs + vs_regs <= vd {
+        return true;
+    }
+    match vd_eew.cmp(&vs_eew) {
+        Ordering::Equal => true,
+        Ordering::Less => vd == vs,
+        Ordering::Greater => vs_emul >= 0 && vs + vs_regs == vd + vd_regs,
+    }
+}
+
+fn v_check_src(
+    inst: u32,
+    vd: usize,
+    vd_emul: i32,
+    vd_eew: usize,
+    vs: usize,
+    vs_emul: i32,
+    vs_eew: usize,
+) -> Result<(), TrapType> {
+    v_require(
+        v_aligned(vs, vs_emul) && v_overlap_ok(vd, vd_emul, vd_eew, vs, vs_emul, vs_eew),
+        inst,
+    )
+}
+
+// the configuration set by vsetvl{i}
+#[derive(Clone, Copy)]
+struct VState {
+    sew: usize, // bits
+    lmul: i32,  // log2(LMUL)
+    vl: usize,
+    vstart: usize,
+    vlmax: usize,
+}
+
+impl VState {
+    // SEW in bytes
+    fn sewb(&self) -> usize {
+        self.sew / 8
+    }
+}
+
+fn v_state(cpu: &CpuCore, inst: u32) -> Result<VState, TrapType> {
+    v_check_enable(cpu, inst)?;
+    let vtype = cpu.csr_regs.vtype.get();
+    v_require(!vtype.vill(), inst)?;
+    Ok(VState {
+        sew: vtype.sew(),
+        lmul: vtype.lmul(),
+        vl: cpu.csr_regs.vl.get() as usize,
+        vstart: cpu.csr_regs.vstart.get() as usize,
+        vlmax: v_vlmax(cpu.config.vlen(), vtype.sew(), vtype.lmul()),
+    })
+}
+
+// The AVL of vsetvli and vsetvl: rs1 = x0 requests VLMAX, or keeps vl when rd is also x0
+fn v_avl(cpu: &CpuCore, rd: u64, rs1: u64) -> u64 {
+    match (rd, rs1) {
+        (0, 0) => cpu.csr_regs.vl.get(),
+        (_, 0) => u64::MAX,
+        (_, rs1) => cpu.gpr.read(rs1),
+    }
+}
+
+// vl = min(AVL, VLMAX), an unsupported vtype sets vill and clears vl
+fn v_set_config(
+    cpu: &mut CpuCore,
+    inst: u32,
+    rd: u64,
+    avl: u64,
+    vtype: u64,
+) -> Result<(), TrapType> {
+    v_check_enable(cpu, inst)?;
+    let elen = cpu.config.elen();
+    let new = VtypeIn::from(vtype);
+    // fractional LMUL must still hold one SEW element per ELEN
+    let supported = vtype >> 8 == 0
+        && new.vsew() <= 3
+        && new.vlmul() != 0b100
+        && new.sew() <= elen
+        && (new.lmul() >= 0 || new.sew() <= elen >> -new.lmul());
+
+    let vl = match supported {
+        true => {
+            let vlmax = v_vlmax(cpu.config.vlen(), new.sew(), new.lmul());
+            cpu.csr_regs.vtype.set(new);
+            avl.min(vlmax as u64)
+        }
+        false => {
+            cpu.csr_regs.vtype.set(VtypeIn::new().with_vill(true));
+            0
+        }
+    };
+    cpu.csr_regs.vl.set(vl);
+    cpu.csr_regs.vstart.set(0);
+    cpu.gpr.write(rd, vl);
+    v_set_dirty(cpu);
+    Ok(())
+}
+
+// element width and fixed-point state seen by the element operations
+struct VCtx {
+    sew: usize,
+    vxrm: u8,
+    vxsat: bool,
+}
+
+fn v_ctx(cpu: &CpuCore, sew: usize) -> VCtx {
+    VCtx {
+        sew,
+        vxrm: cpu.csr_regs.vcsr.get().vxrm(),
+        vxsat: false,
+    }
+}
+
+// a completed instruction resets vstart and accrues the saturation flag
+fn v_finish(cpu: &mut CpuCore, vxsat: bool) {
+    if vxsat {
+        let vcsr = cpu.csr_regs.vcsr.get();
+        cpu.csr_regs.vcsr.set(vcsr.with_vxsat(true));
+    }
+    cpu.csr_regs.vstart.set(0);
+    v_set_dirty(cpu);
+}
+
+fn v_active(cpu: &CpuCore, vm: bool, idx: usize) -> bool {
+    vm || cpu.vpr.read_mask(0, idx)
+}
+
+fn v_is_vv(inst: u32) -> bool {
+    matches!((inst >> 12) & 0x7, OPIVV | OPMVV)
+}
+
+// shifts, gathers and slides take an unsigned immediate
+fn v_uimm(inst: u32) -> bool {
+    matches!(
+        inst >> 26,
+        0b001100 | 0b001110 | 0b001111 | 0b100101 | 0b101000..=0b101111
+    )
+}
+
+// the scalar operand of the .vx and .vi forms
+fn v_scalar(cpu: &CpuCore, inst: u32, f: &FormatV) -> u64 {
+    match (inst >> 12) & 0x7 {
+        OPIVI if v_uimm(inst) => f.vs1 as u64,
+        OPIVI => f.simm5() as u64,
+        _ => cpu.gpr.read(f.vs1 as u64),
+    }
+}
+
+// element idx of vs1, or the scalar operand, truncated to width bits
+fn v_src1(cpu: &CpuCore, inst: u32, f: &FormatV, idx: usize, width: usize) -> u64 {
+    match v_is_vv(inst) {
+        true => cpu.vpr.read_elem(f.vs1, width / 8, idx),
+        false => v_scalar(cpu, inst, f) & umax(width),
+    }
+}
+
+// EEW of vd and vs2 relative to SEW, vs1 and the scalar operand are always SEW wide
+#[derive(Clone, Copy)]
+enum VWidth {
+    Single,
+    Widen,
+    // vd and vs2 are both 2*SEW wide (.wv and .wx)
+    WidenW,
+    Narrow,
+}
+
+impl VWidth {
+    // log2 of EEW / SEW for vd and vs2
+    fn scale(self) -> (i32, i32) {
+        match self {
+            VWidth::Single => (0, 0),
+            VWidth::Widen => (1, 0),
+            VWidth::WidenW => (1, 1),
+            VWidth::Narrow => (0, 1),
+        }
+    }
+}
+
+type VBinaryOp = fn(u64, u64, &mut VCtx) -> u64;
+type VTernaryOp = fn(u64, u64, u64, usize) -> u64;
+
+// vd[i] = op(vs2[i], vs1[i] or the scalar operand, vd[i]) for the active body elements
+fn v_arith(
+    cpu: &mut CpuCore,
+    inst: u32,
+    width: VWidth,
+    op: impl Fn(u64, u64, u64, &mut VCtx) -> u64,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    let (vd_scale, vs2_scale) = width.scale();
+    let (vd_eew, vs2_eew) = (st.sew << vd_scale, st.sew << vs2_scale);
+    let (vd_emul, vs2_emul) = (st.lmul + vd_scale, st.lmul + vs2_scale);
+
+    v_require(
+        vd_emul.max(vs2_emul) <= 3 && vd_eew.max(vs2_eew) <= cpu.config.elen(),
+        inst,
+    )?;
+    v_require(v_aligned(f.vd, vd_emul) && (f.vm || f.vd != 0), inst)?;
+    v_check_src(inst, f.vd, vd_emul, vd_eew, f.vs2, vs2_emul, vs2_eew)?;
+    if v_is_vv(inst) {
+        v_check_src(inst, f.vd, vd_emul, vd_eew, f.vs1, st.lmul, st.sew)?;
+    }
+
+    let mut ctx = v_ctx(cpu, st.sew);
+    for i in st.vstart..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let a = cpu.vpr.read_elem(f.vs2, vs2_eew / 8, i);
+        let b = v_src1(cpu, inst, &f, i, st.sew);
+        let c = cpu.vpr.read_elem(f.vd, vd_eew / 8, i);
+        cpu.vpr
+            .write_elem(f.vd, vd_eew / 8, i, op(a, b, c, &mut ctx));
+    }
+    v_finish(cpu, ctx.vxsat);
+    Ok(())
+}
+
+// vd[i] = op(vs2[i], vs1[i]), all SEW wide
+fn v_binary(cpu: &mut CpuCore, inst: u32, op: VBinaryOp) -> Result<(), TrapType> {
+    v_arith(cpu, inst, VWidth::Single, |a, b, _, ctx| op(a, b, ctx))
+}
+
+// 2*SEW = op(SEW, SEW), or 2*SEW = op(2*SEW, SEW) for the .w forms
+fn v_widen(cpu: &mut CpuCore, inst: u32, wide_vs2: bool, op: VBinaryOp) -> Result<(), TrapType> {
+    let width = match wide_vs2 {
+        true => VWidth::WidenW,
+        false => VWidth::Widen,
+    };
+    v_arith(cpu, inst, width, |a, b, _, ctx| op(a, b, ctx))
+}
+
+// SEW = op(2*SEW, SEW)
+fn v_narrow(cpu: &mut CpuCore, inst: u32, op: VBinaryOp) -> Result<(), TrapType> {
+    v_arith(cpu, inst, VWidth::Narrow, |a, b, _, ctx| op(a, b, ctx))
+}
+
+// vd[i] = op(vs2[i], vs1[i], vd[i]) for the multiply-add instructions
+fn v_ternary(cpu: &mut CpuCore, inst: u32, wide: bool, op: VTernaryOp) -> Result<(), TrapType> {
+    let width = match wide {
+        true => VWidth::Widen,
+        false => VWidth::Single,
+    };
+    v_arith(cpu, inst, width, |a, b, c, ctx| op(a, b, c, ctx.sew))
+}
+
+// vd.mask[i] = op(vs2[i], vs1[i])
+fn v_compare(
+    cpu: &mut CpuCore,
+    inst: u32,
+    op: fn(u64, u64, usize) -> bool,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_check_src(inst, f.vd, 0, 1, f.vs2, st.lmul, st.sew)?;
+    if v_is_vv(inst) {
+        v_check_src(inst, f.vd, 0, 1, f.vs1, st.lmul, st.sew)?;
+    }
+
+    for i in st.vstart..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let a = cpu.vpr.read_elem(f.vs2, st.sewb(), i);
+        let b = v_src1(cpu, inst, &f, i, st.sew);
+        cpu.vpr.write_mask(f.vd, i, op(a, b, st.sew));
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vadc/vsbc: vd[i] = op(vs2[i], vs1[i], v0.mask[i]) for all body elements
+fn v_carry(cpu: &mut CpuCore, inst: u32, op: fn(u64, u64, bool) -> u64) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(v_aligned(f.vd, st.lmul) && f.vd != 0, inst)?;
+    v_check_src(inst, f.vd, st.lmul, st.sew, f.vs2, st.lmul, st.sew)?;
+    if v_is_vv(inst) {
+        v_check_src(inst, f.vd, st.lmul, st.sew, f.vs1, st.lmul, st.sew)?;
+    }
+
+    for i in st.vstart..st.vl {
+        let a = cpu.vpr.read_elem(f.vs2, st.sewb(), i);
+        let b = v_src1(cpu, inst, &f, i, st.sew);
+        let carry = cpu.vpr.read_mask(0, i);
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, op(a, b, carry));
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vmadc/vmsbc: vd.mask[i] = carry out of op, the carry in is v0.mask[i] when vm=0
+fn v_carry_out(
+    cpu: &mut CpuCore,
+    inst: u32,
+    op: fn(u64, u64, bool, usize) -> bool,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_check_src(inst, f.vd, 0, 1, f.vs2, st.lmul, st.sew)?;
+    if v_is_vv(inst) {
+        v_check_src(inst, f.vd, 0, 1, f.vs1, st.lmul, st.sew)?;
+    }
+
+    for i in st.vstart..st.vl {
+        let a = cpu.vpr.read_elem(f.vs2, st.sewb(), i);
+        let b = v_src1(cpu, inst, &f, i, st.sew);
+        let carry = !f.vm && cpu.vpr.read_mask(0, i);
+        cpu.vpr.write_mask(f.vd, i, op(a, b, carry, st.sew));
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vmerge: vd[i] = v0.mask[i] ? vs1[i] : vs2[i], vmv.v: vd[i] = vs1[i]
+fn v_merge(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(v_aligned(f.vd, st.lmul) && (f.vm || f.vd != 0), inst)?;
+    v_require(v_aligned(f.vs2, st.lmul), inst)?;
+    v_require(!v_is_vv(inst) || v_aligned(f.vs1, st.lmul), inst)?;
+
+    for i in st.vstart..st.vl {
+        let data = match f.vm || cpu.vpr.read_mask(0, i) {
+            true => v_src1(cpu, inst, &f, i, st.sew),
+            false => cpu.vpr.read_elem(f.vs2, st.sewb(), i),
+        };
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vzext/vsext: vd[i] = extend(vs2[i]), the source EEW is SEW / 2^frac
+fn v_extend(cpu: &mut CpuCore, inst: u32, frac: i32, signed: bool) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    let (src_eew, src_emul) = (st.sew >> frac, st.lmul - frac);
+    v_require(src_eew >= 8 && src_emul >= -3, inst)?;
+    v_require(v_aligned(f.vd, st.lmul) && (f.vm || f.vd != 0), inst)?;
+    v_check_src(inst, f.vd, st.lmul, st.sew, f.vs2, src_emul, src_eew)?;
+
+    for i in st.vstart..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let data = cpu.vpr.read_elem(f.vs2, src_eew / 8, i);
+        let data = match signed {
+            true => sext(data, src_eew) as u64,
+            false => data,
+        };
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vd[0] = fold(op, vs1[0], active vs2[i]), widening reductions extend vs2[i] to 2*SEW
+fn v_reduce(
+    cpu: &mut CpuCore,
+    inst: u32,
+    widen: Option<bool>,
+    op: fn(u64, u64, usize) -> u64,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    let acc_sew = match widen {
+        Some(_) => st.sew * 2,
+        None => st.sew,
+    };
+    v_require(
+        acc_sew <= cpu.config.elen() && v_aligned(f.vs2, st.lmul),
+        inst,
+    )?;
+    // reductions can not be interrupted
+    v_require(st.vstart == 0, inst)?;
+
+    if st.vl > 0 {
+        let mut acc = cpu.vpr.read_elem(f.vs1, acc_sew / 8, 0);
+        for i in 0..st.vl {
+            if !v_active(cpu, f.vm, i) {
+                continue;
+            }
+            let data = cpu.vpr.read_elem(f.vs2, st.sewb(), i);
+            let data = match widen {
+                Some(true) => sext(data, st.sew) as u64,
+                _ => data,
+            };
+            acc = op(acc, data, acc_sew) & umax(acc_sew);
+        }
+        cpu.vpr.write_elem(f.vd, acc_sew / 8, 0, acc);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vd.mask[i] = op(vs2.mask[i], vs1.mask[i])
+fn v_mask_logical(
+    cpu: &mut CpuCore,
+    inst: u32,
+    op: fn(bool, bool) -> bool,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    for i in st.vstart..st.vl {
+        let a = cpu.vpr.read_mask(f.vs2, i);
+        let b = cpu.vpr.read_mask(f.vs1, i);
+        cpu.vpr.write_mask(f.vd, i, op(a, b));
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vcpop.m: x[rd] = the number of active set bits of vs2
+// vfirst.m: x[rd] = the index of the first active set bit, or -1
+fn v_mask_scalar(cpu: &mut CpuCore, inst: u32, first: bool) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(st.vstart == 0, inst)?;
+
+    let mut bits = (0..st.vl).filter(|i| v_active(cpu, f.vm, *i) && cpu.vpr.read_mask(f.vs2, *i));
+    let data = match first {
+        true => bits.next().map_or(u64::MAX, |i| i as u64),
+        false => bits.count() as u64,
+    };
+    cpu.gpr.write(f.vd as u64, data);
+    cpu.csr_regs.vstart.set(0);
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VSetFirst {
+    // set-before-first, set-including-first and set-only-first
+    Before,
+    Including,
+    Only,
+}
+
+// vmsbf/vmsif/vmsof
+fn v_set_first(cpu: &mut CpuCore, inst: u32, kind: VSetFirst) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(st.vstart == 0 && f.vd != f.vs2 && (f.vm || f.vd != 0), inst)?;
+
+    let mut found = false;
+    for i in 0..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let bit = cpu.vpr.read_mask(f.vs2, i);
+        let data = match kind {
+            VSetFirst::Before => !found && !bit,
+            VSetFirst::Including => !found,
+            VSetFirst::Only => !found && bit,
+        };
+        found |= bit;
+        cpu.vpr.write_mask(f.vd, i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// viota.m: vd[i] = the number of active set bits of vs2 below element i
+fn v_iota(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(
+        st.vstart == 0 && v_aligned(f.vd, st.lmul) && (f.vm || f.vd != 0),
+        inst,
+    )?;
+    v_require(
+        v_overlap_ok(f.vd, st.lmul, st.sew, f.vs2, 0, 1) && f.vd != f.vs2,
+        inst,
+    )?;
+
+    let mut count = 0;
+    for i in 0..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, count);
+        count += cpu.vpr.read_mask(f.vs2, i) as u64;
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vid.v: vd[i] = i
+fn v_id(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(v_aligned(f.vd, st.lmul) && (f.vm || f.vd != 0), inst)?;
+
+    for i in st.vstart..st.vl {
+        if v_active(cpu, f.vm, i) {
+            cpu.vpr.write_elem(f.vd, st.sewb(), i, i as u64);
+        }
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vslideup: vd[i + offset] = vs2[i], vslide1up: vd[0] = x[rs1], vd[i + 1] = vs2[i]
+fn v_slide_up(
+    cpu: &mut CpuCore,
+    inst: u32,
+    offset: u64,
+    fill: Option<u64>,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(v_aligned(f.vd, st.lmul) && v_aligned(f.vs2, st.lmul), inst)?;
+    v_require(f.vd != f.vs2 && (f.vm || f.vd != 0), inst)?;
+
+    let start = st.vstart.max(offset.min(st.vl as u64) as usize);
+    if let Some(data) = fill {
+        if st.vstart == 0 && st.vl > 0 && v_active(cpu, f.vm, 0) {
+            cpu.vpr.write_elem(f.vd, st.sewb(), 0, data);
+        }
+    }
+    for i in start..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let data = cpu.vpr.read_elem(f.vs2, st.sewb(), i - offset as usize);
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vslidedown: vd[i] = vs2[i + offset], zero past VLMAX
+// vslide1down: vd[i] = vs2[i + 1], x[rs1] for the last body element
+fn v_slide_down(
+    cpu: &mut CpuCore,
+    inst: u32,
+    offset: u64,
+    fill: Option<u64>,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(v_aligned(f.vd, st.lmul) && v_aligned(f.vs2, st.lmul), inst)?;
+    v_require(f.vm || f.vd != 0, inst)?;
+
+    for i in st.vstart..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let src = (i as u64).saturating_add(offset);
+        let data = match fill {
+            Some(data) if i + 1 == st.vl => data,
+            _ if src < st.vlmax as u64 => cpu.vpr.read_elem(f.vs2, st.sewb(), src as usize),
+            _ => 0,
+        };
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vrgather: vd[i] = vs2[index[i]], zero when the index is not below VLMAX
+// the indices are SEW (or 16 bits for vrgatherei16) wide elements of vs1, or the scalar operand
+fn v_gather(cpu: &mut CpuCore, inst: u32, ei16: bool) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(v_aligned(f.vd, st.lmul) && (f.vm || f.vd != 0), inst)?;
+    v_require(
+        v_aligned(f.vs2, st.lmul) && !v_groups_overlap(f.vd, st.lmul, f.vs2, st.lmul),
+        inst,
+    )?;
+    let index_eew = match ei16 {
+        true => 16,
+        false => st.sew,
+    };
+    if v_is_vv(inst) {
+        let index_emul = st.lmul + index_eew.ilog2() as i32 - st.sew.ilog2() as i32;
+        v_require(
+            (-3..=3).contains(&index_emul) && v_aligned(f.vs1, index_emul),
+            inst,
+        )?;
+        v_require(!v_groups_overlap(f.vd, st.lmul, f.vs1, index_emul), inst)?;
+    }
+
+    for i in st.vstart..st.vl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let index = match v_is_vv(inst) {
+            true => cpu.vpr.read_elem(f.vs1, index_eew / 8, i),
+            false => v_scalar(cpu, inst, &f),
+        };
+        let data = match index < st.vlmax as u64 {
+            true => cpu.vpr.read_elem(f.vs2, st.sewb(), index as usize),
+            false => 0,
+        };
+        cpu.vpr.write_elem(f.vd, st.sewb(), i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+fn v_groups_overlap(a: usize, a_emul: i32, b: usize, b_emul: i32) -> bool {
+    a < b + v_regs(b_emul) && b < a + v_regs(a_emul)
+}
+
+// vcompress: pack the elements of vs2 selected by the vs1 mask into vd
+fn v_compress(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    v_require(
+        st.vstart == 0 && v_aligned(f.vd, st.lmul) && v_aligned(f.vs2, st.lmul),
+        inst,
+    )?;
+    v_require(!v_groups_overlap(f.vd, st.lmul, f.vs2, st.lmul), inst)?;
+    v_require(!v_groups_overlap(f.vd, st.lmul, f.vs1, 0), inst)?;
+
+    let mut idx = 0;
+    for i in 0..st.vl {
+        if cpu.vpr.read_mask(f.vs1, i) {
+            let data = cpu.vpr.read_elem(f.vs2, st.sewb(), i);
+            cpu.vpr.write_elem(f.vd, st.sewb(), idx, data);
+            idx += 1;
+        }
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// vmv<nr>r.v copies whole registers, with EEW = SEW
+fn v_move_whole(cpu: &mut CpuCore, inst: u32) -> Result<(), TrapType> {
+    v_check_enable(cpu, inst)?;
+    let f = parse_format_v(inst);
+    let nreg = f.vs1 + 1;
+    v_require(f.vd.is_multiple_of(nreg) && f.vs2.is_multiple_of(nreg), inst)?;
+
+    let vtype = cpu.csr_regs.vtype.get();
+    let eew = match vtype.vill() {
+        true => 1,
+        false => vtype.sew() / 8,
+    };
+    let evl = nreg * cpu.vpr.vlenb() / eew;
+    for i in cpu.csr_regs.vstart.get() as usize..evl {
+        let data = cpu.vpr.read_elem(f.vs2, eew, i);
+        cpu.vpr.write_elem(f.vd, eew, i, data);
+    }
+    v_finish(cpu, false);
+    Ok(())
+}
+
+// addressing modes of vector loads and stores
+#[derive(Clone, Copy, PartialEq)]
+enum VMem {
+    Unit,
+    FaultFirst,
+    Mask,
+    Strided,
+    Indexed,
+}
+
+// Loads and stores of up to 8 fields per element (segments). A fault sets vstart to
+// the faulting element, except for fault-only-first loads which trim vl instead.
+// eew is the data width in bytes, or the index width of indexed accesses.
+fn v_mem(
+    cpu: &mut CpuCore,
+    inst: u32,
+    mode: VMem,
+    eew: usize,
+    store: bool,
+) -> Result<(), TrapType> {
+    let st = v_state(cpu, inst)?;
+    let f = parse_format_v(inst);
+    let nfields = f.nf + 1;
+    // log2(EMUL) = log2(LMUL) + log2(EEW / SEW)
+    let eew_emul = st.lmul + (eew * 8).ilog2() as i32 - st.sew.ilog2() as i32;
+    let (data_eew, data_emul, evl) = match mode {
+        VMem::Mask => (1, 0, st.vl.div_ceil(8)),
+        VMem::Indexed => (st.sewb(), st.lmul, st.vl),
+        _ => (eew, eew_emul, st.vl),
+    };
+    let group = v_regs(data_emul);
+
+    v_require(
+        eew * 8 <= cpu.config.elen() && (-3..=3).contains(&data_emul),
+        inst,
+    )?;
+    v_require(group * nfields <= 8 && f.vd + group * nfields <= 32, inst)?;
+    v_require(
+        v_aligned(f.vd, data_emul) && (store || f.vm || f.vd != 0),
+        inst,
+    )?;
+    if mode == VMem::Indexed {
+        v_require(
+            (-3..=3).contains(&eew_emul) && v_aligned(f.vs2, eew_emul),
+            inst,
+        )?;
+        for s in 0..nfields {
+            let vd = f.vd + s * group;
+            v_require(
+                store || v_overlap_ok(vd, data_emul, st.sew, f.vs2, eew_emul, eew * 8),
+                inst,
+            )?;
+        }
+    }
+
+    let base = cpu.gpr.read(f.vs1 as u64);
+    let stride = match mode {
+        VMem::Strided => cpu.gpr.read(f.vs2 as u64),
+        _ => (nfields * data_eew) as u64,
+    };
+    for i in st.vstart..evl {
+        if !v_active(cpu, f.vm, i) {
+            continue;
+        }
+        let offset = match mode {
+            VMem::Indexed => cpu.vpr.read_elem(f.vs2, eew, i),
+            _ => (i as u64).wrapping_mul(stride),
+        };
+        for s in 0..nfields {
+            let addr = base
+                .wrapping_add(offset)
+                .wrapping_add((s * data_eew) as u64);
+            let reg = f.vd + s * group;
+            let ret = match store {
+                true => {
+                    let data = cpu.vpr.read_elem(reg, data_eew, i);
+                    cpu.write(addr, data, data_eew, AccessType::Store(addr))
+                        .map(|_| ())
+                }
+                false => cpu
+                    .read(addr, data_eew, AccessType::Load(addr))
+                    .map(|data| cpu.vpr.write_elem(reg, data_eew, i, data)),
+            };
+            if let Err(trap) = ret {
+                if mode == VMem::FaultFirst && i > 0 {
+                    cpu.csr_regs.vl.set(i as u64);
+                    v_finish(cpu, false);
+                    return Ok(());
+                }
+                cpu.csr_regs.vstart.set(i as u64);
+                if !store {
+                    v_set_dirty(cpu);
+                }
+                return Err(trap);
+            }
+        }
+    }
+    match store {
+        true => cpu.csr_regs.vstart.set(0),
+        false => v_finish(cpu, false),
+    }
+    Ok(())
+}
+
+// vl<nr>re<eew>.v and vs<nr>r.v transfer whole registers regardless of vtype and vl
+fn v_mem_whole(cpu: &mut CpuCore, inst: u32, eew: usize, store: bool) -> Result<(), TrapType> {
+    v_check_enable(cpu, inst)?;
+    let f = parse_format_v(inst);
+    let nreg = f.nf + 1;
+    v_require(nreg.is_power_of_two() && f.vd.is_multiple_of(nreg), inst)?;
+
+    let base = cpu.gpr.read(f.vs1 as u64);
+    let evl = nreg * cpu.vpr.vlenb() / eew;
+    for i in cpu.csr_regs.vstart.get() as usize..evl {
+        let addr = base.wrapping_add((i * eew) as u64);
+        let ret = match store {
+            true => {
+                let data = cpu.vpr.read_elem(f.vd, eew, i);
+                cpu.write(addr, data, eew, AccessType::Store(addr))
+                    .map(|_| ())
+            }
+            false => cpu
+                .read(addr, eew, AccessType::Load(addr))
+                .map(|data| cpu.vpr.write_elem(f.vd, eew, i, data)),
+        };
+        if let Err(trap) = ret {
+            cpu.csr_regs.vstart.set(i as u64);
+            return Err(trap);
+        }
+    }
+    match store {
+        true => cpu.csr_regs.vstart.set(0),
+        false => v_finish(cpu, false),
+    }
+    Ok(())
+}
+
+// Rounding increment for shifting data right by d bits under vxrm:
+// 0 round-to-nearest-up, 1 round-to-nearest-even, 2 round-down, 3 round-to-odd
+fn v_round(data: u128, d: u32, vxrm: u8) -> u128 {
+    if d == 0 {
+        return 0;
+    }
+    let bit = |n: u32| (data >> n) & 1;
+    let lower = |n: u32| (data & ((1 << n) - 1) != 0) as u128;
+    match vxrm {
+        0 => bit(d - 1),
+        1 => bit(d - 1) & (lower(d - 1) | bit(d)),
+        2 => 0,
+        _ => (bit(d) ^ 1) & lower(d),
+    }
+}
+
+fn roundoff_unsigned(data: u128, d: u32, vxrm: u8) -> u128 {
+    (data >> d) + v_round(data, d, vxrm)
+}
+
+fn roundoff_signed(data: i128, d: u32, vxrm: u8) -> i128 {
+    (data >> d) + v_round(data as u128, d, vxrm) as i128
+}
+
+// clamp to the SEW range, setting vxsat when saturated
+fn v_sat_unsigned(data: u128, ctx: &mut VCtx) -> u64 {
+    match data > umax(ctx.sew) as u128 {
+        true => {
+            ctx.vxsat = true;
+            umax(ctx.sew)
+        }
+        false => data as u64,
+    }
+}
+
+fn v_sat_signed(data: i128, ctx: &mut VCtx) -> u64 {
+    let (min, max) = (smin(ctx.sew) as i128, smax(ctx.sew) as i128);
+    if data < min || data > max {
+        ctx.vxsat = true;
+    }
+    data.clamp(min, max) as u64
+}
+
+fn v_shamt(data: u64, width: usize) -> u32 {
+    (data & (width as u64 - 1)) as u32
+}
+
+// element operations shared by the .vv, .vx and .vi forms
+fn v_min(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    match sext(a, ctx.sew) < sext(b, ctx.sew) {
+        true => a,
+        false => b,
+    }
+}
+
+fn v_max(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    match sext(a, ctx.sew) > sext(b, ctx.sew) {
+        true => a,
+        false => b,
+    }
+}
+
+fn v_sadd(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    v_sat_signed(sext(a, ctx.sew) as i128 + sext(b, ctx.sew) as i128, ctx)
+}
+
+fn v_ssub(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    v_sat_signed(sext(a, ctx.sew) as i128 - sext(b, ctx.sew) as i128, ctx)
+}
+
+fn v_saddu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    v_sat_unsigned(a as u128 + b as u128, ctx)
+}
+
+fn v_ssubu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    match a < b {
+        true => {
+            ctx.vxsat = true;
+            0
+        }
+        false => a - b,
+    }
+}
+
+fn v_aaddu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    roundoff_unsigned(a as u128 + b as u128, 1, ctx.vxrm) as u64
+}
+
+fn v_aadd(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    let sum = sext(a, ctx.sew) as i128 + sext(b, ctx.sew) as i128;
+    roundoff_signed(sum, 1, ctx.vxrm) as u64
+}
+
+fn v_asubu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    roundoff_signed(a as i128 - b as i128, 1, ctx.vxrm) as u64
+}
+
+fn v_asub(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    let diff = sext(a, ctx.sew) as i128 - sext(b, ctx.sew) as i128;
+    roundoff_signed(diff, 1, ctx.vxrm) as u64
+}
+
+// (a * b) >> (SEW - 1) with rounding, only -1.0 * -1.0 saturates
+fn v_smul(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    let prod = sext(a, ctx.sew) as i128 * sext(b, ctx.sew) as i128;
+    let data = roundoff_signed(prod, ctx.sew as u32 - 1, ctx.vxrm);
+    v_sat_signed(data, ctx)
+}
+
+fn v_ssrl(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    roundoff_unsigned(a as u128, v_shamt(b, ctx.sew), ctx.vxrm) as u64
+}
+
+fn v_ssra(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    roundoff_signed(sext(a, ctx.sew) as i128, v_shamt(b, ctx.sew), ctx.vxrm) as u64
+}
+
+fn v_nclipu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    let data = roundoff_unsigned(a as u128, v_shamt(b, ctx.sew * 2), ctx.vxrm);
+    v_sat_unsigned(data, ctx)
+}
+
+fn v_nclip(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    let data = sext(a, ctx.sew * 2) as i128;
+    let data = roundoff_signed(data, v_shamt(b, ctx.sew * 2), ctx.vxrm);
+    v_sat_signed(data, ctx)
+}
+
+fn v_div(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    match b {
+        0 => u64::MAX,
+        _ => sext(a, ctx.sew).wrapping_div(sext(b, ctx.sew)) as u64,
+    }
+}
+
+fn v_rem(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    match b {
+        0 => a,
+        _ => sext(a, ctx.sew).wrapping_rem(sext(b, ctx.sew)) as u64,
+    }
+}
+
+fn v_mulh(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    ((sext(a, ctx.sew) as i128 * sext(b, ctx.sew) as i128) >> ctx.sew) as u64
+}
+
+fn v_mulhu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    ((a as u128 * b as u128) >> ctx.sew) as u64
+}
+
+// signed vs2 * unsigned vs1
+fn v_mulhsu(a: u64, b: u64, ctx: &mut VCtx) -> u64 {
+    ((sext(a, ctx.sew) as i128 * b as i128) >> ctx.sew) as u64
+}
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_V: &[Instruction] = &[
+    Instruction {
+        mask: MASK_VSETVLI,
+        match_data: MATCH_VSETVLI,
+        name: "VSETVLI",
+        operation: |cpu, inst, pc, ops| {
+            // vtypei is zimm[10:0]
+            let f = parse_format_i(inst);
+            let avl = v_avl(cpu, f.rd, f.rs1);
+            v_set_config(cpu, inst, f.rd, avl, (inst >> 20) as u64 & 0x7ff)
+        },
+    },
+    Instruction {
+        mask: MASK_VSETIVLI,
+        match_data: MATCH_VSETIVLI,
+        name: "VSETIVLI",
+        operation: |cpu, inst, pc, ops| {
+            // AVL is uimm[4:0], vtypei is zimm[9:0]
+            let f = parse_format_i(inst);
+            v_set_config(cpu, inst, f.rd, f.rs1, (inst >> 20) as u64 & 0x3ff)
+        },
+    },
+    Instruction {
+        mask: MASK_VSETVL,
+        match_data: MATCH_VSETVL,
+        name: "VSETVL",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let avl = v_avl(cpu, f.rd, f.rs1);
+            let vtype = cpu.gpr.read(f.rs2);
+            v_set_config(cpu, inst, f.rd, avl, vtype)
+        },
+    },
+    Instruction {
+        mask: MASK_VLE8_V & !MASK_NF,
+        match_data: MATCH_VLE8_V,
+        name: "VLE8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 1, false),
+    },
+    Instruction {
+        mask: MASK_VLE8FF_V & !MASK_NF,
+        match_data: MATCH_VLE8FF_V,
+        name: "VLE8FF_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::FaultFirst, 1, false),
+    },
+    Instruction {
+        mask: MASK_VLSE8_V & !MASK_NF,
+        match_data: MATCH_VLSE8_V,
+        name: "VLSE8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 1, false),
+    },
+    Instruction {
+        mask: MASK_VLUXEI8_V & !MASK_NF,
+        match_data: MATCH_VLUXEI8_V,
+        name: "VLUXEI8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 1, false),
+    },
+    Instruction {
+        mask: MASK_VLOXEI8_V & !MASK_NF,
+        match_data: MATCH_VLOXEI8_V,
+        name: "VLOXEI8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 1, false),
+    },
+    Instruction {
+        mask: MASK_VL1RE8_V & !MASK_NF,
+        match_data: MATCH_VL1RE8_V,
+        name: "VL1RE8_V",
+        operation: |cpu, inst, pc, ops| v_mem_whole(cpu, inst, 1, false),
+    },
+    Instruction {
+        mask: MASK_VSE8_V & !MASK_NF,
+        match_data: MATCH_VSE8_V,
+        name: "VSE8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 1, true),
+    },
+    Instruction {
+        mask: MASK_VSSE8_V & !MASK_NF,
+        match_data: MATCH_VSSE8_V,
+        name: "VSSE8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 1, true),
+    },
+    Instruction {
+        mask: MASK_VSUXEI8_V & !MASK_NF,
+        match_data: MATCH_VSUXEI8_V,
+        name: "VSUXEI8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 1, true),
+    },
+    Instruction {
+        mask: MASK_VSOXEI8_V & !MASK_NF,
+        match_data: MATCH_VSOXEI8_V,
+        name: "VSOXEI8_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 1, true),
+    },
+    Instruction {
+        mask: MASK_VLE16_V & !MASK_NF,
+        match_data: MATCH_VLE16_V,
+        name: "VLE16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 2, false),
+    },
+    Instruction {
+        mask: MASK_VLE16FF_V & !MASK_NF,
+        match_data: MATCH_VLE16FF_V,
+        name: "VLE16FF_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::FaultFirst, 2, false),
+    },
+    Instruction {
+        mask: MASK_VLSE16_V & !MASK_NF,
+        match_data: MATCH_VLSE16_V,
+        name: "VLSE16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 2, false),
+    },
+    Instruction {
+        mask: MASK_VLUXEI16_V & !MASK_NF,
+        match_data: MATCH_VLUXEI16_V,
+        name: "VLUXEI16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 2, false),
+    },
+    Instruction {
+        mask: MASK_VLOXEI16_V & !MASK_NF,
+        match_data: MATCH_VLOXEI16_V,
+        name: "VLOXEI16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 2, false),
+    },
+    Instruction {
+        mask: MASK_VL1RE16_V & !MASK_NF,
+        match_data: MATCH_VL1RE16_V,
+        name: "VL1RE16_V",
+        operation: |cpu, inst, pc, ops| v_mem_whole(cpu, inst, 2, false),
+    },
+    Instruction {
+        mask: MASK_VSE16_V & !MASK_NF,
+        match_data: MATCH_VSE16_V,
+        name: "VSE16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 2, true),
+    },
+    Instruction {
+        mask: MASK_VSSE16_V & !MASK_NF,
+        match_data: MATCH_VSSE16_V,
+        name: "VSSE16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 2, true),
+    },
+    Instruction {
+        mask: MASK_VSUXEI16_V & !MASK_NF,
+        match_data: MATCH_VSUXEI16_V,
+        name: "VSUXEI16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 2, true),
+    },
+    Instruction {
+        mask: MASK_VSOXEI16_V & !MASK_NF,
+        match_data: MATCH_VSOXEI16_V,
+        name: "VSOXEI16_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 2, true),
+    },
+    Instruction {
+        mask: MASK_VLE32_V & !MASK_NF,
+        match_data: MATCH_VLE32_V,
+        name: "VLE32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 4, false),
+    },
+    Instruction {
+        mask: MASK_VLE32FF_V & !MASK_NF,
+        match_data: MATCH_VLE32FF_V,
+        name: "VLE32FF_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::FaultFirst, 4, false),
+    },
+    Instruction {
+        mask: MASK_VLSE32_V & !MASK_NF,
+        match_data: MATCH_VLSE32_V,
+        name: "VLSE32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 4, false),
+    },
+    Instruction {
+        mask: MASK_VLUXEI32_V & !MASK_NF,
+        match_data: MATCH_VLUXEI32_V,
+        name: "VLUXEI32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 4, false),
+    },
+    Instruction {
+        mask: MASK_VLOXEI32_V & !MASK_NF,
+        match_data: MATCH_VLOXEI32_V,
+        name: "VLOXEI32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 4, false),
+    },
+    Instruction {
+        mask: MASK_VL1RE32_V & !MASK_NF,
+        match_data: MATCH_VL1RE32_V,
+        name: "VL1RE32_V",
+        operation: |cpu, inst, pc, ops| v_mem_whole(cpu, inst, 4, false),
+    },
+    Instruction {
+        mask: MASK_VSE32_V & !MASK_NF,
+        match_data: MATCH_VSE32_V,
+        name: "VSE32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 4, true),
+    },
+    Instruction {
+        mask: MASK_VSSE32_V & !MASK_NF,
+        match_data: MATCH_VSSE32_V,
+        name: "VSSE32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 4, true),
+    },
+    Instruction {
+        mask: MASK_VSUXEI32_V & !MASK_NF,
+        match_data: MATCH_VSUXEI32_V,
+        name: "VSUXEI32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 4, true),
+    },
+    Instruction {
+        mask: MASK_VSOXEI32_V & !MASK_NF,
+        match_data: MATCH_VSOXEI32_V,
+        name: "VSOXEI32_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 4, true),
+    },
+    Instruction {
+        mask: MASK_VLE64_V & !MASK_NF,
+        match_data: MATCH_VLE64_V,
+        name: "VLE64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 8, false),
+    },
+    Instruction {
+        mask: MASK_VLE64FF_V & !MASK_NF,
+        match_data: MATCH_VLE64FF_V,
+        name: "VLE64FF_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::FaultFirst, 8, false),
+    },
+    Instruction {
+        mask: MASK_VLSE64_V & !MASK_NF,
+        match_data: MATCH_VLSE64_V,
+        name: "VLSE64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 8, false),
+    },
+    Instruction {
+        mask: MASK_VLUXEI64_V & !MASK_NF,
+        match_data: MATCH_VLUXEI64_V,
+        name: "VLUXEI64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 8, false),
+    },
+    Instruction {
+        mask: MASK_VLOXEI64_V & !MASK_NF,
+        match_data: MATCH_VLOXEI64_V,
+        name: "VLOXEI64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 8, false),
+    },
+    Instruction {
+        mask: MASK_VL1RE64_V & !MASK_NF,
+        match_data: MATCH_VL1RE64_V,
+        name: "VL1RE64_V",
+        operation: |cpu, inst, pc, ops| v_mem_whole(cpu, inst, 8, false),
+    },
+    Instruction {
+        mask: MASK_VSE64_V & !MASK_NF,
+        match_data: MATCH_VSE64_V,
+        name: "VSE64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Unit, 8, true),
+    },
+    Instruction {
+        mask: MASK_VSSE64_V & !MASK_NF,
+        match_data: MATCH_VSSE64_V,
+        name: "VSSE64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Strided, 8, true),
+    },
+    Instruction {
+        mask: MASK_VSUXEI64_V & !MASK_NF,
+        match_data: MATCH_VSUXEI64_V,
+        name: "VSUXEI64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 8, true),
+    },
+    Instruction {
+        mask: MASK_VSOXEI64_V & !MASK_NF,
+        match_data: MATCH_VSOXEI64_V,
+        name: "VSOXEI64_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Indexed, 8, true),
+    },
+    Instruction {
+        mask: MASK_VLM_V,
+        match_data: MATCH_VLM_V,
+        name: "VLM_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Mask, 1, false),
+    },
+    Instruction {
+        mask: MASK_VSM_V,
+        match_data: MATCH_VSM_V,
+        name: "VSM_V",
+        operation: |cpu, inst, pc, ops| v_mem(cpu, inst, VMem::Mask, 1, true),
+    },
+    Instruction {
+        mask: MASK_VS1R_V & !MASK_NF,
+        match_data: MATCH_VS1R_V,
+        name: "VS1R_V",
+        operation: |cpu, inst, pc, ops| v_mem_whole(cpu, inst, 1, true),
+    },
+    Instruction {
+        mask: MASK_VADD_VV,
+        match_data: MATCH_VADD_VV,
+        name: "VADD_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VADD_VX,
+        match_data: MATCH_VADD_VX,
+        name: "VADD_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VADD_VI,
+        match_data: MATCH_VADD_VI,
+        name: "VADD_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VSUB_VV,
+        match_data: MATCH_VSUB_VV,
+        name: "VSUB_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_sub(b)),
+    },
+    Instruction {
+        mask: MASK_VSUB_VX,
+        match_data: MATCH_VSUB_VX,
+        name: "VSUB_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_sub(b)),
+    },
+    Instruction {
+        mask: MASK_VRSUB_VX,
+        match_data: MATCH_VRSUB_VX,
+        name: "VRSUB_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| b.wrapping_sub(a)),
+    },
+    Instruction {
+        mask: MASK_VRSUB_VI,
+        match_data: MATCH_VRSUB_VI,
+        name: "VRSUB_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| b.wrapping_sub(a)),
+    },
+    Instruction {
+        mask: MASK_VWADDU_VV,
+        match_data: MATCH_VWADDU_VV,
+        name: "VWADDU_VV",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, false, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VWADDU_VX,
+        match_data: MATCH_VWADDU_VX,
+        name: "VWADDU_VX",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, false, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VWADD_VV,
+        match_data: MATCH_VWADD_VV,
+        name: "VWADD_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) + sext(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWADD_VX,
+        match_data: MATCH_VWADD_VX,
+        name: "VWADD_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) + sext(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWSUBU_VV,
+        match_data: MATCH_VWSUBU_VV,
+        name: "VWSUBU_VV",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, false, |a, b, _| a.wrapping_sub(b)),
+    },
+    Instruction {
+        mask: MASK_VWSUBU_VX,
+        match_data: MATCH_VWSUBU_VX,
+        name: "VWSUBU_VX",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, false, |a, b, _| a.wrapping_sub(b)),
+    },
+    Instruction {
+        mask: MASK_VWSUB_VV,
+        match_data: MATCH_VWSUB_VV,
+        name: "VWSUB_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) - sext(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWSUB_VX,
+        match_data: MATCH_VWSUB_VX,
+        name: "VWSUB_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) - sext(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWADDU_WV,
+        match_data: MATCH_VWADDU_WV,
+        name: "VWADDU_WV",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, true, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VWADDU_WX,
+        match_data: MATCH_VWADDU_WX,
+        name: "VWADDU_WX",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, true, |a, b, _| a.wrapping_add(b)),
+    },
+    Instruction {
+        mask: MASK_VWADD_WV,
+        match_data: MATCH_VWADD_WV,
+        name: "VWADD_WV",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, true, |a, b, x| {
+                a.wrapping_add(sext(b, x.sew) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWADD_WX,
+        match_data: MATCH_VWADD_WX,
+        name: "VWADD_WX",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, true, |a, b, x| {
+                a.wrapping_add(sext(b, x.sew) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWSUBU_WV,
+        match_data: MATCH_VWSUBU_WV,
+        name: "VWSUBU_WV",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, true, |a, b, _| a.wrapping_sub(b)),
+    },
+    Instruction {
+        mask: MASK_VWSUBU_WX,
+        match_data: MATCH_VWSUBU_WX,
+        name: "VWSUBU_WX",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, true, |a, b, _| a.wrapping_sub(b)),
+    },
+    Instruction {
+        mask: MASK_VWSUB_WV,
+        match_data: MATCH_VWSUB_WV,
+        name: "VWSUB_WV",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, true, |a, b, x| {
+                a.wrapping_sub(sext(b, x.sew) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWSUB_WX,
+        match_data: MATCH_VWSUB_WX,
+        name: "VWSUB_WX",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, true, |a, b, x| {
+                a.wrapping_sub(sext(b, x.sew) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VZEXT_VF2,
+        match_data: MATCH_VZEXT_VF2,
+        name: "VZEXT_VF2",
+        operation: |cpu, inst, pc, ops| v_extend(cpu, inst, 1, false),
+    },
+    Instruction {
+        mask: MASK_VSEXT_VF2,
+        match_data: MATCH_VSEXT_VF2,
+        name: "VSEXT_VF2",
+        operation: |cpu, inst, pc, ops| v_extend(cpu, inst, 1, true),
+    },
+    Instruction {
+        mask: MASK_VZEXT_VF4,
+        match_data: MATCH_VZEXT_VF4,
+        name: "VZEXT_VF4",
+        operation: |cpu, inst, pc, ops| v_extend(cpu, inst, 2, false),
+    },
+    Instruction {
+        mask: MASK_VSEXT_VF4,
+        match_data: MATCH_VSEXT_VF4,
+        name: "VSEXT_VF4",
+        operation: |cpu, inst, pc, ops| v_extend(cpu, inst, 2, true),
+    },
+    Instruction {
+        mask: MASK_VZEXT_VF8,
+        match_data: MATCH_VZEXT_VF8,
+        name: "VZEXT_VF8",
+        operation: |cpu, inst, pc, ops| v_extend(cpu, inst, 3, false),
+    },
+    Instruction {
+        mask: MASK_VSEXT_VF8,
+        match_data: MATCH_VSEXT_VF8,
+        name: "VSEXT_VF8",
+        operation: |cpu, inst, pc, ops| v_extend(cpu, inst, 3, true),
+    },
+    Instruction {
+        mask: MASK_VADC_VVM,
+        match_data: MATCH_VADC_VVM,
+        name: "VADC_VVM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry(cpu, inst, |a, b, c| {
+                a.wrapping_add(b).wrapping_add(c as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADC_VVM,
+        match_data: MATCH_VMADC_VVM,
+        name: "VMADC_VVM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, sew| {
+                a as u128 + b as u128 + c as u128 > umax(sew) as u128
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADC_VV,
+        match_data: MATCH_VMADC_VV,
+        name: "VMADC_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, sew| {
+                a as u128 + b as u128 + c as u128 > umax(sew) as u128
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VADC_VXM,
+        match_data: MATCH_VADC_VXM,
+        name: "VADC_VXM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry(cpu, inst, |a, b, c| {
+                a.wrapping_add(b).wrapping_add(c as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADC_VXM,
+        match_data: MATCH_VMADC_VXM,
+        name: "VMADC_VXM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, sew| {
+                a as u128 + b as u128 + c as u128 > umax(sew) as u128
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADC_VX,
+        match_data: MATCH_VMADC_VX,
+        name: "VMADC_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, sew| {
+                a as u128 + b as u128 + c as u128 > umax(sew) as u128
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VADC_VIM,
+        match_data: MATCH_VADC_VIM,
+        name: "VADC_VIM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry(cpu, inst, |a, b, c| {
+                a.wrapping_add(b).wrapping_add(c as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADC_VIM,
+        match_data: MATCH_VMADC_VIM,
+        name: "VMADC_VIM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, sew| {
+                a as u128 + b as u128 + c as u128 > umax(sew) as u128
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADC_VI,
+        match_data: MATCH_VMADC_VI,
+        name: "VMADC_VI",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, sew| {
+                a as u128 + b as u128 + c as u128 > umax(sew) as u128
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VSBC_VVM,
+        match_data: MATCH_VSBC_VVM,
+        name: "VSBC_VVM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry(cpu, inst, |a, b, c| {
+                a.wrapping_sub(b).wrapping_sub(c as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMSBC_VVM,
+        match_data: MATCH_VMSBC_VVM,
+        name: "VMSBC_VVM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, _| (a as u128) < b as u128 + c as u128)
+        },
+    },
+    Instruction {
+        mask: MASK_VMSBC_VV,
+        match_data: MATCH_VMSBC_VV,
+        name: "VMSBC_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, _| (a as u128) < b as u128 + c as u128)
+        },
+    },
+    Instruction {
+        mask: MASK_VSBC_VXM,
+        match_data: MATCH_VSBC_VXM,
+        name: "VSBC_VXM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry(cpu, inst, |a, b, c| {
+                a.wrapping_sub(b).wrapping_sub(c as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMSBC_VXM,
+        match_data: MATCH_VMSBC_VXM,
+        name: "VMSBC_VXM",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, _| (a as u128) < b as u128 + c as u128)
+        },
+    },
+    Instruction {
+        mask: MASK_VMSBC_VX,
+        match_data: MATCH_VMSBC_VX,
+        name: "VMSBC_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_carry_out(cpu, inst, |a, b, c, _| (a as u128) < b as u128 + c as u128)
+        },
+    },
+    Instruction {
+        mask: MASK_VAND_VV,
+        match_data: MATCH_VAND_VV,
+        name: "VAND_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a & b),
+    },
+    Instruction {
+        mask: MASK_VAND_VX,
+        match_data: MATCH_VAND_VX,
+        name: "VAND_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a & b),
+    },
+    Instruction {
+        mask: MASK_VAND_VI,
+        match_data: MATCH_VAND_VI,
+        name: "VAND_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a & b),
+    },
+    Instruction {
+        mask: MASK_VOR_VV,
+        match_data: MATCH_VOR_VV,
+        name: "VOR_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a | b),
+    },
+    Instruction {
+        mask: MASK_VOR_VX,
+        match_data: MATCH_VOR_VX,
+        name: "VOR_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a | b),
+    },
+    Instruction {
+        mask: MASK_VOR_VI,
+        match_data: MATCH_VOR_VI,
+        name: "VOR_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a | b),
+    },
+    Instruction {
+        mask: MASK_VXOR_VV,
+        match_data: MATCH_VXOR_VV,
+        name: "VXOR_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a ^ b),
+    },
+    Instruction {
+        mask: MASK_VXOR_VX,
+        match_data: MATCH_VXOR_VX,
+        name: "VXOR_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a ^ b),
+    },
+    Instruction {
+        mask: MASK_VXOR_VI,
+        match_data: MATCH_VXOR_VI,
+        name: "VXOR_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a ^ b),
+    },
+    Instruction {
+        mask: MASK_VSLL_VV,
+        match_data: MATCH_VSLL_VV,
+        name: "VSLL_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, x| a << v_shamt(b, x.sew)),
+    },
+    Instruction {
+        mask: MASK_VSLL_VX,
+        match_data: MATCH_VSLL_VX,
+        name: "VSLL_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, x| a << v_shamt(b, x.sew)),
+    },
+    Instruction {
+        mask: MASK_VSLL_VI,
+        match_data: MATCH_VSLL_VI,
+        name: "VSLL_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, x| a << v_shamt(b, x.sew)),
+    },
+    Instruction {
+        mask: MASK_VSRL_VV,
+        match_data: MATCH_VSRL_VV,
+        name: "VSRL_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, x| a >> v_shamt(b, x.sew)),
+    },
+    Instruction {
+        mask: MASK_VSRL_VX,
+        match_data: MATCH_VSRL_VX,
+        name: "VSRL_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, x| a >> v_shamt(b, x.sew)),
+    },
+    Instruction {
+        mask: MASK_VSRL_VI,
+        match_data: MATCH_VSRL_VI,
+        name: "VSRL_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, x| a >> v_shamt(b, x.sew)),
+    },
+    Instruction {
+        mask: MASK_VSRA_VV,
+        match_data: MATCH_VSRA_VV,
+        name: "VSRA_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_binary(cpu, inst, |a, b, x| {
+                (sext(a, x.sew) >> v_shamt(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VSRA_VX,
+        match_data: MATCH_VSRA_VX,
+        name: "VSRA_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_binary(cpu, inst, |a, b, x| {
+                (sext(a, x.sew) >> v_shamt(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VSRA_VI,
+        match_data: MATCH_VSRA_VI,
+        name: "VSRA_VI",
+        operation: |cpu, inst, pc, ops| {
+            v_binary(cpu, inst, |a, b, x| {
+                (sext(a, x.sew) >> v_shamt(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNSRL_WV,
+        match_data: MATCH_VNSRL_WV,
+        name: "VNSRL_WV",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, |a, b, x| a >> v_shamt(b, x.sew * 2)),
+    },
+    Instruction {
+        mask: MASK_VNSRL_WX,
+        match_data: MATCH_VNSRL_WX,
+        name: "VNSRL_WX",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, |a, b, x| a >> v_shamt(b, x.sew * 2)),
+    },
+    Instruction {
+        mask: MASK_VNSRL_WI,
+        match_data: MATCH_VNSRL_WI,
+        name: "VNSRL_WI",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, |a, b, x| a >> v_shamt(b, x.sew * 2)),
+    },
+    Instruction {
+        mask: MASK_VNSRA_WV,
+        match_data: MATCH_VNSRA_WV,
+        name: "VNSRA_WV",
+        operation: |cpu, inst, pc, ops| {
+            v_narrow(cpu, inst, |a, b, x| {
+                (sext(a, x.sew * 2) >> v_shamt(b, x.sew * 2)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNSRA_WX,
+        match_data: MATCH_VNSRA_WX,
+        name: "VNSRA_WX",
+        operation: |cpu, inst, pc, ops| {
+            v_narrow(cpu, inst, |a, b, x| {
+                (sext(a, x.sew * 2) >> v_shamt(b, x.sew * 2)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNSRA_WI,
+        match_data: MATCH_VNSRA_WI,
+        name: "VNSRA_WI",
+        operation: |cpu, inst, pc, ops| {
+            v_narrow(cpu, inst, |a, b, x| {
+                (sext(a, x.sew * 2) >> v_shamt(b, x.sew * 2)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMSEQ_VV,
+        match_data: MATCH_VMSEQ_VV,
+        name: "VMSEQ_VV",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a == b),
+    },
+    Instruction {
+        mask: MASK_VMSEQ_VX,
+        match_data: MATCH_VMSEQ_VX,
+        name: "VMSEQ_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a == b),
+    },
+    Instruction {
+        mask: MASK_VMSEQ_VI,
+        match_data: MATCH_VMSEQ_VI,
+        name: "VMSEQ_VI",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a == b),
+    },
+    Instruction {
+        mask: MASK_VMSNE_VV,
+        match_data: MATCH_VMSNE_VV,
+        name: "VMSNE_VV",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a != b),
+    },
+    Instruction {
+        mask: MASK_VMSNE_VX,
+        match_data: MATCH_VMSNE_VX,
+        name: "VMSNE_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a != b),
+    },
+    Instruction {
+        mask: MASK_VMSNE_VI,
+        match_data: MATCH_VMSNE_VI,
+        name: "VMSNE_VI",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a != b),
+    },
+    Instruction {
+        mask: MASK_VMSLTU_VV,
+        match_data: MATCH_VMSLTU_VV,
+        name: "VMSLTU_VV",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a < b),
+    },
+    Instruction {
+        mask: MASK_VMSLTU_VX,
+        match_data: MATCH_VMSLTU_VX,
+        name: "VMSLTU_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a < b),
+    },
+    Instruction {
+        mask: MASK_VMSLT_VV,
+        match_data: MATCH_VMSLT_VV,
+        name: "VMSLT_VV",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) < sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMSLT_VX,
+        match_data: MATCH_VMSLT_VX,
+        name: "VMSLT_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) < sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMSLEU_VV,
+        match_data: MATCH_VMSLEU_VV,
+        name: "VMSLEU_VV",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a <= b),
+    },
+    Instruction {
+        mask: MASK_VMSLEU_VX,
+        match_data: MATCH_VMSLEU_VX,
+        name: "VMSLEU_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a <= b),
+    },
+    Instruction {
+        mask: MASK_VMSLEU_VI,
+        match_data: MATCH_VMSLEU_VI,
+        name: "VMSLEU_VI",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a <= b),
+    },
+    Instruction {
+        mask: MASK_VMSLE_VV,
+        match_data: MATCH_VMSLE_VV,
+        name: "VMSLE_VV",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) <= sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMSLE_VX,
+        match_data: MATCH_VMSLE_VX,
+        name: "VMSLE_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) <= sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMSLE_VI,
+        match_data: MATCH_VMSLE_VI,
+        name: "VMSLE_VI",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) <= sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMSGTU_VX,
+        match_data: MATCH_VMSGTU_VX,
+        name: "VMSGTU_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a > b),
+    },
+    Instruction {
+        mask: MASK_VMSGTU_VI,
+        match_data: MATCH_VMSGTU_VI,
+        name: "VMSGTU_VI",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, _| a > b),
+    },
+    Instruction {
+        mask: MASK_VMSGT_VX,
+        match_data: MATCH_VMSGT_VX,
+        name: "VMSGT_VX",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) > sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMSGT_VI,
+        match_data: MATCH_VMSGT_VI,
+        name: "VMSGT_VI",
+        operation: |cpu, inst, pc, ops| v_compare(cpu, inst, |a, b, sew| sext(a, sew) > sext(b, sew)),
+    },
+    Instruction {
+        mask: MASK_VMINU_VV,
+        match_data: MATCH_VMINU_VV,
+        name: "VMINU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.min(b)),
+    },
+    Instruction {
+        mask: MASK_VMINU_VX,
+        match_data: MATCH_VMINU_VX,
+        name: "VMINU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.min(b)),
+    },
+    Instruction {
+        mask: MASK_VMIN_VV,
+        match_data: MATCH_VMIN_VV,
+        name: "VMIN_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_min),
+    },
+    Instruction {
+        mask: MASK_VMIN_VX,
+        match_data: MATCH_VMIN_VX,
+        name: "VMIN_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_min),
+    },
+    Instruction {
+        mask: MASK_VMAXU_VV,
+        match_data: MATCH_VMAXU_VV,
+        name: "VMAXU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.max(b)),
+    },
+    Instruction {
+        mask: MASK_VMAXU_VX,
+        match_data: MATCH_VMAXU_VX,
+        name: "VMAXU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.max(b)),
+    },
+    Instruction {
+        mask: MASK_VMAX_VV,
+        match_data: MATCH_VMAX_VV,
+        name: "VMAX_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_max),
+    },
+    Instruction {
+        mask: MASK_VMAX_VX,
+        match_data: MATCH_VMAX_VX,
+        name: "VMAX_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_max),
+    },
+    Instruction {
+        mask: MASK_VMUL_VV,
+        match_data: MATCH_VMUL_VV,
+        name: "VMUL_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_mul(b)),
+    },
+    Instruction {
+        mask: MASK_VMUL_VX,
+        match_data: MATCH_VMUL_VX,
+        name: "VMUL_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.wrapping_mul(b)),
+    },
+    Instruction {
+        mask: MASK_VMULH_VV,
+        match_data: MATCH_VMULH_VV,
+        name: "VMULH_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_mulh),
+    },
+    Instruction {
+        mask: MASK_VMULH_VX,
+        match_data: MATCH_VMULH_VX,
+        name: "VMULH_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_mulh),
+    },
+    Instruction {
+        mask: MASK_VMULHU_VV,
+        match_data: MATCH_VMULHU_VV,
+        name: "VMULHU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_mulhu),
+    },
+    Instruction {
+        mask: MASK_VMULHU_VX,
+        match_data: MATCH_VMULHU_VX,
+        name: "VMULHU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_mulhu),
+    },
+    Instruction {
+        mask: MASK_VMULHSU_VV,
+        match_data: MATCH_VMULHSU_VV,
+        name: "VMULHSU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_mulhsu),
+    },
+    Instruction {
+        mask: MASK_VMULHSU_VX,
+        match_data: MATCH_VMULHSU_VX,
+        name: "VMULHSU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_mulhsu),
+    },
+    Instruction {
+        mask: MASK_VDIVU_VV,
+        match_data: MATCH_VDIVU_VV,
+        name: "VDIVU_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_binary(cpu, inst, |a, b, _| a.checked_div(b).unwrap_or(u64::MAX))
+        },
+    },
+    Instruction {
+        mask: MASK_VDIVU_VX,
+        match_data: MATCH_VDIVU_VX,
+        name: "VDIVU_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_binary(cpu, inst, |a, b, _| a.checked_div(b).unwrap_or(u64::MAX))
+        },
+    },
+    Instruction {
+        mask: MASK_VDIV_VV,
+        match_data: MATCH_VDIV_VV,
+        name: "VDIV_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_div),
+    },
+    Instruction {
+        mask: MASK_VDIV_VX,
+        match_data: MATCH_VDIV_VX,
+        name: "VDIV_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_div),
+    },
+    Instruction {
+        mask: MASK_VREMU_VV,
+        match_data: MATCH_VREMU_VV,
+        name: "VREMU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.checked_rem(b).unwrap_or(a)),
+    },
+    Instruction {
+        mask: MASK_VREMU_VX,
+        match_data: MATCH_VREMU_VX,
+        name: "VREMU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, |a, b, _| a.checked_rem(b).unwrap_or(a)),
+    },
+    Instruction {
+        mask: MASK_VREM_VV,
+        match_data: MATCH_VREM_VV,
+        name: "VREM_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_rem),
+    },
+    Instruction {
+        mask: MASK_VREM_VX,
+        match_data: MATCH_VREM_VX,
+        name: "VREM_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_rem),
+    },
+    Instruction {
+        mask: MASK_VWMULU_VV,
+        match_data: MATCH_VWMULU_VV,
+        name: "VWMULU_VV",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, false, |a, b, _| a * b),
+    },
+    Instruction {
+        mask: MASK_VWMULU_VX,
+        match_data: MATCH_VWMULU_VX,
+        name: "VWMULU_VX",
+        operation: |cpu, inst, pc, ops| v_widen(cpu, inst, false, |a, b, _| a * b),
+    },
+    Instruction {
+        mask: MASK_VWMULSU_VV,
+        match_data: MATCH_VWMULSU_VV,
+        name: "VWMULSU_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) * b as i64) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMULSU_VX,
+        match_data: MATCH_VWMULSU_VX,
+        name: "VWMULSU_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) * b as i64) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMUL_VV,
+        match_data: MATCH_VWMUL_VV,
+        name: "VWMUL_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) * sext(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMUL_VX,
+        match_data: MATCH_VWMUL_VX,
+        name: "VWMUL_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_widen(cpu, inst, false, |a, b, x| {
+                (sext(a, x.sew) * sext(b, x.sew)) as u64
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMACC_VV,
+        match_data: MATCH_VMACC_VV,
+        name: "VMACC_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                b.wrapping_mul(a).wrapping_add(c)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMACC_VX,
+        match_data: MATCH_VMACC_VX,
+        name: "VMACC_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                b.wrapping_mul(a).wrapping_add(c)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNMSAC_VV,
+        match_data: MATCH_VNMSAC_VV,
+        name: "VNMSAC_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                c.wrapping_sub(b.wrapping_mul(a))
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNMSAC_VX,
+        match_data: MATCH_VNMSAC_VX,
+        name: "VNMSAC_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                c.wrapping_sub(b.wrapping_mul(a))
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADD_VV,
+        match_data: MATCH_VMADD_VV,
+        name: "VMADD_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                b.wrapping_mul(c).wrapping_add(a)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMADD_VX,
+        match_data: MATCH_VMADD_VX,
+        name: "VMADD_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                b.wrapping_mul(c).wrapping_add(a)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNMSUB_VV,
+        match_data: MATCH_VNMSUB_VV,
+        name: "VNMSUB_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                a.wrapping_sub(b.wrapping_mul(c))
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VNMSUB_VX,
+        match_data: MATCH_VNMSUB_VX,
+        name: "VNMSUB_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, false, |a, b, c, _| {
+                a.wrapping_sub(b.wrapping_mul(c))
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMACCU_VV,
+        match_data: MATCH_VWMACCU_VV,
+        name: "VWMACCU_VV",
+        operation: |cpu, inst, pc, ops| v_ternary(cpu, inst, true, |a, b, c, _| c.wrapping_add(a * b)),
+    },
+    Instruction {
+        mask: MASK_VWMACCU_VX,
+        match_data: MATCH_VWMACCU_VX,
+        name: "VWMACCU_VX",
+        operation: |cpu, inst, pc, ops| v_ternary(cpu, inst, true, |a, b, c, _| c.wrapping_add(a * b)),
+    },
+    Instruction {
+        mask: MASK_VWMACC_VV,
+        match_data: MATCH_VWMACC_VV,
+        name: "VWMACC_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, true, |a, b, c, sew| {
+                c.wrapping_add((sext(a, sew) * sext(b, sew)) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMACC_VX,
+        match_data: MATCH_VWMACC_VX,
+        name: "VWMACC_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, true, |a, b, c, sew| {
+                c.wrapping_add((sext(a, sew) * sext(b, sew)) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMACCSU_VV,
+        match_data: MATCH_VWMACCSU_VV,
+        name: "VWMACCSU_VV",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, true, |a, b, c, sew| {
+                c.wrapping_add((sext(b, sew) * a as i64) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMACCSU_VX,
+        match_data: MATCH_VWMACCSU_VX,
+        name: "VWMACCSU_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, true, |a, b, c, sew| {
+                c.wrapping_add((sext(b, sew) * a as i64) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWMACCUS_VX,
+        match_data: MATCH_VWMACCUS_VX,
+        name: "VWMACCUS_VX",
+        operation: |cpu, inst, pc, ops| {
+            v_ternary(cpu, inst, true, |a, b, c, sew| {
+                c.wrapping_add((b as i64 * sext(a, sew)) as u64)
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VMERGE_VVM,
+        match_data: MATCH_VMERGE_VVM,
+        name: "VMERGE_VVM",
+        operation: |cpu, inst, pc, ops| v_merge(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMERGE_VXM,
+        match_data: MATCH_VMERGE_VXM,
+        name: "VMERGE_VXM",
+        operation: |cpu, inst, pc, ops| v_merge(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMERGE_VIM,
+        match_data: MATCH_VMERGE_VIM,
+        name: "VMERGE_VIM",
+        operation: |cpu, inst, pc, ops| v_merge(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV_V_V,
+        match_data: MATCH_VMV_V_V,
+        name: "VMV_V_V",
+        operation: |cpu, inst, pc, ops| v_merge(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV_V_X,
+        match_data: MATCH_VMV_V_X,
+        name: "VMV_V_X",
+        operation: |cpu, inst, pc, ops| v_merge(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV_V_I,
+        match_data: MATCH_VMV_V_I,
+        name: "VMV_V_I",
+        operation: |cpu, inst, pc, ops| v_merge(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VSADDU_VV,
+        match_data: MATCH_VSADDU_VV,
+        name: "VSADDU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_saddu),
+    },
+    Instruction {
+        mask: MASK_VSADDU_VX,
+        match_data: MATCH_VSADDU_VX,
+        name: "VSADDU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_saddu),
+    },
+    Instruction {
+        mask: MASK_VSADDU_VI,
+        match_data: MATCH_VSADDU_VI,
+        name: "VSADDU_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_saddu),
+    },
+    Instruction {
+        mask: MASK_VSADD_VV,
+        match_data: MATCH_VSADD_VV,
+        name: "VSADD_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_sadd),
+    },
+    Instruction {
+        mask: MASK_VSADD_VX,
+        match_data: MATCH_VSADD_VX,
+        name: "VSADD_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_sadd),
+    },
+    Instruction {
+        mask: MASK_VSADD_VI,
+        match_data: MATCH_VSADD_VI,
+        name: "VSADD_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_sadd),
+    },
+    Instruction {
+        mask: MASK_VSSUBU_VV,
+        match_data: MATCH_VSSUBU_VV,
+        name: "VSSUBU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssubu),
+    },
+    Instruction {
+        mask: MASK_VSSUBU_VX,
+        match_data: MATCH_VSSUBU_VX,
+        name: "VSSUBU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssubu),
+    },
+    Instruction {
+        mask: MASK_VSSUB_VV,
+        match_data: MATCH_VSSUB_VV,
+        name: "VSSUB_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssub),
+    },
+    Instruction {
+        mask: MASK_VSSUB_VX,
+        match_data: MATCH_VSSUB_VX,
+        name: "VSSUB_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssub),
+    },
+    Instruction {
+        mask: MASK_VAADDU_VV,
+        match_data: MATCH_VAADDU_VV,
+        name: "VAADDU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_aaddu),
+    },
+    Instruction {
+        mask: MASK_VAADDU_VX,
+        match_data: MATCH_VAADDU_VX,
+        name: "VAADDU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_aaddu),
+    },
+    Instruction {
+        mask: MASK_VAADD_VV,
+        match_data: MATCH_VAADD_VV,
+        name: "VAADD_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_aadd),
+    },
+    Instruction {
+        mask: MASK_VAADD_VX,
+        match_data: MATCH_VAADD_VX,
+        name: "VAADD_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_aadd),
+    },
+    Instruction {
+        mask: MASK_VASUBU_VV,
+        match_data: MATCH_VASUBU_VV,
+        name: "VASUBU_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_asubu),
+    },
+    Instruction {
+        mask: MASK_VASUBU_VX,
+        match_data: MATCH_VASUBU_VX,
+        name: "VASUBU_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_asubu),
+    },
+    Instruction {
+        mask: MASK_VASUB_VV,
+        match_data: MATCH_VASUB_VV,
+        name: "VASUB_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_asub),
+    },
+    Instruction {
+        mask: MASK_VASUB_VX,
+        match_data: MATCH_VASUB_VX,
+        name: "VASUB_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_asub),
+    },
+    Instruction {
+        mask: MASK_VSMUL_VV,
+        match_data: MATCH_VSMUL_VV,
+        name: "VSMUL_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_smul),
+    },
+    Instruction {
+        mask: MASK_VSMUL_VX,
+        match_data: MATCH_VSMUL_VX,
+        name: "VSMUL_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_smul),
+    },
+    Instruction {
+        mask: MASK_VSSRL_VV,
+        match_data: MATCH_VSSRL_VV,
+        name: "VSSRL_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssrl),
+    },
+    Instruction {
+        mask: MASK_VSSRL_VX,
+        match_data: MATCH_VSSRL_VX,
+        name: "VSSRL_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssrl),
+    },
+    Instruction {
+        mask: MASK_VSSRL_VI,
+        match_data: MATCH_VSSRL_VI,
+        name: "VSSRL_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssrl),
+    },
+    Instruction {
+        mask: MASK_VSSRA_VV,
+        match_data: MATCH_VSSRA_VV,
+        name: "VSSRA_VV",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssra),
+    },
+    Instruction {
+        mask: MASK_VSSRA_VX,
+        match_data: MATCH_VSSRA_VX,
+        name: "VSSRA_VX",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssra),
+    },
+    Instruction {
+        mask: MASK_VSSRA_VI,
+        match_data: MATCH_VSSRA_VI,
+        name: "VSSRA_VI",
+        operation: |cpu, inst, pc, ops| v_binary(cpu, inst, v_ssra),
+    },
+    Instruction {
+        mask: MASK_VNCLIPU_WV,
+        match_data: MATCH_VNCLIPU_WV,
+        name: "VNCLIPU_WV",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, v_nclipu),
+    },
+    Instruction {
+        mask: MASK_VNCLIPU_WX,
+        match_data: MATCH_VNCLIPU_WX,
+        name: "VNCLIPU_WX",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, v_nclipu),
+    },
+    Instruction {
+        mask: MASK_VNCLIPU_WI,
+        match_data: MATCH_VNCLIPU_WI,
+        name: "VNCLIPU_WI",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, v_nclipu),
+    },
+    Instruction {
+        mask: MASK_VNCLIP_WV,
+        match_data: MATCH_VNCLIP_WV,
+        name: "VNCLIP_WV",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, v_nclip),
+    },
+    Instruction {
+        mask: MASK_VNCLIP_WX,
+        match_data: MATCH_VNCLIP_WX,
+        name: "VNCLIP_WX",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, v_nclip),
+    },
+    Instruction {
+        mask: MASK_VNCLIP_WI,
+        match_data: MATCH_VNCLIP_WI,
+        name: "VNCLIP_WI",
+        operation: |cpu, inst, pc, ops| v_narrow(cpu, inst, v_nclip),
+    },
+    Instruction {
+        mask: MASK_VREDSUM_VS,
+        match_data: MATCH_VREDSUM_VS,
+        name: "VREDSUM_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, None, |acc, x, _| acc.wrapping_add(x)),
+    },
+    Instruction {
+        mask: MASK_VREDAND_VS,
+        match_data: MATCH_VREDAND_VS,
+        name: "VREDAND_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, None, |acc, x, _| acc & x),
+    },
+    Instruction {
+        mask: MASK_VREDOR_VS,
+        match_data: MATCH_VREDOR_VS,
+        name: "VREDOR_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, None, |acc, x, _| acc | x),
+    },
+    Instruction {
+        mask: MASK_VREDXOR_VS,
+        match_data: MATCH_VREDXOR_VS,
+        name: "VREDXOR_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, None, |acc, x, _| acc ^ x),
+    },
+    Instruction {
+        mask: MASK_VREDMINU_VS,
+        match_data: MATCH_VREDMINU_VS,
+        name: "VREDMINU_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, None, |acc, x, _| acc.min(x)),
+    },
+    Instruction {
+        mask: MASK_VREDMIN_VS,
+        match_data: MATCH_VREDMIN_VS,
+        name: "VREDMIN_VS",
+        operation: |cpu, inst, pc, ops| {
+            v_reduce(cpu, inst, None, |acc, x, sew| {
+                match sext(x, sew) < sext(acc, sew) {
+                    true => x,
+                    false => acc,
+                }
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VREDMAXU_VS,
+        match_data: MATCH_VREDMAXU_VS,
+        name: "VREDMAXU_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, None, |acc, x, _| acc.max(x)),
+    },
+    Instruction {
+        mask: MASK_VREDMAX_VS,
+        match_data: MATCH_VREDMAX_VS,
+        name: "VREDMAX_VS",
+        operation: |cpu, inst, pc, ops| {
+            v_reduce(cpu, inst, None, |acc, x, sew| {
+                match sext(x, sew) > sext(acc, sew) {
+                    true => x,
+                    false => acc,
+                }
+            })
+        },
+    },
+    Instruction {
+        mask: MASK_VWREDSUMU_VS,
+        match_data: MATCH_VWREDSUMU_VS,
+        name: "VWREDSUMU_VS",
+        operation: |cpu, inst, pc, ops| {
+            v_reduce(cpu, inst, Some(false), |acc, x, _| acc.wrapping_add(x))
+        },
+    },
+    Instruction {
+        mask: MASK_VWREDSUM_VS,
+        match_data: MATCH_VWREDSUM_VS,
+        name: "VWREDSUM_VS",
+        operation: |cpu, inst, pc, ops| v_reduce(cpu, inst, Some(true), |acc, x, _| acc.wrapping_add(x)),
+    },
+    Instruction {
+        mask: MASK_VMAND_MM,
+        match_data: MATCH_VMAND_MM,
+        name: "VMAND_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| a & b),
+    },
+    Instruction {
+        mask: MASK_VMNAND_MM,
+        match_data: MATCH_VMNAND_MM,
+        name: "VMNAND_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| !(a & b)),
+    },
+    Instruction {
+        mask: MASK_VMANDN_MM,
+        match_data: MATCH_VMANDN_MM,
+        name: "VMANDN_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| a & !b),
+    },
+    Instruction {
+        mask: MASK_VMXOR_MM,
+        match_data: MATCH_VMXOR_MM,
+        name: "VMXOR_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| a ^ b),
+    },
+    Instruction {
+        mask: MASK_VMOR_MM,
+        match_data: MATCH_VMOR_MM,
+        name: "VMOR_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| a | b),
+    },
+    Instruction {
+        mask: MASK_VMNOR_MM,
+        match_data: MATCH_VMNOR_MM,
+        name: "VMNOR_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| !(a | b)),
+    },
+    Instruction {
+        mask: MASK_VMORN_MM,
+        match_data: MATCH_VMORN_MM,
+        name: "VMORN_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| a | !b),
+    },
+    Instruction {
+        mask: MASK_VMXNOR_MM,
+        match_data: MATCH_VMXNOR_MM,
+        name: "VMXNOR_MM",
+        operation: |cpu, inst, pc, ops| v_mask_logical(cpu, inst, |a, b| !(a ^ b)),
+    },
+    Instruction {
+        mask: MASK_VCPOP_M,
+        match_data: MATCH_VCPOP_M,
+        name: "VCPOP_M",
+        operation: |cpu, inst, pc, ops| v_mask_scalar(cpu, inst, false),
+    },
+    Instruction {
+        mask: MASK_VFIRST_M,
+        match_data: MATCH_VFIRST_M,
+        name: "VFIRST_M",
+        operation: |cpu, inst, pc, ops| v_mask_scalar(cpu, inst, true),
+    },
+    Instruction {
+        mask: MASK_VMSBF_M,
+        match_data: MATCH_VMSBF_M,
+        name: "VMSBF_M",
+        operation: |cpu, inst, pc, ops| v_set_first(cpu, inst, VSetFirst::Before),
+    },
+    Instruction {
+        mask: MASK_VMSIF_M,
+        match_data: MATCH_VMSIF_M,
+        name: "VMSIF_M",
+        operation: |cpu, inst, pc, ops| v_set_first(cpu, inst, VSetFirst::Including),
+    },
+    Instruction {
+        mask: MASK_VMSOF_M,
+        match_data: MATCH_VMSOF_M,
+        name: "VMSOF_M",
+        operation: |cpu, inst, pc, ops| v_set_first(cpu, inst, VSetFirst::Only),
+    },
+    Instruction {
+        mask: MASK_VIOTA_M,
+        match_data: MATCH_VIOTA_M,
+        name: "VIOTA_M",
+        operation: |cpu, inst, pc, ops| v_iota(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VID_V,
+        match_data: MATCH_VID_V,
+        name: "VID_V",
+        operation: |cpu, inst, pc, ops| v_id(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV_X_S,
+        match_data: MATCH_VMV_X_S,
+        name: "VMV_X_S",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = sext(vs2[0]), regardless of vstart and vl
+            let st = v_state(cpu, inst)?;
+            let f = parse_format_v(inst);
+            let data = cpu.vpr.read_elem(f.vs2, st.sewb(), 0);
+            cpu.gpr.write(f.vd as u64, sext(data, st.sew) as u64);
+            cpu.csr_regs.vstart.set(0);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_VMV_S_X,
+        match_data: MATCH_VMV_S_X,
+        name: "VMV_S_X",
+        operation: |cpu, inst, pc, ops| {
+            // vd[0] = x[rs1]
+            let st = v_state(cpu, inst)?;
+            let f = parse_format_v(inst);
+            if st.vstart < st.vl {
+                let data = cpu.gpr.read(f.vs1 as u64);
+                cpu.vpr.write_elem(f.vd, st.sewb(), 0, data);
+            }
+            v_finish(cpu, false);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_VSLIDEUP_VX,
+        match_data: MATCH_VSLIDEUP_VX,
+        name: "VSLIDEUP_VX",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_v(inst);
+            let offset = v_scalar(cpu, inst, &f);
+            v_slide_up(cpu, inst, offset, None)
+        },
+    },
+    Instruction {
+        mask: MASK_VSLIDEUP_VI,
+        match_data: MATCH_VSLIDEUP_VI,
+        name: "VSLIDEUP_VI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_v(inst);
+            let offset = v_scalar(cpu, inst, &f);
+            v_slide_up(cpu, inst, offset, None)
+        },
+    },
+    Instruction {
+        mask: MASK_VSLIDEDOWN_VX,
+        match_data: MATCH_VSLIDEDOWN_VX,
+        name: "VSLIDEDOWN_VX",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_v(inst);
+            let offset = v_scalar(cpu, inst, &f);
+            v_slide_down(cpu, inst, offset, None)
+        },
+    },
+    Instruction {
+        mask: MASK_VSLIDEDOWN_VI,
+        match_data: MATCH_VSLIDEDOWN_VI,
+        name: "VSLIDEDOWN_VI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_v(inst);
+            let offset = v_scalar(cpu, inst, &f);
+            v_slide_down(cpu, inst, offset, None)
+        },
+    },
+    Instruction {
+        mask: MASK_VSLIDE1UP_VX,
+        match_data: MATCH_VSLIDE1UP_VX,
+        name: "VSLIDE1UP_VX",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_v(inst);
+            let data = cpu.gpr.read(f.vs1 as u64);
+            v_slide_up(cpu, inst, 1, Some(data))
+        },
+    },
+    Instruction {
+        mask: MASK_VSLIDE1DOWN_VX,
+        match_data: MATCH_VSLIDE1DOWN_VX,
+        name: "VSLIDE1DOWN_VX",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_v(inst);
+            let data = cpu.gpr.read(f.vs1 as u64);
+            v_slide_down(cpu, inst, 1, Some(data))
+        },
+    },
+    Instruction {
+        mask: MASK_VRGATHER_VV,
+        match_data: MATCH_VRGATHER_VV,
+        name: "VRGATHER_VV",
+        operation: |cpu, inst, pc, ops| v_gather(cpu, inst, false),
+    },
+    Instruction {
+        mask: MASK_VRGATHER_VX,
+        match_data: MATCH_VRGATHER_VX,
+        name: "VRGATHER_VX",
+        operation: |cpu, inst, pc, ops| v_gather(cpu, inst, false),
+    },
+    Instruction {
+        mask: MASK_VRGATHER_VI,
+        match_data: MATCH_VRGATHER_VI,
+        name: "VRGATHER_VI",
+        operation: |cpu, inst, pc, ops| v_gather(cpu, inst, false),
+    },
+    Instruction {
+        mask: MASK_VRGATHEREI16_VV,
+        match_data: MATCH_VRGATHEREI16_VV,
+        name: "VRGATHEREI16_VV",
+        operation: |cpu, inst, pc, ops| v_gather(cpu, inst, true),
+    },
+    Instruction {
+        mask: MASK_VCOMPRESS_VM,
+        match_data: MATCH_VCOMPRESS_VM,
+        name: "VCOMPRESS_VM",
+        operation: |cpu, inst, pc, ops| v_compress(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV1R_V,
+        match_data: MATCH_VMV1R_V,
+        name: "VMV1R_V",
+        operation: |cpu, inst, pc, ops| v_move_whole(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV2R_V,
+        match_data: MATCH_VMV2R_V,
+        name: "VMV2R_V",
+        operation: |cpu, inst, pc, ops| v_move_whole(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV4R_V,
+        match_data: MATCH_VMV4R_V,
+        name: "VMV4R_V",
+        operation: |cpu, inst, pc, ops| v_move_whole(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_VMV8R_V,
+        match_data: MATCH_VMV8R_V,
+        name: "VMV8R_V",
+        operation: |cpu, inst, pc, ops| v_move_whole(cpu, inst),
+    },
+];
+
+#[test]
+fn v_fixed_point_test() {
+    // 0b1011 >> 2 under rnu, rne, rdn and rod
+    assert_eq!(roundoff_unsigned(0b1011, 2, 0), 0b11);
+    assert_eq!(roundoff_unsigned(0b1010, 2, 1), 0b10);
+    assert_eq!(roundoff_unsigned(0b1110, 2, 1), 0b100);
+    assert_eq!(roundoff_unsigned(0b1011, 2, 2), 0b10);
+    assert_eq!(roundoff_unsigned(0b1001, 2, 3), 0b11);
+    assert_eq!(roundoff_signed(-3, 1, 0), -1);
+
+    let mut ctx = VCtx {
+        sew: 8,
+        vxrm: 0,
+        vxsat: false,
+    };
+    assert_eq!(v_saddu(0xf0, 0x0f, &mut ctx), 0xff);
+    assert!(!ctx.vxsat);
+    assert_eq!(v_sadd(0x7f, 0x01, &mut ctx), 0x7f);
+    assert!(ctx.vxsat);
+    // -1.0 * -1.0 saturates to 0.99
+    assert_eq!(v_smul(0x80, 0x80, &mut ctx), 0x7f);
+    assert_eq!(v_smul(0x40, 0x40, &mut ctx), 0x20);
+    assert_eq!(v_nclipu(0x1234, 4, &mut ctx), 0xff);
+    assert_eq!(v_nclip(0xff80, 1, &mut ctx) as u8, 0xc0);
+}
+
+#[test]
+fn v_group_test() {
+    assert_eq!(v_vlmax(128, 8, 3), 128);
+    assert_eq!(v_vlmax(128, 64, -1), 1);
+    assert!(v_aligned(4, 2) && !v_aligned(2, 2));
+    // widening: only the highest part of vd may overlap a source with EMUL >= 1
+    assert!(v_overlap_ok(2, 1, 16, 3, 0, 8));
+    assert!(!v_overlap_ok(2, 1, 16, 2, 0, 8));
+    // narrowing: only the lowest part of the source may be overlapped
+    assert!(v_overlap_ok(2, 0, 8, 2, 1, 16));
+    assert!(!v_overlap_ok(3, 0, 8, 2, 1, 16));
+    // compares write a mask that may overlap the first source register
+    assert!(v_overlap_ok(4, 0, 1, 4, 2, 32));
+}
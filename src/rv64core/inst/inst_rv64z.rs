@@ -1,24 +1,56 @@
 use log::debug;
 
 use crate::rv64core::{
+    cpu_core::CpuCore,
     inst::inst_base::*,
     traptype::{DebugCause, TrapType},
 };
 
+// seed must be accessed with a csr instruction that writes it, the read-only
+// forms of CSRRS and CSRRC raise an illegal instruction exception
+fn seed_access_check(f: &FormatCSR, inst: u32) -> Result<(), TrapType> {
+    match f.csr == CSR_SEED as u64 && f.rs1 == 0 {
+        true => Err(TrapType::IllegalInstruction(inst.into())),
+        false => Ok(()),
+    }
+}
+
+// The exception a WFI or WRS.NTO raises if it is not woken up in time. Below M-mode
+// mstatus.TW=1 raises an illegal instruction exception, otherwise in VS-mode with
+// hstatus.VTW=1 a virtual instruction exception. A WFI in VU-mode always raises the
+// latter, and a WFI in U-mode the former when S-mode exists.
+pub fn wait_timeout_trap(cpu: &CpuCore, inst: u32, wfi: bool) -> Option<TrapType> {
+    let cur_priv = cpu.cur_priv.get();
+    let virt = cpu.cur_virt.get();
+    let user = cur_priv == PrivilegeLevels::User;
+    if cur_priv == PrivilegeLevels::Machine {
+        None
+    } else if cpu.csr_regs.xstatus.get().tw() || (wfi && user && !virt && cpu.config.s_mode()) {
+        Some(TrapType::IllegalInstruction(inst.into()))
+    } else if virt && (cpu.csr_regs.hstatus.get().vtw() || (wfi && user)) {
+        Some(TrapType::VirtualInstruction(inst.into()))
+    } else {
+        None
+    }
+}
+
 #[allow(unused_variables)]
 pub const INSTRUCTIONS_Z: &[Instruction] = &[
     Instruction {
         mask: MASK_EBREAK,
         match_data: MATCH_EBREAK,
         name: "EBREAK",
-        operation: |cpu, inst, pc| handle_ebreak(cpu, pc),
+        operation: |cpu, inst, pc, ops| handle_ebreak(cpu, pc),
     },
     Instruction {
         mask: MASK_ECALL,
         match_data: MATCH_ECALL,
         name: "ECALL",
-        operation: |cpu, inst, pc| match cpu.cur_priv.get() {
+        operation: |cpu, inst, pc, ops| match cpu.cur_priv.get() {
             PrivilegeLevels::User => Err(TrapType::EnvironmentCallFromUMode),
+            PrivilegeLevels::Supervisor if cpu.cur_virt.get() => {
+                Err(TrapType::EnvironmentCallFromVSMode)
+            }
             PrivilegeLevels::Supervisor => Err(TrapType::EnvironmentCallFromSMode),
             PrivilegeLevels::Machine => Err(TrapType::EnvironmentCallFromMMode),
         },
@@ -27,13 +59,16 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_WFI,
         match_data: MATCH_WFI,
         name: "WFI",
-        operation: |cpu, inst, pc| Ok(()),
+        operation: |cpu, inst, pc, ops| {
+            let timeout_trap = wait_timeout_trap(cpu, inst, true);
+            cpu.wait_for_interrupt(timeout_trap)
+        },
     },
     Instruction {
         mask: MASK_MRET,
         match_data: MATCH_MRET,
         name: "MRET",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // An MRET or SRET instruction is used to return from a trap in M-mode or S-mode respectively.
             // When executing an xRET instruction, supposing xPP holds the value y, xIE is set to xPIE; the
             // privilege mode is changed to y; xPIE is set to 1; and xPP is set to the least-privileged supported
@@ -62,6 +97,10 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
             if y != PrivilegeLevels::Machine {
                 mstatus.set_mprv(false);
             }
+            // the virtualization mode is changed to MPV when returning to a mode below M
+            cpu.cur_virt
+                .set(mstatus.mpv() && y != PrivilegeLevels::Machine);
+            mstatus.set_mpv(false);
 
             // warn!("MRET:mstatus_now:{mstatus_val:x}");
             cpu.csr_regs.xstatus.set(mstatus);
@@ -71,6 +110,7 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
             let mepc = cpu.csr_regs.mepc.get();
             // warn!("mret->{mepc_val:x}");
             cpu.npc = mepc;
+            cpu.csr_regs.triggers.mret();
 
             Ok(())
         },
@@ -79,7 +119,7 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_SRET,
         match_data: MATCH_SRET,
         name: "SRET",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             //  SRET should also raise an illegal instruction exception when TSR=1 in mstatus
             // An MRET or SRET instruction is used to return from a trap in M-mode or S-mode respectively.
             // When executing an xRET instruction, supposing xPP holds the value y, xIE is set to xPIE; the
@@ -88,12 +128,20 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
             //  xRET sets the pc to the value stored in the xepc register.
             // let mstatus_val = cpu.csr_regs.read_raw(CSR_MSTATUS.into());
             // let mut mstatus = Mstatus::from(mstatus_val);
+            if cpu.cur_virt.get() {
+                return sret_from_vs(cpu, inst);
+            }
             let mut mstatus = cpu.csr_regs.xstatus.get();
 
             // SRET should also raise an illegal instruction exception when TSR=1 in mstatus
             if mstatus.tsr() {
                 return Err(TrapType::IllegalInstruction(inst.into()));
             }
+            // SRET in HS-mode returns to the virtualization mode held in hstatus.SPV
+            let mut hstatus = cpu.csr_regs.hstatus.get();
+            cpu.cur_virt.set(hstatus.spv());
+            hstatus.set_spv(false);
+            cpu.csr_regs.hstatus.set(hstatus);
 
             // supposing xPP holds the value y
             let y = mstatus.get_spp_priv();
@@ -129,8 +177,9 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_FENCE_I,
         match_data: MATCH_FENCE_I,
         name: "FENCE_I",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             cpu.cache_system.borrow_mut().clear();
+            cpu.block_cache.clear();
             Ok(())
         },
     },
@@ -138,48 +187,22 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_SFENCE_VMA,
         match_data: MATCH_SFENCE_VMA,
         name: "SFENCE_VMA",
-        operation: |cpu, inst, pc| {
-            // if (cur_priv < S_MODE || (cur_priv == S_MODE && mstatus->tvm))
-            // return false;
-            // require_privilege(get_field(STATE.mstatus->read(), MSTATUS_TVM) ? PRV_M : PRV_S);
-
-            let f = parse_format_r(inst);
-            let rs1_data = cpu.gpr.read(f.rs1);
-            let rs2_data = cpu.gpr.read(f.rs2);
-
-            let mstatus = cpu.csr_regs.xstatus.get();
-            let cur_priv = cpu.cur_priv.get();
-
-            let require_priv = if mstatus.tvm() {
-                PrivilegeLevels::Machine
-            } else {
-                PrivilegeLevels::Supervisor
-            };
-
-            // warn!("SFENCE_VMA:cur_priv:{:?},require_priv:{:?}", cur_priv, require_priv);
-
-            if !require_priv.check_priv(cur_priv) {
-                Err(TrapType::IllegalInstruction(inst.into()))
-            } else {
-                // info!("SFENCE_VMA:rs1_data:{:x},rs2_data:{:x}", rs1_data, rs2_data);
-                cpu.mmu.fence_vma(rs1_data, rs2_data as u16);
-                Ok(())
-            }
-        },
+        operation: |cpu, inst, pc, ops| sfence_vma(cpu, inst),
     },
     Instruction {
         mask: MASK_FENCE,
         match_data: MATCH_FENCE,
         name: "FENCE",
-        operation: |cpu, inst, pc| Ok(()),
+        operation: |cpu, inst, pc, ops| Ok(()),
     },
     Instruction {
         mask: MASK_CSRRC,
         match_data: MATCH_CSRRC,
         name: "CSRRC",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // t = CSRs[csr]; CSRs[csr] = t &∼x[rs1]; x[rd] = t
             let f = parse_format_csr(inst);
+            seed_access_check(&f, inst)?;
             let csr_ret = cpu.csr_regs.read(f.csr, cpu.cur_priv.get());
 
             let t = match csr_ret {
@@ -204,9 +227,10 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_CSRRS,
         match_data: MATCH_CSRRS,
         name: "CSRRS",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // t = CSRs[csr]; CSRs[csr] = t | x[rs1]; x[rd] = t
             let f = parse_format_csr(inst);
+            seed_access_check(&f, inst)?;
             let csr_ret = cpu.csr_regs.read(f.csr, cpu.cur_priv.get());
 
             let t = match csr_ret {
@@ -231,7 +255,7 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_CSRRW,
         match_data: MATCH_CSRRW,
         name: "CSRRW",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // t = CSRs[csr]; CSRs[csr] = x[rs1]; x[rd] = t
             let f = parse_format_csr(inst);
 
@@ -258,9 +282,10 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_CSRRCI,
         match_data: MATCH_CSRRCI,
         name: "CSRRCI",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // t = CSRs[csr]; CSRs[csr] = t &∼zimm; x[rd] =
             let f = parse_format_csr(inst);
+            seed_access_check(&f, inst)?;
             let csr_ret = cpu.csr_regs.read(f.csr, cpu.cur_priv.get());
 
             let t = match csr_ret {
@@ -286,9 +311,10 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_CSRRSI,
         match_data: MATCH_CSRRSI,
         name: "CSRRSI",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // t = CSRs[csr]; CSRs[csr] = t | zimm; x[rd] = t
             let f = parse_format_csr(inst);
+            seed_access_check(&f, inst)?;
             let csr_ret = cpu.csr_regs.read(f.csr, cpu.cur_priv.get());
 
             let t = match csr_ret {
@@ -312,7 +338,7 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
         mask: MASK_CSRRWI,
         match_data: MATCH_CSRRWI,
         name: "CSRRWI",
-        operation: |cpu, inst, pc| {
+        operation: |cpu, inst, pc, ops| {
             // x[rd] = CSRs[csr]; CSRs[csr] = zimm
             let f = parse_format_csr(inst);
 
@@ -337,6 +363,89 @@ pub const INSTRUCTIONS_Z: &[Instruction] = &[
     },
 ];
 
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_SVINVAL: &[Instruction] = &[
+    Instruction {
+        mask: MASK_SINVAL_VMA,
+        match_data: MATCH_SINVAL_VMA,
+        name: "SINVAL_VMA",
+        operation: |cpu, inst, pc, ops| sfence_vma(cpu, inst),
+    },
+    // the invalidations are not reordered, so the ordering fences have nothing to wait for
+    Instruction {
+        mask: MASK_SFENCE_W_INVAL,
+        match_data: MATCH_SFENCE_W_INVAL,
+        name: "SFENCE_W_INVAL",
+        operation: |cpu, inst, pc, ops| svinval_check_priv(cpu, inst),
+    },
+    Instruction {
+        mask: MASK_SFENCE_INVAL_IR,
+        match_data: MATCH_SFENCE_INVAL_IR,
+        name: "SFENCE_INVAL_IR",
+        operation: |cpu, inst, pc, ops| svinval_check_priv(cpu, inst),
+    },
+];
+
+// SFENCE.VMA and SINVAL.VMA need S-mode, or M-mode when mstatus.TVM=1
+fn sfence_vma(cpu: &mut crate::rv64core::cpu_core::CpuCore, inst: u32) -> Result<(), TrapType> {
+    let f = parse_format_r(inst);
+    let rs1_data = cpu.gpr.read(f.rs1);
+    let rs2_data = cpu.gpr.read(f.rs2);
+
+    let mstatus = cpu.csr_regs.xstatus.get();
+    let cur_priv = cpu.cur_priv.get();
+
+    // in VS-mode the fence applies to the guest address space, which is never cached
+    if cpu.cur_virt.get() {
+        let vtvm = cpu.csr_regs.hstatus.get().vtvm();
+        if cur_priv == PrivilegeLevels::User || vtvm {
+            return Err(TrapType::VirtualInstruction(inst.into()));
+        }
+        return Ok(());
+    }
+
+    let require_priv = if mstatus.tvm() {
+        PrivilegeLevels::Machine
+    } else {
+        PrivilegeLevels::Supervisor
+    };
+
+    if !require_priv.check_priv(cur_priv) {
+        Err(TrapType::IllegalInstruction(inst.into()))
+    } else {
+        cpu.mmu.fence_vma(rs1_data, rs2_data as u16);
+        cpu.block_cache.clear();
+        Ok(())
+    }
+}
+
+// SFENCE.W.INVAL and SFENCE.INVAL.IR are not affected by TVM, they only trap in U/VU-mode
+fn svinval_check_priv(cpu: &crate::rv64core::cpu_core::CpuCore, inst: u32) -> Result<(), TrapType> {
+    match (cpu.cur_priv.get(), cpu.cur_virt.get()) {
+        (PrivilegeLevels::User, true) => Err(TrapType::VirtualInstruction(inst.into())),
+        (PrivilegeLevels::User, false) => Err(TrapType::IllegalInstruction(inst.into())),
+        _ => Ok(()),
+    }
+}
+
+// SRET executed in VS-mode returns through vsstatus and vsepc
+fn sret_from_vs(cpu: &mut crate::rv64core::cpu_core::CpuCore, inst: u32) -> Result<(), TrapType> {
+    let hstatus = cpu.csr_regs.hstatus.get();
+    if cpu.cur_priv.get() == PrivilegeLevels::User || hstatus.vtsr() {
+        return Err(TrapType::VirtualInstruction(inst.into()));
+    }
+    let mut vsstatus = cpu.csr_regs.vsstatus.get();
+    let y = vsstatus.get_spp_priv();
+    vsstatus.set_sie(vsstatus.spie());
+    cpu.cur_priv.set(y);
+    vsstatus.set_spie(true);
+    vsstatus.set_spp(false);
+    cpu.csr_regs.vsstatus.set(vsstatus);
+
+    cpu.npc = cpu.csr_regs.vsepc.get();
+    Ok(())
+}
+
 pub fn handle_ebreak(
     cpu: &mut crate::rv64core::cpu_core::CpuCore,
     pc: u64,
@@ -344,15 +453,20 @@ pub fn handle_ebreak(
     let in_debug_mode = cpu.debug_state.debug_mode;
     let dcsr = cpu.csr_regs.dcsr.get();
     let cur_priv = cpu.cur_priv.get();
+    let virt = cpu.cur_virt.get();
 
     let cur_priv_m = cur_priv == PrivilegeLevels::Machine;
-    let cur_priv_s = cur_priv == PrivilegeLevels::Supervisor;
-    let cur_priv_u = cur_priv == PrivilegeLevels::User;
+    let cur_priv_s = cur_priv == PrivilegeLevels::Supervisor && !virt;
+    let cur_priv_u = cur_priv == PrivilegeLevels::User && !virt;
+    let cur_priv_vs = cur_priv == PrivilegeLevels::Supervisor && virt;
+    let cur_priv_vu = cur_priv == PrivilegeLevels::User && virt;
 
     let enter_debug_cond = [
         cur_priv_m && dcsr.ebreakm(),
         cur_priv_s & dcsr.ebreaks(),
         cur_priv_u & dcsr.ebreaku(),
+        cur_priv_vs & dcsr.ebreakvs(),
+        cur_priv_vu & dcsr.ebreakvu(),
     ]
     .iter()
     .any(|&x| x);
@@ -361,6 +475,8 @@ pub fn handle_ebreak(
         cur_priv_m && !dcsr.ebreakm(),
         cur_priv_s & !dcsr.ebreaks(),
         cur_priv_u & !dcsr.ebreaku(),
+        cur_priv_vs & !dcsr.ebreakvs(),
+        cur_priv_vu & !dcsr.ebreakvu(),
     ]
     .iter()
     .any(|&x| x);
@@ -0,0 +1,610 @@
+use crate::rv64core::inst::inst_base::*;
+
+// carry-less multiply, the full 128-bit product
+fn clmul_full(a: u64, b: u64) -> u128 {
+    (0..64)
+        .filter(|i| (b >> i) & 1 == 1)
+        .fold(0_u128, |acc, i| acc ^ ((a as u128) << i))
+}
+
+// Zba: address generation
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBA: &[Instruction] = &[
+    Instruction {
+        mask: MASK_ADD_UW,
+        match_data: MATCH_ADD_UW,
+        name: "ADD_UW",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + zext(x[rs1][31:0])
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32 as u64;
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SH1ADD,
+        match_data: MATCH_SH1ADD,
+        name: "SH1ADD",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + (x[rs1] << 1)
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1 << 1));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SH2ADD,
+        match_data: MATCH_SH2ADD,
+        name: "SH2ADD",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + (x[rs1] << 2)
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1 << 2));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SH3ADD,
+        match_data: MATCH_SH3ADD,
+        name: "SH3ADD",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + (x[rs1] << 3)
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1 << 3));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SH1ADD_UW,
+        match_data: MATCH_SH1ADD_UW,
+        name: "SH1ADD_UW",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + (zext(x[rs1][31:0]) << 1)
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32 as u64;
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1 << 1));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SH2ADD_UW,
+        match_data: MATCH_SH2ADD_UW,
+        name: "SH2ADD_UW",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + (zext(x[rs1][31:0]) << 2)
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32 as u64;
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1 << 2));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SH3ADD_UW,
+        match_data: MATCH_SH3ADD_UW,
+        name: "SH3ADD_UW",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] + (zext(x[rs1][31:0]) << 3)
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32 as u64;
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2.wrapping_add(rs1 << 3));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SLLI_UW,
+        match_data: MATCH_SLLI_UW,
+        name: "SLLI_UW",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = zext(x[rs1][31:0]) << shamt
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32 as u64;
+            let shamt = (f.imm & 0x3f) as u64;
+
+            cpu.gpr.write(f.rd, rs1 << shamt);
+            Ok(())
+        },
+    },
+];
+
+// Zbb: basic bit-manipulation
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBB: &[Instruction] = &[
+    Instruction {
+        mask: MASK_CLZ,
+        match_data: MATCH_CLZ,
+        name: "CLZ",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(f.rd, rs1.leading_zeros() as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CLZW,
+        match_data: MATCH_CLZW,
+        name: "CLZW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(f.rd, rs1.leading_zeros() as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CTZ,
+        match_data: MATCH_CTZ,
+        name: "CTZ",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(f.rd, rs1.trailing_zeros() as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CTZW,
+        match_data: MATCH_CTZW,
+        name: "CTZW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(f.rd, rs1.trailing_zeros() as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CPOP,
+        match_data: MATCH_CPOP,
+        name: "CPOP",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(f.rd, rs1.count_ones() as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CPOPW,
+        match_data: MATCH_CPOPW,
+        name: "CPOPW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(f.rd, rs1.count_ones() as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_MAX,
+        match_data: MATCH_MAX,
+        name: "MAX",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as i64;
+            let rs2 = cpu.gpr.read(f.rs2) as i64;
+
+            cpu.gpr.write(f.rd, rs1.max(rs2) as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_MAXU,
+        match_data: MATCH_MAXU,
+        name: "MAXU",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs1.max(rs2));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_MIN,
+        match_data: MATCH_MIN,
+        name: "MIN",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as i64;
+            let rs2 = cpu.gpr.read(f.rs2) as i64;
+
+            cpu.gpr.write(f.rd, rs1.min(rs2) as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_MINU,
+        match_data: MATCH_MINU,
+        name: "MINU",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs1.min(rs2));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SEXT_B,
+        match_data: MATCH_SEXT_B,
+        name: "SEXT_B",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as i8;
+
+            cpu.gpr.write(f.rd, rs1 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SEXT_H,
+        match_data: MATCH_SEXT_H,
+        name: "SEXT_H",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as i16;
+
+            cpu.gpr.write(f.rd, rs1 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_ZEXT_H,
+        match_data: MATCH_ZEXT_H,
+        name: "ZEXT_H",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u16;
+
+            cpu.gpr.write(f.rd, rs1 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_ORC_B,
+        match_data: MATCH_ORC_B,
+        name: "ORC_B",
+        operation: |cpu, inst, pc, ops| {
+            // each byte becomes 0xff if any bit of it is set
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            let wb_data = (0..8)
+                .filter(|i| (rs1 >> (i * 8)) & 0xff != 0)
+                .fold(0_u64, |acc, i| acc | (0xff << (i * 8)));
+            cpu.gpr.write(f.rd, wb_data);
+            Ok(())
+        },
+    },
+];
+
+// the rotations, logic with negate and byte-reverse, shared by Zbb and Zbkb
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBB_ZBKB: &[Instruction] = &[
+    Instruction {
+        mask: MASK_ANDN,
+        match_data: MATCH_ANDN,
+        name: "ANDN",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs1] & ~x[rs2]
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs1 & !rs2);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_ORN,
+        match_data: MATCH_ORN,
+        name: "ORN",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs1] | ~x[rs2]
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs1 | !rs2);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_XNOR,
+        match_data: MATCH_XNOR,
+        name: "XNOR",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = ~(x[rs1] ^ x[rs2])
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, !(rs1 ^ rs2));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_ROL,
+        match_data: MATCH_ROL,
+        name: "ROL",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let shamt = cpu.gpr.read(f.rs2) & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1.rotate_left(shamt as u32));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_ROLW,
+        match_data: MATCH_ROLW,
+        name: "ROLW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32;
+            let shamt = cpu.gpr.read(f.rs2) & 0x1f;
+
+            let wb_data = rs1.rotate_left(shamt as u32);
+            cpu.gpr.write(f.rd, wb_data as i32 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_ROR,
+        match_data: MATCH_ROR,
+        name: "ROR",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let shamt = cpu.gpr.read(f.rs2) & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1.rotate_right(shamt as u32));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_RORW,
+        match_data: MATCH_RORW,
+        name: "RORW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32;
+            let shamt = cpu.gpr.read(f.rs2) & 0x1f;
+
+            let wb_data = rs1.rotate_right(shamt as u32);
+            cpu.gpr.write(f.rd, wb_data as i32 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_RORI,
+        match_data: MATCH_RORI,
+        name: "RORI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let shamt = (f.imm & 0x3f) as u32;
+
+            cpu.gpr.write(f.rd, rs1.rotate_right(shamt));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_RORIW,
+        match_data: MATCH_RORIW,
+        name: "RORIW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1) as u32;
+            let shamt = (f.imm & 0x1f) as u32;
+
+            let wb_data = rs1.rotate_right(shamt);
+            cpu.gpr.write(f.rd, wb_data as i32 as i64 as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_REV8,
+        match_data: MATCH_REV8,
+        name: "REV8",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(f.rd, rs1.swap_bytes());
+            Ok(())
+        },
+    },
+];
+
+// Zbc: carry-less multiplication
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBC: &[Instruction] = &[Instruction {
+    mask: MASK_CLMULR,
+    match_data: MATCH_CLMULR,
+    name: "CLMULR",
+    operation: |cpu, inst, pc, ops| {
+        // bits [2*XLEN-2 : XLEN-1] of the product
+        let f = parse_format_r(inst);
+        let rs1 = cpu.gpr.read(f.rs1);
+        let rs2 = cpu.gpr.read(f.rs2);
+
+        cpu.gpr.write(f.rd, (clmul_full(rs1, rs2) >> 63) as u64);
+        Ok(())
+    },
+}];
+
+// clmul and clmulh, shared by Zbc and Zbkc
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBC_ZBKC: &[Instruction] = &[
+    Instruction {
+        mask: MASK_CLMUL,
+        match_data: MATCH_CLMUL,
+        name: "CLMUL",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, clmul_full(rs1, rs2) as u64);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CLMULH,
+        match_data: MATCH_CLMULH,
+        name: "CLMULH",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, (clmul_full(rs1, rs2) >> 64) as u64);
+            Ok(())
+        },
+    },
+];
+
+// Zbs: single-bit instructions
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBS: &[Instruction] = &[
+    Instruction {
+        mask: MASK_BCLR,
+        match_data: MATCH_BCLR,
+        name: "BCLR",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = cpu.gpr.read(f.rs2) & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1 & !(1 << index));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BCLRI,
+        match_data: MATCH_BCLRI,
+        name: "BCLRI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = f.imm & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1 & !(1 << index));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BEXT,
+        match_data: MATCH_BEXT,
+        name: "BEXT",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = cpu.gpr.read(f.rs2) & 0x3f;
+
+            cpu.gpr.write(f.rd, (rs1 >> index) & 1);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BEXTI,
+        match_data: MATCH_BEXTI,
+        name: "BEXTI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = f.imm & 0x3f;
+
+            cpu.gpr.write(f.rd, (rs1 >> index) & 1);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BINV,
+        match_data: MATCH_BINV,
+        name: "BINV",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = cpu.gpr.read(f.rs2) & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1 ^ (1 << index));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BINVI,
+        match_data: MATCH_BINVI,
+        name: "BINVI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = f.imm & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1 ^ (1 << index));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BSET,
+        match_data: MATCH_BSET,
+        name: "BSET",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = cpu.gpr.read(f.rs2) & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1 | (1 << index));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BSETI,
+        match_data: MATCH_BSETI,
+        name: "BSETI",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_i(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let index = f.imm & 0x3f;
+
+            cpu.gpr.write(f.rd, rs1 | (1 << index));
+            Ok(())
+        },
+    },
+];
+
+#[test]
+fn clmul_test() {
+    assert_eq!(clmul_full(0b101, 0b11), 0b1111);
+    assert_eq!(clmul_full(u64::MAX, 2) >> 64, 1);
+    assert_eq!((clmul_full(1 << 63, 1 << 63) >> 63) as u64, 1 << 63);
+}
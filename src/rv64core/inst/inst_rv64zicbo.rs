@@ -0,0 +1,294 @@
+use crate::rv64core::{
+    cpu_core::CpuCore, csr_regs_define::MenvcfgIn, inst::inst_base::*, traptype::TrapType,
+    trigger::TriggerOp,
+};
+
+// the cache-block size of cbo.clean/flush/inval is the dcache line size
+const CBOM_BLOCK_SIZE: u64 = 64;
+
+// prefetch.i/r/w (Zicbop) are ORI hints with rd=0, they are executed as ORI and need no entry here
+
+// Below M-mode the instruction is enabled by menvcfg, then by henvcfg in VS/VU-mode,
+// then by senvcfg in U-mode. A disable by menvcfg is always illegal, the others raise a
+// virtual instruction exception when V=1.
+fn cbo_check_enable(
+    cpu: &CpuCore,
+    inst: u32,
+    enable: fn(MenvcfgIn) -> bool,
+) -> Result<(), TrapType> {
+    let cur_priv = cpu.cur_priv.get();
+    let virt = cpu.cur_virt.get();
+    if cur_priv == PrivilegeLevels::Machine {
+        return Ok(());
+    }
+    if !enable(cpu.csr_regs.menvcfg.get()) {
+        return Err(TrapType::IllegalInstruction(inst.into()));
+    }
+    let henvcfg_off = virt && !enable(cpu.csr_regs.henvcfg.get());
+    let senvcfg_off = cur_priv == PrivilegeLevels::User && !enable(cpu.csr_regs.senvcfg.get());
+    match (henvcfg_off || senvcfg_off, virt) {
+        (false, _) => Ok(()),
+        (true, true) => Err(TrapType::VirtualInstruction(inst.into())),
+        (true, false) => Err(TrapType::IllegalInstruction(inst.into())),
+    }
+}
+
+// cbo.inval is performed as cbo.flush unless every enabling envcfg has CBIE=0b11
+fn cbo_inval_is_flush(cpu: &CpuCore) -> bool {
+    let cur_priv = cpu.cur_priv.get();
+    let m_flush = cur_priv != PrivilegeLevels::Machine && cpu.csr_regs.menvcfg.get().cbie() == 1;
+    let h_flush = cpu.cur_virt.get() && cpu.csr_regs.henvcfg.get().cbie() == 1;
+    let s_flush = cur_priv == PrivilegeLevels::User && cpu.csr_regs.senvcfg.get().cbie() == 1;
+    m_flush || h_flush || s_flush
+}
+
+// The management instructions are permitted with either read or write permission,
+// faults are always reported as store faults.
+fn cbom_translate(cpu: &mut CpuCore, addr: u64) -> Result<u64, TrapType> {
+    let base = addr & !(CBOM_BLOCK_SIZE - 1);
+    cpu.mmu.update_access_type(&AccessType::Load(addr));
+    if let Ok(paddr) = cpu.mmu.translate(base, CBOM_BLOCK_SIZE as usize) {
+        return Ok(paddr);
+    }
+    cpu.mmu.update_access_type(&AccessType::Store(addr));
+    cpu.mmu.translate(base, CBOM_BLOCK_SIZE as usize)
+}
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZICBOM: &[Instruction] = &[
+    Instruction {
+        mask: MASK_CBO_CLEAN,
+        match_data: MATCH_CBO_CLEAN,
+        name: "CBO_CLEAN",
+        operation: |cpu, inst, pc, ops| {
+            cbo_check_enable(cpu, inst, |envcfg| envcfg.cbcfe())?;
+            let f = parse_format_r(inst);
+            let paddr = cbom_translate(cpu, cpu.gpr.read(f.rs1))?;
+            cpu.cache_system.borrow_mut().dcache.clean(paddr);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CBO_FLUSH,
+        match_data: MATCH_CBO_FLUSH,
+        name: "CBO_FLUSH",
+        operation: |cpu, inst, pc, ops| {
+            cbo_check_enable(cpu, inst, |envcfg| envcfg.cbcfe())?;
+            let f = parse_format_r(inst);
+            let paddr = cbom_translate(cpu, cpu.gpr.read(f.rs1))?;
+            cpu.cache_system.borrow_mut().dcache.flush(paddr);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CBO_INVAL,
+        match_data: MATCH_CBO_INVAL,
+        name: "CBO_INVAL",
+        operation: |cpu, inst, pc, ops| {
+            cbo_check_enable(cpu, inst, |envcfg| envcfg.cbie() != 0)?;
+            let f = parse_format_r(inst);
+            let paddr = cbom_translate(cpu, cpu.gpr.read(f.rs1))?;
+            let mut cache_system = cpu.cache_system.borrow_mut();
+            match cbo_inval_is_flush(cpu) {
+                true => cache_system.dcache.flush(paddr),
+                false => cache_system.dcache.inval(paddr),
+            }
+            Ok(())
+        },
+    },
+];
+
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZICBOZ: &[Instruction] = &[Instruction {
+    mask: MASK_CBO_ZERO,
+    match_data: MATCH_CBO_ZERO,
+    name: "CBO_ZERO",
+    operation: |cpu, inst, pc, ops| {
+        // zero the whole block containing rs1, it behaves as a store
+        cbo_check_enable(cpu, inst, |envcfg| envcfg.cbze())?;
+        let f = parse_format_r(inst);
+        let addr = cpu.gpr.read(f.rs1);
+        let block_size = cpu.config.cboz_block_size();
+        let base = addr & !(block_size as u64 - 1);
+
+        cpu.trigger_check(TriggerOp::Store, addr, 0)?;
+        let access_type = AccessType::Store(addr);
+        cpu.mmu.update_access_type(&access_type);
+        let paddr = cpu.mmu.translate(base, block_size)?;
+
+        let uncached = cpu.mmu.uncacheable();
+        for offset in (0..block_size as u64).step_by(8) {
+            if cpu.dcache_write(paddr + offset, 0, 8, uncached).is_err() {
+                return Err(access_type.throw_access_exception());
+            }
+        }
+        Ok(())
+    },
+}];
+
+#[cfg(test)]
+fn cbo_cpu_for_test(mut config: crate::config::Config) -> CpuCore {
+    config.set_isa("rv64imach_zicbom_zicboz").unwrap();
+    config.set_s_mode();
+    let mut cpu = crate::rv64core::cpu_core::cpu_for_test(config);
+    cpu.pmp_for_test();
+    cpu.gpr.write(11, 0x8000_1010);
+    cpu
+}
+
+// run the instruction on (a1) in the mode, the exception code is returned or 0
+#[cfg(test)]
+fn cbo_run_for_test(cpu: &mut CpuCore, m: u32, privi: PrivilegeLevels, virt: bool) -> u64 {
+    cpu.cur_priv.set(privi);
+    cpu.cur_virt.set(virt);
+    cpu.npc = 0x8000_0000;
+    cpu.csr_regs.mcause.set(0.into());
+    cpu.exec_for_test(m | 11 << 15);
+    cpu.csr_regs.mcause.get().exception_code()
+}
+
+#[test]
+fn cbo_enable_test() {
+    use crate::config::Config;
+    use PrivilegeLevels::{Machine, Supervisor, User};
+
+    let mut cpu = cbo_cpu_for_test(Config::new());
+    let cbos = [
+        MATCH_CBO_CLEAN,
+        MATCH_CBO_FLUSH,
+        MATCH_CBO_INVAL,
+        MATCH_CBO_ZERO,
+    ];
+    let envcfg = |cpu: &mut CpuCore, m: u64, h: u64, s: u64| {
+        cpu.csr_regs.write_raw(CSR_MENVCFG.into(), m);
+        cpu.csr_regs.write_raw(CSR_HENVCFG.into(), h);
+        cpu.csr_regs.write_raw(CSR_SENVCFG.into(), s);
+    };
+    // CBIE=11, CBCFE=1, CBZE=1
+    let all = 0xf0;
+
+    // menvcfg disables them for every mode below M-mode, as an illegal instruction
+    envcfg(&mut cpu, 0, all, all);
+    for m in cbos {
+        assert_eq!(cbo_run_for_test(&mut cpu, m, Machine, false), 0);
+        for (privi, virt) in [(Supervisor, false), (User, false), (Supervisor, true)] {
+            assert_eq!(cbo_run_for_test(&mut cpu, m, privi, virt), 2);
+        }
+    }
+
+    // henvcfg and senvcfg raise a virtual instruction exception when V=1
+    envcfg(&mut cpu, all, 0, 0);
+    for m in cbos {
+        assert_eq!(cbo_run_for_test(&mut cpu, m, Supervisor, false), 0);
+        assert_eq!(cbo_run_for_test(&mut cpu, m, User, false), 2);
+        assert_eq!(cbo_run_for_test(&mut cpu, m, Supervisor, true), 22);
+        assert_eq!(cbo_run_for_test(&mut cpu, m, User, true), 22);
+    }
+    envcfg(&mut cpu, all, all, 0);
+    for m in cbos {
+        assert_eq!(cbo_run_for_test(&mut cpu, m, Supervisor, true), 0);
+        assert_eq!(cbo_run_for_test(&mut cpu, m, User, true), 22);
+    }
+    envcfg(&mut cpu, all, all, all);
+    for m in cbos {
+        assert_eq!(cbo_run_for_test(&mut cpu, m, User, false), 0);
+        assert_eq!(cbo_run_for_test(&mut cpu, m, User, true), 0);
+    }
+
+    // each field enables its own instructions
+    envcfg(&mut cpu, 0x30, 0, 0);
+    assert_eq!(
+        cbo_run_for_test(&mut cpu, MATCH_CBO_INVAL, Supervisor, false),
+        0
+    );
+    assert_eq!(
+        cbo_run_for_test(&mut cpu, MATCH_CBO_CLEAN, Supervisor, false),
+        2
+    );
+    assert_eq!(
+        cbo_run_for_test(&mut cpu, MATCH_CBO_ZERO, Supervisor, false),
+        2
+    );
+}
+
+#[test]
+fn cbo_writeback_test() {
+    use crate::config::Config;
+
+    let mut config = Config::new();
+    config.set_dcache_size(8);
+    let mut cpu = cbo_cpu_for_test(config);
+    let line = 0x8000_1000;
+    // a store that stays in the dcache until the line is written back
+    let store = |cpu: &mut CpuCore, data: u64| {
+        cpu.cur_priv.set(PrivilegeLevels::Machine);
+        cpu.write(line + 8, data, 8, AccessType::Store(line + 8))
+            .unwrap();
+        assert_ne!(cpu.mem_read(line + 8, 8), data);
+    };
+    let load = |cpu: &mut CpuCore| cpu.read(line + 8, 8, AccessType::Load(line + 8)).unwrap();
+    let run = |cpu: &mut CpuCore, m: u32| {
+        assert_eq!(
+            cbo_run_for_test(cpu, m, PrivilegeLevels::Supervisor, false),
+            0
+        );
+    };
+    cpu.csr_regs.write_raw(CSR_MENVCFG.into(), 0xf0);
+
+    // clean writes the line back and keeps it
+    store(&mut cpu, 1);
+    run(&mut cpu, MATCH_CBO_CLEAN);
+    assert_eq!(cpu.mem_read(line + 8, 8), 1);
+    cpu.mem_write(line + 8, 2, 8);
+    assert_eq!(load(&mut cpu), 1);
+
+    // flush writes the line back and drops it
+    store(&mut cpu, 3);
+    run(&mut cpu, MATCH_CBO_FLUSH);
+    assert_eq!(cpu.mem_read(line + 8, 8), 3);
+    cpu.mem_write(line + 8, 4, 8);
+    assert_eq!(load(&mut cpu), 4);
+
+    // inval drops the line and its dirty data
+    store(&mut cpu, 5);
+    run(&mut cpu, MATCH_CBO_INVAL);
+    assert_eq!(cpu.mem_read(line + 8, 8), 4);
+    assert_eq!(load(&mut cpu), 4);
+
+    // with menvcfg.CBIE=01 inval is performed as a flush below M-mode
+    cpu.csr_regs.write_raw(CSR_MENVCFG.into(), 0xd0);
+    store(&mut cpu, 6);
+    run(&mut cpu, MATCH_CBO_INVAL);
+    assert_eq!(cpu.mem_read(line + 8, 8), 6);
+    // but not in M-mode
+    store(&mut cpu, 7);
+    cbo_run_for_test(&mut cpu, MATCH_CBO_INVAL, PrivilegeLevels::Machine, false);
+    assert_eq!(cpu.mem_read(line + 8, 8), 6);
+    assert_eq!(load(&mut cpu), 6);
+}
+
+#[test]
+fn cbo_zero_block_size_test() {
+    use crate::config::Config;
+
+    for size in [8, 64, 256, 4096] {
+        let mut config = Config::new();
+        config.set_cboz_block_size(size);
+        let mut cpu = cbo_cpu_for_test(config);
+        let base = 0x8000_2000;
+        for addr in (base - 8..base + size as u64 + 8).step_by(8) {
+            cpu.mem_write(addr, 0x5555_5555_5555_5555, 8);
+        }
+        // rs1 points into the last doubleword of the block
+        cpu.gpr.write(11, base + size as u64 - 4);
+        assert_eq!(
+            cbo_run_for_test(&mut cpu, MATCH_CBO_ZERO, PrivilegeLevels::Machine, false),
+            0
+        );
+        for addr in (base..base + size as u64).step_by(8) {
+            assert_eq!(cpu.mem_read(addr, 8), 0);
+        }
+        assert_eq!(cpu.mem_read(base - 8, 8), 0x5555_5555_5555_5555);
+        assert_eq!(cpu.mem_read(base + size as u64, 8), 0x5555_5555_5555_5555);
+    }
+}
@@ -0,0 +1,647 @@
+use crate::rv64core::{inst::inst_base::*, traptype::TrapType};
+
+// the AES forward s-box
+const AES_SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+// the AES inverse s-box
+const AES_INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+// the SM4 s-box
+const SM4_SBOX: [u8; 256] = [
+    0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+    0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+    0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+    0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+    0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+    0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+    0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+    0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+    0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+    0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+    0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+    0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+    0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+    0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+    0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+    0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48,
+];
+
+// the round constants of the AES-128 key schedule, rnum 0xA has none
+const AES_RCON: [u8; 11] = [
+    0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36, 0x00,
+];
+
+fn get_byte(val: u64, i: usize) -> u8 {
+    (val >> (i * 8)) as u8
+}
+
+fn sub_word(word: u32, sbox: &[u8; 256]) -> u32 {
+    u32::from_le_bytes(word.to_le_bytes().map(|b| sbox[b as usize]))
+}
+
+// multiply by x in GF(2^8)
+fn xtime(b: u8) -> u8 {
+    (b << 1) ^ if b & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    (0..8)
+        .fold((0_u8, a), |(acc, a), i| {
+            let acc = if (b >> i) & 1 == 1 { acc ^ a } else { acc };
+            (acc, xtime(a))
+        })
+        .0
+}
+
+fn mix_column(col: u32, coef: [u8; 4]) -> u32 {
+    let b = col.to_le_bytes();
+    let row = |r: usize| (0..4).fold(0, |acc, i| acc ^ gf_mul(b[i], coef[(i + 4 - r) % 4]));
+    u32::from_le_bytes([row(0), row(1), row(2), row(3)])
+}
+
+fn mix_columns_fwd(col: u32) -> u32 {
+    mix_column(col, [2, 3, 1, 1])
+}
+
+fn mix_columns_inv(col: u32) -> u32 {
+    mix_column(col, [0xe, 0xb, 0xd, 0x9])
+}
+
+// the low half of ShiftRows (or InvShiftRows) over the state rs1 (columns 0,1) : rs2 (columns 2,3)
+fn aes_shift_rows(rs1: u64, rs2: u64, inverse: bool) -> u64 {
+    (0..8).fold(0, |acc, j| {
+        let (r, c) = (j % 4, j / 4);
+        let src_col = if inverse {
+            (c + 4 - r) % 4
+        } else {
+            (c + r) % 4
+        };
+        let k = r + 4 * src_col;
+        let byte = if k < 8 {
+            get_byte(rs1, k)
+        } else {
+            get_byte(rs2, k - 8)
+        };
+        acc | (byte as u64) << (j * 8)
+    })
+}
+
+// one 64-bit half of an AES round: shift rows, sub bytes and optionally mix columns
+fn aes64_round(rs1: u64, rs2: u64, inverse: bool, mix: bool) -> u64 {
+    let sbox = if inverse { &AES_INV_SBOX } else { &AES_SBOX };
+    let state = aes_shift_rows(rs1, rs2, inverse);
+    let cols = [state as u32, (state >> 32) as u32].map(|col| {
+        let col = sub_word(col, sbox);
+        match (mix, inverse) {
+            (false, _) => col,
+            (true, false) => mix_columns_fwd(col),
+            (true, true) => mix_columns_inv(col),
+        }
+    });
+    (cols[1] as u64) << 32 | cols[0] as u64
+}
+
+fn aes64im(rs1: u64) -> u64 {
+    (mix_columns_inv((rs1 >> 32) as u32) as u64) << 32 | mix_columns_inv(rs1 as u32) as u64
+}
+
+fn aes64ks1i(rs1: u64, rnum: u8) -> u64 {
+    let word = (rs1 >> 32) as u32;
+    let word = if rnum == 0xa {
+        word
+    } else {
+        word.rotate_right(8)
+    };
+    let word = sub_word(word, &AES_SBOX) ^ AES_RCON[rnum as usize] as u32;
+    (word as u64) << 32 | word as u64
+}
+
+fn aes64ks2(rs1: u64, rs2: u64) -> u64 {
+    let w0 = (rs1 >> 32) as u32 ^ rs2 as u32;
+    let w1 = w0 ^ (rs2 >> 32) as u32;
+    (w1 as u64) << 32 | w0 as u64
+}
+
+// one s-box byte of the SM4 round function (ed) or key schedule (ks), through its linear layer
+fn sm4_round(rs1: u64, rs2: u64, bs: u32, key_schedule: bool) -> u64 {
+    let x = SM4_SBOX[get_byte(rs2, bs as usize) as usize] as u32;
+    let y = if key_schedule {
+        x ^ x.rotate_left(13) ^ x.rotate_left(23)
+    } else {
+        x ^ x.rotate_left(2) ^ x.rotate_left(10) ^ x.rotate_left(18) ^ x.rotate_left(24)
+    };
+    let z = y.rotate_left(8 * bs);
+    (z ^ rs1 as u32) as i32 as i64 as u64
+}
+
+// xperm4 and xperm8: look up each element of rs2 in the elements of rs1
+fn xperm(rs1: u64, rs2: u64, width: u32) -> u64 {
+    let mask = (1_u64 << width) - 1;
+    (0..64 / width).fold(0, |acc, i| {
+        let index = (rs2 >> (i * width)) & mask;
+        let elem = if index < (64 / width) as u64 {
+            (rs1 >> (index as u32 * width)) & mask
+        } else {
+            0
+        };
+        acc | elem << (i * width)
+    })
+}
+
+fn sext_w(val: u32) -> u64 {
+    val as i32 as i64 as u64
+}
+
+// Zbkb: the packs and brev8, the rest is shared with Zbb
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBKB: &[Instruction] = &[
+    Instruction {
+        mask: MASK_PACK,
+        match_data: MATCH_PACK,
+        name: "PACK",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, rs2 << 32 | (rs1 & 0xffff_ffff));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_PACKH,
+        match_data: MATCH_PACKH,
+        name: "PACKH",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, (rs2 & 0xff) << 8 | (rs1 & 0xff));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_PACKW,
+        match_data: MATCH_PACKW,
+        name: "PACKW",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            let wb_data = (rs2 as u32 & 0xffff) << 16 | (rs1 as u32 & 0xffff);
+            cpu.gpr.write(f.rd, sext_w(wb_data));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_BREV8,
+        match_data: MATCH_BREV8,
+        name: "BREV8",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            let wb_data = u64::from_le_bytes(rs1.to_le_bytes().map(u8::reverse_bits));
+            cpu.gpr.write(f.rd, wb_data);
+            Ok(())
+        },
+    },
+];
+
+// Zbkx: crossbar permutations
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZBKX: &[Instruction] = &[
+    Instruction {
+        mask: MASK_XPERM4,
+        match_data: MATCH_XPERM4,
+        name: "XPERM4",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, xperm(rs1, rs2, 4));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_XPERM8,
+        match_data: MATCH_XPERM8,
+        name: "XPERM8",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, xperm(rs1, rs2, 8));
+            Ok(())
+        },
+    },
+];
+
+// Zknd: AES decryption
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZKND: &[Instruction] = &[
+    Instruction {
+        mask: MASK_AES64DS,
+        match_data: MATCH_AES64DS,
+        name: "AES64DS",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, aes64_round(rs1, rs2, true, false));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_AES64DSM,
+        match_data: MATCH_AES64DSM,
+        name: "AES64DSM",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, aes64_round(rs1, rs2, true, true));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_AES64IM,
+        match_data: MATCH_AES64IM,
+        name: "AES64IM",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(f.rd, aes64im(rs1));
+            Ok(())
+        },
+    },
+];
+
+// Zkne: AES encryption
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZKNE: &[Instruction] = &[
+    Instruction {
+        mask: MASK_AES64ES,
+        match_data: MATCH_AES64ES,
+        name: "AES64ES",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, aes64_round(rs1, rs2, false, false));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_AES64ESM,
+        match_data: MATCH_AES64ESM,
+        name: "AES64ESM",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, aes64_round(rs1, rs2, false, true));
+            Ok(())
+        },
+    },
+];
+
+// the AES key schedule, shared by Zknd and Zkne
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZKND_ZKNE: &[Instruction] = &[
+    Instruction {
+        mask: MASK_AES64KS1I,
+        match_data: MATCH_AES64KS1I,
+        name: "AES64KS1I",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rnum = ((inst >> 20) & 0xf) as u8;
+            if rnum > 0xa {
+                return Err(TrapType::IllegalInstruction(inst.into()));
+            }
+            let rs1 = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(f.rd, aes64ks1i(rs1, rnum));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_AES64KS2,
+        match_data: MATCH_AES64KS2,
+        name: "AES64KS2",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, aes64ks2(rs1, rs2));
+            Ok(())
+        },
+    },
+];
+
+// Zknh: SHA-256 and SHA-512 sigma functions
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZKNH: &[Instruction] = &[
+    Instruction {
+        mask: MASK_SHA256SIG0,
+        match_data: MATCH_SHA256SIG0,
+        name: "SHA256SIG0",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(
+                f.rd,
+                sext_w(x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)),
+            );
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA256SIG1,
+        match_data: MATCH_SHA256SIG1,
+        name: "SHA256SIG1",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(
+                f.rd,
+                sext_w(x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)),
+            );
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA256SUM0,
+        match_data: MATCH_SHA256SUM0,
+        name: "SHA256SUM0",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(
+                f.rd,
+                sext_w(x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)),
+            );
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA256SUM1,
+        match_data: MATCH_SHA256SUM1,
+        name: "SHA256SUM1",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr.write(
+                f.rd,
+                sext_w(x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)),
+            );
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA512SIG0,
+        match_data: MATCH_SHA512SIG0,
+        name: "SHA512SIG0",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1);
+
+            cpu.gpr
+                .write(f.rd, x.rotate_right(1) ^ x.rotate_right(8) ^ (x >> 7));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA512SIG1,
+        match_data: MATCH_SHA512SIG1,
+        name: "SHA512SIG1",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1);
+
+            cpu.gpr
+                .write(f.rd, x.rotate_right(19) ^ x.rotate_right(61) ^ (x >> 6));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA512SUM0,
+        match_data: MATCH_SHA512SUM0,
+        name: "SHA512SUM0",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(
+                f.rd,
+                x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39),
+            );
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SHA512SUM1,
+        match_data: MATCH_SHA512SUM1,
+        name: "SHA512SUM1",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1);
+
+            cpu.gpr.write(
+                f.rd,
+                x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41),
+            );
+            Ok(())
+        },
+    },
+];
+
+// Zksed: SM4 block cipher
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZKSED: &[Instruction] = &[
+    Instruction {
+        mask: MASK_SM4ED,
+        match_data: MATCH_SM4ED,
+        name: "SM4ED",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let bs = inst >> 30;
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, sm4_round(rs1, rs2, bs, false));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SM4KS,
+        match_data: MATCH_SM4KS,
+        name: "SM4KS",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let bs = inst >> 30;
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, sm4_round(rs1, rs2, bs, true));
+            Ok(())
+        },
+    },
+];
+
+// Zksh: SM3 hash permutations
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZKSH: &[Instruction] = &[
+    Instruction {
+        mask: MASK_SM3P0,
+        match_data: MATCH_SM3P0,
+        name: "SM3P0",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr
+                .write(f.rd, sext_w(x ^ x.rotate_left(9) ^ x.rotate_left(17)));
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_SM3P1,
+        match_data: MATCH_SM3P1,
+        name: "SM3P1",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            let x = cpu.gpr.read(f.rs1) as u32;
+
+            cpu.gpr
+                .write(f.rd, sext_w(x ^ x.rotate_left(15) ^ x.rotate_left(23)));
+            Ok(())
+        },
+    },
+];
+
+#[test]
+fn aes64_test() {
+    // FIPS-197 appendix C.1, AES-128
+    let key = [0x0706050403020100_u64, 0x0f0e0d0c0b0a0908];
+    let plain = [0x7766554433221100_u64, 0xffeeddccbbaa9988];
+    let cipher = [0x30047b6ad8e0c469_u64, 0x5ac5b47080b7cdd8];
+
+    let mut rk = [[0_u64; 2]; 11];
+    rk[0] = key;
+    for i in 0..10 {
+        let t = aes64ks1i(rk[i][1], i as u8);
+        let k0 = aes64ks2(t, rk[i][0]);
+        rk[i + 1] = [k0, aes64ks2(k0, rk[i][1])];
+    }
+
+    let mut s = [plain[0] ^ rk[0][0], plain[1] ^ rk[0][1]];
+    for (i, k) in rk.iter().enumerate().skip(1) {
+        let mix = i != 10;
+        s = [
+            aes64_round(s[0], s[1], false, mix) ^ k[0],
+            aes64_round(s[1], s[0], false, mix) ^ k[1],
+        ];
+    }
+    assert_eq!(s, cipher);
+
+    // the equivalent inverse cipher takes the mixed round keys
+    s = [s[0] ^ rk[10][0], s[1] ^ rk[10][1]];
+    for i in (0..10).rev() {
+        let mix = i != 0;
+        let k = if mix { rk[i].map(aes64im) } else { rk[i] };
+        s = [
+            aes64_round(s[0], s[1], true, mix) ^ k[0],
+            aes64_round(s[1], s[0], true, mix) ^ k[1],
+        ];
+    }
+    assert_eq!(s, plain);
+}
+
+#[test]
+fn sm4_test() {
+    // GB/T 32907-2016 example 1, the key is also the plaintext
+    let key = [0x01234567_u32, 0x89abcdef, 0xfedcba98, 0x76543210];
+    let fk = [0xa3b1bac6_u32, 0x56aa3350, 0x677d9197, 0xb27022dc];
+    let round = |x: [u32; 4], rk: u32, ks: bool| {
+        let t = (x[1] ^ x[2] ^ x[3] ^ rk) as u64;
+        let z = (0..4).fold(x[0] as u64, |acc, bs| sm4_round(acc, t, bs, ks));
+        [x[1], x[2], x[3], z as u32]
+    };
+
+    let mut k = [0; 4];
+    for i in 0..4 {
+        k[i] = key[i] ^ fk[i];
+    }
+    let mut rk = [0_u32; 32];
+    for (i, rk) in rk.iter_mut().enumerate() {
+        let ck = (0..4).fold(0, |acc, j| acc << 8 | ((4 * i + j) * 7 % 256) as u32);
+        k = round(k, ck, true);
+        *rk = k[3];
+    }
+
+    let x = rk.iter().fold(key, |x, &rk| round(x, rk, false));
+    assert_eq!(
+        [x[3], x[2], x[1], x[0]],
+        [0x681edf34, 0xd206965e, 0x86b3e94f, 0x536e4246]
+    );
+}
+
+#[test]
+fn xperm_test() {
+    assert_eq!(
+        xperm(0x0706050403020100, 0x0001020304050607, 8),
+        0x0001020304050607
+    );
+    // out of range indices select zero
+    assert_eq!(xperm(0xfedcba9876543210, 0x10ff, 8), 0x1010_1010_1010_0000);
+    assert_eq!(xperm(0x0123456789abcdef, 0x0f1e, 4), 0xffff_ffff_ffff_f0e1);
+}
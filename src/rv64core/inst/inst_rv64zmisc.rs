@@ -0,0 +1,105 @@
+use crate::rv64core::{
+    cpu_core::CpuCore,
+    inst::{inst_base::*, inst_rv64z::wait_timeout_trap},
+    traptype::TrapType,
+};
+
+// the cycles a WRS.STO stalls at most
+pub const WRS_STO_TIMEOUT: usize = 1024;
+
+// A WRS without a reservation completes at once. Otherwise WRS.NTO below M-mode
+// is trapped like WFI by mstatus.TW, and by hstatus.VTW in VS-mode and VU-mode.
+fn wrs(cpu: &mut CpuCore, inst: u32, timeout: Option<usize>) -> Result<(), TrapType> {
+    if !cpu.holds_reservation() {
+        return Ok(());
+    }
+    let timeout_trap = match timeout {
+        Some(_) => None,
+        None => wait_timeout_trap(cpu, inst, false),
+    };
+    cpu.wait_on_reservation(timeout, timeout_trap)
+}
+
+// Zicond: conditional zero
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZICOND: &[Instruction] = &[
+    Instruction {
+        mask: MASK_CZERO_EQZ,
+        match_data: MATCH_CZERO_EQZ,
+        name: "CZERO_EQZ",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] == 0 ? 0 : x[rs1]
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, if rs2 == 0 { 0 } else { rs1 });
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_CZERO_NEZ,
+        match_data: MATCH_CZERO_NEZ,
+        name: "CZERO_NEZ",
+        operation: |cpu, inst, pc, ops| {
+            // x[rd] = x[rs2] != 0 ? 0 : x[rs1]
+            let f = parse_format_r(inst);
+            let rs1 = cpu.gpr.read(f.rs1);
+            let rs2 = cpu.gpr.read(f.rs2);
+
+            cpu.gpr.write(f.rd, if rs2 != 0 { 0 } else { rs1 });
+            Ok(())
+        },
+    },
+];
+
+// Zihintpause: a FENCE hint, the harts are not executed in parallel so it does nothing
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZIHINTPAUSE: &[Instruction] = &[Instruction {
+    mask: MASK_PAUSE,
+    match_data: MATCH_PAUSE,
+    name: "PAUSE",
+    operation: |cpu, inst, pc, ops| Ok(()),
+}];
+
+// Zawrs: wait on the reservation set of a previous LR
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZAWRS: &[Instruction] = &[
+    Instruction {
+        mask: MASK_WRS_NTO,
+        match_data: MATCH_WRS_NTO,
+        name: "WRS_NTO",
+        operation: |cpu, inst, pc, ops| wrs(cpu, inst, None),
+    },
+    Instruction {
+        mask: MASK_WRS_STO,
+        match_data: MATCH_WRS_STO,
+        name: "WRS_STO",
+        operation: |cpu, inst, pc, ops| wrs(cpu, inst, Some(WRS_STO_TIMEOUT)),
+    },
+];
+
+// Zimop: may-be-operations, without any extension redefining them they write zero to rd
+#[allow(unused_variables)]
+pub const INSTRUCTIONS_ZIMOP: &[Instruction] = &[
+    Instruction {
+        mask: MASK_MOP_R_N,
+        match_data: MATCH_MOP_R_N,
+        name: "MOP_R_N",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            cpu.gpr.write(f.rd, 0);
+            Ok(())
+        },
+    },
+    Instruction {
+        mask: MASK_MOP_RR_N,
+        match_data: MATCH_MOP_RR_N,
+        name: "MOP_RR_N",
+        operation: |cpu, inst, pc, ops| {
+            let f = parse_format_r(inst);
+            cpu.gpr.write(f.rd, 0);
+            Ok(())
+        },
+    },
+];
@@ -3,4 +3,12 @@ pub mod inst_rv64a;
 pub mod inst_rv64i;
 pub mod inst_rv64z;
 pub mod inst_rv64m;
+pub mod inst_rv64f;
+pub mod inst_rv64d;
+pub mod inst_rv64zb;
+pub mod inst_rv64zk;
+pub mod inst_rv64zmisc;
+pub mod inst_rv64zicbo;
+pub mod inst_rv64v;
+pub mod inst_rv64h;
 pub mod inst_rv64c;
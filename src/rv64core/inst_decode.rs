@@ -2,21 +2,48 @@ use alloc::{rc::Rc, vec::Vec};
 use hashlink::LruCache;
 use log::info;
 
-use crate::rv64core::inst::inst_rv64a::INSTRUCTIONS_A;
-use crate::rv64core::inst::inst_rv64c::INSTRUCTIONS_C;
-use crate::rv64core::inst::inst_rv64m::INSTRUCTIONS_M;
+use crate::rv64core::inst::inst_rv64a::{
+    INSTRUCTIONS_A, INSTRUCTIONS_A_RV64, INSTRUCTIONS_ZABHA, INSTRUCTIONS_ZABHA_ZACAS,
+    INSTRUCTIONS_ZACAS,
+};
+use crate::rv64core::inst::inst_rv64c::{
+    INSTRUCTIONS_C, INSTRUCTIONS_C_D, INSTRUCTIONS_C_F_RV32, INSTRUCTIONS_C_RV32,
+    INSTRUCTIONS_C_RV64, INSTRUCTIONS_ZCB, INSTRUCTIONS_ZCB_M, INSTRUCTIONS_ZCB_ZBA,
+    INSTRUCTIONS_ZCB_ZBB, INSTRUCTIONS_ZCMP, INSTRUCTIONS_ZCMT,
+};
+use crate::rv64core::inst::inst_rv64d::{INSTRUCTIONS_D, INSTRUCTIONS_D_RV64};
+use crate::rv64core::inst::inst_rv64f::{INSTRUCTIONS_F, INSTRUCTIONS_F_RV64};
+use crate::rv64core::inst::inst_rv64h::{INSTRUCTIONS_H, INSTRUCTIONS_H_SVINVAL};
+use crate::rv64core::inst::inst_rv64m::{INSTRUCTIONS_M, INSTRUCTIONS_M_RV32, INSTRUCTIONS_M_RV64};
+use crate::rv64core::inst::inst_rv64v::INSTRUCTIONS_V;
+use crate::rv64core::inst::inst_rv64zb::{
+    INSTRUCTIONS_ZBA, INSTRUCTIONS_ZBB, INSTRUCTIONS_ZBB_ZBKB, INSTRUCTIONS_ZBC,
+    INSTRUCTIONS_ZBC_ZBKC, INSTRUCTIONS_ZBS,
+};
+use crate::rv64core::inst::inst_rv64zicbo::{INSTRUCTIONS_ZICBOM, INSTRUCTIONS_ZICBOZ};
+use crate::rv64core::inst::inst_rv64zk::{
+    INSTRUCTIONS_ZBKB, INSTRUCTIONS_ZBKX, INSTRUCTIONS_ZKND, INSTRUCTIONS_ZKND_ZKNE,
+    INSTRUCTIONS_ZKNE, INSTRUCTIONS_ZKNH, INSTRUCTIONS_ZKSED, INSTRUCTIONS_ZKSH,
+};
+use crate::rv64core::inst::inst_rv64zmisc::{
+    INSTRUCTIONS_ZAWRS, INSTRUCTIONS_ZICOND, INSTRUCTIONS_ZIHINTPAUSE, INSTRUCTIONS_ZIMOP,
+};
 
 use crate::{
     config::Config,
     rv64core::{
-        inst::inst_base::Instruction, inst::inst_rv64i::INSTRUCTIONS_I,
-        inst::inst_rv64z::INSTRUCTIONS_Z,
+        inst::inst_base::Instruction,
+        inst::inst_rv64i::{INSTRUCTIONS_I, INSTRUCTIONS_I_RV32, INSTRUCTIONS_I_RV64},
+        inst::inst_rv64z::{INSTRUCTIONS_SVINVAL, INSTRUCTIONS_Z},
     },
 };
 
 pub struct InstDecode {
     inst_vec: Vec<&'static Instruction>,
     inst_hash: LruCache<u32, &'static Instruction>,
+    other_vec: Vec<&'static Instruction>,
+    other_hash: LruCache<u32, &'static Instruction>,
+    rv32: bool,
     pub hit: u64,
     pub miss: u64,
     remove_count: u64,
@@ -24,26 +51,179 @@ pub struct InstDecode {
 }
 
 impl InstDecode {
-    pub fn new(config: Rc<Config>) -> Self {
+    // the decode table of one XLEN, the instructions that only exist in RV64 are left out of the RV32 one
+    fn inst_table(config: &Config, rv32: bool) -> Vec<&'static Instruction> {
         let mut i_vec = Vec::new();
         i_vec.extend(INSTRUCTIONS_I);
+        i_vec.extend(match rv32 {
+            true => INSTRUCTIONS_I_RV32,
+            false => INSTRUCTIONS_I_RV64,
+        });
         i_vec.extend(INSTRUCTIONS_Z);
 
         if config.is_enable_isa(b'm') {
             i_vec.extend(INSTRUCTIONS_M);
+            i_vec.extend(match rv32 {
+                true => INSTRUCTIONS_M_RV32,
+                false => INSTRUCTIONS_M_RV64,
+            });
         }
         if config.is_enable_isa(b'a') {
             i_vec.extend(INSTRUCTIONS_A);
+            if !rv32 {
+                i_vec.extend(INSTRUCTIONS_A_RV64);
+            }
+            if !rv32 && config.is_enable_ext("zacas") {
+                i_vec.extend(INSTRUCTIONS_ZACAS);
+            }
+            if config.is_enable_ext("zabha") {
+                i_vec.extend(INSTRUCTIONS_ZABHA);
+            }
+            if !rv32 && config.is_enable_ext("zabha") && config.is_enable_ext("zacas") {
+                i_vec.extend(INSTRUCTIONS_ZABHA_ZACAS);
+            }
+        }
+        if config.is_enable_isa(b'f') {
+            i_vec.extend(INSTRUCTIONS_F);
+            if !rv32 {
+                i_vec.extend(INSTRUCTIONS_F_RV64);
+            }
+        }
+        if config.is_enable_isa(b'd') {
+            i_vec.extend(INSTRUCTIONS_D);
+            if !rv32 {
+                i_vec.extend(INSTRUCTIONS_D_RV64);
+            }
+        }
+        if !rv32 && config.is_enable_isa(b'v') {
+            i_vec.extend(INSTRUCTIONS_V);
+        }
+        if !rv32 && config.h_mode() {
+            i_vec.extend(INSTRUCTIONS_H);
         }
         if config.is_enable_isa(b'c') {
             i_vec.extend(INSTRUCTIONS_C);
+            i_vec.extend(match rv32 {
+                true => INSTRUCTIONS_C_RV32,
+                false => INSTRUCTIONS_C_RV64,
+            });
+            if config.is_enable_isa(b'd') {
+                i_vec.extend(INSTRUCTIONS_C_D);
+            }
+            if rv32 && config.is_enable_isa(b'f') {
+                i_vec.extend(INSTRUCTIONS_C_F_RV32);
+            }
+            if !rv32 && config.is_enable_ext("zcb") {
+                i_vec.extend(INSTRUCTIONS_ZCB);
+                if config.is_enable_ext("zbb") {
+                    i_vec.extend(INSTRUCTIONS_ZCB_ZBB);
+                }
+                if config.is_enable_ext("zba") {
+                    i_vec.extend(INSTRUCTIONS_ZCB_ZBA);
+                }
+                if config.is_enable_isa(b'm') {
+                    i_vec.extend(INSTRUCTIONS_ZCB_M);
+                }
+            }
+            // Zcmp and Zcmt reuse the c.fsdsp encodings, set_isa rejects them together with D
+            if !rv32 && config.is_enable_ext("zcmp") {
+                i_vec.extend(INSTRUCTIONS_ZCMP);
+            }
+            if !rv32 && config.is_enable_ext("zcmt") {
+                i_vec.extend(INSTRUCTIONS_ZCMT);
+            }
+        }
+        // Zb* and Zk* are implemented for RV64 only
+        if !rv32 {
+            if config.is_enable_ext("zba") {
+                i_vec.extend(INSTRUCTIONS_ZBA);
+            }
+            if config.is_enable_ext("zbb") {
+                i_vec.extend(INSTRUCTIONS_ZBB);
+            }
+            if config.is_enable_ext("zbc") {
+                i_vec.extend(INSTRUCTIONS_ZBC);
+            }
+            if config.is_enable_ext("zbs") {
+                i_vec.extend(INSTRUCTIONS_ZBS);
+            }
+            if config.is_enable_ext("zbb") || config.is_enable_ext("zbkb") {
+                i_vec.extend(INSTRUCTIONS_ZBB_ZBKB);
+            }
+            if config.is_enable_ext("zbc") || config.is_enable_ext("zbkc") {
+                i_vec.extend(INSTRUCTIONS_ZBC_ZBKC);
+            }
+            if config.is_enable_ext("zbkb") {
+                i_vec.extend(INSTRUCTIONS_ZBKB);
+            }
+            if config.is_enable_ext("zbkx") {
+                i_vec.extend(INSTRUCTIONS_ZBKX);
+            }
+            if config.is_enable_ext("zknd") {
+                i_vec.extend(INSTRUCTIONS_ZKND);
+            }
+            if config.is_enable_ext("zkne") {
+                i_vec.extend(INSTRUCTIONS_ZKNE);
+            }
+            if config.is_enable_ext("zknd") || config.is_enable_ext("zkne") {
+                i_vec.extend(INSTRUCTIONS_ZKND_ZKNE);
+            }
+            if config.is_enable_ext("zknh") {
+                i_vec.extend(INSTRUCTIONS_ZKNH);
+            }
+            if config.is_enable_ext("zksed") {
+                i_vec.extend(INSTRUCTIONS_ZKSED);
+            }
+            if config.is_enable_ext("zksh") {
+                i_vec.extend(INSTRUCTIONS_ZKSH);
+            }
+        }
+        if config.is_enable_ext("zicond") {
+            i_vec.extend(INSTRUCTIONS_ZICOND);
+        }
+        if config.is_enable_ext("zihintpause") {
+            i_vec.extend(INSTRUCTIONS_ZIHINTPAUSE);
+        }
+        if config.is_enable_ext("zawrs") {
+            i_vec.extend(INSTRUCTIONS_ZAWRS);
+        }
+        if config.is_enable_ext("zimop") {
+            i_vec.extend(INSTRUCTIONS_ZIMOP);
+        }
+        if config.is_enable_ext("zicbom") {
+            i_vec.extend(INSTRUCTIONS_ZICBOM);
+        }
+        if config.is_enable_ext("zicboz") {
+            i_vec.extend(INSTRUCTIONS_ZICBOZ);
+        }
+        if config.s_mode() && config.is_enable_ext("svinval") {
+            i_vec.extend(INSTRUCTIONS_SVINVAL);
+            if !rv32 && config.h_mode() {
+                i_vec.extend(INSTRUCTIONS_H_SVINVAL);
+            }
         }
 
         i_vec.sort_by(|a: &&Instruction, b: &&Instruction| Instruction::inst_cmp(a, b));
+        i_vec
+    }
+
+    pub fn new(config: Rc<Config>) -> Self {
+        let rv32 = config.xlen() == 32;
+        // mstatus.SXL/UXL may switch the XLEN of S-mode and U-mode at run time
+        let (other_vec, other_cache) = match config.xlen_switchable() {
+            true => (
+                Self::inst_table(&config, !rv32),
+                config.decode_cache_size().unwrap_or(0),
+            ),
+            false => (Vec::new(), 0),
+        };
 
         InstDecode {
-            inst_vec: i_vec,
+            inst_vec: Self::inst_table(&config, rv32),
             inst_hash: LruCache::new(config.decode_cache_size().unwrap_or(0)),
+            other_vec,
+            other_hash: LruCache::new(other_cache),
+            rv32,
             hit: 0,
             miss: 0,
             remove_count: 0,
@@ -51,18 +231,28 @@ impl InstDecode {
         }
     }
 
+    // swap in the table of the other XLEN, each keeps its own decode cache
+    pub fn set_rv32(&mut self, rv32: bool) {
+        if self.rv32 != rv32 {
+            core::mem::swap(&mut self.inst_vec, &mut self.other_vec);
+            core::mem::swap(&mut self.inst_hash, &mut self.other_hash);
+            self.rv32 = rv32;
+        }
+    }
+
     fn no_decode_cache(&self) -> bool {
         self.inst_hash.capacity() == 0
     }
 
     pub fn reset(&mut self) {
         self.inst_hash.clear();
+        self.other_hash.clear();
         self.hit = 0;
         self.miss = 0;
         self.remove_count = 0;
     }
 
-    fn slow_path(&mut self, inst_i: u32) -> Option<&Instruction> {
+    fn slow_path(&mut self, inst_i: u32) -> Option<&'static Instruction> {
         let slowpath = self
             .inst_vec
             .iter()
@@ -78,7 +268,7 @@ impl InstDecode {
         slowpath
     }
 
-    pub fn fast_path(&mut self, inst_i: u32) -> Option<&Instruction> {
+    pub fn fast_path(&mut self, inst_i: u32) -> Option<&'static Instruction> {
         if self.no_decode_cache() {
             return self.slow_path(inst_i);
         }
@@ -104,3 +294,136 @@ impl InstDecode {
         )
     }
 }
+
+#[test]
+fn zcmp_fsdsp_overlap_test() {
+    let decode_name = |isa: &str, inst: u32| {
+        let mut config = Config::new();
+        config.set_isa(isa).unwrap();
+        let mut decode = InstDecode::new(Rc::new(config));
+        decode.fast_path(inst).map(|i| i.name)
+    };
+    // cm.push {ra, s0-s11}, -112 and cm.jt 3 sit in the c.fsdsp space
+    let isa = "rv64imafc_zcb_zcmp_zcmt";
+    assert_eq!(decode_name(isa, 0xb8f2), Some("cm.push"));
+    assert_eq!(decode_name(isa, 0xa00e), Some("cm.jt"));
+    assert_eq!(decode_name(isa, 0xa42a), None);
+    assert_eq!(decode_name("rv64imafdc", 0xb8f2), Some("c.fsdsp"));
+    // c.lbu and c.mul
+    assert_eq!(decode_name(isa, 0x8040), Some("c.lbu"));
+    assert_eq!(decode_name(isa, 0x9c45), Some("c.mul"));
+
+    // the decoder never sees both
+    for (isa, ext) in [
+        ("rv64imafdc_zcmp", "zcmp"),
+        ("rv64imafdc_zcmt", "zcmt"),
+        ("rv64gc_zcb_zcmp_zcmt", "zcmp"),
+    ] {
+        let mut config = Config::new();
+        assert_eq!(
+            config.set_isa(isa),
+            Err(crate::config::IsaErr::Conflict(ext.into(), "d"))
+        );
+    }
+}
+
+// every extension that has instructions except Zcmp and Zcmt, which conflict with D,
+// H needs S-mode
+#[cfg(test)]
+fn full_decode() -> InstDecode {
+    let mut config = Config::new();
+    config
+        .set_isa(concat!(
+            "rv64imafdcvh_zicsr_zifencei_zacas_zabha_zba_zbb_zbc_zbs_zbkb_zbkc_zbkx",
+            "_zknd_zkne_zknh_zksed_zksh_zcb_zicond_zihintpause_zawrs_zimop",
+            "_zicbom_zicboz_svinval",
+        ))
+        .unwrap();
+    config.set_s_mode();
+    config.set_decode_cache_size(64);
+    InstDecode::new(Rc::new(config))
+}
+
+#[test]
+fn decode_round_trip_test() {
+    let mut decode = full_decode();
+    let inst_vec = decode.inst_vec.clone();
+    for inst in inst_vec {
+        // the second lookup hits the decode cache
+        for _ in 0..2 {
+            let found = decode.fast_path(inst.match_data).unwrap();
+            assert_eq!(found.mask & inst.match_data, found.match_data);
+            // an encoding may only be taken over by a more specific one, like c.addi by c.nop
+            let more_specific = found.mask & inst.mask == inst.mask && found.mask != inst.mask;
+            assert!(
+                found.name == inst.name || more_specific,
+                "{} decoded as {}",
+                inst.name,
+                found.name
+            );
+        }
+    }
+    assert!(decode.hit > 0 && decode.miss > 0);
+}
+
+#[test]
+fn decode_zicond_zawrs_zimop_test() {
+    let mut decode = full_decode();
+    let mut name = |inst: u32| decode.fast_path(inst).map(|i| i.name);
+    // czero.eqz a0, a1, a2 and czero.nez a0, a1, a2
+    assert_eq!(name(0x0ec5d533), Some("CZERO_EQZ"));
+    assert_eq!(name(0x0ec5f533), Some("CZERO_NEZ"));
+    assert_eq!(name(0x0100000f), Some("PAUSE"));
+    // fence w, 0 with rs1 or rd set is still a fence
+    assert_eq!(name(0x0100800f), Some("FENCE"));
+    assert_eq!(name(0x00d00073), Some("WRS_NTO"));
+    assert_eq!(name(0x01d00073), Some("WRS_STO"));
+
+    // mop.r.n a0, a1 and mop.rr.n a0, a1, a2
+    for n in 0..32_u32 {
+        let inst = 0x81c5c573 | (n >> 4) << 30 | ((n >> 2) & 3) << 26 | (n & 3) << 20;
+        assert_eq!(name(inst), Some("MOP_R_N"), "mop.r.{n}");
+    }
+    for n in 0..8_u32 {
+        let inst = 0x82c5c573 | (n >> 2) << 30 | (n & 3) << 26;
+        assert_eq!(name(inst), Some("MOP_RR_N"), "mop.rr.{n}");
+    }
+
+    // without the extensions
+    let mut config = Config::new();
+    config.set_isa("rv64imac").unwrap();
+    let mut decode = InstDecode::new(Rc::new(config));
+    assert_eq!(decode.fast_path(0x0100000f).map(|i| i.name), Some("FENCE"));
+    assert!(decode.fast_path(0x0ec5d533).is_none());
+    assert!(decode.fast_path(0x00d00073).is_none());
+    assert!(decode.fast_path(0x81c5c573).is_none());
+}
+
+#[test]
+fn decode_rv32_test() {
+    let name = |decode: &mut InstDecode, inst: u32| decode.fast_path(inst).map(|i| i.name);
+    let mut config = Config::new();
+    config.set_isa("rv32imafdc").unwrap();
+    let mut decode = InstDecode::new(Rc::new(config));
+    // c.jal and c.flw take the c.addiw and c.ld encodings
+    assert_eq!(name(&mut decode, 0x2001), Some("c.jal"));
+    assert_eq!(name(&mut decode, 0x6000), Some("c.flw"));
+    // ld a0, 0(a0), addiw a0, a0, 1 and slli a0, a0, 32 are RV64 only
+    assert_eq!(name(&mut decode, 0x00053503), None);
+    assert_eq!(name(&mut decode, 0x0015051b), None);
+    assert_eq!(name(&mut decode, 0x02051513), None);
+    assert_eq!(name(&mut decode, 0x01f51513), Some("SLLI"));
+
+    // an RV64 hart with U-mode swaps the tables when UXL changes
+    let mut config = Config::new();
+    config.set_isa("rv64imafdc").unwrap();
+    config.set_u_mode();
+    config.set_decode_cache_size(64);
+    let mut decode = InstDecode::new(Rc::new(config));
+    assert_eq!(name(&mut decode, 0x2001), Some("c.addiw"));
+    decode.set_rv32(true);
+    assert_eq!(name(&mut decode, 0x2001), Some("c.jal"));
+    assert_eq!(name(&mut decode, 0x0015051b), None);
+    decode.set_rv32(false);
+    assert_eq!(name(&mut decode, 0x2001), Some("c.addiw"));
+}
@@ -6,63 +6,130 @@ use log::{info, trace};
 
 use crate::{
     config::Config,
-    rv64core::csr_regs_define::{SatpIn, StapMode, XstatusIn},
+    rv64core::csr_regs_define::{HgatpIn, HstatusIn, MenvcfgIn, SatpIn, StapMode, XstatusIn},
     rv64core::{
         cache::cache_system::CacheSystem,
-        inst::inst_base::{AccessType, PrivilegeLevels},
+        hpm::{Hpm, HpmEvent},
+        inst::{
+            inst_base::{AccessType, PrivilegeLevels},
+            inst_rv64a::LrScReservation,
+        },
         traptype::TrapType,
     },
     tools::{check_aligned, RcCell, RcRefCell},
 };
 
 use super::{
+    pmp::Pmp,
     sv48::{Sv48PA, Sv48PTE, Sv48VA},
-    vm_info::{PAenume, PAops, PTEenume, PTEops, PageSize, TLBEntry, TLBKey, VAenume, VAops},
+    vm_info::{
+        PAenume, PAops, PTEenume, PTEops, PageSize, Pbmt, TLBEntry, TLBKey, VAenume, VAops,
+    },
 };
 
 const PAGESIZE: u64 = 4096; // 2 ^ 12
 
+// the kind of access translated by the G-stage
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GStageAccess {
+    // the original access of the guest
+    Explicit,
+    // implicit accesses of the VS-stage page table
+    PteRead,
+    PteWrite,
+}
+
+// the csrs used by the two-stage address translation of the hypervisor extension
+pub struct MmuHypervisorCsrs {
+    pub virt: RcCell<bool>,
+    pub hstatus: RcCell<HstatusIn>,
+    pub vsstatus: RcCell<XstatusIn>,
+    pub vsatp: RcCell<SatpIn>,
+    pub hgatp: RcCell<HgatpIn>,
+    pub henvcfg: RcCell<MenvcfgIn>,
+}
+
 pub struct Mmu {
     pub caches: RcRefCell<CacheSystem>,
+    // the PTE updates of Svadu are stores, they break the LR reservations
+    lr_sc_set: Rc<LrScReservation>,
     pub access_type: AccessType,
     mstatus: RcCell<XstatusIn>,
     satp: RcCell<SatpIn>,
+    menvcfg: RcCell<MenvcfgIn>,
+    pmp: Rc<Pmp>,
     cur_priv: Rc<Cell<PrivilegeLevels>>,
+    h_csrs: MmuHypervisorCsrs,
+    // HLV/HSV access the memory as the guest would do, HLVX also reads execute-only pages
+    pub hlsv: bool,
+    pub hlvx: bool,
+    // with XLEN=32 the virtual addresses are 32-bit, set by the cpu
+    pub rv32: bool,
     mmu_effective_priv: PrivilegeLevels,
+    mmu_effective_virt: bool,
     satp_mode: StapMode,
+    // Svnapot, Svpbmt and Svadu are fixed by the isa string
+    napot: bool,
+    svpbmt: bool,
+    svadu: bool,
+    // the memory type of the last translation
+    pbmt: Pbmt,
     config: Rc<Config>,
     tlb: LruCache<TLBKey, TLBEntry>,
     tlb_hit: u64,
     tlb_miss: u64,
+    hpm: Rc<Hpm>,
     /* tmp val */
     i: i8,
     level: i8,
     a: u64,
+    // the address of the current PTE, before and after the G-stage translation
+    pte_gpa: u64,
+    pte_addr: u64,
     va: VAenume,
     pa: PAenume,
     pte: PTEenume,
 }
 
 impl Mmu {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         caches: RcRefCell<CacheSystem>,
-
+        lr_sc_set: Rc<LrScReservation>,
         privilege: Rc<Cell<PrivilegeLevels>>,
         mstatus: RcCell<XstatusIn>,
         satp: RcCell<SatpIn>,
+        menvcfg: RcCell<MenvcfgIn>,
+        pmp: Rc<Pmp>,
+        h_csrs: MmuHypervisorCsrs,
+        hpm: Rc<Hpm>,
         config: Rc<Config>,
     ) -> Self {
         Mmu {
             caches,
+            lr_sc_set,
             access_type: AccessType::Load(0),
             mstatus,
             satp,
+            menvcfg,
+            pmp,
             cur_priv: privilege,
+            h_csrs,
+            hlsv: false,
+            hlvx: false,
+            rv32: false,
             mmu_effective_priv: PrivilegeLevels::Machine,
+            mmu_effective_virt: false,
             satp_mode: StapMode::Bare,
+            napot: config.is_enable_ext("svnapot"),
+            svpbmt: config.is_enable_ext("svpbmt"),
+            svadu: config.is_enable_ext("svadu"),
+            pbmt: Pbmt::Pma,
             i: 0,
             level: 0,
             a: 0,
+            pte_gpa: 0,
+            pte_addr: 0,
             va: Sv48VA::new().into(),
             pa: Sv48PA::new().into(),
             pte: Sv48PTE::new().into(),
@@ -70,6 +137,7 @@ impl Mmu {
             config,
             tlb_hit: 0,
             tlb_miss: 0,
+            hpm,
         }
     }
 
@@ -82,28 +150,45 @@ impl Mmu {
         assert_ne!(self.mmu_effective_priv, PrivilegeLevels::Machine); // check privilege mode
         self.level = self.satp_mode.get_levels() as i8;
         self.i = self.level - 1;
-        self.a = self.satp.get().ppn() * PAGESIZE;
+        // the VS-stage root is a guest physical address
+        self.a = match self.mmu_effective_virt {
+            true => self.h_csrs.vsatp.get().ppn() * PAGESIZE,
+            false => self.satp.get().ppn() * PAGESIZE,
+        };
         Ok(2)
     }
     // 2. Let pte be the value of the PTE at address a+va.vpn[i]×PTESIZE. (For Sv32, PTESIZE=4.)
     // If accessing pte violates a PMA or PMP check, raise an access-fault exception corresponding
     // to the original access type.
 
-    // todo! PMA or PMP check
     fn va_translation_step2(&mut self) -> Result<(), TrapType> {
         let pte_size = self.satp_mode.get_ptesize() as u64;
 
-        let pte_addr = self.a + self.va.get_ppn_by_idx(self.i as u8) * pte_size;
+        let mut pte_addr = self.a + self.va.get_ppn_by_idx(self.i as u8) * pte_size;
+        self.pte_gpa = pte_addr;
+        if self.mmu_effective_virt {
+            pte_addr = self.g_stage_translate(pte_addr, GStageAccess::PteRead)?;
+        }
+        self.pte_addr = pte_addr;
         // warn!("va:{:?}", self.stap);
         // warn!("va:{:?}", self.va);
         // assert_eq!(self.stap.ppn() * 4096, self.a);
-        // todo! PMA or PMP check
+
+        // implicit page-table accesses are checked as S-mode loads
+        if !self.pmp.check(
+            pte_addr,
+            pte_size as usize,
+            &AccessType::Load(pte_addr),
+            PrivilegeLevels::Supervisor,
+        ) {
+            return Err(self.access_type.throw_access_exception());
+        }
         let pte_data = self
             .caches
             .borrow_mut()
             .dcache
             .read(pte_addr, pte_size as usize)
-            .unwrap();
+            .map_err(|_| self.access_type.throw_access_exception())?;
         // self.pte = Sv39PTE::from(pte_data).into();
         self.pte = self.get_pteops(pte_data);
 
@@ -113,7 +198,14 @@ impl Mmu {
     // future standard use are set within pte, stop and raise a page-fault exception corresponding
     // to the original access type.
     fn va_translation_step3(&self) -> Result<(), TrapType> {
-        if !self.pte.v() || (!self.pte.r() && self.pte.w()) {
+        // the VS-stage PBMT is also enabled by henvcfg.PBMTE
+        let pbmte = self.svpbmt
+            && self.menvcfg.get().pbmte()
+            && (!self.mmu_effective_virt || self.h_csrs.henvcfg.get().pbmte());
+        if !self.pte.v()
+            || (!self.pte.r() && self.pte.w())
+            || pte_reserved(&self.pte, self.i, pbmte, self.napot)
+        {
             Err(self.access_type.throw_page_exception())
         } else {
             Ok(())
@@ -162,10 +254,7 @@ impl Mmu {
             // When MXR=0, only loads from pages marked readable (R=1 in Figure 4.18) will succeed.
             // When MXR=1, loads from pages marked either readable or executable (R=1 or X=1) will succeed.
             // MXR has no effect when page-based virtual memory is not in effect.
-            AccessType::Load(_)
-                if !(self.pte.r() || self.pte.x() & self.mstatus.get().mxr())
-                    || !self.check_sum_bit() =>
-            {
+            AccessType::Load(_) if !self.check_read_perm() || !self.check_sum_bit() => {
                 return Err(self.access_type.throw_page_exception());
             }
             AccessType::Store(_) | AccessType::Amo(_) if !self.pte.w() || !self.check_sum_bit() => {
@@ -207,12 +296,67 @@ impl Mmu {
     //       – If the comparison fails, return to step 2
 
     fn va_translation_step7(&mut self) -> Result<u8, TrapType> {
-        // choese to raise a exception
-        if !self.pte.a() || ((!self.pte.d()) && self.access_type.is_store()) {
-            Err(self.access_type.throw_page_exception())
-        } else {
-            Ok(8)
+        let need_dirty = self.access_type.is_store();
+        if self.pte.a() && (self.pte.d() || !need_dirty) {
+            return Ok(8);
+        }
+        // choese to raise a exception unless Svadu is enabled
+        if !self.hardware_ad_update() {
+            return Err(self.access_type.throw_page_exception());
         }
+        // the update of a VS-stage PTE is a store for the G-stage
+        let pte_addr = match self.mmu_effective_virt {
+            true => self.g_stage_translate(self.pte_gpa, GStageAccess::PteWrite)?,
+            false => self.pte_addr,
+        };
+        let pte_size = self.satp_mode.get_ptesize();
+        let new_pte = match self.update_pte_ad(pte_addr, pte_size, self.pte.raw(), need_dirty)? {
+            Some(new_pte) => new_pte,
+            None => return Ok(2),
+        };
+        self.pte = self.get_pteops(new_pte);
+        Ok(8)
+    }
+
+    // Svadu: with menvcfg.ADUE (and henvcfg.ADUE for the VS-stage) the page walker sets A/D
+    fn hardware_ad_update(&self) -> bool {
+        self.svadu
+            && self.menvcfg.get().adue()
+            && (!self.mmu_effective_virt || self.h_csrs.henvcfg.get().adue())
+    }
+
+    // Atomically set A, and D for a store, in the PTE at pte_addr. Returns None if the PTE
+    // in memory no longer matches, the walk must then be restarted.
+    fn update_pte_ad(
+        &mut self,
+        pte_addr: u64,
+        pte_size: usize,
+        pte: u64,
+        dirty: bool,
+    ) -> Result<Option<u64>, TrapType> {
+        if !self.pmp.check(
+            pte_addr,
+            pte_size,
+            &AccessType::Store(pte_addr),
+            PrivilegeLevels::Supervisor,
+        ) {
+            return Err(self.access_type.throw_access_exception());
+        }
+        let mut caches = self.caches.borrow_mut();
+        let cur_pte = caches
+            .dcache
+            .read(pte_addr, pte_size)
+            .map_err(|_| self.access_type.throw_access_exception())?;
+        if cur_pte != pte {
+            return Ok(None);
+        }
+        let new_pte = pte | 1 << 6 | (dirty as u64) << 7;
+        self.lr_sc_set.invalidate(pte_addr, pte_size);
+        caches
+            .dcache
+            .write(pte_addr, new_pte, pte_size)
+            .map_err(|_| self.access_type.throw_access_exception())?;
+        Ok(Some(new_pte))
     }
 
     // 8. The translation is successful. The translated physical address is given as follows:
@@ -221,8 +365,16 @@ impl Mmu {
     //      + pa.ppn[LEVELS − 1 : i] = pte.ppn[LEVELS − 1 : i].
 
     fn va_translation_step8(&mut self) -> Result<u8, TrapType> {
+        self.pbmt = Pbmt::from_bits(self.pte.pbmt());
+        // guest translations are not cached in the tlb
+        if self.mmu_effective_virt {
+            let page_size = self.leaf_page_size();
+            let entry = TLBEntry::new(self.pte, page_size, 0);
+            self.pa = self.get_paops(entry.get_pa(&self.va));
+            return Ok(1);
+        }
         let asid = self.satp.get().asid() as u16;
-        let page_size = PageSize::from_i(self.i as usize);
+        let page_size = self.leaf_page_size();
 
         let tlb_key = TLBKey {
             va: self.va.raw() & page_size.get_mask(),
@@ -251,12 +403,122 @@ impl Mmu {
         Ok(1)
     }
 
+    // a level 0 leaf with N=1 maps the naturally aligned 64 KiB range
+    fn leaf_page_size(&self) -> PageSize {
+        match self.i == 0 && self.pte.n() {
+            true => PageSize::P64K,
+            false if self.i == 1 && self.satp_mode == StapMode::Sv32 => PageSize::P4M,
+            false => PageSize::from_i(self.i as usize),
+        }
+    }
+
     fn check_sum_bit(&self) -> bool {
         if self.mmu_effective_priv != PrivilegeLevels::Supervisor {
             return true;
         }
-        // in S-mode
-        self.mstatus.get().sum() || !self.pte.u()
+        // in S-mode, the guest uses vsstatus.SUM
+        let sum = match self.mmu_effective_virt {
+            true => self.h_csrs.vsstatus.get().sum(),
+            false => self.mstatus.get().sum(),
+        };
+        sum || !self.pte.u()
+    }
+
+    // vsstatus.MXR only affects the VS-stage, mstatus.MXR affects both stages
+    fn check_read_perm(&self) -> bool {
+        if self.hlvx {
+            return self.pte.x();
+        }
+        let mxr = self.mstatus.get().mxr()
+            || (self.mmu_effective_virt && self.h_csrs.vsstatus.get().mxr());
+        self.pte.r() || (self.pte.x() && mxr)
+    }
+
+    // Translate a guest physical address with hgatp (Sv39x4, Sv48x4 or Sv57x4). The root page
+    // table is 16 KiB and indexed by two more bits. Implicit accesses of the VS-stage page table
+    // are checked as loads, and all G-stage leaf PTEs must have U=1.
+    fn g_stage_translate(&mut self, gpa: u64, access: GStageAccess) -> Result<u64, TrapType> {
+        let hgatp = self.h_csrs.hgatp.get();
+        let mode = hgatp.stage_mode();
+        if mode == StapMode::Bare {
+            return Ok(gpa);
+        }
+        let guest_page_fault = self.access_type.throw_guest_page_exception(gpa);
+        let levels = mode.get_levels();
+        if gpa >> (12 + 9 * levels + 2) != 0 {
+            return Err(guest_page_fault);
+        }
+
+        let mut a = hgatp.ppn() * PAGESIZE;
+        for i in (0..levels).rev() {
+            let vpn_bits = if i == levels - 1 { 11 } else { 9 };
+            let vpn = (gpa >> (12 + 9 * i)) & ((1 << vpn_bits) - 1);
+            let pte_addr = a + vpn * 8;
+
+            if !self.pmp.check(
+                pte_addr,
+                8,
+                &AccessType::Load(pte_addr),
+                PrivilegeLevels::Supervisor,
+            ) {
+                return Err(self.access_type.throw_access_exception());
+            }
+            let pte_data = self
+                .caches
+                .borrow_mut()
+                .dcache
+                .read(pte_addr, 8)
+                .map_err(|_| self.access_type.throw_access_exception())?;
+            let pte = pte_by_mode(mode, pte_data);
+
+            let pbmte = self.svpbmt && self.menvcfg.get().pbmte();
+            if !pte.v() || (!pte.r() && pte.w()) || pte_reserved(&pte, i as i8, pbmte, self.napot)
+            {
+                return Err(guest_page_fault);
+            }
+            if pte.point_next_level() {
+                a = pte.ppn_all() * PAGESIZE;
+                continue;
+            }
+
+            let mxr = self.mstatus.get().mxr();
+            let permit = match self.access_type {
+                _ if access == GStageAccess::PteRead => pte.r() || (pte.x() && mxr),
+                _ if access == GStageAccess::PteWrite => pte.w(),
+                AccessType::Fetch(_) => pte.x(),
+                AccessType::Load(_) if self.hlvx => pte.x(),
+                AccessType::Load(_) => pte.r() || (pte.x() && mxr),
+                AccessType::Store(_) | AccessType::Amo(_) => pte.w(),
+            };
+            // a NAPOT PTE stores the low 4 bits of the 64 KiB ppn as 0b1000
+            let (ppn, page_mask) = match i == 0 && pte.n() {
+                true => (pte.ppn_all() & !0xf, (1_u64 << 16) - 1),
+                false => (pte.ppn_all(), (1_u64 << (12 + 9 * i)) - 1),
+            };
+            let misaligned = (ppn << 12) & page_mask != 0;
+            let need_dirty = match access {
+                GStageAccess::Explicit => self.access_type.is_store(),
+                GStageAccess::PteRead => false,
+                GStageAccess::PteWrite => true,
+            };
+            if !permit || !pte.u() || misaligned {
+                return Err(guest_page_fault);
+            }
+            if !pte.a() || (need_dirty && !pte.d()) {
+                // the G-stage A/D updating is only enabled by menvcfg.ADUE
+                if !(self.svadu && self.menvcfg.get().adue()) {
+                    return Err(guest_page_fault);
+                }
+                if self.update_pte_ad(pte_addr, 8, pte.raw(), need_dirty)?.is_none() {
+                    return self.g_stage_translate(gpa, access);
+                }
+            }
+            if access == GStageAccess::Explicit {
+                self.pbmt = Pbmt::from_bits(pte.pbmt());
+            }
+            return Ok(((ppn << 12) & !page_mask) | (gpa & page_mask));
+        }
+        Err(guest_page_fault)
     }
 
     pub fn page_table_walk(&mut self) -> Result<u64, TrapType> {
@@ -267,15 +529,18 @@ impl Mmu {
             self.va_translation_step2()?;
             self.va_translation_step3()?;
             if let Ok(step) = self.va_translation_step4() {
-                if step == 5 {
+                if step != 5 {
+                    continue;
+                }
+                self.va_translation_step5()?;
+                self.va_translation_step6()?;
+                // step 7 goes back to step 2 if the PTE changed before A/D were set
+                if self.va_translation_step7()? == 8 {
                     break;
                 }
             }
         }
 
-        self.va_translation_step5()?;
-        self.va_translation_step6()?;
-        self.va_translation_step7()?;
         self.va_translation_step8()?;
 
         // debug!("translate: {:x} -> {:x}", self.va.raw(), self.pa.raw());
@@ -291,31 +556,83 @@ impl Mmu {
         let satp_bare_mode = self.satp_mode.eq(&StapMode::Bare);
         let mstatus: XstatusIn = self.mstatus.get();
         self.mmu_effective_priv = self.cur_priv.get();
+        self.mmu_effective_virt = self.h_csrs.virt.get();
 
         // When MPRV=1, load and store memory addresses are translated and protected, and endianness is applied, as though
         //the current privilege mode were set to MPP. Instruction address-translation and protection are
         // unaffected by the setting of MPRV. MPRV is read-only 0 if U-mode is not supported.
-        if self.access_type != AccessType::Fetch(0) && mstatus.mprv() {
+        if self.hlsv {
+            // HLV/HSV use the guest privilege in hstatus.SPVP
+            self.mmu_effective_priv = self.h_csrs.hstatus.get().get_spvp_priv();
+            self.mmu_effective_virt = true;
+        } else if self.access_type != AccessType::Fetch(0) && mstatus.mprv() {
             self.mmu_effective_priv = mstatus.get_mpp_priv();
+            // with MPRV=1 the virtualization mode is taken from MPV
+            self.mmu_effective_virt =
+                mstatus.mpv() && self.mmu_effective_priv != PrivilegeLevels::Machine;
         }
 
         // If the effective privilege level is machine mode or if the satp mode is bare mode, then the MMU is effectively disabled
-        // (i.e. no_mmu() returns true)
+        // (i.e. no_mmu() returns true). The guest always goes through the G-stage.
         let machine_mdoe = self.mmu_effective_priv.eq(&PrivilegeLevels::Machine);
-        machine_mdoe || satp_bare_mode
+        machine_mdoe || (satp_bare_mode && !self.mmu_effective_virt)
+    }
+
+    // Svpbmt NC and IO pages bypass the data cache
+    pub fn uncacheable(&self) -> bool {
+        !self.pbmt.cacheable()
+    }
+
+    // whether the last translation was done for the guest, the tval is a guest virtual address then
+    pub fn effective_virt(&self) -> bool {
+        self.mmu_effective_virt
     }
 
     pub fn translate(&mut self, addr: u64, len: usize) -> Result<u64, TrapType> {
+        let pa = self.va_to_pa(self.xlen_addr(addr), len)?;
+
+        if !self
+            .pmp
+            .check(pa, len, &self.access_type, self.mmu_effective_priv)
+        {
+            return Err(self.access_type.throw_access_exception());
+        }
+        Ok(pa)
+    }
+
+    // check the pmp for an access to [pa, pa + len) already translated with the current access type
+    pub fn pmp_check(&self, pa: u64, len: usize) -> bool {
+        self.pmp.check(pa, len, &self.access_type, self.mmu_effective_priv)
+    }
+
+    // the address calculation wraps around at 4 GiB with XLEN=32
+    fn xlen_addr(&self, addr: u64) -> u64 {
+        match self.rv32 {
+            true => addr & 0xffff_ffff,
+            false => addr,
+        }
+    }
+
+    fn va_to_pa(&mut self, addr: u64, len: usize) -> Result<u64, TrapType> {
         if !check_aligned(addr, len) {
             return Err(self.access_type.throw_addr_misaligned_exception());
         }
         if self.no_mmu() {
+            self.pbmt = Pbmt::Pma;
             return Ok(addr);
         }
+        if self.mmu_effective_virt {
+            return self.two_stage_translate(addr);
+        }
 
         if !self.no_tlb() {
             // todo! refactor!!!!!!!!!!!!!
-            if let Some(tlb_entry) = self.fast_path(addr) {
+            let tlb_ret = self.fast_path(addr);
+            self.hpm.record(match tlb_ret {
+                Some(_) => HpmEvent::TlbHit,
+                None => HpmEvent::TlbMiss,
+            });
+            if let Some(tlb_entry) = tlb_ret {
                 self.pte = tlb_entry.pte;
 
                 // 1. If accessing pte violates a PMA or PMP check, raise an access-fault exception corresponding to the original access type.
@@ -351,13 +668,17 @@ impl Mmu {
                     _ => {}
                 }
                 // 4. If pte.a = 0, or if the original memory access is a store and pte.d = 0, either raise a page-fault
-                // exception corresponding to the original access type
-                if !self.pte.a() || ((!self.pte.d()) && self.access_type.is_store()) {
+                // exception corresponding to the original access type, or walk the page table to set A/D with Svadu
+                let need_ad = !self.pte.a() || ((!self.pte.d()) && self.access_type.is_store());
+                if need_ad && !self.hardware_ad_update() {
                     return Err(self.access_type.throw_page_exception());
                 }
-                let pa = tlb_entry.get_pa(&self.get_vaops(addr));
-                // debug!("translate: {:x} -> {:x}", addr, pa);
-                return Ok(pa);
+                if !need_ad {
+                    self.pbmt = Pbmt::from_bits(self.pte.pbmt());
+                    let pa = tlb_entry.get_pa(&self.get_vaops(addr));
+                    // debug!("translate: {:x} -> {:x}", addr, pa);
+                    return Ok(pa);
+                }
             }
         }
 
@@ -367,6 +688,27 @@ impl Mmu {
         self.page_table_walk()
     }
 
+    // VS-stage with vsatp, then G-stage with hgatp
+    fn two_stage_translate(&mut self, addr: u64) -> Result<u64, TrapType> {
+        self.satp_mode = self.h_csrs.vsatp.get().mode();
+        self.pbmt = Pbmt::Pma;
+        let gpa = match self.satp_mode {
+            StapMode::Bare => addr,
+            _ => {
+                self.va = self.get_vaops(addr);
+                self.pa = self.get_paops(0);
+                self.page_table_walk()?
+            }
+        };
+        // a non-default VS-stage memory type overrides the G-stage one
+        let vs_pbmt = self.pbmt;
+        let pa = self.g_stage_translate(gpa, GStageAccess::Explicit)?;
+        if vs_pbmt != Pbmt::Pma {
+            self.pbmt = vs_pbmt;
+        }
+        Ok(pa)
+    }
+
     pub fn update_access_type(&mut self, access_type: &AccessType) {
         self.access_type = access_type.clone();
         // update satp mode
@@ -374,16 +716,12 @@ impl Mmu {
     }
 
     fn get_pteops(&self, pte_data: u64) -> PTEenume {
-        match self.satp_mode {
-            StapMode::Sv39 => PTEenume::Sv39PTE(pte_data.into()),
-            StapMode::Sv48 => PTEenume::Sv48PTE(pte_data.into()),
-            StapMode::Sv57 => PTEenume::Sv57PTE(pte_data.into()),
-            _ => PTEenume::Sv39PTE(pte_data.into()),
-        }
+        pte_by_mode(self.satp_mode, pte_data)
     }
 
     fn get_paops(&self, pa_data: u64) -> PAenume {
         match self.satp_mode {
+            StapMode::Sv32 => PAenume::Sv32PA(pa_data.into()),
             StapMode::Sv39 => PAenume::Sv39PA(pa_data.into()),
             StapMode::Sv48 => PAenume::Sv48PA(pa_data.into()),
             StapMode::Sv57 => PAenume::Sv57PA(pa_data.into()),
@@ -393,6 +731,7 @@ impl Mmu {
 
     fn get_vaops(&self, va_data: u64) -> VAenume {
         match self.satp_mode {
+            StapMode::Sv32 => VAenume::Sv32VA(va_data.into()),
             StapMode::Sv39 => VAenume::Sv39VA(va_data.into()),
             StapMode::Sv48 => VAenume::Sv48VA(va_data.into()),
             StapMode::Sv57 => VAenume::Sv57VA(va_data.into()),
@@ -422,6 +761,24 @@ impl Mmu {
             }
         }
 
+        let va_p4m = va & PageSize::P4M.get_mask();
+        // Check for the P4M page size of Sv32
+        if let Some(entry) = self.tlb.get(&TLBKey { va: va_p4m, asid }).copied() {
+            if entry.page_size == PageSize::P4M {
+                self.tlb_hit += 1;
+                return Some(entry);
+            }
+        }
+
+        let va_p64k = va & PageSize::P64K.get_mask();
+        // Check for P64K page size
+        if let Some(entry) = self.tlb.get(&TLBKey { va: va_p64k, asid }).copied() {
+            if entry.page_size == PageSize::P64K {
+                self.tlb_hit += 1;
+                return Some(entry);
+            }
+        }
+
         let va_p1g = va & PageSize::P1G.get_mask();
         // Check for P1G page size
         if let Some(entry) = self.tlb.get(&TLBKey { va: va_p1g, asid }).copied() {
@@ -463,6 +820,7 @@ impl Mmu {
 
     pub fn fence_vma(&mut self, va: u64, asid: u16) {
         // self.debug_tlb();
+        let va = self.xlen_addr(va);
 
 
         match (va, asid) {
@@ -513,3 +871,132 @@ impl Mmu {
         }
     }
 }
+
+fn pte_by_mode(mode: StapMode, pte_data: u64) -> PTEenume {
+    match mode {
+        StapMode::Sv32 => PTEenume::Sv32PTE(pte_data.into()),
+        StapMode::Sv39 => PTEenume::Sv39PTE(pte_data.into()),
+        StapMode::Sv48 => PTEenume::Sv48PTE(pte_data.into()),
+        StapMode::Sv57 => PTEenume::Sv57PTE(pte_data.into()),
+        _ => PTEenume::Sv39PTE(pte_data.into()),
+    }
+}
+
+// Bits 60:54 are reserved. PBMT is reserved unless enabled by the envcfg, and the encoding 3
+// is always reserved. N is only valid in a level 0 leaf whose ppn[3:0] is 0b1000, that is
+// the 64 KiB NAPOT encoding. Non-leaf PTEs must have N and PBMT cleared.
+fn pte_reserved(pte: &PTEenume, level: i8, pbmte: bool, napot: bool) -> bool {
+    if pte.reserved_bits() != 0 || pte.pbmt() == 3 || (!pbmte && pte.pbmt() != 0) {
+        return true;
+    }
+    if pte.point_next_level() {
+        return pte.n() || pte.pbmt() != 0;
+    }
+    pte.n() && !(napot && level == 0 && pte.ppn_all() & 0xf == 0b1000)
+}
+
+#[test]
+fn pte_reserved_test() {
+    let pte = |data: u64| pte_by_mode(StapMode::Sv39, data);
+    // V R W X A D leaf, a pointer has only V set
+    let leaf = 0xc7_u64;
+    let napot = leaf | 1 << 63 | 0b1000 << 10;
+    assert!(!pte_reserved(&pte(leaf), 2, false, false));
+    assert!(pte_reserved(&pte(leaf | 1 << 54), 0, true, true));
+    // pbmt
+    assert!(pte_reserved(&pte(leaf | 1 << 61), 0, false, true));
+    assert!(!pte_reserved(&pte(leaf | 2 << 61), 0, true, true));
+    assert!(pte_reserved(&pte(leaf | 3 << 61), 0, true, true));
+    assert!(pte_reserved(&pte(0x1 | 1 << 61), 1, true, true));
+    // napot
+    assert!(!pte_reserved(&pte(napot), 0, false, true));
+    assert!(pte_reserved(&pte(napot), 0, false, false));
+    assert!(pte_reserved(&pte(napot), 1, false, true));
+    assert!(pte_reserved(&pte(napot ^ 0b1100 << 10), 0, false, true));
+    assert!(pte_reserved(&pte(0x1 | 1 << 63), 1, false, true));
+}
+
+#[test]
+fn svadu_test() {
+    use crate::rv64core::{
+        cpu_core::{cpu_for_test, CpuCore},
+        inst::inst_base::{CSR_HENVCFG, CSR_HGATP, CSR_MENVCFG, CSR_VSATP},
+    };
+
+    let mut config = Config::new();
+    config.set_isa("rv64imach_svadu").unwrap();
+    config.set_mmu_type("sv39");
+    config.set_s_mode();
+    let mut cpu = cpu_for_test(config);
+    let root = 0x8008_0000;
+    cpu.sv39_for_test(root);
+    cpu.cur_priv.set(PrivilegeLevels::Supervisor);
+    let pte_flags = |cpu: &CpuCore, pte_addr: u64| cpu.mem_read(pte_addr, 8) & 0xff;
+    let adue = |cpu: &mut CpuCore, m: bool, h: bool| {
+        cpu.csr_regs.write_raw(CSR_MENVCFG.into(), (m as u64) << 61);
+        cpu.csr_regs.write_raw(CSR_HENVCFG.into(), (h as u64) << 61);
+    };
+
+    // V|R|W with A=0 and D=0 page faults without menvcfg.ADUE
+    let pte_addr = cpu.map_for_test(root, 0x1000, 0x8000_3000, 0x07);
+    adue(&mut cpu, false, true);
+    assert_eq!(
+        cpu.read(0x1000, 8, AccessType::Load(0x1000)),
+        Err(TrapType::LoadPageFault(0x1000))
+    );
+    assert_eq!(
+        cpu.write(0x1000, 1, 8, AccessType::Store(0x1000)),
+        Err(TrapType::StorePageFault(0x1000))
+    );
+    assert_eq!(pte_flags(&cpu, pte_addr), 0x07);
+
+    // a load sets A in memory, a store sets D
+    adue(&mut cpu, true, false);
+    cpu.read(0x1000, 8, AccessType::Load(0x1000)).unwrap();
+    assert_eq!(pte_flags(&cpu, pte_addr), 0x47);
+    cpu.write(0x1000, 1, 8, AccessType::Store(0x1000)).unwrap();
+    assert_eq!(pte_flags(&cpu, pte_addr), 0xc7);
+
+    // the walk goes back to step 2 when the PTE no longer holds what was read,
+    // and memory is left alone
+    let pte_addr = cpu.map_for_test(root, 0x2000, 0x8000_3000, 0x07);
+    let mmu = &mut cpu.mmu;
+    mmu.update_access_type(&AccessType::Load(0x2000));
+    mmu.mmu_effective_virt = false;
+    mmu.pte_addr = pte_addr;
+    mmu.pte = mmu.get_pteops(0x8000_4000 >> 2 | 0x07);
+    assert_eq!(mmu.va_translation_step7(), Ok(2));
+    assert_eq!(pte_flags(&cpu, pte_addr), 0x07);
+    cpu.read(0x2000, 8, AccessType::Load(0x2000)).unwrap();
+    assert_eq!(pte_flags(&cpu, pte_addr), 0x47);
+
+    // the VS-stage also needs henvcfg.ADUE, hgatp is Bare
+    let pte_addr = cpu.map_for_test(root, 0x3000, 0x8000_3000, 0x07);
+    cpu.csr_regs
+        .write_raw(CSR_VSATP.into(), 8 << 60 | root >> 12);
+    cpu.cur_virt.set(true);
+    assert_eq!(
+        cpu.read(0x3000, 8, AccessType::Load(0x3000)),
+        Err(TrapType::LoadPageFault(0x3000))
+    );
+    assert_eq!(pte_flags(&cpu, pte_addr), 0x07);
+    adue(&mut cpu, true, true);
+    cpu.read(0x3000, 8, AccessType::Load(0x3000)).unwrap();
+    assert_eq!(pte_flags(&cpu, pte_addr), 0x47);
+
+    // the G-stage only needs menvcfg.ADUE. A gpa below 2^39 indexes the Sv39x4 root
+    // like Sv39, the next levels are in its unused upper entries.
+    let g_root = 0x8009_0000;
+    let pte_addr = cpu.map_for_test(g_root, 0x4000, 0x8000_3000, 0x17);
+    cpu.csr_regs.write_raw(CSR_VSATP.into(), 0);
+    cpu.csr_regs
+        .write_raw(CSR_HGATP.into(), 8 << 60 | g_root >> 12);
+    adue(&mut cpu, false, true);
+    assert_eq!(
+        cpu.write(0x4000, 1, 8, AccessType::Store(0x4000)),
+        Err(TrapType::StoreGuestPageFault(0x4000, 0x4000))
+    );
+    adue(&mut cpu, true, false);
+    cpu.write(0x4000, 1, 8, AccessType::Store(0x4000)).unwrap();
+    assert_eq!(pte_flags(&cpu, pte_addr), 0xd7);
+}
@@ -1,5 +1,7 @@
 pub mod sv48;
 pub mod sv39;
+pub mod sv32;
 pub mod cpu_mmu;
 pub mod vm_info;
 pub mod sv57;
+pub mod pmp;
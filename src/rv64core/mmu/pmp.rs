@@ -0,0 +1,247 @@
+use core::cell::Cell;
+
+use alloc::vec::Vec;
+
+use crate::rv64core::{
+    csr_regs_define::{PMPcfgIn, PmpAddrMatch},
+    inst::inst_base::{AccessType, PrivilegeLevels},
+};
+
+// pmpaddr holds bits 55:2 of a 56-bit physical address
+const PMPADDR_MASK: u64 = (1 << 54) - 1;
+// bits 6:5 of pmpcfg are reserved
+const PMPCFG_MASK: u8 = 0x9f;
+
+#[derive(Clone, Copy)]
+struct PmpEntry {
+    cfg: PMPcfgIn,
+    addr: u64,
+}
+
+impl PmpEntry {
+    const fn new() -> Self {
+        PmpEntry {
+            cfg: PMPcfgIn::new(),
+            addr: 0,
+        }
+    }
+}
+
+// Physical Memory Protection unit, shared between the pmpcfg/pmpaddr csrs and the mmu
+pub struct Pmp {
+    entries: Vec<Cell<PmpEntry>>,
+    // M-mode accesses only need to be checked once an entry is locked
+    any_locked: Cell<bool>,
+}
+
+impl Pmp {
+    pub fn new(num: usize) -> Self {
+        assert!(
+            matches!(num, 0 | 16 | 64),
+            "pmp entries must be 0, 16 or 64"
+        );
+        Pmp {
+            entries: (0..num).map(|_| Cell::new(PmpEntry::new())).collect(),
+            any_locked: Cell::new(false),
+        }
+    }
+
+    pub fn num(&self) -> usize {
+        self.entries.len()
+    }
+
+    // the A and L fields are zeroed on reset
+    pub fn reset(&self) {
+        self.entries
+            .iter()
+            .for_each(|entry| entry.set(PmpEntry::new()));
+        self.any_locked.set(false);
+    }
+
+    fn entry(&self, idx: usize) -> PmpEntry {
+        self.entries[idx].get()
+    }
+
+    pub fn read_cfg(&self, idx: usize) -> u8 {
+        self.entries.get(idx).map_or(0, |e| e.get().cfg.into())
+    }
+
+    pub fn write_cfg(&self, idx: usize, data: u8) {
+        let Some(cell) = self.entries.get(idx) else {
+            return;
+        };
+        let mut entry = cell.get();
+        if entry.cfg.l() {
+            return;
+        }
+        let mut cfg = PMPcfgIn::from(data & PMPCFG_MASK);
+        // R=0 and W=1 is reserved, treat it as no access
+        if !cfg.r() {
+            cfg.set_w(false);
+        }
+        entry.cfg = cfg;
+        cell.set(entry);
+
+        if cfg.l() {
+            self.any_locked.set(true);
+        }
+    }
+
+    pub fn read_addr(&self, idx: usize) -> u64 {
+        self.entries.get(idx).map_or(0, |e| e.get().addr)
+    }
+
+    pub fn write_addr(&self, idx: usize, data: u64) {
+        let Some(cell) = self.entries.get(idx) else {
+            return;
+        };
+        // a locked TOR entry also locks the pmpaddr below it
+        let locked_by_next = self.entries.get(idx + 1).is_some_and(|next| {
+            let cfg = next.get().cfg;
+            cfg.l() && cfg.a() == PmpAddrMatch::Tor
+        });
+        let mut entry = cell.get();
+        if entry.cfg.l() || locked_by_next {
+            return;
+        }
+        entry.addr = data & PMPADDR_MASK;
+        cell.set(entry);
+    }
+
+    // return the matched range [start, end) of entry idx
+    fn range(&self, idx: usize) -> Option<(u64, u64)> {
+        let entry = self.entry(idx);
+        match entry.cfg.a() {
+            PmpAddrMatch::Off => None,
+            PmpAddrMatch::Tor => {
+                let start = match idx {
+                    0 => 0,
+                    _ => self.entry(idx - 1).addr << 2,
+                };
+                Some((start, entry.addr << 2))
+            }
+            PmpAddrMatch::Na4 => Some((entry.addr << 2, (entry.addr << 2) + 4)),
+            PmpAddrMatch::Napot => {
+                // yyyy...y0111 -> the trailing ones select the region size
+                let mask = entry.addr ^ (entry.addr + 1);
+                let start = (entry.addr & !mask) << 2;
+                Some((start, start + ((mask + 1) << 2)))
+            }
+        }
+    }
+
+    // The lowest-numbered entry matching any byte of the access determines
+    // whether it succeeds, and it must match all bytes of the access.
+    pub fn check(
+        &self,
+        addr: u64,
+        len: usize,
+        access_type: &AccessType,
+        privi: PrivilegeLevels,
+    ) -> bool {
+        let machine_mode = privi == PrivilegeLevels::Machine;
+        if machine_mode && !self.any_locked.get() {
+            return true;
+        }
+
+        let end = addr.saturating_add(len as u64);
+        for idx in 0..self.num() {
+            let Some((start, limit)) = self.range(idx) else {
+                continue;
+            };
+            if addr >= limit || end <= start {
+                continue;
+            }
+            if addr < start || end > limit {
+                return false;
+            }
+
+            let cfg = self.entry(idx).cfg;
+            if machine_mode && !cfg.l() {
+                return true;
+            }
+            return match access_type {
+                AccessType::Fetch(_) => cfg.x(),
+                AccessType::Load(_) => cfg.r(),
+                AccessType::Store(_) => cfg.w(),
+                AccessType::Amo(_) => cfg.r() && cfg.w(),
+            };
+        }
+
+        // no entry matched: only M-mode accesses succeed
+        machine_mode || self.num() == 0
+    }
+}
+
+#[test]
+fn pmp_match_test() {
+    let pmp = Pmp::new(16);
+    let load = AccessType::Load(0);
+    let store = AccessType::Store(0);
+
+    // with no entry matched, only M-mode succeeds
+    assert!(pmp.check(0x8000_0000, 8, &load, PrivilegeLevels::Machine));
+    assert!(!pmp.check(0x8000_0000, 8, &load, PrivilegeLevels::Supervisor));
+
+    // entry 0: NAPOT 0x8000_0000 ~ 0x8000_ffff, no access
+    pmp.write_addr(0, (0x8000_0000 >> 2) | 0x1fff);
+    pmp.write_cfg(0, PMPcfgIn::new().with_a(PmpAddrMatch::Napot).into());
+    // entry 1: TOR 0 ~ 0x1_0000_0000, read only
+    pmp.write_addr(1, 0x1_0000_0000 >> 2);
+    pmp.write_cfg(
+        1,
+        PMPcfgIn::new()
+            .with_a(PmpAddrMatch::Tor)
+            .with_r(true)
+            .into(),
+    );
+
+    assert!(!pmp.check(0x8000_fff8, 8, &load, PrivilegeLevels::Supervisor));
+    assert!(pmp.check(0x8001_0000, 8, &load, PrivilegeLevels::Supervisor));
+    assert!(!pmp.check(0x8001_0000, 8, &store, PrivilegeLevels::User));
+    // M-mode ignores unlocked entries
+    assert!(pmp.check(0x8000_0000, 8, &store, PrivilegeLevels::Machine));
+
+    // NA4 entry 2 only partially covers an 8-byte access
+    pmp.write_addr(2, 0x2000_0000 >> 2);
+    pmp.write_cfg(
+        2,
+        PMPcfgIn::new()
+            .with_a(PmpAddrMatch::Na4)
+            .with_r(true)
+            .with_w(true)
+            .into(),
+    );
+    pmp.write_cfg(1, 0);
+    assert!(pmp.check(0x2000_0000, 4, &store, PrivilegeLevels::Supervisor));
+    assert!(!pmp.check(0x2000_0000, 8, &store, PrivilegeLevels::Supervisor));
+}
+
+#[test]
+fn pmp_lock_test() {
+    let pmp = Pmp::new(16);
+    let fetch = AccessType::Fetch(0);
+
+    pmp.write_addr(1, 0x8000_1000 >> 2);
+    pmp.write_cfg(
+        1,
+        PMPcfgIn::new()
+            .with_a(PmpAddrMatch::Tor)
+            .with_l(true)
+            .into(),
+    );
+    // locked entries also apply to M-mode
+    assert!(!pmp.check(0x8000_0000, 4, &fetch, PrivilegeLevels::Machine));
+    assert!(pmp.check(0x8000_1000, 4, &fetch, PrivilegeLevels::Machine));
+
+    // the entry and the pmpaddr below a locked TOR entry can not be changed
+    pmp.write_cfg(1, 0);
+    pmp.write_addr(1, 0);
+    pmp.write_addr(0, 0x1000);
+    assert_eq!(pmp.read_addr(1), 0x8000_1000 >> 2);
+    assert_eq!(pmp.read_addr(0), 0);
+    assert!(PMPcfgIn::from(pmp.read_cfg(1)).l());
+
+    pmp.reset();
+    assert_eq!(pmp.read_cfg(1), 0);
+}
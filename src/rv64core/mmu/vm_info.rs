@@ -1,3 +1,4 @@
+use super::sv32::{Sv32PA, Sv32PTE, Sv32VA};
 use super::sv39::{Sv39PA, Sv39PTE, Sv39VA};
 use super::sv48::{Sv48PA, Sv48PTE, Sv48VA};
 use super::sv57::{Sv57PA, Sv57PTE, Sv57VA};
@@ -6,7 +7,11 @@ use enum_dispatch::enum_dispatch;
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PageSize {
     P4K,
+    // Svnapot 64 KiB contiguous mapping, a level 0 PTE with N=1
+    P64K,
     P2M,
+    // Sv32 megapage, a level 1 leaf
+    P4M,
     P1G,
     P512G,
     P256T,
@@ -26,7 +31,9 @@ impl PageSize {
     pub const fn get_mask(&self) -> u64 {
         match self {
             PageSize::P4K => zero_mask(12),
+            PageSize::P64K => zero_mask(16),
             PageSize::P2M => zero_mask(21),
+            PageSize::P4M => zero_mask(22),
             PageSize::P1G => zero_mask(30),
 
             _ => panic!("Invalid page size"),
@@ -34,6 +41,31 @@ impl PageSize {
     }
 }
 
+// Svpbmt page-based memory types, 3 is reserved
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pbmt {
+    // use the PMA of the physical address
+    Pma = 0,
+    // non-cacheable, idempotent, weakly-ordered main memory
+    Nc = 1,
+    // non-cacheable, non-idempotent, strongly-ordered I/O
+    Io = 2,
+}
+
+impl Pbmt {
+    pub const fn from_bits(val: u8) -> Self {
+        match val {
+            0 => Pbmt::Pma,
+            1 => Pbmt::Nc,
+            2 => Pbmt::Io,
+            _ => panic!("Invalid pbmt"),
+        }
+    }
+    pub fn cacheable(&self) -> bool {
+        *self == Pbmt::Pma
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct TLBEntry {
     pub pte: PTEenume,
@@ -89,11 +121,21 @@ impl TLBEntry {
         // debug!("pagesize:{:?}", self.page_size);
         match self.page_size {
             PageSize::P4K => (self.pte.ppn_all() << 12) | va.offset() as u64,
+            PageSize::P64K => {
+                ((self.pte.ppn_all() & zero_mask(4)) << 12)
+                    | (va.get_ppn_by_idx(0) & 0xf) << 12
+                    | va.offset() as u64
+            }
             PageSize::P2M => {
                 ((self.pte.ppn_all() & zero_mask(9)) << 12)
                     | va.get_ppn_by_idx(0) << 12
                     | va.offset() as u64
             }
+            PageSize::P4M => {
+                ((self.pte.ppn_all() & zero_mask(10)) << 12)
+                    | va.get_ppn_by_idx(0) << 12
+                    | va.offset() as u64
+            }
             PageSize::P1G => {
                 ((self.pte.ppn_all() & zero_mask(18)) << 12)
                     | va.get_ppn_by_idx(1) << (12 + 9)
@@ -127,6 +169,10 @@ pub trait PTEops {
     fn ppn_all(&self) -> u64 {
         (self.raw() >> 10) & 0xfff_ffff_ffff
     }
+    // bits 60:54 are reserved for future standard use
+    fn reserved_bits(&self) -> u64 {
+        (self.raw() >> 54) & 0x7f
+    }
     fn point_next_level(&self) -> bool {
         // 0 0 0
         !(self.x() | self.w() | self.r())
@@ -164,6 +210,7 @@ pub trait VAops {
 #[enum_dispatch]
 #[derive(Copy, Clone)]
 pub enum PTEenume {
+    Sv32PTE,
     Sv39PTE,
     Sv48PTE,
     Sv57PTE,
@@ -171,6 +218,7 @@ pub enum PTEenume {
 
 #[enum_dispatch]
 pub enum PAenume {
+    Sv32PA,
     Sv39PA,
     Sv48PA,
     Sv57PA,
@@ -178,7 +226,18 @@ pub enum PAenume {
 
 #[enum_dispatch]
 pub enum VAenume {
+    Sv32VA,
     Sv39VA,
     Sv48VA,
     Sv57VA,
 }
+
+#[test]
+fn sv32_megapage_test() {
+    // a 4 MiB leaf at pa 0x8040_0000, vpn0 and the offset come from the va
+    let pte: PTEenume = Sv32PTE::from((0x80400 << 10) | 0xcf).into();
+    let va: VAenume = Sv32VA::from(0x4012_3456).into();
+    let entry = TLBEntry::new(pte, PageSize::P4M, 0);
+    assert_eq!(entry.get_pa(&va), 0x8052_3456);
+    assert_eq!(0x4012_3456 & PageSize::P4M.get_mask(), 0x4000_0000);
+}
@@ -4,7 +4,13 @@ pub mod csr_regs;
 pub mod csr_regs_define;
 pub mod mmu;
 pub mod gpr;
+pub mod fpr;
+pub mod vpr;
+pub mod softfloat;
 pub mod inst_decode;
+pub mod block_cache;
 pub mod traptype;
 pub mod inst;
-pub mod cache;
\ No newline at end of file
+pub mod cache;
+pub mod trigger;
+pub mod hpm;
\ No newline at end of file
@@ -0,0 +1,938 @@
+use bitfield_struct::bitfield;
+
+// A small IEEE-754 binary32/binary64 implementation with RISC-V semantics:
+// tininess is detected after rounding, every NaN result is the canonical NaN,
+// and float-to-integer conversions saturate as described in the F extension.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    Rne = 0, // Round to Nearest, ties to Even
+    Rtz = 1, // Round towards Zero
+    Rdn = 2, // Round Down (towards -inf)
+    Rup = 3, // Round Up (towards +inf)
+    Rmm = 4, // Round to Nearest, ties to Max Magnitude
+}
+
+impl RoundingMode {
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0 => Some(RoundingMode::Rne),
+            1 => Some(RoundingMode::Rtz),
+            2 => Some(RoundingMode::Rdn),
+            3 => Some(RoundingMode::Rup),
+            4 => Some(RoundingMode::Rmm),
+            _ => None,
+        }
+    }
+}
+
+// accrued exception flags, same layout as fflags
+#[bitfield(u8)]
+pub struct FFlags {
+    pub nx: bool, // inexact
+    pub uf: bool, // underflow
+    pub of: bool, // overflow
+    pub dz: bool, // divide by zero
+    pub nv: bool, // invalid operation
+    #[bits(3)]
+    _pad: u8,
+}
+
+pub trait FloatFormat {
+    const EXP_BITS: u32;
+    const FRAC_BITS: u32;
+
+    const BIAS: i32 = (1 << (Self::EXP_BITS - 1)) - 1;
+    const EXP_MAX: u64 = (1 << Self::EXP_BITS) - 1;
+    const FRAC_MASK: u64 = (1 << Self::FRAC_BITS) - 1;
+    const SIGN_MASK: u64 = 1 << (Self::EXP_BITS + Self::FRAC_BITS);
+    const QUIET_BIT: u64 = 1 << (Self::FRAC_BITS - 1);
+    const CANONICAL_NAN: u64 = (Self::EXP_MAX << Self::FRAC_BITS) | Self::QUIET_BIT;
+}
+
+pub struct F32;
+pub struct F64;
+
+impl FloatFormat for F32 {
+    const EXP_BITS: u32 = 8;
+    const FRAC_BITS: u32 = 23;
+}
+
+impl FloatFormat for F64 {
+    const EXP_BITS: u32 = 11;
+    const FRAC_BITS: u32 = 52;
+}
+
+// unpacked value, a finite number is sig * 2^exp
+#[derive(Debug, Clone, Copy)]
+enum Float {
+    Zero { sign: bool },
+    Finite { sign: bool, exp: i32, sig: u128 },
+    Inf { sign: bool },
+    NaN { signaling: bool },
+}
+
+impl Float {
+    fn is_nan(&self) -> bool {
+        matches!(self, Float::NaN { .. })
+    }
+    fn is_snan(&self) -> bool {
+        matches!(self, Float::NaN { signaling: true })
+    }
+    fn negate(self) -> Self {
+        match self {
+            Float::Zero { sign } => Float::Zero { sign: !sign },
+            Float::Finite { sign, exp, sig } => Float::Finite {
+                sign: !sign,
+                exp,
+                sig,
+            },
+            Float::Inf { sign } => Float::Inf { sign: !sign },
+            nan => nan,
+        }
+    }
+}
+
+fn unpack<F: FloatFormat>(bits: u64) -> Float {
+    let sign = bits & F::SIGN_MASK != 0;
+    let biased_exp = (bits >> F::FRAC_BITS) & F::EXP_MAX;
+    let frac = bits & F::FRAC_MASK;
+
+    if biased_exp == F::EXP_MAX {
+        if frac == 0 {
+            Float::Inf { sign }
+        } else {
+            Float::NaN {
+                signaling: frac & F::QUIET_BIT == 0,
+            }
+        }
+    } else if biased_exp == 0 {
+        if frac == 0 {
+            Float::Zero { sign }
+        } else {
+            Float::Finite {
+                sign,
+                exp: 1 - F::BIAS - F::FRAC_BITS as i32,
+                sig: frac as u128,
+            }
+        }
+    } else {
+        Float::Finite {
+            sign,
+            exp: biased_exp as i32 - F::BIAS - F::FRAC_BITS as i32,
+            sig: (frac | (1 << F::FRAC_BITS)) as u128,
+        }
+    }
+}
+
+fn pack_zero<F: FloatFormat>(sign: bool) -> u64 {
+    if sign {
+        F::SIGN_MASK
+    } else {
+        0
+    }
+}
+
+fn pack_inf<F: FloatFormat>(sign: bool) -> u64 {
+    pack_zero::<F>(sign) | (F::EXP_MAX << F::FRAC_BITS)
+}
+
+fn pack_max_finite<F: FloatFormat>(sign: bool) -> u64 {
+    pack_inf::<F>(sign) - 1
+}
+
+fn bit_len(x: u128) -> i32 {
+    (128 - x.leading_zeros()) as i32
+}
+
+// shift right, returning (kept bits, round bit, sticky bit)
+fn shift_right_round(sig: u128, shift: i32) -> (u128, bool, bool) {
+    debug_assert!(shift > 0);
+    match shift {
+        1..=127 => {
+            let kept = sig >> shift;
+            let round = (sig >> (shift - 1)) & 1 != 0;
+            let sticky = sig & ((1 << (shift - 1)) - 1) != 0;
+            (kept, round, sticky)
+        }
+        128 => (0, sig >> 127 != 0, sig & !(1 << 127) != 0),
+        _ => (0, false, sig != 0),
+    }
+}
+
+// shift right, or-ing every lost bit into the lsb
+fn shift_right_jam(sig: u128, shift: i32) -> u128 {
+    match shift {
+        0 => sig,
+        1..=127 => (sig >> shift) | (sig & ((1 << shift) - 1) != 0) as u128,
+        _ => (sig != 0) as u128,
+    }
+}
+
+fn round_increment(rm: RoundingMode, sign: bool, odd: bool, round: bool, sticky: bool) -> bool {
+    match rm {
+        RoundingMode::Rne => round && (sticky || odd),
+        RoundingMode::Rtz => false,
+        RoundingMode::Rdn => sign && (round || sticky),
+        RoundingMode::Rup => !sign && (round || sticky),
+        RoundingMode::Rmm => round,
+    }
+}
+
+// round sig * 2^exp to the format F
+fn round_pack<F: FloatFormat>(
+    sign: bool,
+    exp: i32,
+    sig: u128,
+    rm: RoundingMode,
+    flags: &mut FFlags,
+) -> u64 {
+    if sig == 0 {
+        return pack_zero::<F>(sign);
+    }
+    let frac_bits = F::FRAC_BITS as i32;
+    let emin = 1 - F::BIAS;
+    // exponent of the most significant bit
+    let msb_exp = exp + bit_len(sig) - 1;
+
+    if msb_exp > F::BIAS {
+        return overflow::<F>(sign, rm, flags);
+    }
+
+    // RISC-V detects tininess after rounding: the result is tiny if it would
+    // be below 2^emin after rounding with an unbounded exponent range.
+    let tiny = if msb_exp < emin - 1 {
+        true
+    } else if msb_exp == emin - 1 {
+        let shift = msb_exp - frac_bits - exp;
+        let carried = if shift > 0 {
+            let (kept, round, sticky) = shift_right_round(sig, shift);
+            let kept = kept + round_increment(rm, sign, kept & 1 != 0, round, sticky) as u128;
+            kept >> (frac_bits + 1) != 0
+        } else {
+            false
+        };
+        !carried
+    } else {
+        false
+    };
+
+    let lsb_exp = core::cmp::max(msb_exp, emin) - frac_bits;
+    let shift = lsb_exp - exp;
+    let (kept, round, sticky) = if shift > 0 {
+        shift_right_round(sig, shift)
+    } else {
+        (sig << -shift, false, false)
+    };
+    let inexact = round || sticky;
+    let kept = kept + round_increment(rm, sign, kept & 1 != 0, round, sticky) as u128;
+
+    // a carry out of the significand bumps the exponent automatically
+    let biased_exp = (lsb_exp + frac_bits + F::BIAS) as u64;
+    let bits = ((biased_exp - 1) << F::FRAC_BITS) + kept as u64;
+    if bits >= F::EXP_MAX << F::FRAC_BITS {
+        return overflow::<F>(sign, rm, flags);
+    }
+
+    if inexact {
+        flags.set_nx(true);
+        if tiny {
+            flags.set_uf(true);
+        }
+    }
+    bits | pack_zero::<F>(sign)
+}
+
+fn overflow<F: FloatFormat>(sign: bool, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    flags.set_of(true);
+    flags.set_nx(true);
+    let to_inf = match rm {
+        RoundingMode::Rne | RoundingMode::Rmm => true,
+        RoundingMode::Rtz => false,
+        RoundingMode::Rdn => sign,
+        RoundingMode::Rup => !sign,
+    };
+    if to_inf {
+        pack_inf::<F>(sign)
+    } else {
+        pack_max_finite::<F>(sign)
+    }
+}
+
+fn canonical_nan<F: FloatFormat>(invalid: bool, flags: &mut FFlags) -> u64 {
+    if invalid {
+        flags.set_nv(true);
+    }
+    F::CANONICAL_NAN
+}
+
+// normalize a significand so that its msb is at bit 125
+fn normalize(exp: i32, sig: u128) -> (i32, u128) {
+    let shift = 126 - bit_len(sig);
+    (exp - shift, sig << shift)
+}
+
+fn add_finite<F: FloatFormat>(
+    (sign_a, exp_a, sig_a): (bool, i32, u128),
+    (sign_b, exp_b, sig_b): (bool, i32, u128),
+    rm: RoundingMode,
+    flags: &mut FFlags,
+) -> u64 {
+    let (exp_a, sig_a) = normalize(exp_a, sig_a);
+    let (exp_b, sig_b) = normalize(exp_b, sig_b);
+
+    // align to the larger exponent, the jammed bits stay far below the rounding point
+    let (exp, sig_a, sig_b) = if exp_a >= exp_b {
+        (exp_a, sig_a, shift_right_jam(sig_b, exp_a - exp_b))
+    } else {
+        (exp_b, shift_right_jam(sig_a, exp_b - exp_a), sig_b)
+    };
+
+    if sign_a == sign_b {
+        round_pack::<F>(sign_a, exp, sig_a + sig_b, rm, flags)
+    } else if sig_a > sig_b {
+        round_pack::<F>(sign_a, exp, sig_a - sig_b, rm, flags)
+    } else if sig_a < sig_b {
+        round_pack::<F>(sign_b, exp, sig_b - sig_a, rm, flags)
+    } else {
+        pack_zero::<F>(rm == RoundingMode::Rdn)
+    }
+}
+
+fn add_unpacked<F: FloatFormat>(a: Float, b: Float, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    match (a, b) {
+        (Float::NaN { .. }, _) | (_, Float::NaN { .. }) => {
+            canonical_nan::<F>(a.is_snan() || b.is_snan(), flags)
+        }
+        (Float::Inf { sign: sa }, Float::Inf { sign: sb }) => {
+            if sa == sb {
+                pack_inf::<F>(sa)
+            } else {
+                canonical_nan::<F>(true, flags)
+            }
+        }
+        (Float::Inf { sign }, _) | (_, Float::Inf { sign }) => pack_inf::<F>(sign),
+        (Float::Zero { sign: sa }, Float::Zero { sign: sb }) => {
+            if sa == sb {
+                pack_zero::<F>(sa)
+            } else {
+                pack_zero::<F>(rm == RoundingMode::Rdn)
+            }
+        }
+        (Float::Zero { .. }, Float::Finite { sign, exp, sig })
+        | (Float::Finite { sign, exp, sig }, Float::Zero { .. }) => {
+            round_pack::<F>(sign, exp, sig, rm, flags)
+        }
+        (
+            Float::Finite {
+                sign: sa,
+                exp: ea,
+                sig: ma,
+            },
+            Float::Finite {
+                sign: sb,
+                exp: eb,
+                sig: mb,
+            },
+        ) => add_finite::<F>((sa, ea, ma), (sb, eb, mb), rm, flags),
+    }
+}
+
+pub fn add<F: FloatFormat>(a: u64, b: u64, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    add_unpacked::<F>(unpack::<F>(a), unpack::<F>(b), rm, flags)
+}
+
+pub fn sub<F: FloatFormat>(a: u64, b: u64, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    add_unpacked::<F>(unpack::<F>(a), unpack::<F>(b).negate(), rm, flags)
+}
+
+pub fn mul<F: FloatFormat>(a: u64, b: u64, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    match (unpack::<F>(a), unpack::<F>(b)) {
+        (a @ Float::NaN { .. }, b) | (a, b @ Float::NaN { .. }) => {
+            canonical_nan::<F>(a.is_snan() || b.is_snan(), flags)
+        }
+        (Float::Inf { .. }, Float::Zero { .. }) | (Float::Zero { .. }, Float::Inf { .. }) => {
+            canonical_nan::<F>(true, flags)
+        }
+        (Float::Inf { sign: sa }, Float::Inf { sign: sb })
+        | (Float::Inf { sign: sa }, Float::Finite { sign: sb, .. })
+        | (Float::Finite { sign: sa, .. }, Float::Inf { sign: sb }) => pack_inf::<F>(sa ^ sb),
+        (Float::Zero { sign: sa }, Float::Zero { sign: sb })
+        | (Float::Zero { sign: sa }, Float::Finite { sign: sb, .. })
+        | (Float::Finite { sign: sa, .. }, Float::Zero { sign: sb }) => pack_zero::<F>(sa ^ sb),
+        (
+            Float::Finite {
+                sign: sa,
+                exp: ea,
+                sig: ma,
+            },
+            Float::Finite {
+                sign: sb,
+                exp: eb,
+                sig: mb,
+            },
+        ) => round_pack::<F>(sa ^ sb, ea + eb, ma * mb, rm, flags),
+    }
+}
+
+pub fn div<F: FloatFormat>(a: u64, b: u64, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    match (unpack::<F>(a), unpack::<F>(b)) {
+        (a @ Float::NaN { .. }, b) | (a, b @ Float::NaN { .. }) => {
+            canonical_nan::<F>(a.is_snan() || b.is_snan(), flags)
+        }
+        (Float::Inf { .. }, Float::Inf { .. }) | (Float::Zero { .. }, Float::Zero { .. }) => {
+            canonical_nan::<F>(true, flags)
+        }
+        (Float::Inf { sign: sa }, Float::Zero { sign: sb })
+        | (Float::Inf { sign: sa }, Float::Finite { sign: sb, .. }) => pack_inf::<F>(sa ^ sb),
+        (Float::Finite { sign: sa, .. }, Float::Zero { sign: sb }) => {
+            flags.set_dz(true);
+            pack_inf::<F>(sa ^ sb)
+        }
+        (Float::Zero { sign: sa }, Float::Inf { sign: sb })
+        | (Float::Zero { sign: sa }, Float::Finite { sign: sb, .. })
+        | (Float::Finite { sign: sa, .. }, Float::Inf { sign: sb }) => pack_zero::<F>(sa ^ sb),
+        (
+            Float::Finite {
+                sign: sa,
+                exp: ea,
+                sig: ma,
+            },
+            Float::Finite {
+                sign: sb,
+                exp: eb,
+                sig: mb,
+            },
+        ) => {
+            let (ea, ma) = normalize(ea, ma);
+            let (quo, rem) = (ma / mb, ma % mb);
+            let sig = (quo << 1) | (rem != 0) as u128;
+            round_pack::<F>(sa ^ sb, ea - eb - 1, sig, rm, flags)
+        }
+    }
+}
+
+fn isqrt(n: u128) -> u128 {
+    let mut x = n;
+    let mut root = 0;
+    let mut bit = 1_u128 << 126;
+    while bit > n {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if x >= root + bit {
+            x -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+pub fn sqrt<F: FloatFormat>(a: u64, rm: RoundingMode, flags: &mut FFlags) -> u64 {
+    match unpack::<F>(a) {
+        a @ Float::NaN { .. } => canonical_nan::<F>(a.is_snan(), flags),
+        Float::Zero { sign } => pack_zero::<F>(sign),
+        Float::Inf { sign: false } => pack_inf::<F>(false),
+        Float::Inf { sign: true } | Float::Finite { sign: true, .. } => {
+            canonical_nan::<F>(true, flags)
+        }
+        Float::Finite {
+            sign: false,
+            exp,
+            sig,
+        } => {
+            let shift = 120 - bit_len(sig);
+            let (mut exp, mut sig) = (exp - shift, sig << shift);
+            if exp & 1 != 0 {
+                exp -= 1;
+                sig <<= 1;
+            }
+            let root = isqrt(sig);
+            let sticky = root * root != sig;
+            round_pack::<F>(false, exp / 2 - 1, (root << 1) | sticky as u128, rm, flags)
+        }
+    }
+}
+
+// (a * b) + c with a single rounding, the product and/or c may be negated
+// to build FMSUB, FNMSUB and FNMADD.
+pub fn mul_add<F: FloatFormat>(
+    a: u64,
+    b: u64,
+    c: u64,
+    negate_product: bool,
+    negate_c: bool,
+    rm: RoundingMode,
+    flags: &mut FFlags,
+) -> u64 {
+    let (a, b, c) = (unpack::<F>(a), unpack::<F>(b), unpack::<F>(c));
+    let c = if negate_c { c.negate() } else { c };
+
+    if a.is_nan() || b.is_nan() {
+        return canonical_nan::<F>(a.is_snan() || b.is_snan() || c.is_snan(), flags);
+    }
+    // inf * 0 is invalid even when c is a quiet NaN
+    if matches!(
+        (a, b),
+        (Float::Inf { .. }, Float::Zero { .. }) | (Float::Zero { .. }, Float::Inf { .. })
+    ) {
+        return canonical_nan::<F>(true, flags);
+    }
+    if c.is_nan() {
+        return canonical_nan::<F>(c.is_snan(), flags);
+    }
+
+    let product = match (a, b) {
+        (Float::Inf { sign: sa }, Float::Inf { sign: sb })
+        | (Float::Inf { sign: sa }, Float::Finite { sign: sb, .. })
+        | (Float::Finite { sign: sa, .. }, Float::Inf { sign: sb }) => Float::Inf { sign: sa ^ sb },
+        (Float::Zero { sign: sa }, Float::Zero { sign: sb })
+        | (Float::Zero { sign: sa }, Float::Finite { sign: sb, .. })
+        | (Float::Finite { sign: sa, .. }, Float::Zero { sign: sb }) => {
+            Float::Zero { sign: sa ^ sb }
+        }
+        (
+            Float::Finite {
+                sign: sa,
+                exp: ea,
+                sig: ma,
+            },
+            Float::Finite {
+                sign: sb,
+                exp: eb,
+                sig: mb,
+            },
+        ) => Float::Finite {
+            sign: sa ^ sb,
+            exp: ea + eb,
+            sig: ma * mb,
+        },
+        _ => unreachable!(),
+    };
+    let product = if negate_product {
+        product.negate()
+    } else {
+        product
+    };
+
+    // the exact product is wider than F, add_unpacked only rounds once
+    add_unpacked::<F>(product, c, rm, flags)
+}
+
+// sortable key for ordered values, +0 and -0 compare equal
+fn order_key<F: FloatFormat>(bits: u64) -> i128 {
+    let magnitude = (bits & !F::SIGN_MASK) as i128;
+    if bits & F::SIGN_MASK != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+// FEQ is a quiet comparison, only signaling NaNs raise invalid
+pub fn eq<F: FloatFormat>(a: u64, b: u64, flags: &mut FFlags) -> bool {
+    let (ua, ub) = (unpack::<F>(a), unpack::<F>(b));
+    if ua.is_nan() || ub.is_nan() {
+        if ua.is_snan() || ub.is_snan() {
+            flags.set_nv(true);
+        }
+        return false;
+    }
+    order_key::<F>(a) == order_key::<F>(b)
+}
+
+// FLT and FLE are signaling comparisons, any NaN raises invalid
+pub fn lt<F: FloatFormat>(a: u64, b: u64, flags: &mut FFlags) -> bool {
+    if unpack::<F>(a).is_nan() || unpack::<F>(b).is_nan() {
+        flags.set_nv(true);
+        return false;
+    }
+    order_key::<F>(a) < order_key::<F>(b)
+}
+
+pub fn le<F: FloatFormat>(a: u64, b: u64, flags: &mut FFlags) -> bool {
+    if unpack::<F>(a).is_nan() || unpack::<F>(b).is_nan() {
+        flags.set_nv(true);
+        return false;
+    }
+    order_key::<F>(a) <= order_key::<F>(b)
+}
+
+// IEEE 754-2019 minimumNumber/maximumNumber, -0 is considered less than +0
+fn min_max<F: FloatFormat>(a: u64, b: u64, is_max: bool, flags: &mut FFlags) -> u64 {
+    let (ua, ub) = (unpack::<F>(a), unpack::<F>(b));
+    if ua.is_snan() || ub.is_snan() {
+        flags.set_nv(true);
+    }
+    match (ua.is_nan(), ub.is_nan()) {
+        (true, true) => return F::CANONICAL_NAN,
+        (true, false) => return b,
+        (false, true) => return a,
+        (false, false) => {}
+    }
+    let (ka, kb) = (order_key::<F>(a), order_key::<F>(b));
+    let a_first = if ka == kb {
+        // only reachable for zeros (or identical values)
+        (a & F::SIGN_MASK != 0) != is_max
+    } else {
+        (ka < kb) != is_max
+    };
+    if a_first {
+        a
+    } else {
+        b
+    }
+}
+
+pub fn min<F: FloatFormat>(a: u64, b: u64, flags: &mut FFlags) -> u64 {
+    min_max::<F>(a, b, false, flags)
+}
+
+pub fn max<F: FloatFormat>(a: u64, b: u64, flags: &mut FFlags) -> u64 {
+    min_max::<F>(a, b, true, flags)
+}
+
+// convert to a signed or unsigned integer of `width` bits, the result is
+// sign-extended to 64 bits as required by FCVT.W[U] on RV64
+pub fn to_int<F: FloatFormat>(
+    a: u64,
+    signed: bool,
+    width: u32,
+    rm: RoundingMode,
+    flags: &mut FFlags,
+) -> u64 {
+    let (int_max, int_min): (i128, i128) = if signed {
+        ((1 << (width - 1)) - 1, -(1 << (width - 1)))
+    } else {
+        ((1 << width) - 1, 0)
+    };
+    let sext = |val: i128| -> u64 {
+        let val = val as u64;
+        if width == 32 {
+            val as i32 as i64 as u64
+        } else {
+            val
+        }
+    };
+
+    let (sign, magnitude, inexact) = match unpack::<F>(a) {
+        Float::NaN { .. } | Float::Inf { sign: false } => {
+            flags.set_nv(true);
+            return sext(int_max);
+        }
+        Float::Inf { sign: true } => {
+            flags.set_nv(true);
+            return sext(int_min);
+        }
+        Float::Zero { .. } => return 0,
+        Float::Finite { sign, exp, sig } => {
+            if exp >= 0 {
+                if bit_len(sig) + exp > 65 {
+                    // far out of range, saturate below
+                    (sign, u128::MAX >> 1, false)
+                } else {
+                    (sign, sig << exp, false)
+                }
+            } else {
+                let (kept, round, sticky) = shift_right_round(sig, -exp);
+                let kept = kept + round_increment(rm, sign, kept & 1 != 0, round, sticky) as u128;
+                (sign, kept, round || sticky)
+            }
+        }
+    };
+
+    let value = if sign {
+        -(magnitude as i128)
+    } else {
+        magnitude as i128
+    };
+    if value > int_max {
+        flags.set_nv(true);
+        return sext(int_max);
+    }
+    if value < int_min {
+        flags.set_nv(true);
+        return sext(int_min);
+    }
+    if inexact {
+        flags.set_nx(true);
+    }
+    sext(value)
+}
+
+// convert a signed or unsigned integer of `width` bits held in the low bits of `a`
+pub fn from_int<F: FloatFormat>(
+    a: u64,
+    signed: bool,
+    width: u32,
+    rm: RoundingMode,
+    flags: &mut FFlags,
+) -> u64 {
+    let value: i128 = match (signed, width) {
+        (true, 32) => a as i32 as i128,
+        (false, 32) => a as u32 as i128,
+        (true, _) => a as i64 as i128,
+        (false, _) => a as i128,
+    };
+    round_pack::<F>(value < 0, 0, value.unsigned_abs(), rm, flags)
+}
+
+// convert between formats, e.g. FCVT.S.D and FCVT.D.S
+pub fn convert<F: FloatFormat, T: FloatFormat>(
+    a: u64,
+    rm: RoundingMode,
+    flags: &mut FFlags,
+) -> u64 {
+    match unpack::<F>(a) {
+        a @ Float::NaN { .. } => canonical_nan::<T>(a.is_snan(), flags),
+        Float::Inf { sign } => pack_inf::<T>(sign),
+        Float::Zero { sign } => pack_zero::<T>(sign),
+        Float::Finite { sign, exp, sig } => round_pack::<T>(sign, exp, sig, rm, flags),
+    }
+}
+
+// FCLASS result, one bit set:
+// 0:-inf 1:-normal 2:-subnormal 3:-0 4:+0 5:+subnormal 6:+normal 7:+inf 8:sNaN 9:qNaN
+pub fn classify<F: FloatFormat>(a: u64) -> u64 {
+    let sign = a & F::SIGN_MASK != 0;
+    let is_subnormal = (a >> F::FRAC_BITS) & F::EXP_MAX == 0;
+    let bit = match unpack::<F>(a) {
+        Float::NaN { signaling: true } => 8,
+        Float::NaN { signaling: false } => 9,
+        Float::Inf { .. } => 0,
+        Float::Finite { .. } if is_subnormal => 2,
+        Float::Finite { .. } => 1,
+        Float::Zero { .. } => 3,
+    };
+    match (sign, bit) {
+        (_, 8 | 9) => 1 << bit,
+        (true, _) => 1 << bit,
+        (false, _) => 1 << (7 - bit),
+    }
+}
+
+#[cfg(test)]
+mod test_softfloat {
+    use super::*;
+
+    const RNE: RoundingMode = RoundingMode::Rne;
+
+    fn f32_bits(x: f32) -> u64 {
+        x.to_bits() as u64
+    }
+
+    // a deterministic spread of bit patterns covering every class
+    fn f32_samples() -> impl Iterator<Item = u32> {
+        let special = [
+            0x0000_0000,
+            0x8000_0000,
+            0x0000_0001,
+            0x807f_ffff,
+            0x0080_0000,
+            0x7f7f_ffff,
+            0xff7f_ffff,
+            0x3f80_0000,
+            0xbf80_0001,
+            0x4b7f_ffff,
+            0x7f80_0000,
+            0xff80_0000,
+        ];
+        let mut seed = 0x1234_5678_u32;
+        let random = (0..400).map(move |_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        });
+        special.into_iter().chain(random)
+    }
+
+    fn check_f32(expect: f32, got: u64) {
+        if expect.is_nan() {
+            assert_eq!(got, F32::CANONICAL_NAN);
+        } else {
+            assert_eq!(got, f32_bits(expect), "expect {expect:e}");
+        }
+    }
+
+    #[test]
+    fn arith_matches_host_rne() {
+        let mut flags = FFlags::new();
+        for a in f32_samples() {
+            for b in f32_samples().step_by(7) {
+                let (fa, fb) = (f32::from_bits(a), f32::from_bits(b));
+                let (a, b) = (a as u64, b as u64);
+                check_f32(fa + fb, add::<F32>(a, b, RNE, &mut flags));
+                check_f32(fa - fb, sub::<F32>(a, b, RNE, &mut flags));
+                check_f32(fa * fb, mul::<F32>(a, b, RNE, &mut flags));
+                check_f32(fa / fb, div::<F32>(a, b, RNE, &mut flags));
+                check_f32(
+                    fa.mul_add(fb, fa),
+                    mul_add::<F32>(a, b, a, false, false, RNE, &mut flags),
+                );
+            }
+            let fa = f32::from_bits(a);
+            check_f32(fa.sqrt(), sqrt::<F32>(a as u64, RNE, &mut flags));
+        }
+    }
+
+    #[test]
+    fn f64_arith_matches_host_rne() {
+        let mut flags = FFlags::new();
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..5000 {
+            let (a, b, c) = (next(), next(), next());
+            let (fa, fb, fc) = (f64::from_bits(a), f64::from_bits(b), f64::from_bits(c));
+            let check = |expect: f64, got: u64| {
+                if expect.is_nan() {
+                    assert_eq!(got, F64::CANONICAL_NAN);
+                } else {
+                    assert_eq!(got, expect.to_bits(), "expect {expect:e}");
+                }
+            };
+            check(fa + fb, add::<F64>(a, b, RNE, &mut flags));
+            check(fa * fb, mul::<F64>(a, b, RNE, &mut flags));
+            check(fa / fb, div::<F64>(a, b, RNE, &mut flags));
+            check(
+                fa.abs().sqrt(),
+                sqrt::<F64>(a & !F64::SIGN_MASK, RNE, &mut flags),
+            );
+            check(
+                fa.mul_add(fb, fc),
+                mul_add::<F64>(a, b, c, false, false, RNE, &mut flags),
+            );
+        }
+    }
+
+    #[test]
+    fn directed_rounding_and_flags() {
+        let one = f32_bits(1.0);
+        let tiny = 0x0000_0001; // smallest subnormal
+
+        let mut flags = FFlags::new();
+        assert_eq!(
+            add::<F32>(one, tiny, RoundingMode::Rup, &mut flags),
+            0x3f80_0001
+        );
+        assert_eq!(u8::from(flags), 0x01); // NX
+
+        let mut flags = FFlags::new();
+        assert_eq!(add::<F32>(one, tiny, RoundingMode::Rdn, &mut flags), one);
+        assert_eq!(
+            sub::<F32>(one, one, RoundingMode::Rdn, &mut flags),
+            0x8000_0000
+        );
+
+        // overflow
+        let mut flags = FFlags::new();
+        let max = 0x7f7f_ffff;
+        assert_eq!(mul::<F32>(max, max, RoundingMode::Rtz, &mut flags), max);
+        assert_eq!(u8::from(flags), 0x05); // OF | NX
+        assert_eq!(mul::<F32>(max, max, RNE, &mut flags), 0x7f80_0000);
+
+        // underflow, tininess after rounding
+        let mut flags = FFlags::new();
+        assert_eq!(mul::<F32>(tiny, f32_bits(0.5), RNE, &mut flags), 0);
+        assert_eq!(u8::from(flags), 0x03); // UF | NX
+
+        // divide by zero and invalid
+        let mut flags = FFlags::new();
+        assert_eq!(div::<F32>(one, 0, RNE, &mut flags), 0x7f80_0000);
+        assert_eq!(u8::from(flags), 0x08);
+        let mut flags = FFlags::new();
+        assert_eq!(
+            sqrt::<F32>(f32_bits(-1.0), RNE, &mut flags),
+            F32::CANONICAL_NAN
+        );
+        assert_eq!(u8::from(flags), 0x10);
+    }
+
+    #[test]
+    fn conversions() {
+        let mut flags = FFlags::new();
+        assert_eq!(
+            to_int::<F32>(f32_bits(-1.5), true, 32, RNE, &mut flags),
+            (-2_i64) as u64
+        );
+        assert_eq!(to_int::<F32>(f32_bits(2.5), true, 64, RNE, &mut flags), 2);
+        assert_eq!(
+            to_int::<F32>(f32_bits(2.5), true, 64, RoundingMode::Rmm, &mut flags),
+            3
+        );
+        assert!(flags.nx() && !flags.nv());
+
+        let mut flags = FFlags::new();
+        assert_eq!(to_int::<F32>(f32_bits(-0.4), false, 32, RNE, &mut flags), 0);
+        assert_eq!(u8::from(flags), 0x01);
+        assert_eq!(to_int::<F32>(f32_bits(-3.0), false, 32, RNE, &mut flags), 0);
+        assert!(flags.nv());
+        assert_eq!(
+            to_int::<F32>(F32::CANONICAL_NAN, true, 32, RNE, &mut flags),
+            0x7fff_ffff
+        );
+        assert_eq!(
+            to_int::<F32>(F32::CANONICAL_NAN, false, 32, RNE, &mut flags),
+            u64::MAX
+        );
+        assert_eq!(
+            to_int::<F64>(f64::INFINITY.to_bits(), true, 64, RNE, &mut flags),
+            i64::MAX as u64
+        );
+
+        let mut flags = FFlags::new();
+        assert_eq!(
+            from_int::<F32>(u64::MAX, false, 64, RNE, &mut flags),
+            f32_bits(u64::MAX as f32)
+        );
+        assert_eq!(
+            from_int::<F64>(0xffff_fffe, true, 32, RNE, &mut flags),
+            (-2.0_f64).to_bits()
+        );
+        assert_eq!(
+            convert::<F64, F32>(0.1_f64.to_bits(), RNE, &mut flags),
+            f32_bits(0.1)
+        );
+        assert_eq!(
+            convert::<F32, F64>(f32_bits(0.1), RNE, &mut flags),
+            (0.1_f32 as f64).to_bits()
+        );
+    }
+
+    #[test]
+    fn compare_and_classify() {
+        let mut flags = FFlags::new();
+        let (pz, nz, qnan, snan) = (0, 0x8000_0000, 0x7fc0_0000, 0x7f80_0001);
+        assert!(eq::<F32>(pz, nz, &mut flags));
+        assert_eq!(min::<F32>(pz, nz, &mut flags), nz);
+        assert_eq!(max::<F32>(nz, pz, &mut flags), pz);
+        assert!(!eq::<F32>(qnan, qnan, &mut flags));
+        assert!(!flags.nv());
+        assert!(!lt::<F32>(qnan, pz, &mut flags));
+        assert!(flags.nv());
+
+        let mut flags = FFlags::new();
+        assert_eq!(min::<F32>(snan, f32_bits(1.0), &mut flags), f32_bits(1.0));
+        assert!(flags.nv());
+        assert_eq!(max::<F32>(qnan, snan, &mut flags), F32::CANONICAL_NAN);
+
+        assert_eq!(classify::<F32>(0xff80_0000), 1 << 0);
+        assert_eq!(classify::<F32>(f32_bits(-1.0)), 1 << 1);
+        assert_eq!(classify::<F32>(0x8000_0001), 1 << 2);
+        assert_eq!(classify::<F32>(nz), 1 << 3);
+        assert_eq!(classify::<F32>(pz), 1 << 4);
+        assert_eq!(classify::<F32>(0x0000_0001), 1 << 5);
+        assert_eq!(classify::<F32>(f32_bits(1.0)), 1 << 6);
+        assert_eq!(classify::<F32>(0x7f80_0000), 1 << 7);
+        assert_eq!(classify::<F32>(snan), 1 << 8);
+        assert_eq!(classify::<F32>(qnan), 1 << 9);
+    }
+}
@@ -0,0 +1,587 @@
+use core::cell::Cell;
+
+use alloc::vec::Vec;
+use bitfield_struct::bitfield;
+
+use crate::rv64core::inst::inst_base::PrivilegeLevels;
+
+// the number of triggers selected by tselect
+pub const TRIGGER_NUM: usize = 4;
+
+const TYPE_MCONTROL: u8 = 2;
+const TYPE_ICOUNT: u8 = 3;
+const TYPE_MCONTROL6: u8 = 6;
+const TYPE_DISABLED: u8 = 15;
+
+// tinfo: Sdtrig version 1.0, and the supported trigger types
+const TINFO_VAL: u64 =
+    1 << 24 | 1 << TYPE_MCONTROL | 1 << TYPE_ICOUNT | 1 << TYPE_MCONTROL6 | 1 << TYPE_DISABLED;
+
+// the fields shared by every tdata1 layout
+#[bitfield(u64)]
+pub struct Tdata1In {
+    #[bits(59)]
+    pub data: u64,
+    pub dmode: bool,
+    #[bits(4)]
+    pub ty: u8,
+}
+
+// the legacy address/data match trigger, type 2
+#[bitfield(u64)]
+pub struct McontrolIn {
+    pub load: bool,
+    pub store: bool,
+    pub execute: bool,
+    pub u: bool,
+    pub s: bool,
+    _zero0: bool,
+    pub m: bool,
+    #[bits(4)]
+    pub match_type: u8,
+    pub chain: bool,
+    #[bits(4)]
+    pub action: u8,
+    #[bits(2)]
+    pub sizelo: u8,
+    pub timing: bool,
+    pub select: bool,
+    pub hit: bool,
+    #[bits(2)]
+    pub sizehi: u8,
+    #[bits(30)]
+    _zero1: u64,
+    #[bits(6)]
+    pub maskmax: u8,
+    pub dmode: bool,
+    #[bits(4)]
+    pub ty: u8,
+}
+
+// the address/data match trigger with VS/VU-mode filters, type 6
+#[bitfield(u64)]
+pub struct Mcontrol6In {
+    pub load: bool,
+    pub store: bool,
+    pub execute: bool,
+    pub u: bool,
+    pub s: bool,
+    pub uncertainen: bool,
+    pub m: bool,
+    #[bits(4)]
+    pub match_type: u8,
+    pub chain: bool,
+    #[bits(4)]
+    pub action: u8,
+    #[bits(3)]
+    pub size: u8,
+    #[bits(2)]
+    _zero0: u8,
+    pub select: bool,
+    pub hit0: bool,
+    pub vu: bool,
+    pub vs: bool,
+    pub hit1: bool,
+    pub uncertain: bool,
+    #[bits(32)]
+    _zero1: u64,
+    pub dmode: bool,
+    #[bits(4)]
+    pub ty: u8,
+}
+
+// the instruction count trigger, type 3
+#[bitfield(u64)]
+pub struct IcountIn {
+    #[bits(6)]
+    pub action: u8,
+    pub u: bool,
+    pub s: bool,
+    pub pending: bool,
+    pub m: bool,
+    #[bits(14)]
+    pub count: u16,
+    pub hit: bool,
+    pub vu: bool,
+    pub vs: bool,
+    #[bits(32)]
+    _zero0: u64,
+    pub dmode: bool,
+    #[bits(4)]
+    pub ty: u8,
+}
+
+#[bitfield(u64)]
+pub struct TcontrolIn {
+    #[bits(3)]
+    _zero0: u8,
+    pub mte: bool,
+    #[bits(3)]
+    _zero1: u8,
+    pub mpte: bool,
+    #[bits(56)]
+    _zero2: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerOp {
+    Execute,
+    Load,
+    Store,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerAction {
+    // raise a breakpoint exception
+    Breakpoint,
+    // enter debug mode with DebugCause::Trigger
+    DebugMode,
+}
+
+impl TriggerAction {
+    fn from_bits(action: u8) -> Self {
+        match action {
+            1 => TriggerAction::DebugMode,
+            _ => TriggerAction::Breakpoint,
+        }
+    }
+}
+
+// WARL legalization: only breakpoint and debug mode actions, the latter needs dmode=1
+fn legal_action(action: u8, dmode: bool) -> u8 {
+    match action {
+        1 if dmode => 1,
+        _ => 0,
+    }
+}
+
+// WARL legalization: equal, napot, greater or equal and less than
+fn legal_match(match_type: u8) -> u8 {
+    match match_type {
+        0..=3 => match_type,
+        _ => 0,
+    }
+}
+
+fn value_match(match_type: u8, tdata2: u64, value: u64) -> bool {
+    match match_type {
+        0 => value == tdata2,
+        // the bits above the lowest zero bit of tdata2 are compared
+        1 => {
+            let mask = tdata2 ^ tdata2.wrapping_add(1);
+            (value & !mask) == (tdata2 & !mask)
+        }
+        2 => value >= tdata2,
+        3 => value < tdata2,
+        _ => false,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct TriggerEntry {
+    tdata1: u64,
+    tdata2: u64,
+}
+
+impl TriggerEntry {
+    const fn new() -> Self {
+        TriggerEntry {
+            tdata1: (TYPE_DISABLED as u64) << 60,
+            tdata2: 0,
+        }
+    }
+}
+
+// M-mode breakpoints are enabled out of reset, and stay enabled across an mret
+// that was not preceded by a trap
+const TCONTROL_RESET: TcontrolIn = TcontrolIn::new().with_mte(true).with_mpte(true);
+
+// Sdtrig debug triggers, shared between the trigger csrs and the cpu core
+pub struct Triggers {
+    entries: Vec<Cell<TriggerEntry>>,
+    tselect: Cell<usize>,
+    tcontrol: Cell<TcontrolIn>,
+    // only debug mode can write the tdata registers of a trigger with dmode=1
+    debug_mode: Cell<bool>,
+    // the instruction writing tdata1 does not decrement an icount trigger
+    tdata1_written: Cell<bool>,
+    // skip the checks on the hot path unless some trigger is in use
+    armed: Cell<bool>,
+}
+
+impl Triggers {
+    pub fn new(num: usize) -> Self {
+        Triggers {
+            entries: (0..num).map(|_| Cell::new(TriggerEntry::new())).collect(),
+            tselect: Cell::new(0),
+            tcontrol: Cell::new(TCONTROL_RESET),
+            debug_mode: Cell::new(false),
+            tdata1_written: Cell::new(false),
+            armed: Cell::new(false),
+        }
+    }
+
+    // tcontrol.MTE resets to 1, so M-mode software unaware of tcontrol still gets its breakpoints
+    pub fn reset(&self) {
+        self.entries
+            .iter()
+            .for_each(|entry| entry.set(TriggerEntry::new()));
+        self.tselect.set(0);
+        self.tcontrol.set(TCONTROL_RESET);
+        self.debug_mode.set(false);
+        self.tdata1_written.set(false);
+        self.armed.set(false);
+    }
+
+    pub fn set_debug_mode(&self, debug_mode: bool) {
+        self.debug_mode.set(debug_mode);
+    }
+
+    pub fn read_tselect(&self) -> u64 {
+        self.tselect.get() as u64
+    }
+
+    // out of range values are ignored
+    pub fn write_tselect(&self, data: u64) {
+        if (data as usize) < self.entries.len() {
+            self.tselect.set(data as usize);
+        }
+    }
+
+    fn selected(&self) -> &Cell<TriggerEntry> {
+        &self.entries[self.tselect.get()]
+    }
+
+    pub fn read_tdata(&self, idx: usize) -> u64 {
+        let entry = self.selected().get();
+        match idx {
+            1 => entry.tdata1,
+            2 => entry.tdata2,
+            // textra is not implemented, tdata3 is zero
+            _ => 0,
+        }
+    }
+
+    pub fn write_tdata(&self, idx: usize, data: u64) {
+        let cell = self.selected();
+        let mut entry = cell.get();
+        if Tdata1In::from(entry.tdata1).dmode() && !self.debug_mode.get() {
+            return;
+        }
+        match idx {
+            1 => {
+                entry.tdata1 = self.legalize_tdata1(data);
+                self.tdata1_written.set(!self.debug_mode.get());
+            }
+            2 => entry.tdata2 = data,
+            _ => {}
+        }
+        cell.set(entry);
+        let armed = self.entries.iter().any(|entry| {
+            let ty = Tdata1In::from(entry.get().tdata1).ty();
+            matches!(ty, TYPE_MCONTROL | TYPE_ICOUNT | TYPE_MCONTROL6)
+        });
+        self.armed.set(armed);
+    }
+
+    pub fn armed(&self) -> bool {
+        self.armed.get()
+    }
+
+    pub fn read_tinfo(&self) -> u64 {
+        TINFO_VAL
+    }
+
+    pub fn read_tcontrol(&self) -> u64 {
+        self.tcontrol.get().into()
+    }
+
+    pub fn write_tcontrol(&self, data: u64) {
+        let data = TcontrolIn::from(data);
+        self.tcontrol.set(
+            TcontrolIn::new()
+                .with_mte(data.mte())
+                .with_mpte(data.mpte()),
+        );
+    }
+
+    // a trap into M-mode saves MTE to MPTE and clears MTE
+    pub fn trap_to_m(&self) {
+        let tcontrol = self.tcontrol.get();
+        self.tcontrol
+            .set(tcontrol.with_mpte(tcontrol.mte()).with_mte(false));
+    }
+
+    // MRET restores MTE from MPTE
+    pub fn mret(&self) {
+        let tcontrol = self.tcontrol.get();
+        self.tcontrol.set(tcontrol.with_mte(tcontrol.mpte()));
+    }
+
+    fn legalize_tdata1(&self, data: u64) -> u64 {
+        let tdata1 = Tdata1In::from(data);
+        // the hart can not set dmode, only the debugger can
+        let dmode = tdata1.dmode() && self.debug_mode.get();
+        match tdata1.ty() {
+            TYPE_MCONTROL => {
+                let c = McontrolIn::from(data);
+                McontrolIn::new()
+                    .with_load(c.load())
+                    .with_store(c.store())
+                    .with_execute(c.execute())
+                    .with_u(c.u())
+                    .with_s(c.s())
+                    .with_m(c.m())
+                    .with_match_type(legal_match(c.match_type()))
+                    .with_action(legal_action(c.action(), dmode))
+                    .with_select(c.select())
+                    .with_hit(c.hit())
+                    .with_dmode(dmode)
+                    .with_ty(TYPE_MCONTROL)
+                    .into()
+            }
+            TYPE_MCONTROL6 => {
+                let c = Mcontrol6In::from(data);
+                Mcontrol6In::new()
+                    .with_load(c.load())
+                    .with_store(c.store())
+                    .with_execute(c.execute())
+                    .with_u(c.u())
+                    .with_s(c.s())
+                    .with_m(c.m())
+                    .with_vu(c.vu())
+                    .with_vs(c.vs())
+                    .with_match_type(legal_match(c.match_type()))
+                    .with_action(legal_action(c.action(), dmode))
+                    .with_select(c.select())
+                    .with_hit0(c.hit0())
+                    .with_hit1(c.hit1())
+                    .with_dmode(dmode)
+                    .with_ty(TYPE_MCONTROL6)
+                    .into()
+            }
+            TYPE_ICOUNT => {
+                let c = IcountIn::from(data);
+                IcountIn::new()
+                    .with_action(legal_action(c.action(), dmode))
+                    .with_u(c.u())
+                    .with_s(c.s())
+                    .with_m(c.m())
+                    .with_vu(c.vu())
+                    .with_vs(c.vs())
+                    .with_pending(c.pending())
+                    .with_count(c.count())
+                    .with_hit(c.hit())
+                    .with_dmode(dmode)
+                    .with_ty(TYPE_ICOUNT)
+                    .into()
+            }
+            // type 0 and unsupported types disable the trigger
+            _ => Tdata1In::new()
+                .with_ty(TYPE_DISABLED)
+                .with_dmode(dmode)
+                .into(),
+        }
+    }
+
+    // Triggers with action=0 do not fire in M-mode while tcontrol.MTE=0
+    fn action_allowed(&self, action: u8, privi: PrivilegeLevels) -> bool {
+        action != 0 || privi != PrivilegeLevels::Machine || self.tcontrol.get().mte()
+    }
+
+    // Check the address/data match triggers before an instruction fetch, load or store
+    // completes. The value is the instruction for a fetch, otherwise the data accessed.
+    // A load checks its address triggers (select=0) before the access and its data
+    // triggers (select=1) after it, the select filter picks one kind, None checks both.
+    pub fn match_access(
+        &self,
+        op: TriggerOp,
+        addr: u64,
+        value: u64,
+        select: Option<bool>,
+        privi: PrivilegeLevels,
+        virt: bool,
+    ) -> Option<TriggerAction> {
+        if !self.armed.get() || self.debug_mode.get() {
+            return None;
+        }
+        for cell in self.entries.iter() {
+            let mut entry = cell.get();
+            let (matched, action) = match Tdata1In::from(entry.tdata1).ty() {
+                TYPE_MCONTROL => {
+                    let c = McontrolIn::from(entry.tdata1);
+                    // mcontrol has no VS/VU filters, the S/U bits apply
+                    let mode = match privi {
+                        PrivilegeLevels::Machine => c.m(),
+                        PrivilegeLevels::Supervisor => c.s(),
+                        PrivilegeLevels::User => c.u(),
+                    };
+                    let op_en = [c.execute(), c.load(), c.store()][op as usize]
+                        && select.is_none_or(|select| select == c.select());
+                    let val = if c.select() { value } else { addr };
+                    let matched = mode && op_en && value_match(c.match_type(), entry.tdata2, val);
+                    if matched {
+                        entry.tdata1 = c.with_hit(true).into();
+                    }
+                    (matched, c.action())
+                }
+                TYPE_MCONTROL6 => {
+                    let c = Mcontrol6In::from(entry.tdata1);
+                    let mode = match (privi, virt) {
+                        (PrivilegeLevels::Machine, _) => c.m(),
+                        (PrivilegeLevels::Supervisor, false) => c.s(),
+                        (PrivilegeLevels::User, false) => c.u(),
+                        (PrivilegeLevels::Supervisor, true) => c.vs(),
+                        (PrivilegeLevels::User, true) => c.vu(),
+                    };
+                    let op_en = [c.execute(), c.load(), c.store()][op as usize]
+                        && select.is_none_or(|select| select == c.select());
+                    let val = if c.select() { value } else { addr };
+                    let matched = mode && op_en && value_match(c.match_type(), entry.tdata2, val);
+                    // hit1:hit0 = 0b01, the trigger fired before the access
+                    if matched {
+                        entry.tdata1 = c.with_hit0(true).with_hit1(false).into();
+                    }
+                    (matched, c.action())
+                }
+                _ => (false, 0),
+            };
+            if matched && self.action_allowed(action, privi) {
+                cell.set(entry);
+                return Some(TriggerAction::from_bits(action));
+            }
+        }
+        None
+    }
+
+    // An icount trigger counts the instructions retired in the enabled modes, and becomes
+    // pending when the count reaches zero.
+    pub fn icount_retire(&self, privi: PrivilegeLevels, virt: bool) {
+        if self.tdata1_written.replace(false) || !self.armed.get() || self.debug_mode.get() {
+            return;
+        }
+        for cell in self.entries.iter() {
+            let mut entry = cell.get();
+            if Tdata1In::from(entry.tdata1).ty() != TYPE_ICOUNT {
+                continue;
+            }
+            let c = IcountIn::from(entry.tdata1);
+            let mode = match (privi, virt) {
+                (PrivilegeLevels::Machine, _) => c.m(),
+                (PrivilegeLevels::Supervisor, false) => c.s(),
+                (PrivilegeLevels::User, false) => c.u(),
+                (PrivilegeLevels::Supervisor, true) => c.vs(),
+                (PrivilegeLevels::User, true) => c.vu(),
+            };
+            if mode && c.count() > 0 {
+                let count = c.count() - 1;
+                entry.tdata1 = c.with_count(count).with_pending(count == 0).into();
+                cell.set(entry);
+            }
+        }
+    }
+
+    // fire a pending icount trigger before the next instruction
+    pub fn icount_fire(&self, privi: PrivilegeLevels) -> Option<TriggerAction> {
+        if !self.armed.get() {
+            return None;
+        }
+        for cell in self.entries.iter() {
+            let mut entry = cell.get();
+            if Tdata1In::from(entry.tdata1).ty() != TYPE_ICOUNT {
+                continue;
+            }
+            let c = IcountIn::from(entry.tdata1);
+            if c.pending() && self.action_allowed(c.action(), privi) {
+                entry.tdata1 = c.with_pending(false).with_hit(true).into();
+                cell.set(entry);
+                return Some(TriggerAction::from_bits(c.action()));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn trigger_match_test() {
+    let triggers = Triggers::new(TRIGGER_NUM);
+    let m = PrivilegeLevels::Machine;
+    let s = PrivilegeLevels::Supervisor;
+
+    // trigger 0: mcontrol6 store in S-mode, napot 0x8000_1000 ~ 0x8000_10ff
+    triggers.write_tdata(
+        1,
+        Mcontrol6In::new()
+            .with_store(true)
+            .with_s(true)
+            .with_match_type(1)
+            .with_ty(TYPE_MCONTROL6)
+            .into(),
+    );
+    triggers.write_tdata(2, 0x8000_107f);
+    let store = TriggerOp::Store;
+    assert_eq!(
+        triggers.match_access(store, 0x8000_1000, 0, None, m, false),
+        None
+    );
+    assert_eq!(
+        triggers.match_access(TriggerOp::Load, 0x8000_1000, 0, None, s, false),
+        None
+    );
+    assert_eq!(
+        triggers.match_access(store, 0x8000_10f8, 0, None, s, false),
+        Some(TriggerAction::Breakpoint)
+    );
+    assert!(Mcontrol6In::from(triggers.read_tdata(1)).hit0());
+
+    // a load checks the address before the access and the data after it
+    triggers.write_tselect(3);
+    triggers.write_tdata(
+        1,
+        McontrolIn::new()
+            .with_load(true)
+            .with_select(true)
+            .with_m(true)
+            .with_ty(TYPE_MCONTROL)
+            .into(),
+    );
+    triggers.write_tdata(2, 0x1234);
+    triggers.write_tcontrol(1 << 3);
+    let load = TriggerOp::Load;
+    assert_eq!(
+        triggers.match_access(load, 0x1234, 0x1234, Some(false), m, false),
+        None
+    );
+    assert_eq!(
+        triggers.match_access(load, 0x8000_0000, 0x1234, Some(true), m, false),
+        Some(TriggerAction::Breakpoint)
+    );
+    triggers.write_tdata(1, 0);
+    triggers.write_tcontrol(0);
+
+    // the hart can not set dmode, so the debug mode action falls back to a breakpoint
+    triggers.write_tselect(1);
+    let icount = IcountIn::new()
+        .with_count(2)
+        .with_m(true)
+        .with_action(1)
+        .with_dmode(true)
+        .with_ty(TYPE_ICOUNT);
+    triggers.write_tdata(1, icount.into());
+    assert_eq!(IcountIn::from(triggers.read_tdata(1)).action(), 0);
+    triggers.set_debug_mode(true);
+    triggers.write_tdata(1, icount.into());
+    triggers.set_debug_mode(false);
+    assert_eq!(triggers.read_tdata(1), u64::from(icount));
+
+    // written by the debugger, the count starts with the next retired instruction
+    triggers.icount_retire(m, false);
+    assert_eq!(triggers.icount_fire(m), None);
+    triggers.icount_retire(m, false);
+    assert_eq!(triggers.icount_fire(m), Some(TriggerAction::DebugMode));
+
+    // unsupported types disable the trigger
+    triggers.write_tselect(2);
+    triggers.write_tdata(1, 0);
+    assert_eq!(Tdata1In::from(triggers.read_tdata(1)).ty(), TYPE_DISABLED);
+}
@@ -0,0 +1,82 @@
+use alloc::{vec, vec::Vec};
+
+// The 32 vector registers are stored back to back, so the elements of a
+// register group are contiguous and can be addressed from the first register.
+pub struct Vpr {
+    regs: Vec<u8>,
+    vlenb: usize,
+}
+
+impl Vpr {
+    pub fn new(vlen: usize) -> Self {
+        assert!(vlen.is_power_of_two() && vlen >= 64);
+        let vlenb = vlen / 8;
+        Vpr {
+            regs: vec![0; 32 * vlenb],
+            vlenb,
+        }
+    }
+
+    pub fn vlenb(&self) -> usize {
+        self.vlenb
+    }
+
+    fn offset(&self, reg: usize, eew: usize, idx: usize) -> usize {
+        let offset = reg * self.vlenb + idx * eew;
+        assert!(
+            offset + eew <= self.regs.len(),
+            "vector register out of range"
+        );
+        offset
+    }
+
+    // read element idx of the register group starting at reg, eew in bytes
+    pub fn read_elem(&self, reg: usize, eew: usize, idx: usize) -> u64 {
+        let offset = self.offset(reg, eew, idx);
+        let mut bytes = [0; 8];
+        bytes[..eew].copy_from_slice(&self.regs[offset..offset + eew]);
+        u64::from_le_bytes(bytes)
+    }
+
+    pub fn write_elem(&mut self, reg: usize, eew: usize, idx: usize, data: u64) {
+        let offset = self.offset(reg, eew, idx);
+        self.regs[offset..offset + eew].copy_from_slice(&data.to_le_bytes()[..eew]);
+    }
+
+    // mask registers hold one bit per element
+    pub fn read_mask(&self, reg: usize, idx: usize) -> bool {
+        let byte = self.read_elem(reg, 1, idx / 8);
+        (byte >> (idx % 8)) & 1 != 0
+    }
+
+    pub fn write_mask(&mut self, reg: usize, idx: usize, data: bool) {
+        let byte = self.read_elem(reg, 1, idx / 8);
+        let bit = 1 << (idx % 8);
+        let byte = match data {
+            true => byte | bit,
+            false => byte & !bit,
+        };
+        self.write_elem(reg, 1, idx / 8, byte);
+    }
+
+    pub fn reset(&mut self) {
+        self.regs.fill(0);
+    }
+}
+
+#[test]
+fn vpr_elem_test() {
+    let mut vpr = Vpr::new(128);
+    vpr.write_elem(2, 4, 0, 0x1122_3344);
+    // element 4 of a group starting at v2 lives in v3
+    vpr.write_elem(2, 4, 4, 0xdead_beef);
+    assert_eq!(vpr.read_elem(3, 4, 0), 0xdead_beef);
+    assert_eq!(vpr.read_elem(2, 1, 0), 0x44);
+    assert_eq!(vpr.read_elem(2, 8, 0), 0x1122_3344);
+
+    vpr.write_mask(0, 9, true);
+    assert_eq!(vpr.read_elem(0, 2, 0), 0x200);
+    assert!(vpr.read_mask(0, 9));
+    vpr.write_mask(0, 9, false);
+    assert!(!vpr.read_mask(0, 9));
+}
@@ -174,6 +174,22 @@ impl RVsim {
             hart.borrow_mut().execute(interval_cycle);
         });
         let mut bus = self.bus.borrow_mut();
+        // When every hart waits in WFI, only a timer can wake them up, skip
+        // straight to the next timer interrupt.
+        let all_waiting = self
+            .harts
+            .iter()
+            .all(|hart| hart.borrow().cpu_state == CpuState::Waiting);
+        if all_waiting {
+            let hart_delta = self
+                .harts
+                .iter()
+                .filter_map(|hart| hart.borrow().timer_delta())
+                .min();
+            if let Some(delta) = bus.timer_delta().into_iter().chain(hart_delta).min() {
+                bus.advance_mtime(delta);
+            }
+        }
         bus.update(interval_cycle);
 
         drop(bus);
@@ -282,3 +298,33 @@ impl RVsim {
         );
     }
 }
+
+#[test]
+fn wfi_fast_forward_test() {
+    use crate::rv64core::{cpu_core::cpu_for_test, inst::inst_base::CSR_MIE};
+    use crate::tools::rc_refcell_new;
+    use alloc::vec;
+
+    let mut config = Config::new();
+    config.set_isa("rv64imac").unwrap();
+    let mut cpu = cpu_for_test(config);
+    cpu.csr_regs.write_raw(CSR_MIE.into(), 1 << 7);
+    // wfi
+    cpu.mem_write(0x8000_0000, 0x1050_0073, 4);
+    let hart = rc_refcell_new(cpu);
+    let mut sim = RVsim::new(vec![hart.clone()], 0);
+    let clint_mtime = 0x0200_bff8;
+    let clint_mtimecmp = 0x0200_4000;
+    let cmp = 10_000_000;
+    sim.bus.borrow_mut().write(clint_mtimecmp, cmp, 8).unwrap();
+
+    // every hart waits, so mtime jumps to the timer instead of ticking 5000 / 10
+    sim.run_once(5000);
+    assert_eq!(hart.borrow().cpu_state, CpuState::Waiting);
+    assert!(sim.bus.borrow_mut().read(clint_mtime, 8).unwrap() >= cmp);
+    assert!(hart.borrow().csr_regs.xip.get().mtip());
+
+    sim.run_once(1);
+    assert_eq!(hart.borrow().cpu_state, CpuState::Running);
+    assert_eq!(hart.borrow().npc, 0x8000_0004);
+}
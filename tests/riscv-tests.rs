@@ -25,7 +25,9 @@ fn get_riscv_tests_path() -> std::path::PathBuf {
 }
 
 // ture: pass, false: fail
-fn start_test(img: &str) -> bool {
+// misaligned: split misaligned load/store or raise an address-misaligned exception
+// block_cache: run the pre-decoded basic blocks
+fn start_test(img: &str, misaligned: bool, block_cache: bool) -> bool {
     // let bus_u = Rc::new(Mutex::new(Bus::new()));
     let bus_u: RcRefCell<Bus> = RcRefCell::new(Bus::new().into());
 
@@ -33,9 +35,26 @@ fn start_test(img: &str) -> bool {
     config.set_tlb_size(256);
     config.set_icache_size(4096);
     config.set_decode_cache_size(4096);
-    config.set_isa("rv64ima");
-    config.set_mmu_type("sv39");
+    // the rv32 suites boot a 32-bit hart
+    let rv32 = Path::new(img)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("rv32"));
+    match rv32 {
+        true => {
+            config.set_isa("rv32imac").unwrap();
+            config.set_mmu_type("sv32");
+        }
+        false => {
+            config.set_isa("rv64imafd").unwrap();
+            config.set_mmu_type("sv39");
+        }
+    }
     config.set_s_mode();
+    config.set_misaligned_access(misaligned);
+    if block_cache {
+        config.set_block_cache_size(1024);
+    }
 
     let config = Rc::new(config);
 
@@ -67,10 +86,59 @@ fn start_test(img: &str) -> bool {
 #[test]
 fn test_once() {
     let img = get_riscv_tests_path().join("rv64mi-p-csr");
-    let ret = start_test(img.to_str().unwrap());
+    let ret = start_test(img.to_str().unwrap(), true, false);
     assert!(ret);
 }
 
+#[test]
+fn test_misaligned_trap() {
+    // ma_addr accepts both split and trapping misaligned accesses
+    let img = get_riscv_tests_path().join("rv64mi-p-ma_addr");
+    let ret = start_test(img.to_str().unwrap(), false, false);
+    assert!(ret);
+}
+
+#[test]
+fn test_block_cache() {
+    // self-modifying code, page aliases and traps in the middle of a block
+    let tests = [
+        "rv64ui-p-fence_i",
+        "rv64ui-v-fence_i",
+        "rv64si-p-icache-alias",
+        "rv64ui-v-jalr",
+        "rv64mi-p-breakpoint",
+        "rv64ua-v-lrsc",
+        "rv64mi-p-illegal",
+        "rv64si-p-dirty",
+    ];
+    for test in tests {
+        let img = get_riscv_tests_path().join(test);
+        assert!(start_test(img.to_str().unwrap(), true, true), "{test}");
+    }
+}
+
+#[test]
+fn test_rv32() {
+    // 32-bit firmware, with trapping misaligned accesses as well
+    let tests = [
+        "rv32ui-p-add",
+        "rv32ui-p-jalr",
+        "rv32um-p-mulh",
+        "rv32ua-p-lrsc",
+        "rv32mi-p-csr",
+        "rv32mi-p-mcsr",
+        "rv32mi-p-zicntr",
+        "rv32si-p-csr",
+        "rv32si-p-dirty",
+    ];
+    for test in tests {
+        let img = get_riscv_tests_path().join(test);
+        assert!(start_test(img.to_str().unwrap(), true, false), "{test}");
+    }
+    let img = get_riscv_tests_path().join("rv32mi-p-ma_addr");
+    assert!(start_test(img.to_str().unwrap(), false, false));
+}
+
 struct TestRet {
     pub name: String,
     pub ret: bool,
@@ -78,13 +146,7 @@ struct TestRet {
 
 #[test]
 fn run_arch_tests() {
-    // not support misaligned load/store, so skip these tests
-
-    let sikp_files = [
-        "rv64ui-p-ma_data",
-        "rv64ui-v-ma_data",
-        "rv64mi-p-breakpoint",
-    ];
+    let sikp_files: [&str; 0] = [];
     simple_logger::SimpleLogger::new()
         .with_level(LevelFilter::Debug)
         .init()
@@ -102,7 +164,7 @@ fn run_arch_tests() {
             continue;
         }
         if let Some(p) = path.to_str() {
-            let ret = start_test(p);
+            let ret = start_test(p, true, false);
             tests_ret.push(TestRet {
                 name: String::from(file_name),
                 ret,